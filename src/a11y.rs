@@ -0,0 +1,131 @@
+//! A hidden DOM mirror of the board for screen readers.
+//!
+//! The canvas is opaque to assistive tech, so the cells around the middle of the
+//! view are kept as an ARIA grid of buttons labelled with their coordinates and
+//! contents ("3, −2: White stone"), which play when activated, and an
+//! `aria-live` region announces each move ("Black plays 3, −2"). Both carry the
+//! page's `sr-only` class, which hides them from sight but not from readers.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Document, Element, Event, Node};
+
+use crate::i18n::{self, t, tf, Msg};
+use crate::{Game, Pt};
+
+/// Cells mirrored on each side of the centre, for a 9×9 window.
+const RADIUS: i32 = 4;
+
+pub(crate) struct A11y {
+    grid: Element,
+    /// One button per cell of the window, row by row.
+    buttons: Vec<Element>,
+    live: Element,
+    /// The centre, position key and playability the grid was last labelled for.
+    shown: Option<(Pt, u64, bool)>,
+}
+
+impl A11y {
+    /// Add the grid and the live region to the page.
+    pub(crate) fn new(doc: &Document) -> Result<A11y, JsValue> {
+        let body = doc.body().ok_or("no document body")?;
+        let grid = doc.create_element("div")?;
+        grid.set_class_name("sr-only");
+        grid.set_attribute("role", "grid")?;
+        let mut buttons = Vec::new();
+        for _ in -RADIUS..=RADIUS {
+            let row = doc.create_element("div")?;
+            row.set_attribute("role", "row")?;
+            for _ in -RADIUS..=RADIUS {
+                let cell = doc.create_element("div")?;
+                cell.set_attribute("role", "gridcell")?;
+                let button = doc.create_element("button")?;
+                button.set_attribute("type", "button")?;
+                cell.append_child(&button)?;
+                row.append_child(&cell)?;
+                buttons.push(button);
+            }
+            grid.append_child(&row)?;
+        }
+        let live = doc.create_element("div")?;
+        live.set_class_name("sr-only");
+        live.set_attribute("aria-live", "polite")?;
+        body.append_child(&grid)?;
+        body.append_child(&live)?;
+        Ok(A11y {
+            grid,
+            buttons,
+            live,
+            shown: None,
+        })
+    }
+
+    pub(crate) fn grid(&self) -> &Element {
+        &self.grid
+    }
+
+    /// Relabel the window around `centre` for `game` if either has changed.
+    /// `playable` says whether activating an empty cell would play there now.
+    pub(crate) fn sync(&mut self, game: &Game, centre: Pt, playable: bool) {
+        let state = (centre, game.key(), playable);
+        if self.shown == Some(state) {
+            return;
+        }
+        self.shown = Some(state);
+        let _ = self
+            .grid
+            .set_attribute("aria-label", &tf(Msg::BoardAround, &[&coords(centre)]));
+        let cells = (-RADIUS..=RADIUS)
+            .flat_map(|dy| (-RADIUS..=RADIUS).map(move |dx| centre.add(dx, dy)));
+        for (button, p) in self.buttons.iter().zip(cells) {
+            let mut label = match game.color_at(p) {
+                Some(&c) => tf(Msg::StoneOn, &[&i18n::color(c)]),
+                None if !game.in_bounds(p) => t(Msg::OffBoard),
+                None => t(Msg::Empty),
+            };
+            if game.last_move == Some(p) {
+                label = format!("{label}, {}", t(Msg::LastMove));
+            }
+            let enabled = playable && game.is_empty(p);
+            let _ = button.set_attribute("aria-label", &format!("{}: {label}", coords(p)));
+            let _ = button.set_attribute("aria-disabled", if enabled { "false" } else { "true" });
+            let _ = button.set_attribute("data-x", &p.x.to_string());
+            let _ = button.set_attribute("data-y", &p.y.to_string());
+        }
+    }
+
+    /// Relabel every cell on the next `sync`, as after a change of language.
+    pub(crate) fn relabel(&mut self) {
+        self.shown = None;
+    }
+
+    /// Have screen readers read `text` out once they are idle.
+    pub(crate) fn announce(&self, text: &str) {
+        self.live.set_text_content(Some(text));
+    }
+
+    /// Whether `e` happened inside the grid, where keys belong to its buttons.
+    pub(crate) fn owns(&self, e: &Event) -> bool {
+        e.target()
+            .and_then(|t| t.dyn_into::<Node>().ok())
+            .is_some_and(|n| self.grid.contains(Some(&n)))
+    }
+}
+
+/// The cell whose button `e` (a click on the grid) landed on.
+pub(crate) fn clicked_cell(e: &Event) -> Option<Pt> {
+    let target = e.target()?.dyn_into::<Element>().ok()?;
+    let button = target.closest("button").ok()??;
+    let x = button.get_attribute("data-x")?.parse().ok()?;
+    let y = button.get_attribute("data-y")?.parse().ok()?;
+    Some(Pt::new(x, y))
+}
+
+/// `3, −2`, with a true minus sign so readers say "minus" rather than "dash".
+pub(crate) fn coords(p: Pt) -> String {
+    let n = |v: i32| match v {
+        v if v < 0 => format!("−{}", v.unsigned_abs()),
+        v => v.to_string(),
+    };
+    format!("{}, {}", n(p.x), n(p.y))
+}
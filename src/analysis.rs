@@ -0,0 +1,30 @@
+//! Post-game analysis: a static evaluation of every position in a game's history.
+
+use crate::Game;
+
+pub(crate) struct Analysis {
+    /// `evals[i]` is the position after `i` moves, in `[-1, 1]` from Black's point of view.
+    evals: Vec<f64>,
+}
+
+impl Analysis {
+    pub(crate) fn new(game: &Game) -> Self {
+        let mut g = Game::with_rules(game.rules);
+        let mut evals = Vec::with_capacity(game.moves.len() + 1);
+        evals.push(squash(g.evaluate()));
+        for &p in &game.moves {
+            g.play(p);
+            evals.push(squash(g.evaluate()));
+        }
+        Self { evals }
+    }
+
+    pub(crate) fn evals(&self) -> &[f64] {
+        &self.evals
+    }
+}
+
+/// Map raw heuristic scores onto `[-1, 1]`; an open three sits around ±0.5.
+pub(crate) fn squash(score: i32) -> f64 {
+    (score as f64 / 20_000.0).tanh()
+}
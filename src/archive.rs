@@ -0,0 +1,214 @@
+//! Every finished game, kept in IndexedDB for the history screen: its moves,
+//! who won, when, who played each side and the rules it was played under.
+//!
+//! Each game is one object in the `games` store, keyed in the order they were
+//! added: `{ date, moves, winner, black, white, variant, winLength, board }`,
+//! the moves as a game link writes them (see `codec`), the players `"human"`,
+//! `"ai"` or `"remote"`, and the board `null` when infinite. IndexedDB only
+//! answers in callbacks, so the history asks for the list when it opens and
+//! `History::poll` picks it up on a later frame.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::Reflect;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    window, IdbDatabase, IdbObjectStore, IdbObjectStoreParameters, IdbOpenDbRequest,
+    IdbTransactionMode,
+};
+
+use crate::{codec, download_text, Bounds, Color, Controller, Pt, Rules, Variant};
+
+const DB_NAME: &str = "gomoku";
+const DB_VERSION: u32 = 1;
+const STORE: &str = "games";
+
+pub(crate) struct Entry {
+    /// When the game was won, in ms since the epoch.
+    pub(crate) date_ms: f64,
+    pub(crate) moves: Vec<Pt>,
+    pub(crate) winner: Color,
+    pub(crate) players: [Controller; 2],
+    pub(crate) rules: Rules,
+}
+
+impl Entry {
+    fn to_js(&self) -> JsValue {
+        let obj = js_sys::Object::new();
+        let board = self.rules.bounds.map_or(JsValue::NULL, |b| b.size().into());
+        for (key, value) in [
+            ("date", self.date_ms.into()),
+            ("moves", codec::encode_moves(&self.moves).into()),
+            ("winner", color_name(self.winner).into()),
+            ("black", controller_name(self.players[0]).into()),
+            ("white", controller_name(self.players[1]).into()),
+            ("variant", Variant::of(self.rules).name().into()),
+            ("winLength", self.rules.win_len.into()),
+            ("board", board),
+        ] {
+            let _ = Reflect::set(&obj, &key.into(), &value);
+        }
+        obj.into()
+    }
+
+    /// Back from `to_js`; `None` for an object it did not write.
+    fn from_js(obj: &JsValue) -> Option<Entry> {
+        let get = |key: &str| Reflect::get(obj, &key.into()).ok();
+        let text = |key: &str| get(key)?.as_string();
+        let winner = match text("winner")?.as_str() {
+            "black" => Color::Black,
+            "white" => Color::White,
+            _ => return None,
+        };
+        let player = |key: &str| match text(key)?.as_str() {
+            "human" => Some(Controller::Human),
+            "ai" => Some(Controller::AI),
+            "remote" => Some(Controller::Remote),
+            _ => None,
+        };
+        let rules = Rules {
+            win_len: get("winLength")?.as_f64()? as i32,
+            bounds: get("board")?.as_f64().map(|n| Bounds::square(n as i32)),
+            ..Variant::parse(&text("variant")?)?.apply(Rules::default())
+        };
+        Some(Entry {
+            date_ms: get("date")?.as_f64()?,
+            moves: codec::decode_moves(&text("moves")?)?,
+            winner,
+            players: [player("black")?, player("white")?],
+            rules: rules.validated(),
+        })
+    }
+
+    /// Download the game as `gomoku-<date>.json`, with a link that opens it.
+    pub(crate) fn export(&self) -> Result<(), JsValue> {
+        let obj = self.to_js();
+        let loc = window().ok_or("no window")?.location();
+        let link = format!(
+            "{}{}#{}",
+            loc.origin()?,
+            loc.pathname()?,
+            codec::encode_link(&self.rules, &self.moves)
+        );
+        Reflect::set(&obj, &"link".into(), &link.into())?;
+        let json =
+            js_sys::JSON::stringify_with_replacer_and_space(&obj, &JsValue::NULL, &2.into())?;
+        let date = String::from(js_sys::Date::new(&self.date_ms.into()).to_iso_string());
+        let stamp = date.get(..19).unwrap_or("").replace(':', "-");
+        let name = format!("gomoku-{stamp}.json");
+        download_text(&name, "application/json", &String::from(json));
+        Ok(())
+    }
+}
+
+fn color_name(c: Color) -> &'static str {
+    match c {
+        Color::Black => "black",
+        Color::White => "white",
+    }
+}
+
+fn controller_name(c: Controller) -> &'static str {
+    match c {
+        Controller::Human => "human",
+        Controller::AI => "ai",
+        Controller::Remote => "remote",
+    }
+}
+
+/// Open the database, creating the store on first use, and hand `f` the
+/// store in a transaction of `mode` once it is open, or `None` if it fails.
+fn with_store(mode: IdbTransactionMode, f: impl FnOnce(Option<IdbObjectStore>) + 'static) {
+    let f = Rc::new(RefCell::new(Some(f)));
+    let call = move |store: Option<IdbObjectStore>| {
+        if let Some(f) = f.borrow_mut().take() {
+            f(store);
+        }
+    };
+    let Some(open) = window()
+        .and_then(|w| w.indexed_db().ok().flatten())
+        .and_then(|factory| factory.open_with_u32(DB_NAME, DB_VERSION).ok())
+    else {
+        call(None);
+        return;
+    };
+    let database = |req: &IdbOpenDbRequest| req.result()?.dyn_into::<IdbDatabase>();
+    let req = open.clone();
+    let upgrade = Closure::once_into_js(move || {
+        if let Ok(db) = database(&req) {
+            let params = IdbObjectStoreParameters::new();
+            params.set_auto_increment(true);
+            let _ = db.create_object_store_with_optional_parameters(STORE, &params);
+        }
+    });
+    open.set_onupgradeneeded(Some(upgrade.unchecked_ref()));
+    let req = open.clone();
+    let done = call.clone();
+    let success = Closure::once_into_js(move || {
+        let store = database(&req)
+            .and_then(|db| db.transaction_with_str_and_mode(STORE, mode))
+            .and_then(|tx| tx.object_store(STORE));
+        done(store.ok());
+    });
+    open.set_onsuccess(Some(success.unchecked_ref()));
+    let failure = Closure::once_into_js(move || call(None));
+    open.set_onerror(Some(failure.unchecked_ref()));
+}
+
+/// Add `entry` to the archive; quietly does nothing without IndexedDB.
+pub(crate) fn add(entry: &Entry) {
+    let obj = entry.to_js();
+    with_store(IdbTransactionMode::Readwrite, move |store| {
+        if let Some(store) = store {
+            let _ = store.add(&obj);
+        }
+    });
+}
+
+/// The history screen's list: empty until IndexedDB answers, newest first.
+pub(crate) struct History {
+    pub(crate) entries: Option<Vec<Entry>>,
+    /// Entries scrolled past at the top.
+    pub(crate) scroll: usize,
+    pending: Rc<RefCell<Option<Vec<Entry>>>>,
+}
+
+impl History {
+    /// Ask for every archived game.
+    pub(crate) fn open() -> History {
+        let pending = Rc::new(RefCell::new(None));
+        let into = Rc::clone(&pending);
+        with_store(IdbTransactionMode::Readonly, move |store| {
+            let Some(req) = store.and_then(|s| s.get_all().ok()) else {
+                *into.borrow_mut() = Some(Vec::new());
+                return;
+            };
+            let done = req.clone();
+            let on_success = Closure::once_into_js(move || {
+                let list = done.result().map(|r| js_sys::Array::from(&r));
+                let list = list.unwrap_or_default();
+                let mut entries: Vec<Entry> =
+                    list.iter().filter_map(|o| Entry::from_js(&o)).collect();
+                entries.reverse();
+                *into.borrow_mut() = Some(entries);
+            });
+            req.set_onsuccess(Some(on_success.unchecked_ref()));
+        });
+        History {
+            entries: None,
+            scroll: 0,
+            pending,
+        }
+    }
+
+    /// Take the list once it has arrived; whether it just did.
+    pub(crate) fn poll(&mut self) -> bool {
+        if self.entries.is_some() {
+            return false;
+        }
+        self.entries = self.pending.borrow_mut().take();
+        self.entries.is_some()
+    }
+}
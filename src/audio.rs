@@ -0,0 +1,250 @@
+//! Sound effects, synthesised with the Web Audio API: a click for every stone,
+//! a low buzz for a move that cannot be played, a tick each second once the
+//! running clock is under `TICK_BELOW_MS`, and a jingle for a win.
+//!
+//! Browsers only let audio start from a user gesture, so the context is made
+//! (or resumed) by `unlock` on a press or key. The mute switch and volumes are
+//! kept in `localStorage` as `muted,master:0.8,tick:0.5`, listing only what
+//! differs from the defaults; volumes are set from JS with `setSoundVolume`.
+
+use std::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+use web_sys::{window, AudioContext, AudioContextState, OscillatorType, Storage};
+
+const STORAGE_KEY: &str = "gomoku.sound";
+
+/// The clock ticks once a second below this much time left.
+pub(crate) const TICK_BELOW_MS: f64 = 10_000.0;
+
+/// A sound the game makes.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Effect {
+    Stone,
+    Invalid,
+    Tick,
+    Win,
+}
+
+/// One tone of an effect: when it starts (s after the effect), its pitch (Hz),
+/// length (s) and waveform.
+type Note = (f64, f32, f64, OscillatorType);
+
+impl Effect {
+    const ALL: [Effect; 4] = [Effect::Stone, Effect::Invalid, Effect::Tick, Effect::Win];
+
+    fn name(self) -> &'static str {
+        match self {
+            Effect::Stone => "stone",
+            Effect::Invalid => "invalid",
+            Effect::Tick => "tick",
+            Effect::Win => "win",
+        }
+    }
+
+    fn notes(self) -> &'static [Note] {
+        match self {
+            Effect::Stone => &[
+                (0.0, 1800.0, 0.025, OscillatorType::Sine),
+                (0.0, 420.0, 0.08, OscillatorType::Triangle),
+            ],
+            Effect::Invalid => &[
+                (0.0, 150.0, 0.09, OscillatorType::Square),
+                (0.11, 120.0, 0.12, OscillatorType::Square),
+            ],
+            Effect::Tick => &[(0.0, 2400.0, 0.02, OscillatorType::Sine)],
+            // C E G C, after the winning stone's click.
+            Effect::Win => &[
+                (0.15, 523.25, 0.18, OscillatorType::Triangle),
+                (0.30, 659.25, 0.18, OscillatorType::Triangle),
+                (0.45, 783.99, 0.18, OscillatorType::Triangle),
+                (0.60, 1046.5, 0.45, OscillatorType::Triangle),
+            ],
+        }
+    }
+
+    /// Relative loudness before the player's volumes, so that no effect
+    /// drowns out the others at equal settings.
+    fn level(self) -> f32 {
+        match self {
+            Effect::Stone => 0.5,
+            Effect::Invalid => 0.12,
+            Effect::Tick => 0.2,
+            Effect::Win => 0.3,
+        }
+    }
+}
+
+/// The mute switch and volumes, each from 0 to 1.
+struct Settings {
+    muted: bool,
+    master: f32,
+    volumes: [f32; 4],
+}
+
+impl Settings {
+    fn defaults() -> Settings {
+        Settings {
+            muted: false,
+            master: 1.0,
+            volumes: [1.0; 4],
+        }
+    }
+
+    fn load() -> Settings {
+        let mut settings = Settings::defaults();
+        let spec = storage().and_then(|s| s.get_item(STORAGE_KEY).ok().flatten());
+        for entry in spec.iter().flat_map(|s| s.split(',')) {
+            match entry.split_once(':') {
+                None => settings.muted |= entry == "muted",
+                Some((name, v)) => {
+                    if let Ok(v) = v.parse() {
+                        settings.set(name, v);
+                    }
+                }
+            }
+        }
+        settings
+    }
+
+    /// Set the volume `name` (`master` or an effect); `false` if there is none.
+    fn set(&mut self, name: &str, volume: f32) -> bool {
+        let volume = volume.clamp(0.0, 1.0);
+        if name == "master" {
+            self.master = volume;
+        } else if let Some(e) = Effect::ALL.iter().find(|e| e.name() == name) {
+            self.volumes[*e as usize] = volume;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// `muted,master:…,stone:…`: every setting with `all`, else those changed.
+    fn spec(&self, all: bool) -> String {
+        let mut entries = Vec::new();
+        if self.muted || all {
+            entries.push(if self.muted { "muted" } else { "unmuted" }.to_string());
+        }
+        let volumes = std::iter::once(("master", self.master))
+            .chain(Effect::ALL.iter().map(|&e| (e.name(), self.volumes[e as usize])));
+        for (name, v) in volumes {
+            if all || v != 1.0 {
+                entries.push(format!("{name}:{v}"));
+            }
+        }
+        entries.join(",")
+    }
+
+    fn save(&self) {
+        if let Some(s) = storage() {
+            let _ = s.set_item(STORAGE_KEY, &self.spec(false));
+        }
+    }
+}
+
+thread_local! {
+    static SETTINGS: RefCell<Option<Settings>> = const { RefCell::new(None) };
+}
+
+fn with_settings<T>(f: impl FnOnce(&mut Settings) -> T) -> T {
+    SETTINGS.with(|s| f(s.borrow_mut().get_or_insert_with(Settings::load)))
+}
+
+fn storage() -> Option<Storage> {
+    window()?.local_storage().ok().flatten()
+}
+
+pub(crate) fn muted() -> bool {
+    with_settings(|s| s.muted)
+}
+
+/// Flip the mute switch and remember it.
+pub(crate) fn toggle_muted() {
+    with_settings(|s| {
+        s.muted = !s.muted;
+        s.save();
+    });
+}
+
+/// The page's audio output, once a gesture has allowed it.
+#[derive(Default)]
+pub(crate) struct Audio {
+    ctx: Option<AudioContext>,
+}
+
+impl Audio {
+    /// Make or resume the audio context; call from an input handler.
+    pub(crate) fn unlock(&mut self) {
+        match &self.ctx {
+            None => self.ctx = AudioContext::new().ok(),
+            Some(ctx) if ctx.state() == AudioContextState::Suspended => {
+                let _ = ctx.resume();
+            }
+            Some(_) => {}
+        }
+    }
+
+    pub(crate) fn play(&self, effect: Effect) {
+        let Some(ctx) = &self.ctx else {
+            return;
+        };
+        let volume = with_settings(|s| {
+            if s.muted {
+                0.0
+            } else {
+                s.master * s.volumes[effect as usize] * effect.level()
+            }
+        });
+        if volume <= 0.0 {
+            return;
+        }
+        let now = ctx.current_time();
+        for &(at, freq, len, wave) in effect.notes() {
+            let _ = tone(ctx, now + at, freq, len, wave, volume);
+        }
+    }
+}
+
+/// A note of `freq` Hz from `start` for `len` seconds, decaying from `volume`.
+fn tone(
+    ctx: &AudioContext,
+    start: f64,
+    freq: f32,
+    len: f64,
+    wave: OscillatorType,
+    volume: f32,
+) -> Result<(), JsValue> {
+    let osc = ctx.create_oscillator()?;
+    osc.set_type(wave);
+    osc.frequency().set_value_at_time(freq, start)?;
+    let gain = ctx.create_gain()?;
+    gain.gain().set_value_at_time(volume, start)?;
+    // An exponential ramp cannot reach zero; this is well below hearing.
+    gain.gain().exponential_ramp_to_value_at_time(0.0001, start + len)?;
+    osc.connect_with_audio_node(&gain)?;
+    gain.connect_with_audio_node(&ctx.destination())?;
+    osc.start_with_when(start)?;
+    osc.stop_with_when(start + len)?;
+    Ok(())
+}
+
+/// Set a volume from 0 to 1: `"master"`, or one effect's (`"stone"`,
+/// `"invalid"`, `"tick"`, `"win"`), and remember it. `false` if there is no
+/// such volume.
+#[wasm_bindgen(js_name = setSoundVolume)]
+pub fn set_sound_volume(name: &str, volume: f32) -> bool {
+    with_settings(|s| {
+        let known = s.set(name, volume);
+        if known {
+            s.save();
+        }
+        known
+    })
+}
+
+/// The mute switch and every volume, as `unmuted,master:1,stone:1,…`.
+#[wasm_bindgen(js_name = soundSettings)]
+pub fn sound_settings() -> String {
+    with_settings(|s| s.spec(true))
+}
@@ -0,0 +1,109 @@
+//! Opening book for the first few moves of standard five-in-a-row.
+//!
+//! Lines are written relative to Black's first stone at the origin, White's
+//! reply either straight above it (direct openings) or diagonally (indirect).
+//! Lookups normalise the position by translation and the eight symmetries of
+//! the square, so a game started anywhere and in any orientation finds its line.
+
+use crate::rng::Rng;
+use crate::{Color, Game, Pt};
+
+/// Book lines; each prefix of a line is a position and the next entry a reply.
+const LINES: &[&[(i32, i32)]] = &[
+    // Direct: White adjacent.
+    &[(0, 0), (0, -1), (1, -1), (-1, 1)],
+    &[(0, 0), (0, -1), (1, -1), (2, -2)],
+    &[(0, 0), (0, -1), (1, -1), (1, 0)],
+    &[(0, 0), (0, -1), (1, 0), (-1, 0)],
+    &[(0, 0), (0, -1), (1, 0), (2, 0)],
+    &[(0, 0), (0, -1), (1, -2), (1, -1)],
+    // Indirect: White diagonal.
+    &[(0, 0), (1, -1), (1, 0), (-1, 0)],
+    &[(0, 0), (1, -1), (1, 0), (2, 0)],
+    &[(0, 0), (1, -1), (2, 0), (1, 0)],
+    &[(0, 0), (1, -1), (1, 1), (2, 2)],
+    &[(0, 0), (1, -1), (1, 1), (-1, -1)],
+];
+
+/// Plies after which the book is no longer consulted.
+const MAX_PLY: usize = 3;
+
+/// A book reply for the side to move, chosen at random among the known ones.
+pub(crate) fn lookup(game: &Game, rng: &mut Rng) -> Option<Pt> {
+    let r = game.rules;
+    if r.win_len != 5 || r.captures || r.stones_per_turn != 1 || game.moves.len() > MAX_PLY {
+        return None;
+    }
+    let Some(&anchor) = game.moves.first() else {
+        return Some(Pt::new(0, 0)).filter(|&p| game.playable(p));
+    };
+    let stones: Vec<(Pt, Color)> = game.moves.iter().map(|&p| (p, game.cells[&p])).collect();
+    let (key, k) = canonical(&stones, anchor);
+
+    let mut replies = Vec::new();
+    for line in LINES {
+        let line: Vec<Pt> = line.iter().map(|&(x, y)| Pt::new(x, y)).collect();
+        let n = game.moves.len();
+        if line.len() <= n {
+            continue;
+        }
+        let prefix: Vec<(Pt, Color)> = line[..n]
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| (p, [Color::Black, Color::White][i % 2]))
+            .collect();
+        let (line_key, line_k) = canonical(&prefix, line[0]);
+        if line_key == key {
+            let reply = transform(line[n], line_k);
+            if !replies.contains(&reply) {
+                replies.push(reply);
+            }
+        }
+    }
+    if replies.is_empty() {
+        return None;
+    }
+    let q = inverse(replies[rng.below(replies.len())], k);
+    Some(q.add(anchor.x, anchor.y)).filter(|&p| game.playable(p))
+}
+
+type Key = Vec<(i32, i32, u8)>;
+
+/// The smallest image of `stones` (translated so `anchor` is the origin) under
+/// the eight symmetries, and which symmetry produced it.
+fn canonical(stones: &[(Pt, Color)], anchor: Pt) -> (Key, usize) {
+    (0..8)
+        .map(|k| {
+            let mut key: Key = stones
+                .iter()
+                .map(|&(p, c)| {
+                    let q = transform(p.add(-anchor.x, -anchor.y), k);
+                    (q.x, q.y, c as u8)
+                })
+                .collect();
+            key.sort_unstable();
+            (key, k)
+        })
+        .min()
+        .unwrap()
+}
+
+/// Symmetry `k` of the square: `k % 4` quarter turns, mirrored first when `k >= 4`.
+fn transform(p: Pt, k: usize) -> Pt {
+    let x = if k >= 4 { -p.x } else { p.x };
+    match k % 4 {
+        0 => Pt::new(x, p.y),
+        1 => Pt::new(-p.y, x),
+        2 => Pt::new(-x, -p.y),
+        _ => Pt::new(p.y, -x),
+    }
+}
+
+fn inverse(p: Pt, k: usize) -> Pt {
+    let back = transform(p, (4 - k % 4) % 4);
+    if k >= 4 {
+        Pt::new(-back.x, back.y)
+    } else {
+        back
+    }
+}
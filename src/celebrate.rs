@@ -0,0 +1,113 @@
+//! The end-of-game flourish: confetti falling over the board, a pulse on the
+//! winning line and the winner's message fading in. Everything is a function
+//! of the time since the win, so drawing a frame needs no stepping.
+
+use web_sys::CanvasRenderingContext2d;
+
+use crate::rng::Rng;
+
+/// How long the confetti falls and the winning line pulses.
+pub(crate) const CELEBRATION_MS: f64 = 3600.0;
+/// How long the winner's message takes to fade in.
+pub(crate) const MESSAGE_FADE_MS: f64 = 450.0;
+/// One beat of the winning line's pulse.
+const PULSE_MS: f64 = 600.0;
+
+const CONFETTI: usize = 120;
+const CONFETTI_COLORS: [&str; 6] = [
+    "#f87171", "#fbbf24", "#34d399", "#38bdf8", "#a78bfa", "#f472b6",
+];
+
+/// One flake: where across the screen it starts (0 to 1), when it sets off and
+/// how long it takes to fall (ms), its side-to-side sway, size, spin and colour.
+struct Flake {
+    x: f64,
+    delay_ms: f64,
+    fall_ms: f64,
+    sway_px: f64,
+    sway_ms: f64,
+    phase: f64,
+    size: f64,
+    spin_ms: f64,
+    color: &'static str,
+}
+
+pub(crate) struct Celebration {
+    start_ms: f64,
+    flakes: Vec<Flake>,
+}
+
+impl Celebration {
+    pub(crate) fn new(now_ms: f64) -> Celebration {
+        let mut rng = Rng::from_entropy();
+        let mut unit = || (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        let flakes = (0..CONFETTI)
+            .map(|i| Flake {
+                x: unit(),
+                delay_ms: unit() * 700.0,
+                fall_ms: 1800.0 + unit() * 1100.0,
+                sway_px: 8.0 + unit() * 22.0,
+                sway_ms: 500.0 + unit() * 700.0,
+                phase: unit() * std::f64::consts::TAU,
+                size: 5.0 + unit() * 5.0,
+                spin_ms: 250.0 + unit() * 500.0,
+                color: CONFETTI_COLORS[i % CONFETTI_COLORS.len()],
+            })
+            .collect();
+        Celebration {
+            start_ms: now_ms,
+            flakes,
+        }
+    }
+
+    fn elapsed(&self, now_ms: f64) -> f64 {
+        (now_ms - self.start_ms).max(0.0)
+    }
+
+    /// Whether anything is still moving, so frames must keep being drawn.
+    pub(crate) fn running(&self, now_ms: f64) -> bool {
+        self.elapsed(now_ms) < CELEBRATION_MS
+    }
+
+    /// How far the winner's message has faded in, from 0 to 1.
+    pub(crate) fn message_alpha(&self, now_ms: f64) -> f64 {
+        (self.elapsed(now_ms) / MESSAGE_FADE_MS).min(1.0)
+    }
+
+    /// How strongly the winning line is swelling right now, from 0 to 1; the
+    /// beats die away over the celebration.
+    pub(crate) fn pulse(&self, now_ms: f64) -> f64 {
+        let t = self.elapsed(now_ms);
+        if t >= CELEBRATION_MS {
+            return 0.0;
+        }
+        let beat = 0.5 - 0.5 * (t / PULSE_MS * std::f64::consts::TAU).cos();
+        beat * (1.0 - t / CELEBRATION_MS)
+    }
+
+    /// Draw the confetti over a `w`×`h` view.
+    pub(crate) fn draw(&self, ctx: &CanvasRenderingContext2d, now_ms: f64, w: f64, h: f64) {
+        let t = self.elapsed(now_ms);
+        if t >= CELEBRATION_MS {
+            return;
+        }
+        for f in &self.flakes {
+            let age = t - f.delay_ms;
+            if age < 0.0 || age > f.fall_ms {
+                continue;
+            }
+            let y = -f.size + (h + 2.0 * f.size) * age / f.fall_ms;
+            let x = f.x * w + f.sway_px * (f.phase + age / f.sway_ms).sin();
+            // A turning strip of paper: it narrows as it spins edge-on.
+            let turn = (age / f.spin_ms * std::f64::consts::TAU).cos();
+            ctx.save();
+            let _ = ctx.translate(x, y);
+            let _ = ctx.rotate(f.phase + age / f.spin_ms);
+            ctx.set_fill_style_str(f.color);
+            let fw = f.size;
+            let fh = (f.size * 0.5 * turn).abs().max(1.0);
+            ctx.fill_rect(-fw / 2.0, -fh / 2.0, fw, fh);
+            ctx.restore();
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Per-player game clocks.
+
+use crate::Color;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) enum TimeControl {
+    /// A fixed budget for the whole game.
+    Absolute { total_ms: f64 },
+    /// A starting budget plus `increment_ms` after each of the player's moves.
+    Fischer { base_ms: f64, increment_ms: f64 },
+}
+
+impl TimeControl {
+    /// Presets cycled from the keyboard: 1+0, 3+2, 5+0, 10+5.
+    pub(crate) const PRESETS: [TimeControl; 4] = [
+        TimeControl::Absolute { total_ms: 60_000.0 },
+        TimeControl::Fischer {
+            base_ms: 180_000.0,
+            increment_ms: 2_000.0,
+        },
+        TimeControl::Absolute {
+            total_ms: 300_000.0,
+        },
+        TimeControl::Fischer {
+            base_ms: 600_000.0,
+            increment_ms: 5_000.0,
+        },
+    ];
+
+    /// Parse `"<minutes>"` (absolute) or `"<minutes>+<increment seconds>"` (Fischer).
+    pub(crate) fn parse(text: &str) -> Option<TimeControl> {
+        let (base, inc) = match text.split_once('+') {
+            Some((b, i)) => (b, Some(i)),
+            None => (text, None),
+        };
+        let base_ms = base.trim().parse::<f64>().ok().filter(|m| *m > 0.0)? * 60_000.0;
+        match inc {
+            None => Some(TimeControl::Absolute { total_ms: base_ms }),
+            Some(i) => {
+                let increment_ms = i.trim().parse::<f64>().ok().filter(|s| *s >= 0.0)? * 1_000.0;
+                Some(TimeControl::Fischer {
+                    base_ms,
+                    increment_ms,
+                })
+            }
+        }
+    }
+
+    fn start_ms(self) -> f64 {
+        match self {
+            TimeControl::Absolute { total_ms } => total_ms,
+            TimeControl::Fischer { base_ms, .. } => base_ms,
+        }
+    }
+
+    fn increment_ms(self) -> f64 {
+        match self {
+            TimeControl::Absolute { .. } => 0.0,
+            TimeControl::Fischer { increment_ms, .. } => increment_ms,
+        }
+    }
+}
+
+pub(crate) struct Clock {
+    control: TimeControl,
+    /// Remaining time for Black and White.
+    remaining_ms: [f64; 2],
+    last_tick_ms: Option<f64>,
+}
+
+fn slot(c: Color) -> usize {
+    match c {
+        Color::Black => 0,
+        Color::White => 1,
+    }
+}
+
+impl Clock {
+    pub(crate) fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            remaining_ms: [control.start_ms(); 2],
+            last_tick_ms: None,
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        *self = Self::new(self.control);
+    }
+
+    pub(crate) fn remaining_ms(&self, c: Color) -> f64 {
+        self.remaining_ms[slot(c)].max(0.0)
+    }
+
+    /// Count down `running`'s time since the previous tick (nothing runs while `None`).
+    /// Returns the side whose flag fell, if any.
+    pub(crate) fn tick(&mut self, now_ms: f64, running: Option<Color>) -> Option<Color> {
+        let elapsed = self.last_tick_ms.map_or(0.0, |t| now_ms - t);
+        self.last_tick_ms = Some(now_ms);
+        let c = running?;
+        self.remaining_ms[slot(c)] -= elapsed;
+        (self.remaining_ms[slot(c)] <= 0.0).then_some(c)
+    }
+
+    /// Credit the increment after `mover` completes a move.
+    pub(crate) fn on_move(&mut self, mover: Color) {
+        self.remaining_ms[slot(mover)] += self.control.increment_ms();
+    }
+}
+
+/// `m:ss`, or `s.t` under ten seconds.
+pub(crate) fn format_ms(ms: f64) -> String {
+    if ms < 10_000.0 {
+        format!("{:.1}", (ms / 100.0).floor() / 10.0)
+    } else {
+        let s = (ms / 1000.0).ceil() as i64;
+        format!("{}:{:02}", s / 60, s % 60)
+    }
+}
@@ -0,0 +1,258 @@
+//! Compact text encoding of a move list, used for the shareable URL hash and
+//! for positions copied to the clipboard.
+//!
+//! Each coordinate is zigzag-encoded into a LEB128 varint, and the byte stream
+//! is written as unpadded URL-safe base64. Games near the origin cost about
+//! 2–3 characters per move.
+//!
+//! A game link's hash puts the rules before the moves, as
+//! `#v=<variant>&w=<win length>&b=<board size>&m=<moves>`; each part is left
+//! out at its default (standard gomoku, the variant's own win length, the
+//! infinite board, no moves), so a standard game's link is just `#m=…`.
+
+use crate::{Bounds, Pt, Rules, Variant};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub(crate) fn encode_moves(moves: &[Pt]) -> String {
+    let mut bytes = Vec::with_capacity(moves.len() * 2);
+    for p in moves {
+        push_varint(&mut bytes, zigzag(p.x));
+        push_varint(&mut bytes, zigzag(p.y));
+    }
+    base64_encode(&bytes)
+}
+
+/// Inverse of [`encode_moves`]; `None` if the text is not a well-formed move list.
+pub(crate) fn decode_moves(text: &str) -> Option<Vec<Pt>> {
+    let bytes = base64_decode(text)?;
+    let mut it = bytes.into_iter();
+    let mut moves = Vec::new();
+    while let Some(x) = read_varint(&mut it)? {
+        let y = read_varint(&mut it)??;
+        moves.push(Pt::new(unzigzag(x), unzigzag(y)));
+    }
+    Some(moves)
+}
+
+/// A game link's hash, without the `#`; empty for a standard game with no moves.
+pub(crate) fn encode_link(rules: &Rules, moves: &[Pt]) -> String {
+    let variant = Variant::of(*rules);
+    let mut parts = Vec::new();
+    if variant != Variant::Standard {
+        parts.push(format!("v={}", variant.name()));
+    }
+    if rules.win_len != variant.apply(Rules::default()).win_len {
+        parts.push(format!("w={}", rules.win_len));
+    }
+    if let Some(b) = rules.bounds {
+        parts.push(format!("b={}", b.size()));
+    }
+    if !moves.is_empty() {
+        parts.push(format!("m={}", encode_moves(moves)));
+    }
+    parts.join("&")
+}
+
+/// Inverse of [`encode_link`]; `None` if any part is missing its value or
+/// not one it writes. The rules come with the default weights.
+pub(crate) fn decode_link(hash: &str) -> Option<(Rules, Vec<Pt>)> {
+    let (mut variant, mut win_len, mut bounds, mut moves) = (Variant::Standard, None, None, None);
+    for part in hash.split('&').filter(|part| !part.is_empty()) {
+        match part.split_once('=')? {
+            ("v", name) => variant = Variant::parse(name)?,
+            ("w", n) => win_len = Some(n.parse().ok()?),
+            ("b", n) => bounds = Some(Bounds::square(n.parse().ok()?)),
+            ("m", code) => moves = Some(decode_moves(code)?),
+            _ => return None,
+        }
+    }
+    let rules = variant.apply(Rules::default());
+    let rules = Rules {
+        win_len: win_len.unwrap_or(rules.win_len),
+        bounds,
+        ..rules
+    };
+    Some((rules.validated(), moves.unwrap_or_default()))
+}
+
+/// Marks a copied position, so a paste can tell it from other text.
+const POSITION_PREFIX: &str = "gomoku:";
+
+/// A position as text for chat or a bug report: `gomoku:` and what its game
+/// link's hash holds.
+pub(crate) fn encode_position(rules: &Rules, moves: &[Pt]) -> String {
+    format!("{POSITION_PREFIX}{}", encode_link(rules, moves))
+}
+
+/// Inverse of [`encode_position`], which also takes a game link (`…#…`).
+/// Text copied before positions kept their rules holds just the moves, and
+/// comes back under `legacy`.
+pub(crate) fn decode_position(text: &str, legacy: Rules) -> Option<(Rules, Vec<Pt>)> {
+    let text = text.trim();
+    match text.strip_prefix(POSITION_PREFIX) {
+        Some(code) if !code.is_empty() && !code.contains('=') => {
+            Some((legacy, decode_moves(code)?))
+        }
+        Some(hash) => decode_link(hash),
+        None => match text.split_once('#')? {
+            (_, "") => None,
+            (_, hash) => decode_link(hash),
+        },
+    }
+}
+
+fn zigzag(v: i32) -> u32 {
+    ((v << 1) ^ (v >> 31)) as u32
+}
+
+fn unzigzag(v: u32) -> i32 {
+    ((v >> 1) as i32) ^ -((v & 1) as i32)
+}
+
+fn push_varint(out: &mut Vec<u8>, mut v: u32) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+/// `Some(None)` at a clean end of input, `None` on a truncated or oversized varint.
+fn read_varint(it: &mut impl Iterator<Item = u8>) -> Option<Option<u32>> {
+    let mut v = 0u32;
+    for (i, shift) in (0..35).step_by(7).enumerate() {
+        let Some(b) = it.next() else {
+            return if i == 0 { Some(None) } else { None };
+        };
+        // The fifth byte holds just the top four bits.
+        if shift == 28 && b > 0x0f {
+            return None;
+        }
+        v |= ((b & 0x7f) as u32) << shift;
+        if b & 0x80 == 0 {
+            return Some(Some(v));
+        }
+    }
+    None
+}
+
+pub(crate) fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+        }
+    }
+    out
+}
+
+pub(crate) fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for c in text.bytes() {
+        let v = ALPHABET.iter().position(|&a| a == c)? as u32;
+        acc = (acc << 6) | v;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_carry_their_rules() {
+        let moves = [Pt::new(0, 0), Pt::new(1, -1)];
+        let standard = Rules::default();
+        let rules = [
+            standard,
+            Rules {
+                bounds: Some(Bounds::square(15)),
+                win_len: 6,
+                ..standard
+            },
+            Variant::Pente.apply(standard),
+            Variant::Connect6.apply(standard),
+        ];
+        for rules in rules {
+            let back = decode_link(&encode_link(&rules, &moves));
+            assert_eq!(back, Some((rules, moves.to_vec())));
+            let back = decode_link(&encode_link(&rules, &[]));
+            assert_eq!(back, Some((rules, Vec::new())));
+        }
+        assert_eq!(encode_link(&standard, &[]), "");
+        assert!(encode_link(&standard, &moves).starts_with("m="));
+        assert_eq!(decode_link("v=renju"), None);
+        assert_eq!(decode_link("q=1"), None);
+    }
+
+    #[test]
+    fn positions_keep_their_rules() {
+        let moves = vec![Pt::new(0, 0), Pt::new(2, 3), Pt::new(-1, 4)];
+        let rules = Variant::Connect6.apply(Rules::default());
+        let legacy = Variant::Pente.apply(Rules::default());
+        let text = encode_position(&rules, &moves);
+        assert_eq!(decode_position(&text, legacy), Some((rules, moves.clone())));
+        let link = format!("https://example.org/gomoku/#{}", encode_link(&rules, &moves));
+        assert_eq!(decode_position(&link, legacy), Some((rules, moves.clone())));
+        let old = format!("gomoku:{}", encode_moves(&moves));
+        assert_eq!(decode_position(&old, legacy), Some((legacy, moves)));
+        assert_eq!(decode_position("see issue #12", legacy), None);
+    }
+
+    #[test]
+    fn moves_round_trip_far_from_the_origin() {
+        let moves = [
+            Pt::new(0, 0),
+            Pt::new(-1, 1),
+            Pt::new(-64, 63),
+            Pt::new(-100_000, 250_000),
+            Pt::new(i32::MIN, i32::MAX),
+        ];
+        assert_eq!(decode_moves(&encode_moves(&moves)), Some(moves.to_vec()));
+    }
+
+    #[test]
+    fn empty_input_is_no_moves() {
+        assert_eq!(encode_moves(&[]), "");
+        assert_eq!(decode_moves(""), Some(Vec::new()));
+    }
+
+    #[test]
+    fn truncated_input_is_refused() {
+        // A continuation bit with nothing after it.
+        assert_eq!(decode_moves(&base64_encode(&[0x80])), None);
+        // An x with no y.
+        assert_eq!(decode_moves(&base64_encode(&[0x02])), None);
+        assert_eq!(decode_moves(&base64_encode(&[0x02, 0x04, 0x06])), None);
+    }
+
+    #[test]
+    fn oversized_varints_are_refused() {
+        let max = [0xff, 0xff, 0xff, 0xff, 0x0f];
+        assert_eq!(read_varint(&mut max.into_iter()), Some(Some(u32::MAX)));
+        let high_bits = [0xff, 0xff, 0xff, 0xff, 0x1f];
+        assert_eq!(read_varint(&mut high_bits.into_iter()), None);
+        let six_bytes = [0x80, 0x80, 0x80, 0x80, 0x80, 0x00];
+        assert_eq!(read_varint(&mut six_bytes.into_iter()), None);
+    }
+
+    #[test]
+    fn bad_base64_is_refused() {
+        assert_eq!(base64_decode("AB+C"), None);
+        assert_eq!(base64_decode("AB=="), None);
+        assert_eq!(decode_moves("m n"), None);
+        let bytes = b"any bytes \x00\xff".to_vec();
+        assert_eq!(base64_decode(&base64_encode(&bytes)), Some(bytes));
+    }
+}
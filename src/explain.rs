@@ -0,0 +1,127 @@
+//! Plain-language reasons for the AI's moves, shown in the HUD for learners.
+//!
+//! A move is explained by the `score_point` terms that made it: each line
+//! through the point is named by the shape it makes (or blocks), and the
+//! heaviest shapes are listed first.
+
+use crate::i18n::{t, tf, Msg};
+use crate::patterns::{self, Shape};
+use crate::{Color, Game, Pt};
+
+/// How the AI came to its move.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Source {
+    Book,
+    /// A slip allowed by the handicap.
+    Handicap,
+    /// The first move of a proven win, taking this many of the AI's turns in
+    /// all.
+    ForcedWin { turns: u32 },
+    /// The backend's search, or the heuristic when it found nothing.
+    Search,
+}
+
+/// One sentence on why `who` plays `p` in `game`, chosen as `source` says.
+pub(crate) fn explain(game: &Game, p: Pt, who: Color, source: Source) -> String {
+    let reasons = reasons(game, p, who);
+    let and = |a: String, b: String| tf(Msg::And, &[&a, &b]);
+    let because = match reasons.as_slice() {
+        [] => None,
+        [(v, s)] => Some(tf(*v, &[s])),
+        [(v1, s1), (v2, s2), ..] if v1 == v2 => Some(tf(*v1, &[&and(s1.clone(), s2.clone())])),
+        [(v1, s1), (v2, s2), ..] => Some(and(tf(*v1, &[s1]), tf(*v2, &[s2]))),
+    };
+    match (source, because) {
+        (Source::Book, _) => t(Msg::ExplainBook),
+        (Source::Handicap, _) => t(Msg::ExplainHandicap),
+        (Source::ForcedWin { .. }, Some(b)) => tf(Msg::ExplainForcedWinBecause, &[&b]),
+        (Source::ForcedWin { .. }, None) => t(Msg::ExplainForcedWin),
+        (Source::Search, Some(b)) => tf(Msg::ExplainBecause, &[&b]),
+        (Source::Search, None) => t(Msg::ExplainOpenSpace),
+    }
+}
+
+/// The two heaviest named terms of `score_point(p, who)` as (verb, shape); the
+/// verb is a message taking the shape.
+fn reasons(game: &Game, p: Pt, who: Color) -> Vec<(Msg, String)> {
+    let n = game.rules.win_len;
+    // (verb, shape, total weight, count) per distinct shape.
+    let mut terms: Vec<(Msg, String, i32, u32)> = Vec::new();
+    let mut add = |verb: Msg, shape: String, weight: i32| match terms
+        .iter_mut()
+        .find(|t| t.0 == verb && t.1 == shape)
+    {
+        Some(t) => {
+            t.2 += weight;
+            t.3 += 1;
+        }
+        None => terms.push((verb, shape, weight, 1)),
+    };
+    let w = &game.rules.weights;
+    for (mine, theirs) in patterns::shapes(game, p, who) {
+        if mine == Shape::Five {
+            add(Msg::Completes, tf(Msg::InARow, &[&number(n)]), w.attack(mine));
+        } else if let Some(name) = name(mine, n) {
+            add(Msg::Makes, name, w.attack(mine));
+        }
+        // Named by what the opponent has now, one stone less than they would make here.
+        let blocked = match theirs {
+            Shape::Five => Some(tf(Msg::ClosedShape, &[&number(n - 1)])),
+            Shape::OpenFour => Some(tf(Msg::OpenShape, &[&number(n - 2)])),
+            Shape::Four => Some(tf(Msg::ClosedShape, &[&number(n - 2)])),
+            Shape::OpenThree => Some(tf(Msg::OpenShape, &[&number(n - 3)])),
+            Shape::Three => Some(tf(Msg::ClosedShape, &[&number(n - 3)])),
+            _ => None,
+        };
+        if let Some(name) = blocked.filter(|_| w.defence(theirs) > 0) {
+            add(Msg::Blocks, name, w.defence(theirs));
+        }
+    }
+    if game.rules.captures {
+        let (take, deny) = game.capture_weights(p, who);
+        if take > 0 {
+            add(Msg::CapturesShape, t(Msg::APair), take);
+        }
+        if deny > 0 {
+            add(Msg::Saves, t(Msg::PairFromCapture), deny);
+        }
+    }
+    terms.sort_by_key(|t| std::cmp::Reverse(t.2));
+    terms
+        .into_iter()
+        .take(MAX_REASONS)
+        .map(|(verb, shape, _, count)| match count {
+            1 => (verb, shape),
+            n => (verb, format!("{shape} (×{n})")),
+        })
+        .collect()
+}
+
+/// Reasons listed per move, at most.
+const MAX_REASONS: usize = 2;
+
+/// "an open three", "a four": `shape` as it reads toward a `win_len` win.
+fn name(shape: Shape, win_len: i32) -> Option<String> {
+    let (short, open) = match shape {
+        Shape::OpenFour => (1, true),
+        Shape::Four => (1, false),
+        Shape::OpenThree => (2, true),
+        Shape::Three => (2, false),
+        Shape::OpenTwo => (3, true),
+        Shape::Two => (3, false),
+        _ => return None,
+    };
+    let count = number(win_len - short);
+    Some(tf(if open { Msg::OpenShape } else { Msg::ClosedShape }, &[&count]))
+}
+
+fn number(n: i32) -> String {
+    match n {
+        2 => t(Msg::Two),
+        3 => t(Msg::Three),
+        4 => t(Msg::Four),
+        5 => t(Msg::Five),
+        6 => t(Msg::Six),
+        n => n.to_string(),
+    }
+}
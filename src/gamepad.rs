@@ -0,0 +1,110 @@
+//! Gamepad input through the Gamepad API, read once per frame.
+//!
+//! Only the first connected pad is used, through the browser's "standard"
+//! mapping: the left stick pans, the right stick (up/down) or the triggers
+//! zoom, the d-pad steps the cell cursor (repeating while held), A places and
+//! B hides the cursor.
+
+use wasm_bindgen::JsCast;
+use web_sys::{window, GamepadButton};
+
+/// Stick travel ignored around the centre, where worn sticks rest.
+const DEADZONE: f64 = 0.2;
+/// Pan speed at full stick, in screen px per ms.
+const PAN_PX_PER_MS: f64 = 0.8;
+/// Zoom rate at full stick or trigger, as a log factor per ms.
+const ZOOM_PER_MS: f64 = 0.002;
+/// A held d-pad direction repeats after this long, then this often.
+const REPEAT_DELAY_MS: f64 = 350.0;
+const REPEAT_MS: f64 = 110.0;
+/// Longest frame gap allowed for, so a stalled tab does not fling the camera.
+const MAX_FRAME_MS: f64 = 50.0;
+
+const A: usize = 0;
+const B: usize = 1;
+const LEFT_TRIGGER: usize = 6;
+const RIGHT_TRIGGER: usize = 7;
+const DPAD: [(usize, (i32, i32)); 4] = [(12, (0, -1)), (13, (0, 1)), (14, (-1, 0)), (15, (1, 0))];
+
+/// What the pad asks for this frame.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PadFrame {
+    /// Camera movement in screen px (the stick's direction, so the view moves that way).
+    pub(crate) pan: (f64, f64),
+    /// Factor to multiply the zoom by; 1 for none.
+    pub(crate) zoom: f64,
+    /// Cursor steps from the d-pad, in order.
+    pub(crate) steps: Vec<(i32, i32)>,
+    pub(crate) place: bool,
+    pub(crate) cancel: bool,
+}
+
+/// Button state carried between frames for edge detection and repeats.
+#[derive(Default)]
+pub(crate) struct Gamepad {
+    prev: Vec<bool>,
+    repeat_at: f64,
+    last_ms: Option<f64>,
+}
+
+impl Gamepad {
+    /// The first connected pad's input since the last call, or `None` without one.
+    pub(crate) fn poll(&mut self, now_ms: f64) -> Option<PadFrame> {
+        let pads = window()?.navigator().get_gamepads().ok()?;
+        let Some(pad) = pads
+            .iter()
+            .filter_map(|p| p.dyn_into::<web_sys::Gamepad>().ok())
+            .find(|p| p.connected())
+        else {
+            self.last_ms = None;
+            return None;
+        };
+        let dt = now_ms - self.last_ms.unwrap_or(now_ms);
+        let dt = dt.clamp(0.0, MAX_FRAME_MS);
+        self.last_ms = Some(now_ms);
+
+        let axes = pad.axes();
+        let axis = |i: u32| stick(axes.get(i).as_f64().unwrap_or(0.0));
+        let buttons: Vec<(bool, f64)> = pad
+            .buttons()
+            .iter()
+            .filter_map(|b| b.dyn_into::<GamepadButton>().ok())
+            .map(|b| (b.pressed(), b.value()))
+            .collect();
+        let pressed = |i: usize| buttons.get(i).is_some_and(|b| b.0);
+        let value = |i: usize| buttons.get(i).map_or(0.0, |b| b.1);
+        let was = |i: usize| self.prev.get(i).copied().unwrap_or(false);
+        let edge = |i: usize| pressed(i) && !was(i);
+
+        let mut frame = PadFrame {
+            pan: (axis(0) * PAN_PX_PER_MS * dt, axis(1) * PAN_PX_PER_MS * dt),
+            zoom: ((value(RIGHT_TRIGGER) - value(LEFT_TRIGGER) - axis(3)) * ZOOM_PER_MS * dt).exp(),
+            place: edge(A),
+            cancel: edge(B),
+            ..PadFrame::default()
+        };
+        let mut held = false;
+        for (i, step) in DPAD {
+            if edge(i) {
+                frame.steps.push(step);
+                self.repeat_at = now_ms + REPEAT_DELAY_MS;
+            } else if pressed(i) {
+                held = true;
+                if now_ms >= self.repeat_at {
+                    frame.steps.push(step);
+                }
+            }
+        }
+        if held && now_ms >= self.repeat_at {
+            self.repeat_at = now_ms + REPEAT_MS;
+        }
+        self.prev = buttons.iter().map(|b| b.0).collect();
+        Some(frame)
+    }
+}
+
+/// A stick axis with its dead zone cut out and the rest rescaled to `-1..=1`.
+fn stick(v: f64) -> f64 {
+    let live = (v.abs() - DEADZONE).max(0.0) / (1.0 - DEADZONE);
+    live.min(1.0).copysign(v)
+}
@@ -0,0 +1,78 @@
+//! Vibration feedback through `navigator.vibrate`, for touch play: a tap for
+//! every stone, a buzz for a move that cannot be played, and longer patterns
+//! for a win and a loss.
+//!
+//! Browsers without vibration ignore it. The on/off switch is kept in
+//! `localStorage`, and can be set from JS with `setHaptics`.
+
+use std::cell::Cell;
+
+use wasm_bindgen::prelude::*;
+use web_sys::{window, Storage};
+
+const STORAGE_KEY: &str = "gomoku.haptics";
+
+/// Something worth feeling.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Buzz {
+    Stone,
+    Invalid,
+    Win,
+    Loss,
+}
+
+impl Buzz {
+    /// Alternating vibration and pause lengths, in ms.
+    fn pattern(self) -> &'static [u32] {
+        match self {
+            Buzz::Stone => &[12],
+            Buzz::Invalid => &[40, 40, 40],
+            Buzz::Win => &[80, 60, 80, 60, 200],
+            Buzz::Loss => &[300, 100, 300],
+        }
+    }
+}
+
+thread_local! {
+    /// `None` until first read from storage.
+    static ENABLED: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+fn storage() -> Option<Storage> {
+    window()?.local_storage().ok().flatten()
+}
+
+/// Whether vibration is on; it is unless turned off.
+pub(crate) fn enabled() -> bool {
+    ENABLED.with(|e| {
+        *e.get().get_or_insert_with(|| {
+            let stored = storage().and_then(|s| s.get_item(STORAGE_KEY).ok().flatten());
+            stored.as_deref() != Some("off")
+        })
+    })
+}
+
+/// Flip the vibration switch and remember it.
+pub(crate) fn toggle() {
+    set_haptics(!enabled());
+}
+
+/// Turn vibration on or off, and remember it.
+#[wasm_bindgen(js_name = setHaptics)]
+pub fn set_haptics(on: bool) {
+    ENABLED.with(|e| e.set(Some(on)));
+    if let Some(s) = storage() {
+        let _ = s.set_item(STORAGE_KEY, if on { "on" } else { "off" });
+    }
+}
+
+/// Vibrate with `kind`'s pattern, if vibration is on.
+pub(crate) fn buzz(kind: Buzz) {
+    if !enabled() {
+        return;
+    }
+    if let Some(w) = window() {
+        let pattern: js_sys::Array = kind.pattern().iter().map(|&ms| JsValue::from(ms)).collect();
+        w.navigator().vibrate_with_pattern(&pattern);
+    }
+}
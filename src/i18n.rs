@@ -0,0 +1,536 @@
+//! Translated UI text: every string the game shows or announces, looked up in
+//! the player's language.
+//!
+//! English, French, Chinese and Japanese are built in. The locale follows
+//! `navigator.languages` (an exact tag such as `zh-TW` first, then its primary
+//! language) unless `?lang=` or `setLocale` picks one, and more can be added
+//! from JS with `registerLocale`. Messages missing from a locale fall back to
+//! English. `{0}`, `{1}`, … in a message are replaced by its arguments.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+use web_sys::window;
+
+use crate::Color;
+
+/// Tags of the built-in locales, in the order of each message's texts.
+const BUILT_IN: [&str; 4] = ["en", "fr", "zh", "ja"];
+
+macro_rules! messages {
+    ($($msg:ident $key:literal [$($text:literal),*],)*) => {
+        /// A piece of UI text.
+        #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+        pub(crate) enum Msg {
+            $($msg,)*
+        }
+
+        impl Msg {
+            const ALL: &'static [Msg] = &[$(Msg::$msg,)*];
+
+            /// The message's name in `registerLocale` tables.
+            fn key(self) -> &'static str {
+                match self {
+                    $(Msg::$msg => $key,)*
+                }
+            }
+
+            /// The message in each of `BUILT_IN`.
+            fn built_in(self) -> [&'static str; 4] {
+                match self {
+                    $(Msg::$msg => [$($text),*],)*
+                }
+            }
+        }
+    };
+}
+
+messages! {
+    Black "black" ["Black", "Noir", "黑方", "黒"],
+    White "white" ["White", "Blanc", "白方", "白"],
+
+    // Game over
+    GameWon "gameWon" ["{0} wins!", "{0} gagne !", "{0}获胜！", "{0}の勝ち！"],
+    GameWonOnTime "gameWonOnTime"
+        ["{0} wins on time!", "{0} gagne au temps !", "{0}因对方超时获胜！", "時間切れで{0}の勝ち！"],
+    GameWonByCaptures "gameWonByCaptures"
+        ["{0} wins by captures!", "{0} gagne aux prises !", "{0}以吃子获胜！", "石取りで{0}の勝ち！"],
+    WinsAs "winsAs" ["{0} wins as {1}!", "{0} gagne avec {1} !", "{0} 执{1}获胜！", "{0}が{1}で勝ち！"],
+    NextGame "nextGame" ["Next game starting…", "Partie suivante…", "下一局即将开始…", "次の対局を開始…"],
+    PlayAgain "playAgain"
+        ["Click twice or press R twice to play again", "Cliquez ou appuyez sur R deux fois pour rejouer",
+         "点击两次或按两次 R 再来一局", "2回クリックするか R キーを2回押してもう一局"],
+    ConfirmRestartKey "confirmRestartKey"
+        ["Press {0} again for a new game", "Appuyez encore sur {0} pour une nouvelle partie",
+         "再按一次 {0} 开始新对局", "もう一度 {0} で新しい対局"],
+    ConfirmRestartTap "confirmRestartTap"
+        ["Tap again for a new game", "Touchez encore pour une nouvelle partie", "再点一次开始新对局",
+         "もう一度タップで新しい対局"],
+
+    // Controller pills and HUD buttons
+    Pill "pill" ["{0}: {1}", "{0} : {1}", "{0}：{1}", "{0}：{1}"],
+    Human "human" ["Human", "Humain", "人类", "人間"],
+    Ai "ai" ["AI", "IA", "AI", "AI"],
+    Remote "remote" ["Remote", "À distance", "远程", "リモート"],
+    RemoteOffline "remoteOffline"
+        ["Remote (offline)", "À distance (hors ligne)", "远程（离线）", "リモート（オフライン）"],
+    RemoteConnecting "remoteConnecting"
+        ["Remote (connecting)", "À distance (connexion…)", "远程（连接中）", "リモート（接続中）"],
+    Captures "captures" ["{0} caps", "{0} prises", "吃{0}对", "{0}組取り"],
+    StoneOf "stoneOf" ["stone {0}/{1}", "pierre {0}/{1}", "第{0}/{1}子", "{0}/{1}手目"],
+    WinsIn "winsIn" ["wins in {0}", "gagne en {0}", "{0}步胜", "{0}手で勝ち"],
+    RenamePrompt "renamePrompt"
+        ["Name for {0} (leave empty for none):", "Nom pour {0} (vide pour aucun) :",
+         "{0}的名字（留空则不命名）：", "{0}の名前（空欄で名前なし）："],
+    MoveCount "moveCount" ["Move {0}", "Coup {0}", "第{0}手", "{0}手目"],
+    Thinking "thinking"
+        ["Thinking… depth {0}/{1} · {2} positions", "Réflexion… profondeur {0}/{1} · {2} positions",
+         "思考中… 深度 {0}/{1} · {2} 个局面", "思考中… 深さ {0}/{1} · {2} 局面"],
+    HintButton "hintButton" ["Hint", "Indice", "提示", "ヒント"],
+    UndoButton "undoButton" ["Undo", "Annuler", "悔棋", "待った"],
+    ReplayButton "replayButton" ["Replay", "Revoir", "回放", "再生"],
+    SaveImageButton "saveImageButton" ["Save image", "Enregistrer l'image", "保存图片", "画像を保存"],
+    Recording "recording"
+        ["Recording video… move {0} of {1}", "Enregistrement vidéo… coup {0} sur {1}",
+         "正在录制视频… 第 {0} / {1} 手", "動画を記録中… {1}手中{0}手目"],
+    VideoUnsupported "videoUnsupported"
+        ["This browser cannot record video", "Ce navigateur ne peut pas enregistrer de vidéo",
+         "此浏览器无法录制视频", "このブラウザでは動画を記録できません"],
+    ShareButton "shareButton" ["Share", "Partager", "分享", "共有"],
+    ShareCaption "shareCaption"
+        ["Scan to open this game on another device",
+         "Scannez pour ouvrir la partie sur un autre appareil",
+         "扫码在其他设备上打开此对局", "スキャンして別の端末でこの対局を開く"],
+    PositionCopied "positionCopied"
+        ["Position copied", "Position copiée", "已复制局面", "局面をコピーしました"],
+    PositionPasted "positionPasted"
+        ["Position loaded", "Position chargée", "已载入局面", "局面を読み込みました"],
+    PasteInvalid "pasteInvalid"
+        ["The clipboard holds no legal position", "Le presse-papiers ne contient aucune position valide",
+         "剪贴板中没有合法的局面", "クリップボードに有効な局面がありません"],
+    SavesButton "savesButton" ["Saves", "Sauvegardes", "存档", "セーブ"],
+    SavesTitle "savesTitle" ["Saved games", "Parties sauvegardées", "已保存的对局", "保存した対局"],
+    SaveButton "saveButton" ["Save", "Sauver", "保存", "保存"],
+    LoadButton "loadButton" ["Load", "Charger", "载入", "読込"],
+    DeleteButton "deleteButton" ["Delete", "Supprimer", "删除", "削除"],
+    EmptySlot "emptySlot" ["Empty slot", "Emplacement libre", "空位", "空きスロット"],
+    SlotInfo "slotInfo" ["{0} moves · {1}", "{0} coups · {1}", "{0} 手 · {1}", "{0}手 · {1}"],
+    SaveNamePrompt "saveNamePrompt"
+        ["Name this save:", "Nom de la sauvegarde :", "为存档命名：", "セーブの名前："],
+    SaveDefaultName "saveDefaultName" ["Game {0}", "Partie {0}", "对局 {0}", "対局 {0}"],
+    DeleteSaveConfirm "deleteSaveConfirm"
+        ["Delete “{0}”?", "Supprimer « {0} » ?", "删除“{0}”？", "「{0}」を削除しますか？"],
+    GameSaved "gameSaved" ["Game saved", "Partie sauvegardée", "已保存对局", "対局を保存しました"],
+    SaveUnplayable "saveUnplayable"
+        ["That save is not legal under the current rules",
+         "Cette sauvegarde n'est pas valide avec les règles actuelles",
+         "该存档不符合当前规则", "このセーブは現在のルールでは無効です"],
+    HistoryButton "historyButton" ["History", "Historique", "历史", "履歴"],
+    HistoryTitle "historyTitle" ["Finished games", "Parties terminées", "已结束的对局", "終局した対局"],
+    HistoryLoading "historyLoading" ["Loading…", "Chargement…", "载入中…", "読み込み中…"],
+    HistoryEmpty "historyEmpty"
+        ["No finished games yet", "Aucune partie terminée", "还没有已结束的对局", "終局した対局はまだありません"],
+    HistoryPlayers "historyPlayers" ["{0} vs {1}", "{0} contre {1}", "{0} 对 {1}", "{0} 対 {1}"],
+    HistoryInfo "historyInfo"
+        ["{0} · {1} · {2} moves", "{0} · {1} · {2} coups", "{0} · {1} · {2} 手", "{0} · {1} · {2}手"],
+    LibraryTitle "libraryTitle" ["RenjuNet games", "Parties RenjuNet", "RenjuNet 对局", "RenjuNet の対局"],
+    LibraryEmpty "libraryEmpty"
+        ["No games with moves in this file", "Aucune partie avec des coups dans ce fichier",
+         "此文件中没有带着法的对局", "このファイルに手のある対局はありません"],
+    ExportButton "exportButton" ["Export", "Exporter", "导出", "書き出し"],
+    CopyLinkButton "copyLinkButton" ["Copy link", "Copier le lien", "复制链接", "リンクをコピー"],
+    MovesCopied "movesCopied" ["Move list copied", "Liste des coups copiée", "已复制着法列表", "手順をコピーしました"],
+    LinkCopied "linkCopied" ["Game link copied", "Lien de la partie copié", "已复制对局链接", "対局のリンクをコピーしました"],
+    SummaryMoves "summaryMoves" ["{0} moves in {1}", "{0} coups en {1}", "{0} 手，用时 {1}", "{0}手、{1}"],
+    SummaryAverage "summaryAverage"
+        ["Average per move: {0}", "Moyenne par coup : {0}", "每手平均：{0}", "1手あたりの平均：{0}"],
+    SummaryThreats "summaryThreats"
+        ["Threats made / stopped: {0}", "Menaces créées / parées : {0}", "制造 / 化解威胁：{0}", "作った / 防いだ脅威：{0}"],
+    RestartButton "restartButton" ["New game", "Nouvelle partie", "新对局", "新しい対局"],
+    FitButton "fitButton" ["Fit", "Tout voir", "全览", "全体"],
+    StyleButton "styleButton" ["Style: {0}", "Style : {0}", "风格：{0}", "スタイル：{0}"],
+    TimeButton "timeButton" ["Time: {0}", "Temps : {0}", "时间：{0}", "時間：{0}"],
+    Balanced "balanced" ["balanced", "équilibré", "均衡", "バランス"],
+    Aggressive "aggressive" ["aggressive", "offensif", "进攻", "攻撃的"],
+    Defensive "defensive" ["defensive", "défensif", "防守", "守備的"],
+
+    // Status lines and panels
+    LeavesWin "leavesWin"
+        ["That leaves {0} a winning move. Tap it again to play anyway.",
+         "Cela laisse un coup gagnant à {0}. Touchez encore pour jouer quand même.",
+         "这步会让{0}有致胜一手。再点一次仍然下这里。",
+         "{0}に勝ちの手が残ります。もう一度タップすると打ちます。"],
+    AiSays "aiSays" ["AI: {0}", "IA : {0}", "AI：{0}", "AI：{0}"],
+    Playing "playing" ["Playing", "Lecture", "播放中", "再生中"],
+    Paused "paused" ["Paused", "En pause", "已暂停", "一時停止"],
+    ReplayBar "replayBar"
+        ["Replay {0}/{1} · {2} {3}s/move · ←/→ step · Space play · [ ] speed · P exit",
+         "Relecture {0}/{1} · {2} {3} s/coup · ←/→ pas · Espace lecture · [ ] vitesse · P quitter",
+         "回放 {0}/{1} · {2} {3}秒/步 · ←/→ 单步 · 空格 播放 · [ ] 速度 · P 退出",
+         "再生 {0}/{1} · {2} {3}秒/手 · ←/→ コマ送り · Space 再生 · [ ] 速度 · P 終了"],
+    TopMovesFor "topMovesFor"
+        ["Top moves for {0}", "Meilleurs coups pour {0}", "{0}的最佳着法", "{0}の最善手"],
+    GameOver "gameOver"
+        ["None: the game is over.", "Aucun : la partie est finie.", "无：对局已结束。", "なし：対局は終了しました。"],
+    TournamentHeader "tournamentHeader"
+        ["Tournament · {0} played · G to stop", "Tournoi · {0} jouées · G pour arrêter",
+         "锦标赛 · 已赛 {0} 局 · 按 G 停止", "トーナメント · {0}局 · G で停止"],
+    Draws "draws" ["Draws: {0}", "Nulles : {0}", "和棋：{0}", "引き分け：{0}"],
+
+    // Help overlay
+    Controls "controls" ["Controls", "Commandes", "操作", "操作"],
+    Unbound "unbound" ["unbound", "non attribué", "未绑定", "未割り当て"],
+    TapClick "tapClick" ["Tap / click", "Toucher / clic", "点击", "タップ / クリック"],
+    LongPress "longPress" ["Long-press", "Appui long", "长按", "長押し"],
+    Drag "drag" ["Drag / middle-drag", "Glisser / clic du milieu", "拖动 / 中键拖动", "ドラッグ / 中ボタン"],
+    WheelPinch "wheelPinch" ["Wheel / pinch", "Molette / pincement", "滚轮 / 双指缩放", "ホイール / ピンチ"],
+    DoubleTap "doubleTap" ["Double-tap", "Double toucher", "双击", "ダブルタップ"],
+    HelpPlace "helpPlace" ["place a stone", "poser une pierre", "落子", "石を置く"],
+    HelpTwoTap "helpTwoTap"
+        ["two-tap placement (pick, then confirm)", "pose en deux touches (choisir, puis confirmer)",
+         "两次点击落子（选择后确认）", "2タップで置く（選んで確定）"],
+    HelpLongPress "helpLongPress"
+        ["magnifier; release to place", "loupe ; relâcher pour poser", "放大镜；松开落子", "拡大鏡。離すと置く"],
+    HelpCursor "helpCursor"
+        ["move the keyboard cursor", "déplacer le curseur clavier", "移动键盘光标", "キーボードカーソルを動かす"],
+    HelpPlaceCursor "helpPlaceCursor"
+        ["place at the cursor", "poser au curseur", "在光标处落子", "カーソルの位置に置く"],
+    HelpHideCursor "helpHideCursor" ["hide the cursor", "masquer le curseur", "隐藏光标", "カーソルを隠す"],
+    HelpPan "helpPan" ["pan", "déplacer la vue", "平移", "スクロール"],
+    HelpWheel "helpWheel"
+        ["zoom toward the pointer", "zoomer vers le pointeur", "向指针处缩放", "ポインタに向けてズーム"],
+    HelpDoubleTap "helpDoubleTap"
+        ["zoom in (Shift: out) off playable cells", "zoomer (Maj : dézoomer) hors des cases jouables",
+         "在不可落子处放大（Shift：缩小）", "置けないマスで拡大（Shift：縮小）"],
+    HelpZoom "helpZoom" ["zoom in / out", "zoomer / dézoomer", "放大 / 缩小", "拡大 / 縮小"],
+    HelpRestart "helpRestart" ["new game", "nouvelle partie", "新对局", "新しい対局"],
+    HelpUndo "helpUndo"
+        ["take back a move", "annuler un coup", "悔一步棋", "一手戻す"],
+    HelpHint "helpHint" ["hint", "indice", "提示", "ヒント"],
+    HelpReplay "helpReplay"
+        ["replay: {0} step, {1} play, {2} speed, {3} exit",
+         "relecture : {0} pas, {1} lecture, {2} vitesse, {3} quitter",
+         "回放：{0} 单步，{1} 播放，{2} 速度，{3} 退出", "再生：{0} コマ送り、{1} 再生、{2} 速度、{3} 終了"],
+    HelpAnalysis "helpAnalysis"
+        ["after a game: replay with evaluation graph", "après une partie : relecture avec courbe d'évaluation",
+         "对局后：带评估曲线的回放", "対局後：評価グラフ付きで再生"],
+    HelpCoords "helpCoords" ["coordinates", "coordonnées", "坐标", "座標"],
+    HelpMoveNumbers "helpMoveNumbers" ["move numbers", "numéros des coups", "手数", "手数"],
+    HelpHeatmap "helpHeatmap"
+        ["heatmap of candidate scores", "carte de chaleur des scores", "候选点评分热图", "候補手の評価ヒートマップ"],
+    HelpExpectedLine "helpExpectedLine"
+        ["the AI's expected line", "la suite attendue par l'IA", "AI 预期的变化", "AIの読み筋"],
+    HelpMute "helpMute" ["sound on / off", "son activé / coupé", "开关声音", "サウンドのオン / オフ"],
+    HelpZoomToFit "helpZoomToFit"
+        ["zoom to fit every stone", "zoomer sur toutes les pierres", "缩放以显示所有棋子", "全ての石が見えるようズーム"],
+    HelpRecenter "helpRecenter"
+        ["back to the last move", "revenir au dernier coup", "回到最后一步", "最後の手に戻る"],
+    HelpFullscreen "helpFullscreen" ["fullscreen on / off", "plein écran activé / désactivé", "开关全屏", "全画面のオン / オフ"],
+    HelpHaptics "helpHaptics" ["vibration on / off", "vibration activée / coupée", "开关振动", "振動のオン / オフ"],
+    HelpTopMoves "helpTopMoves" ["rank the top moves", "classer les meilleurs coups", "最佳着法排名", "最善手の順位"],
+    HelpExplain "helpExplain" ["explain AI moves", "expliquer les coups de l'IA", "解释 AI 的着法", "AIの手を解説"],
+    HelpBlunderAlerts "helpBlunderAlerts"
+        ["warn before a losing move", "avertir avant un coup perdant", "败着前提醒", "負ける手の前に警告"],
+    HelpContrast "helpContrast" ["high contrast", "contraste élevé", "高对比度", "ハイコントラスト"],
+    HelpColorScheme "helpColorScheme"
+        ["light / dark theme", "thème clair / sombre", "浅色 / 深色主题", "ライト / ダークテーマ"],
+    HelpFollow "helpFollow"
+        ["follow stones played off screen", "suivre les coups hors de l'écran", "跟随屏幕外的落子", "画面外の着手を追う"],
+    HelpStoneMarks "helpStoneMarks"
+        ["ring White's stones", "cercler les pierres blanches", "给白子加圈", "白石に輪を付ける"],
+    HelpBoard "helpBoard" ["board size", "taille du plateau", "棋盘大小", "盤の大きさ"],
+    HelpVariant "helpVariant" ["variant", "variante", "规则变体", "ルール"],
+    HelpClock "helpClock" ["clock", "pendule", "计时", "持ち時間"],
+    HelpEngine "helpEngine"
+        ["engine: alpha-beta / MCTS", "moteur : alpha-bêta / MCTS", "引擎：alpha-beta / MCTS", "エンジン：alpha-beta / MCTS"],
+    HelpStyle "helpStyle" ["AI style", "style de l'IA", "AI 风格", "AIのスタイル"],
+    HelpHandicap "helpHandicap" ["AI handicap", "handicap de l'IA", "AI 让子", "AIのハンデ"],
+    HelpTournament "helpTournament" ["engine tournament", "tournoi de moteurs", "引擎锦标赛", "エンジン同士の対局"],
+    HelpHelp "helpHelp" ["this help", "cette aide", "本帮助", "このヘルプ"],
+    HelpSettings "helpSettings" ["settings", "réglages", "设置", "設定"],
+    HelpSaveImage "helpSaveImage"
+        ["save the board as a PNG", "enregistrer le plateau en PNG", "将棋盘保存为 PNG", "盤面を PNG で保存"],
+    HelpSaveVideo "helpSaveVideo"
+        ["record the game as a video", "enregistrer la partie en vidéo", "将对局录制为视频", "対局を動画で保存"],
+    HelpCopyPaste "helpCopyPaste"
+        ["copy or paste the position", "copier ou coller la position", "复制或粘贴局面", "局面をコピー・貼り付け"],
+    HelpSaves "helpSaves"
+        ["saved games", "parties sauvegardées", "已保存的对局", "保存した対局"],
+    HelpHistory "helpHistory"
+        ["finished games", "parties terminées", "已结束的对局", "終局した対局"],
+    HelpShare "helpShare"
+        ["show the game's link as a QR code", "afficher le lien de la partie en QR code",
+         "以二维码显示对局链接", "対局のリンクを QR コードで表示"],
+    HelpCopyMoves "helpCopyMoves"
+        ["copy the move list as text", "copier la liste des coups en texte", "以文本复制着法列表",
+         "手順をテキストでコピー"],
+    HelpExportReplay "helpExportReplay"
+        ["download a replay that keeps the game's pace", "télécharger une relecture au rythme de la partie",
+         "下载保留对局节奏的回放", "対局のペースを保つリプレイをダウンロード"],
+    HelpExportPsq "helpExportPsq"
+        ["download the game as a .psq file", "télécharger la partie en fichier .psq",
+         "将对局下载为 .psq 文件", "対局を .psq ファイルでダウンロード"],
+    HelpPresentation "helpPresentation"
+        ["presentation mode", "mode présentation", "演示模式", "プレゼンテーションモード"],
+    HelpDiagnostics "helpDiagnostics"
+        ["performance diagnostics", "diagnostics de performance", "性能诊断", "パフォーマンス診断"],
+    HelpFrontierDots "helpFrontierDots"
+        ["dot the frontier cells", "pointer les cases de la frontière", "标出候补点", "候補点に点を表示"],
+
+    // Diagnostics overlay
+    DiagFrames "diagFrames"
+        ["{0} fps · render {1} ms", "{0} i/s · rendu {1} ms", "{0} 帧/秒 · 渲染 {1} 毫秒", "{0} fps · 描画 {1} ms"],
+    DiagGame "diagGame"
+        ["{0} stones · frontier {1}", "{0} pierres · frontière {1}", "{0} 子 · 候补点 {1}", "{0} 石 · 候補点 {1}"],
+    DiagMemory "diagMemory"
+        ["wasm memory {0} MiB", "mémoire wasm {0} Mio", "wasm 内存 {0} MiB", "wasm メモリ {0} MiB"],
+
+    // Settings panel
+    SettingsTitle "settingsTitle" ["Settings", "Réglages", "设置", "設定"],
+    SettingsHint "settingsHint"
+        ["Tap a setting to change it", "Touchez un réglage pour le changer", "点击设置项以更改", "項目をタップして変更"],
+    SettingTheme "settingTheme" ["Theme", "Thème", "主题", "テーマ"],
+    SettingDifficulty "settingDifficulty" ["Difficulty", "Difficulté", "难度", "難易度"],
+    SettingSound "settingSound" ["Sound", "Son", "声音", "サウンド"],
+    SettingVariant "settingVariant" ["Rules", "Règles", "规则", "ルール"],
+    SettingWheel "settingWheel" ["Mouse wheel", "Molette", "鼠标滚轮", "マウスホイール"],
+    SettingAnimations "settingAnimations" ["Animations", "Animations", "动画", "アニメーション"],
+    SettingEvalBar "settingEvalBar" ["Eval bar", "Barre d'évaluation", "形势条", "形勢バー"],
+    SettingImageSize "settingImageSize" ["Image size", "Taille d'image", "图片尺寸", "画像サイズ"],
+    ThemeAuto "themeAuto" ["Auto", "Auto", "自动", "自動"],
+    ThemeLight "themeLight" ["Light", "Clair", "浅色", "ライト"],
+    ThemeDark "themeDark" ["Dark", "Sombre", "深色", "ダーク"],
+    ThemeContrast "themeContrast" ["High contrast", "Contraste élevé", "高对比度", "ハイコントラスト"],
+    ThemeWood "themeWood" ["Wood", "Bois", "木纹", "木目"],
+    DifficultyHard "difficultyHard" ["Hard", "Difficile", "困难", "難しい"],
+    DifficultyMedium "difficultyMedium" ["Medium", "Moyen", "中等", "普通"],
+    DifficultyEasy "difficultyEasy" ["Easy", "Facile", "简单", "易しい"],
+    DifficultyBeginner "difficultyBeginner" ["Beginner", "Débutant", "入门", "入門"],
+    VariantStandard "variantStandard" ["Gomoku", "Gomoku", "五子棋", "五目並べ"],
+    VariantPente "variantPente" ["Pente", "Pente", "Pente", "ペンテ"],
+    VariantConnect6 "variantConnect6" ["Connect6", "Connect6", "六子棋", "六目並べ"],
+    WheelZoom "wheelZoom" ["Zooms", "Zoome", "缩放", "ズーム"],
+    WheelScroll "wheelScroll" ["Scrolls", "Fait défiler", "平移", "スクロール"],
+    ImageSmall "imageSmall" ["Small", "Petite", "小", "小"],
+    ImageMedium "imageMedium" ["Medium", "Moyenne", "中", "中"],
+    ImageLarge "imageLarge" ["Large", "Grande", "大", "大"],
+    On "on" ["On", "Activé", "开", "オン"],
+    Off "off" ["Off", "Désactivé", "关", "オフ"],
+
+    // Move explanations
+    ExplainBook "explainBook"
+        ["Book opening move.", "Coup d'ouverture du répertoire.", "开局库中的着法。", "定石の手。"],
+    ExplainHandicap "explainHandicap"
+        ["A weaker move, on purpose (handicap).", "Un coup plus faible, exprès (handicap).",
+         "故意下的弱着（让子）。", "わざと弱い手（ハンデ）。"],
+    ExplainForcedWinBecause "explainForcedWinBecause"
+        ["Forces a win: it {0}.", "Force la victoire : il {0}.", "必胜：{0}。", "必勝：{0}。"],
+    ExplainForcedWin "explainForcedWin" ["Forces a win.", "Force la victoire.", "必胜。", "必勝。"],
+    ExplainBecause "explainBecause" ["It {0}.", "Il {0}.", "{0}。", "{0}。"],
+    ExplainOpenSpace "explainOpenSpace"
+        ["Develops toward open space.", "Se développe vers l'espace libre.", "向空旷处发展。", "空いた所へ展開。"],
+    And "and" ["{0} and {1}", "{0} et {1}", "{0}并{1}", "{0}、{1}"],
+    Completes "completes" ["completes {0}", "complète {0}", "连成{0}", "{0}を完成"],
+    Makes "makes" ["makes {0}", "forme {0}", "形成{0}", "{0}を作る"],
+    Blocks "blocks" ["blocks {0}", "bloque {0}", "挡住{0}", "{0}を止める"],
+    CapturesShape "capturesShape" ["captures {0}", "prend {0}", "吃掉{0}", "{0}を取る"],
+    Saves "saves" ["saves {0}", "sauve {0}", "保住{0}", "{0}を守る"],
+    InARow "inARow" ["{0} in a row", "{0} à la suite", "{0}连", "{0}連"],
+    OpenShape "openShape" ["an open {0}", "un {0} ouvert", "活{0}", "開いた{0}"],
+    ClosedShape "closedShape" ["a {0}", "un {0}", "眠{0}", "止め{0}"],
+    APair "aPair" ["a pair", "une paire", "一对", "一組"],
+    PairFromCapture "pairFromCapture"
+        ["a pair from capture", "une paire de la prise", "一对免被吃", "一組を取られないよう"],
+    Two "two" ["two", "deux", "二", "二"],
+    Three "three" ["three", "trois", "三", "三"],
+    Four "four" ["four", "quatre", "四", "四"],
+    Five "five" ["five", "cinq", "五", "五"],
+    Six "six" ["six", "six", "六", "六"],
+
+    // Screen-reader announcements
+    BoardAround "boardAround" ["Board around {0}", "Plateau autour de {0}", "{0}附近的棋盘", "{0}付近の盤"],
+    StoneOn "stoneOn" ["{0} stone", "pierre {0}", "{0}棋子", "{0}石"],
+    OffBoard "offBoard" ["off the board", "hors du plateau", "棋盘外", "盤外"],
+    Empty "empty" ["empty", "vide", "空", "空き"],
+    LastMove "lastMove" ["last move", "dernier coup", "最后一手", "最後の手"],
+    Plays "plays" ["{0} plays {1}", "{0} joue {1}", "{0}下在{1}", "{0}が{1}に打つ"],
+    Capturing "capturing"
+        ["capturing {0} stones", "prend {0} pierres", "吃掉{0}子", "{0}個取る"],
+    Wins "wins" ["{0} wins", "{0} gagne", "{0}获胜", "{0}の勝ち"],
+    WinsOnTime "winsOnTime" ["{0} wins on time", "{0} gagne au temps", "{0}因对方超时获胜", "{0}の時間勝ち"],
+    NewGame "newGame" ["New game", "Nouvelle partie", "新对局", "新しい対局"],
+    MoveUndone "moveUndone" ["Move undone", "Coup annulé", "已悔棋", "一手戻しました"],
+    SyncRefused "syncRefused"
+        ["The other player's game is not playable here", "La partie de l'autre joueur n'est pas jouable ici",
+         "对方的对局在此无法进行", "相手の対局はここでは再現できません"],
+    OpponentConnected "opponentConnected"
+        ["Opponent connected", "Adversaire connecté", "对手已连接", "対戦相手が接続しました"],
+    OpponentDisconnected "opponentDisconnected"
+        ["Opponent disconnected", "Adversaire déconnecté", "对手已断开", "対戦相手の接続が切れました"],
+    Saved "saved" ["Saved", "Enregistré", "已保存", "保存しました"],
+    LeavesWinActivate "leavesWinActivate"
+        ["That leaves {0} a winning move. Activate it again to play anyway.",
+         "Cela laisse un coup gagnant à {0}. Activez encore pour jouer quand même.",
+         "这步会让{0}有致胜一手。再次激活仍然下这里。",
+         "{0}に勝ちの手が残ります。もう一度押すと打ちます。"],
+
+    // Peer-to-peer pairing prompts
+    RtcSendOffer "rtcSendOffer"
+        ["Send this offer to your opponent, then paste their answer here:",
+         "Envoyez cette offre à votre adversaire, puis collez sa réponse ici :",
+         "把这个邀请发给对手，然后在此粘贴对方的回复：",
+         "このオファーを相手に送り、相手の応答をここに貼り付けてください："],
+    PairOffer "pairOffer"
+        ["Scan to join on another device, or tap to copy the offer", "Scannez pour rejoindre sur un autre appareil, ou touchez pour copier l'offre",
+         "在另一台设备上扫码加入，或点按复制邀请", "別の端末でスキャンして参加、またはタップしてオファーをコピー"],
+    PairAnswer "pairAnswer"
+        ["Show this answer to the host, or tap to copy it", "Montrez cette réponse à l'hôte, ou touchez pour la copier",
+         "把这个回复给主机看，或点按复制", "この応答をホストに見せるか、タップしてコピー"],
+    RtcPasteOffer "rtcPasteOffer"
+        ["Paste the host's offer:", "Collez l'offre de l'hôte :", "粘贴主机的邀请：", "ホストのオファーを貼り付け："],
+    RtcSendAnswer "rtcSendAnswer"
+        ["Send this answer back to the host:", "Renvoyez cette réponse à l'hôte :",
+         "把这个回复发回给主机：", "この応答をホストに送り返してください："],
+}
+
+impl Msg {
+    fn parse(key: &str) -> Option<Msg> {
+        Msg::ALL.iter().copied().find(|m| m.key() == key)
+    }
+}
+
+struct Locales {
+    /// Tables added from JS, by lower-case tag.
+    registered: HashMap<String, HashMap<Msg, String>>,
+    /// Tags to try in order: the chosen one, then the browser's preferences.
+    wanted: Vec<String>,
+    /// Set whenever the text may have changed, until the next frame takes it.
+    changed: bool,
+}
+
+impl Locales {
+    fn new() -> Locales {
+        let mut wanted = Vec::new();
+        if let Some(nav) = window().map(|w| w.navigator()) {
+            wanted.extend(nav.languages().iter().filter_map(|l| l.as_string()));
+            wanted.extend(nav.language());
+        }
+        Locales {
+            registered: HashMap::new(),
+            wanted: wanted.iter().map(|t| t.to_lowercase()).collect(),
+            changed: false,
+        }
+    }
+
+    fn has(&self, tag: &str) -> bool {
+        self.registered.contains_key(tag) || BUILT_IN.contains(&tag)
+    }
+
+    /// The first wanted tag with a table, trying `zh-tw` before `zh`.
+    fn current(&self) -> &str {
+        self.wanted
+            .iter()
+            .flat_map(|t| [t.as_str(), t.split('-').next().unwrap_or(t)])
+            .find(|t| self.has(t))
+            .unwrap_or("en")
+    }
+
+    fn text(&self, msg: Msg) -> String {
+        let tag = self.current();
+        if let Some(text) = self.registered.get(tag).and_then(|table| table.get(&msg)) {
+            return text.clone();
+        }
+        let i = BUILT_IN.iter().position(|&t| t == tag).unwrap_or(0);
+        msg.built_in()[i].to_string()
+    }
+}
+
+thread_local! {
+    static LOCALES: RefCell<Option<Locales>> = const { RefCell::new(None) };
+}
+
+fn with_locales<T>(f: impl FnOnce(&mut Locales) -> T) -> T {
+    LOCALES.with(|l| f(l.borrow_mut().get_or_insert_with(Locales::new)))
+}
+
+/// `msg` in the current locale.
+pub(crate) fn t(msg: Msg) -> String {
+    with_locales(|l| l.text(msg))
+}
+
+/// `msg` with `{0}`, `{1}`, … replaced by `args`.
+pub(crate) fn tf(msg: Msg, args: &[&dyn std::fmt::Display]) -> String {
+    let mut text = t(msg);
+    for (i, arg) in args.iter().enumerate() {
+        text = text.replace(&format!("{{{i}}}"), &arg.to_string());
+    }
+    text
+}
+
+/// The current locale's tag, for the browser's own date formatting.
+pub(crate) fn tag() -> String {
+    with_locales(|l| l.current().to_string())
+}
+
+/// "Black" or "White", translated.
+pub(crate) fn color(c: Color) -> String {
+    t(match c {
+        Color::Black => Msg::Black,
+        Color::White => Msg::White,
+    })
+}
+
+/// Whether the locale or a table changed since the last call.
+pub(crate) fn take_changed() -> bool {
+    with_locales(|l| std::mem::take(&mut l.changed))
+}
+
+/// Use the locale `tag` (e.g. `"fr"`), or the browser's languages again if it
+/// is empty. `false` if there is no such locale, built in or registered.
+#[wasm_bindgen(js_name = setLocale)]
+pub fn set_locale(tag: &str) -> bool {
+    let tag = tag.to_lowercase();
+    with_locales(|l| {
+        l.wanted.retain(|t| *t != tag);
+        if !tag.is_empty() {
+            if !l.has(&tag) {
+                return false;
+            }
+            l.wanted.insert(0, tag);
+        }
+        l.changed = true;
+        true
+    })
+}
+
+/// Add or extend the locale `tag` with `{ key: text }` pairs; keys are the
+/// names `messageKeys()` lists, and the rest fall back to English. The browser
+/// picks it up if it prefers `tag`; `setLocale(tag)` selects it anyway.
+#[wasm_bindgen(js_name = registerLocale)]
+pub fn register_locale(tag: &str, messages: &js_sys::Object) -> Result<(), JsValue> {
+    let mut table = HashMap::new();
+    for key in js_sys::Object::keys(messages).iter() {
+        let name = key.as_string().unwrap_or_default();
+        let msg = Msg::parse(&name).ok_or_else(|| format!("unknown message {name}"))?;
+        let text = js_sys::Reflect::get(messages, &key)?
+            .as_string()
+            .ok_or_else(|| format!("message {name} must be a string"))?;
+        table.insert(msg, text);
+    }
+    with_locales(|l| {
+        l.registered.entry(tag.to_lowercase()).or_default().extend(table);
+        l.changed = true;
+    });
+    Ok(())
+}
+
+/// Every message as `{ key: English text }`, a template for `registerLocale`.
+#[wasm_bindgen(js_name = messageKeys)]
+pub fn message_keys() -> js_sys::Object {
+    let obj = js_sys::Object::new();
+    for &msg in Msg::ALL {
+        let _ = js_sys::Reflect::set(&obj, &msg.key().into(), &msg.built_in()[0].into());
+    }
+    obj
+}
@@ -0,0 +1,343 @@
+//! Keyboard bindings: which keys trigger which action, remappable from JS and
+//! kept in `localStorage`.
+//!
+//! Keys are named as `KeyboardEvent.key` reports them, with letters in lower
+//! case (`A` and `a` are the same binding), `Space` for the space bar, and a
+//! `Shift+` prefix on named keys (`Shift+ArrowLeft`). Only changes from the
+//! defaults are stored, as `action:key|key,…`; since `,` `:` and `|` separate
+//! the list they cannot themselves be bound.
+//!
+//! Replay mode has actions of its own, which may share keys with the others:
+//! a key is only taken from another action of the same mode. Leaving replay
+//! also follows whatever `replay` is bound to.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+use web_sys::{window, KeyboardEvent, Storage};
+
+use crate::i18n::{t, Msg};
+
+const STORAGE_KEY: &str = "gomoku.keys";
+
+macro_rules! actions {
+    (
+        $($action:ident $name:literal [$($key:literal),*]),*;
+        $($r_action:ident $r_name:literal [$($r_key:literal),*]),* $(,)?
+    ) => {
+        /// Something a key can do, outside replay mode or (after the `;`) in it.
+        #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+        pub(crate) enum Action {
+            $($action,)*
+            $($r_action,)*
+        }
+
+        impl Action {
+            const ALL: &'static [Action] = &[$(Action::$action,)* $(Action::$r_action,)*];
+
+            fn name(self) -> &'static str {
+                match self {
+                    $(Action::$action => $name,)*
+                    $(Action::$r_action => $r_name,)*
+                }
+            }
+
+            fn default_keys(self) -> &'static [&'static str] {
+                match self {
+                    $(Action::$action => &[$($key),*],)*
+                    $(Action::$r_action => &[$($r_key),*],)*
+                }
+            }
+
+            /// Whether the action is one of replay mode's.
+            fn in_replay(self) -> bool {
+                matches!(self, $(Action::$r_action)|*)
+            }
+        }
+    };
+}
+
+actions! {
+    PanLeft "panLeft" ["ArrowLeft"],
+    PanRight "panRight" ["ArrowRight"],
+    PanUp "panUp" ["ArrowUp"],
+    PanDown "panDown" ["ArrowDown"],
+    ZoomOut "zoomOut" ["-"],
+    ZoomIn "zoomIn" ["+", "="],
+    CursorLeft "cursorLeft" ["Shift+ArrowLeft"],
+    CursorRight "cursorRight" ["Shift+ArrowRight"],
+    CursorUp "cursorUp" ["Shift+ArrowUp"],
+    CursorDown "cursorDown" ["Shift+ArrowDown"],
+    Place "place" ["Enter", "Space"],
+    HideCursor "hideCursor" ["Escape"],
+    Coords "coords" ["c"],
+    MoveNumbers "moveNumbers" ["n"],
+    Heatmap "heatmap" ["e"],
+    Mute "mute" ["o"],
+    Haptics "haptics" ["y"],
+    Fullscreen "fullscreen" ["f"],
+    ZoomToFit "zoomToFit" ["0"],
+    Recenter "recenter" ["Home"],
+    Follow "follow" ["j"],
+    BlunderAlerts "blunderAlerts" ["x"],
+    Explain "explain" ["w"],
+    Contrast "contrast" ["i"],
+    ColorScheme "colorScheme" ["z"],
+    StoneMarks "stoneMarks" ["u"],
+    TwoTap "twoTap" ["q"],
+    ExpectedLine "expectedLine" ["l"],
+    TopMoves "topMoves" ["k"],
+    Replay "replay" ["p"],
+    Analysis "analysis" ["a"],
+    Clock "clock" ["t"],
+    Board "board" ["b"],
+    Variant "variant" ["v"],
+    Hint "hint" ["h"],
+    Engine "engine" ["m"],
+    Style "style" ["s"],
+    Handicap "handicap" ["d"],
+    Tournament "tournament" ["g"],
+    Restart "restart" ["r"],
+    Undo "undo" ["Backspace"],
+    CopyLink "copyLink" [],
+    SaveImage "saveImage" ["F4"],
+    SaveVideo "saveVideo" ["Shift+F4"],
+    Share "share" ["F8"],
+    ExportPsq "exportPsq" ["Shift+F8"],
+    CopyMoves "copyMoves" ["F7"],
+    ExportReplay "exportReplay" ["Shift+F7"],
+    Saves "saves" ["F9"],
+    History "history" ["Shift+F9"],
+    Help "help" ["?"],
+    Settings "settings" ["."],
+    Presentation "presentation" ["F2"],
+    Diagnostics "diagnostics" ["F3"],
+    FrontierDots "frontierDots" ["Shift+F3"];
+    ReplayBack "replayBack" ["ArrowLeft"],
+    ReplayForward "replayForward" ["ArrowRight"],
+    ReplayStart "replayStart" ["Home"],
+    ReplayEnd "replayEnd" ["End"],
+    ReplayAutoplay "replayAutoplay" ["Space"],
+    ReplaySlower "replaySlower" ["["],
+    ReplayFaster "replayFaster" ["]"],
+    ReplayExit "replayExit" ["Escape"],
+}
+
+impl Action {
+    fn parse(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|a| a.name() == name)
+    }
+}
+
+/// Every action's keys, defaults first overridden by what was stored.
+struct Keymap {
+    keys: HashMap<Action, Vec<String>>,
+}
+
+impl Keymap {
+    fn defaults() -> Keymap {
+        let keys = Action::ALL
+            .iter()
+            .map(|&a| (a, a.default_keys().iter().map(|k| k.to_string()).collect()))
+            .collect();
+        Keymap { keys }
+    }
+
+    fn load() -> Keymap {
+        let mut map = Keymap::defaults();
+        if let Some(spec) = storage().and_then(|s| s.get_item(STORAGE_KEY).ok().flatten()) {
+            for (action, keys) in parse_spec(&spec).unwrap_or_default() {
+                map.bind(action, keys);
+            }
+        }
+        map
+    }
+
+    /// Give `action` exactly `keys`, taking them from any action of the same
+    /// mode that had them.
+    fn bind(&mut self, action: Action, keys: Vec<String>) {
+        for (a, bound) in &mut self.keys {
+            if a.in_replay() == action.in_replay() {
+                bound.retain(|k| !keys.contains(k));
+            }
+        }
+        self.keys.insert(action, keys);
+    }
+
+    fn action(&self, key: &str, replay: bool) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .filter(|a| a.in_replay() == replay)
+            .find(|a| self.keys[a].iter().any(|k| k == key))
+    }
+
+    /// `action:key|key` for every action whose keys differ from its defaults;
+    /// with `all`, for every action.
+    fn spec(&self, all: bool) -> String {
+        Action::ALL
+            .iter()
+            .filter(|&&a| all || self.keys[&a] != a.default_keys())
+            .map(|&a| format!("{}:{}", a.name(), self.keys[&a].join("|")))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn save(&self) {
+        if let Some(s) = storage() {
+            let _ = s.set_item(STORAGE_KEY, &self.spec(false));
+        }
+    }
+}
+
+thread_local! {
+    static KEYMAP: RefCell<Option<Keymap>> = const { RefCell::new(None) };
+}
+
+fn with_keymap<T>(f: impl FnOnce(&mut Keymap) -> T) -> T {
+    KEYMAP.with(|map| f(map.borrow_mut().get_or_insert_with(Keymap::load)))
+}
+
+fn storage() -> Option<Storage> {
+    window()?.local_storage().ok().flatten()
+}
+
+/// `action:key|key,…`, or `None` if any action or key is malformed.
+fn parse_spec(spec: &str) -> Option<Vec<(Action, Vec<String>)>> {
+    spec.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (name, keys) = entry.split_once(':')?;
+            Some((Action::parse(name.trim())?, parse_keys(keys)?))
+        })
+        .collect()
+}
+
+fn parse_keys(keys: &str) -> Option<Vec<String>> {
+    keys.split('|')
+        .filter(|k| !k.is_empty())
+        .map(|k| normalize(k).filter(|k| !k.is_empty()))
+        .collect()
+}
+
+/// The canonical name of a key as written in a binding.
+fn normalize(key: &str) -> Option<String> {
+    let (shift, name) = match key.strip_prefix("Shift+") {
+        Some(name) => (true, name),
+        None => (false, key),
+    };
+    let name = match name {
+        " " | "Space" => "Space".to_string(),
+        _ if name.chars().count() == 1 => name.to_lowercase(),
+        _ => name.to_string(),
+    };
+    Some(if shift { format!("Shift+{name}") } else { name })
+}
+
+/// The action bound to the key pressed in `e` outside replay mode, trying
+/// `Shift+` combinations before the key alone.
+pub(crate) fn action_for(e: &KeyboardEvent) -> Option<Action> {
+    lookup(e, false)
+}
+
+/// The replay action bound to the key pressed in `e`; `ReplayExit` too for
+/// the keys that open replay.
+pub(crate) fn replay_action_for(e: &KeyboardEvent) -> Option<Action> {
+    lookup(e, true)
+        .or_else(|| (lookup(e, false) == Some(Action::Replay)).then_some(Action::ReplayExit))
+}
+
+fn lookup(e: &KeyboardEvent, replay: bool) -> Option<Action> {
+    let key = normalize(&e.key())?;
+    with_keymap(|map| {
+        let shifted = e.shift_key() && key.len() > 1;
+        shifted
+            .then(|| map.action(&format!("Shift+{key}"), replay))
+            .flatten()
+            .or_else(|| map.action(&key, replay))
+    })
+}
+
+/// The keys bound to `actions`, as shown in the help overlay: `R`, `Shift+←`.
+pub(crate) fn label(actions: &[Action]) -> String {
+    let shown = |k: &String| {
+        let (shift, name) = match k.strip_prefix("Shift+") {
+            Some(name) => ("Shift+", name),
+            None => ("", k.as_str()),
+        };
+        let name = match name {
+            "ArrowLeft" => "←".to_string(),
+            "ArrowRight" => "→".to_string(),
+            "ArrowUp" => "↑".to_string(),
+            "ArrowDown" => "↓".to_string(),
+            "Escape" => "Esc".to_string(),
+            "Backspace" => "⌫".to_string(),
+            _ if name.chars().count() == 1 => name.to_uppercase(),
+            _ => name.to_string(),
+        };
+        format!("{shift}{name}")
+    };
+    with_keymap(|map| {
+        let keys: Vec<String> = actions
+            .iter()
+            .flat_map(|a| map.keys[a].iter().map(shown))
+            .collect();
+        match keys.as_slice() {
+            [] => t(Msg::Unbound),
+            _ => keys.join(" "),
+        }
+    })
+}
+
+/// Bind `keys` (`|`-separated, empty to unbind) to `action`, taking them from
+/// whatever had them, and remember the change. `false` if either is unknown.
+#[wasm_bindgen(js_name = setKeyBinding)]
+pub fn set_key_binding(action: &str, keys: &str) -> bool {
+    let (Some(action), Some(keys)) = (Action::parse(action), parse_keys(keys)) else {
+        return false;
+    };
+    with_keymap(|map| {
+        map.bind(action, keys);
+        map.save();
+    });
+    true
+}
+
+/// Every binding, as `action:key|key,…`.
+#[wasm_bindgen(js_name = keyBindings)]
+pub fn key_bindings() -> String {
+    with_keymap(|map| map.spec(true))
+}
+
+/// Forget every remapping.
+#[wasm_bindgen(js_name = resetKeyBindings)]
+pub fn reset_key_bindings() {
+    with_keymap(|map| {
+        *map = Keymap::defaults();
+        map.save();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_keys_are_bound_apart_from_the_others() {
+        let mut map = Keymap::defaults();
+        assert_eq!(map.action("ArrowLeft", false), Some(Action::PanLeft));
+        assert_eq!(map.action("ArrowLeft", true), Some(Action::ReplayBack));
+        map.bind(Action::ReplayForward, vec!["ArrowLeft".into()]);
+        assert_eq!(map.action("ArrowLeft", true), Some(Action::ReplayForward));
+        assert_eq!(map.action("ArrowLeft", false), Some(Action::PanLeft));
+        assert_eq!(map.spec(false), "replayBack:,replayForward:ArrowLeft");
+    }
+
+    #[test]
+    fn replay_bindings_parse_by_name() {
+        let spec = parse_spec("replayAutoplay:k|Space,replayExit:").unwrap();
+        assert_eq!(spec[0], (Action::ReplayAutoplay, vec!["k".into(), "Space".into()]));
+        assert_eq!(spec[1], (Action::ReplayExit, vec![]));
+    }
+}
@@ -0,0 +1,285 @@
+//! Canvases stacked under the board canvas, each holding a part of the frame
+//! that changes less often than the HUD: the grid only moves with the camera,
+//! and the stones only with the camera or the position. A layer is redrawn when
+//! the key it was drawn for changes; the board canvas on top, which takes the
+//! input, is cleared and redrawn every frame as before.
+//!
+//! Where the browser can hand a canvas to a worker (`transferControlToOffscreen`),
+//! the stone layer is drawn by one, so redrawing a screenful of stones no longer
+//! holds up input on the main thread. The worker runs its own instance of this
+//! module (see `workers`) and is sent what to paint, the stones in view or the
+//! cells that changed, with the look and camera to paint them with. A stone
+//! skin's images cannot be sent there, so setting one brings the layer back
+//! to the main thread on a fresh canvas, as does `?offscreen=off` from the start.
+
+use std::cell::RefCell;
+
+use js_sys::Array;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    CanvasRenderingContext2d, Document, Element, HtmlCanvasElement, OffscreenCanvas, Worker,
+};
+
+use crate::stones::{self, Look, View};
+use crate::{workers, Color, Pt};
+
+pub(crate) struct Layer<K> {
+    pub(crate) canvas: HtmlCanvasElement,
+    surface: Surface,
+    /// What the layer shows now; `None` once it needs drawing whatever the key.
+    drawn: Option<K>,
+}
+
+/// Where a layer is drawn.
+enum Surface {
+    /// On the main thread, through the canvas's own context.
+    Here(CanvasRenderingContext2d),
+    /// By a worker, which owns the canvas's `OffscreenCanvas`.
+    Worker(Worker),
+}
+
+/// What the page asks of an offscreen stone layer's worker, sent as JSON.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum Job<'a> {
+    Resize {
+        w: f64,
+        h: f64,
+        dpr: f64,
+    },
+    Clear {
+        w: f64,
+        h: f64,
+    },
+    /// Every stone in view, on a cleared layer.
+    Redraw {
+        #[serde(borrow)]
+        look: Look<'a>,
+        view: View,
+        stones: Vec<(Pt, Color)>,
+    },
+    /// Just these cells, each with its stone if it has one.
+    Patch {
+        #[serde(borrow)]
+        look: Look<'a>,
+        view: View,
+        cells: Vec<(Pt, Option<Color>)>,
+    },
+}
+
+impl<K: PartialEq> Layer<K> {
+    /// A canvas of the page's `layer` class, inserted just before `above` so
+    /// that it shows beneath it.
+    pub(crate) fn new(doc: &Document, above: &Element) -> Result<Layer<K>, JsValue> {
+        let canvas = Self::insert(doc, above)?;
+        let ctx = canvas
+            .get_context("2d")?
+            .ok_or("no 2d context")?
+            .dyn_into::<CanvasRenderingContext2d>()?;
+        Ok(Layer {
+            canvas,
+            surface: Surface::Here(ctx),
+            drawn: None,
+        })
+    }
+
+    /// Like `new`, but drawn by a worker; `None` where the browser cannot
+    /// hand a canvas over to one.
+    pub(crate) fn offscreen(doc: &Document, above: &Element) -> Option<Layer<K>> {
+        let canvas = Self::insert(doc, above).ok()?;
+        let worker = js_sys::Reflect::has(&canvas, &"transferControlToOffscreen".into())
+            .unwrap_or(false)
+            .then(|| canvas.transfer_control_to_offscreen().ok())
+            .flatten()
+            .and_then(|offscreen| {
+                let transfer = Array::of1(&offscreen);
+                workers::spawn("offscreenAttach", "offscreenDraw", &offscreen, &transfer)
+            });
+        let Some(worker) = worker else {
+            canvas.remove();
+            return None;
+        };
+        Some(Layer {
+            canvas,
+            surface: Surface::Worker(worker),
+            drawn: None,
+        })
+    }
+
+    fn insert(doc: &Document, above: &Element) -> Result<HtmlCanvasElement, JsValue> {
+        let canvas = doc
+            .create_element("canvas")?
+            .dyn_into::<HtmlCanvasElement>()?;
+        canvas.set_class_name("layer");
+        canvas.set_attribute("aria-hidden", "true")?;
+        above
+            .parent_node()
+            .ok_or("board canvas is not in the page")?
+            .insert_before(&canvas, Some(above))?;
+        Ok(canvas)
+    }
+
+    /// The layer's context, unless a worker draws it.
+    pub(crate) fn ctx(&self) -> Option<&CanvasRenderingContext2d> {
+        match &self.surface {
+            Surface::Here(ctx) => Some(ctx),
+            Surface::Worker(_) => None,
+        }
+    }
+
+    /// Whether a worker draws the layer.
+    pub(crate) fn is_offscreen(&self) -> bool {
+        matches!(self.surface, Surface::Worker(_))
+    }
+
+    /// A layer drawn on the main thread in this one's place, which is removed.
+    pub(crate) fn bring_home(&self, doc: &Document) -> Result<Layer<K>, JsValue> {
+        let layer = Self::new(doc, &self.canvas)?;
+        self.canvas.remove();
+        Ok(layer)
+    }
+
+    fn post(worker: &Worker, job: &Job) {
+        if let Ok(json) = serde_json::to_string(job) {
+            let _ = worker.post_message(&json.into());
+        }
+    }
+
+    /// Match the board canvas's size and pixel ratio; the layer is then blank.
+    pub(crate) fn resize(&mut self, w: f64, h: f64, dpr: f64) {
+        match &self.surface {
+            Surface::Here(ctx) => {
+                self.canvas.set_width((w * dpr) as u32);
+                self.canvas.set_height((h * dpr) as u32);
+                let _ = ctx.set_transform(dpr, 0.0, 0.0, dpr, 0.0, 0.0);
+            }
+            Surface::Worker(worker) => Self::post(worker, &Job::Resize { w, h, dpr }),
+        }
+        self.drawn = None;
+    }
+
+    /// Clear the `w`×`h` view.
+    pub(crate) fn clear(&self, w: f64, h: f64) {
+        match &self.surface {
+            Surface::Here(ctx) => ctx.clear_rect(0.0, 0.0, w, h),
+            Surface::Worker(worker) => Self::post(worker, &Job::Clear { w, h }),
+        }
+    }
+
+    /// Paint `stones`, the ones in `view`, on the cleared layer.
+    pub(crate) fn redraw_stones(&self, look: Look, view: View, stones: Vec<(Pt, Color)>) {
+        match &self.surface {
+            Surface::Here(ctx) => stones::all(ctx, &look, &view, &stones),
+            Surface::Worker(worker) => Self::post(worker, &Job::Redraw { look, view, stones }),
+        }
+    }
+
+    /// Repaint just `cells`, each with its stone if it has one.
+    pub(crate) fn patch_stones(&self, look: Look, view: View, cells: Vec<(Pt, Option<Color>)>) {
+        match &self.surface {
+            Surface::Here(ctx) => {
+                for (p, c) in cells {
+                    stones::cell(ctx, &look, &view, p, c);
+                }
+            }
+            Surface::Worker(worker) => Self::post(worker, &Job::Patch { look, view, cells }),
+        }
+    }
+
+    /// Have the layer redrawn at the next frame whatever the key.
+    pub(crate) fn invalidate(&mut self) {
+        self.drawn = None;
+    }
+
+    /// Whether the layer must be redrawn to show `key`; it is then taken as shown.
+    pub(crate) fn stale(&mut self, key: K) -> bool {
+        if self.drawn.as_ref() == Some(&key) {
+            return false;
+        }
+        self.drawn = Some(key);
+        true
+    }
+}
+
+impl<K> Drop for Layer<K> {
+    fn drop(&mut self) {
+        if let Surface::Worker(worker) = &self.surface {
+            worker.terminate();
+        }
+    }
+}
+
+thread_local! {
+    /// In an offscreen layer's worker, its canvas and context.
+    static OFFSCREEN: RefCell<Option<(OffscreenCanvas, CanvasRenderingContext2d)>> =
+        const { RefCell::new(None) };
+}
+
+/// Called by an offscreen layer's worker once started, with its canvas. Not
+/// for embedders.
+#[wasm_bindgen(js_name = offscreenAttach)]
+pub fn offscreen_attach(canvas: JsValue) {
+    let Ok(canvas) = canvas.dyn_into::<OffscreenCanvas>() else {
+        return;
+    };
+    // The worker's 2D context has the same methods as the page's, which is
+    // all the bindings call on it.
+    let Some(ctx) = canvas.get_context("2d").ok().flatten() else {
+        return;
+    };
+    let ctx = ctx.unchecked_into::<CanvasRenderingContext2d>();
+    OFFSCREEN.with(|o| *o.borrow_mut() = Some((canvas, ctx)));
+}
+
+/// Called by an offscreen layer's worker for each job the page posts. Not for
+/// embedders.
+#[wasm_bindgen(js_name = offscreenDraw)]
+pub fn offscreen_draw(job: &str) {
+    let Ok(job) = serde_json::from_str::<Job>(job) else {
+        return;
+    };
+    OFFSCREEN.with(|o| {
+        let Some((canvas, ctx)) = &*o.borrow() else {
+            return;
+        };
+        match job {
+            Job::Resize { w, h, dpr } => {
+                canvas.set_width((w * dpr) as u32);
+                canvas.set_height((h * dpr) as u32);
+                let _ = ctx.set_transform(dpr, 0.0, 0.0, dpr, 0.0, 0.0);
+            }
+            Job::Clear { w, h } => ctx.clear_rect(0.0, 0.0, w, h),
+            Job::Redraw { look, view, stones } => stones::all(ctx, &look, &view, &stones),
+            Job::Patch { look, view, cells } => {
+                for (p, c) in cells {
+                    stones::cell(ctx, &look, &view, p, c);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_reach_the_worker_as_sent() {
+        let look = Look::of(&crate::theme::WOOD, true);
+        let view = View {
+            cam: (0.5, 0.0),
+            cell_px: 36.0,
+            size: (800.0, 600.0),
+        };
+        let cells = vec![(Pt::new(1, -2), Some(Color::White)), (Pt::new(0, 0), None)];
+        let json = serde_json::to_string(&Job::Patch { look, view, cells }).unwrap();
+        let Ok(Job::Patch { look: back, view: seen, cells }) = serde_json::from_str(&json) else {
+            panic!("not a patch: {json}");
+        };
+        assert_eq!((back.stones, back.outline, back.marks), (look.stones, look.outline, true));
+        assert_eq!((seen.cam, seen.cell_px), ((0.5, 0.0), 36.0));
+        assert_eq!(cells, [(Pt::new(1, -2), Some(Color::White)), (Pt::new(0, 0), None)]);
+    }
+}
@@ -1,25 +1,228 @@
 //! Infinite Gomoku (5-in-a-row) in Rust → WebAssembly.
 //!
 //! - Unbounded sparse board keyed by `Pt`.
+//! - The move list lives in the URL hash, so links share and restore games.
 //! - Choose Human/AI per color via on-canvas toggles.
+//! - The AI opens from a small symmetry-aware book, plays out forced wins (continuous
+//!   fours and threes), and otherwise searches moves that make or answer threats,
+//!   picking at random among near-equal choices so games vary. It ponders likely
+//!   replies during the human's turn, and on cross-origin isolated pages searches and
+//!   ponders with helper workers sharing its transposition table (see `smp`).
+//! - Online play through a WebSocket relay (`?ws=<url>`) or peer-to-peer WebRTC
+//!   (`?rtc=host|join`), with `&side=black|white` choosing the local colour.
 //! - Mobile-friendly via Pointer Events; high-DPI aware canvas.
 //! - Mouse/touchpad wheel: zoom toward cursor; horizontal pan.
+//! - Touch: drag or two-finger drag to pan (a flick keeps gliding), pinch to zoom;
+//!   long-press for a magnifier, slide to the cell and release to place.
 //!
-//! Controls
-//! - Tap/click to place.
+//! Controls (`?` lists them on the board)
+//! - Tap/click to place; drag to pan. The middle button, or the left with Space held,
+//!   pans straight away and never places.
 //! - Wheel up/down = zoom in/out (toward cursor).
+//! - Double-tap or double-click where a tap would not play (on a stone, during replay, on
+//!   the AI's turn) to zoom in there; Shift+double-click zooms out.
 //! - Shift+wheel or horizontal wheel = pan left/right.
-//! - Arrow keys to pan; `R` to reset.
+//! - `?wheel=scroll,invert,2x` retunes the wheel: `scroll` pans with the plain wheel and
+//!   zooms with Ctrl (as browsers do), `invert` flips the zoom, `2x` doubles its speed.
+//! - Arrow keys to pan; `R` twice to reset (once the game has moves); Backspace takes back a move (the AI's reply with it).
+//! - Brief notices (a move undone, the opponent leaving, settings saved) appear above
+//!   the minimap and fade away (see `toast`).
+//! - `.` or the ⚙ button opens a settings panel: theme, difficulty, sound, rules, what the
+//!   wheel does, animations (off when the browser asks for reduced motion, or with
+//!   `?animations=off`) and an eval bar down the left edge showing who is ahead, from a
+//!   shallow search of the position on screen (or `?evalbar=on`). Its choices are
+//!   remembered (see `settings`).
+//! - `F4` (or the button on the summary card) downloads the board around the stones as
+//!   a PNG, at the image size picked in settings (or `?imagesize=small|large`).
+//! - `Shift+F4` records the game from its first move to its last as a video, which
+//!   downloads when it finishes (see `record`).
+//! - Ctrl+C copies the position as text (`gomoku:` and the move list of a game link)
+//!   for chat or a bug report; Ctrl+V loads one, or a game link, if its moves are legal.
+//! - `F9` (or the toolbar's Saves button) opens saved games: a few named slots kept in
+//!   `localStorage`, each with a thumbnail of its position and when it was saved, to
+//!   save the live game to, load from (under the rules it was saved with) or empty
+//!   (see `saves`).
+//! - Every finished game is kept in IndexedDB (see `archive`); `Shift+F9` (or History
+//!   in the saves menu) lists them, newest first, to replay under the rules they were
+//!   played by or export as JSON.
+//! - `F8` (or the toolbar's Share button) shows the game's link as a QR code, for a
+//!   phone to pick the position up from the screen (see `qr`).
+//! - Gomocup `.psq` games load by Ctrl+V or by dropping the file on the board, as
+//!   standard gomoku on their board, and `Shift+F8` downloads the live game as one
+//!   (see `psq`).
+//! - A RenjuNet `.rif` library, pasted or dropped the same way, lists its games by
+//!   players, result and date to replay one, centred on the origin (see `rif`).
+//! - `F7` copies the move list as text for forums or issues, a move a line with its
+//!   cell counted from the first stone (`3. B +1 -1`); pasted back, it loads on the
+//!   infinite board (see `notation`).
+//! - Replays keep the pace the game was played at: autoplay waits as long between
+//!   moves as the players did (`[` from the slowest fixed speed goes back to it), and
+//!   `Shift+F7` downloads the game with its move times as a replay file that plays
+//!   back the same way when pasted or dropped on the board (see `schema`).
+//! - `F2` (or `?present=on`) is presentation mode, for showing a game to a room or a
+//!   stream: larger text and stones, the last move named in a banner, and no small
+//!   buttons, toolbar or minimap.
+//! - `F3` shows diagnostics for bug reports: frame rate, the last render's time, the
+//!   stone and frontier counts, and the wasm memory in use. `Shift+F3` dots every cell
+//!   in the game's frontier, for checking how it is kept up to date.
+//! - A toolbar at the bottom left has Undo, Hint, Replay, Share, Saves and New game buttons
+//!   for touch.
+//! - Shift+arrows move a keyboard cursor; Enter or Space plays on its cell, Esc hides it.
+//! - Gamepad: left stick pans, right stick or triggers zoom, d-pad moves the cursor,
+//!   A plays on it (or starts the next game), B hides it.
+//! - `C` toggles coordinate labels; `N` toggles move numbers.
+//! - `P` opens replay: ←/→ step, Space autoplay, `[`/`]` speed, `P`/Esc exit. A list of
+//!   the moves at the right scrolls with the wheel; click one to jump to it.
+//! - `B` cycles the board: infinite, 15×15, 19×19 (or `?board=15`); `?win=6` sets the win length.
+//! - `V` cycles variants: standard, Pente (captures), Connect6 (two stones a turn, six wins);
+//!   or `?variant=pente|connect6`.
+//! - `T` cycles clock presets (off, 1+0, 3+2, 5+0, 10+5); `?clock=3+2` sets one from the URL.
+//! - `E` toggles a heatmap of the heuristic's score for every candidate cell; with it on,
+//!   the empty cell under the mouse shows its score for each side by the cursor.
+//! - `M` switches the AI between alpha-beta and Monte Carlo tree search (or `?engine=mcts`).
+//! - `S` (or the Style button) cycles the AI's personality: balanced, aggressive,
+//!   defensive (or `?style=aggressive`).
+//! - The Time buttons (−/+) set how long the AI thinks per move, from 50 ms to 4 s
+//!   (or `?think=1000`).
+//! - `D` cycles a handicap (0–3) under which the AI sometimes plays a weaker move
+//!   (or `?handicap=2`).
+//! - `G` starts (or stops) an engine-vs-engine tournament between the two sides'
+//!   settings, with colours swapped every game and a running score table;
+//!   `?tournament=alphabeta,d2:mcts` pits two configurations against each other.
+//! - A move that would hand the opponent a win asks for a second tap (`X` turns this off).
+//! - `Q` (or `?taps=2`) makes every placement two taps: the first picks and enlarges the
+//!   cell, the second (on it or its ✓ button) plays.
+//! - Each AI move is explained in a line at the bottom (`W` hides it).
+//! - `L` shows the line the AI expects after each of its moves, as numbered arrows.
+//! - Press and hold a pill (or `?black=Ann&white=Bo`) to name that side's player; the
+//!   name replaces the colour on the pills, in the winner's message and in announcements.
+//! - A strip under the pills shows the move number, each side's stones on the board
+//!   and how long the game has gone on.
+//! - While the alpha-beta AI thinks, a spinner under the strip shows the depth it is
+//!   searching and the positions it has looked at.
+//! - After a win, a card sums the game up (moves, time taken, each side's average time
+//!   per move, threats made and stopped) with buttons to replay it, copy its link, save
+//!   it as an image or start another (see `summary`).
+//! - When the AI proves a forced win, its pill reads "wins in N" and the forcing
+//!   stone is outlined.
+//! - `K` ranks the engine's top moves for the position on screen, with scores and lines.
+//! - `?weights=openFour:60000,…` retunes the heuristic's pattern scores.
+//! - A game goes to and from versioned JSON with `toJson`/`GomokuEngine.fromJson` (see
+//!   `schema`), and the saved settings with `settingsJson`/`setSettingsJson`.
+//!   `toPsq`/`GomokuEngine.fromPsq` do the same for Gomocup `.psq` files.
+//! - Keys can be remapped from JS with `setKeyBinding("hint", "j|F1")`, replay's
+//!   own too (`setKeyBinding("replayBack", "a")`; see `keys`); `keyBindings()` lists
+//!   them and `resetKeyBindings()` restores the defaults.
+//! - `H` (or the Hint button) marks the engine's suggested move for the human to play.
+//! - `A` after a game opens the replay with an evaluation graph; click it to jump.
+//! - `I` (or `?contrast=high`) switches to a high-contrast theme: thick grid lines,
+//!   outlined black-and-yellow stones and larger HUD text. It starts on when the
+//!   browser prefers more contrast.
+//! - The board is dark or light as the browser prefers, following it as it changes;
+//!   `Z` (or `setColorScheme("light" | "dark" | "auto")`) overrides it, and the
+//!   choice is remembered.
+//! - `?theme=wood` draws a wooden goban with black and white stones.
+//! - `setSkin({ black, white, board })` draws the stones and board with images or
+//!   inline SVG given from JS (see `skin`).
+//! - `U` (or `?stones=marked`) rings White's stones, so the sides differ in shape as
+//!   well as colour.
+//! - Sound effects for stones, unplayable taps, a clock under ten seconds and wins;
+//!   `O` or the speaker button mutes them, and `setSoundVolume` sets volumes (see `audio`).
+//! - Phones vibrate for the same events, with their own patterns for a win and a loss;
+//!   `Y` (or `setHaptics(false)`) turns it off (see `haptics`).
+//! - Star points every 8 cells (`?stars=12` to change, `?stars=off` to hide) and a ring
+//!   at the origin give the grid landmarks.
+//! - `J` (or `?follow=on`) pans to each stone played off screen.
+//! - `0` or the Fit button zooms to show every stone; `Home` goes back to the last move.
+//! - A minimap at the bottom right shows every stone and the part of the board on
+//!   screen; click it to move the view there.
+//! - `F` or the ⛶ button puts the board in fullscreen; `F` again, the button or Escape
+//!   leaves it.
+//! - Text follows the browser's language (English, French, Chinese or Japanese built in,
+//!   or `?lang=fr`); `registerLocale` adds more from JS (see `i18n`).
+//! - Screen readers get a hidden grid of the cells around the view's centre (or the
+//!   keyboard cursor), whose buttons play, and hear each move announced (see `a11y`).
 
 use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::rc::Rc;
 
+mod a11y;
+mod analysis;
+mod archive;
+mod audio;
+mod book;
+mod celebrate;
+mod clock;
+mod codec;
+mod explain;
+mod gamepad;
+mod haptics;
+mod i18n;
+mod keys;
+mod layers;
+mod mcts;
+mod net;
+mod notation;
+mod patterns;
+mod psq;
+mod qr;
+mod replay;
+mod rif;
+mod record;
+mod rng;
+mod rtc;
+mod saves;
+mod schema;
+mod search;
+mod settings;
+mod skin;
+mod smp;
+mod solver;
+mod stones;
+mod summary;
+mod theme;
+mod threats;
+mod toast;
+mod tournament;
+mod webgl;
+mod weights;
+mod workers;
+
+use a11y::A11y;
+use analysis::Analysis;
+use audio::{Audio, Effect};
+use celebrate::Celebration;
+use haptics::Buzz;
+use layers::Layer;
+use webgl::{GlStones, WEBGL_STONES};
+use clock::{Clock, TimeControl};
+use explain::Source;
+use archive::History;
+use record::{Recording, Step};
+use saves::Save;
+use summary::Summary;
+use gamepad::Gamepad;
+use i18n::{t, tf, Msg};
+use keys::Action;
+use net::{NetMsg, NetStatus, Pairing, Transport, WsTransport};
+use patterns::Shape;
+use replay::Replay;
+use rif::Library;
+use rng::Rng;
+use search::{ScoredLine, Tt};
+use theme::Theme;
+use threats::Threat;
+use toast::Toasts;
+use tournament::Tournament;
+use weights::Weights;
+use rtc::RtcTransport;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{
-    window, CanvasRenderingContext2d, Element, HtmlCanvasElement, KeyboardEvent, PointerEvent,
-    WheelEvent
+    window, CanvasRenderingContext2d, ClipboardEvent, DragEvent, Element, HtmlCanvasElement,
+    KeyboardEvent, PointerEvent, UrlSearchParams, WheelEvent
 };
 
 /// Entry point invoked by the browser when the module loads.
@@ -28,7 +231,10 @@ pub fn start() -> Result<(), JsValue> {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 
-    let doc = window().unwrap().document().unwrap();
+    // A search helper started by `smp` has no page to run the game in.
+    let Some(doc) = window().and_then(|w| w.document()) else {
+        return Ok(());
+    };
     let canvas: HtmlCanvasElement = doc
         .get_element_by_id("board")
         .unwrap()
@@ -38,16 +244,188 @@ pub fn start() -> Result<(), JsValue> {
         .unwrap()
         .dyn_into::<CanvasRenderingContext2d>()?;
 
+    // The dark and light themes follow the browser's scheme as it changes.
+    theme::watch_scheme();
+
+    let search = window().unwrap().location().search().unwrap_or_default();
+    let params = UrlSearchParams::new_with_str(&search)?;
+    // `?lang=fr` picks the UI language over the browser's.
+    if let Some(tag) = params.get("lang") {
+        i18n::set_locale(&tag);
+    }
+
+    // `?board=15` plays on a bounded 15×15 board instead of the infinite grid;
+    // `?variant=pente|connect6` picks a rule set and `?win=6` overrides its win length.
+    let mut rules = Rules::default();
+    // Settings saved from the panel stand in for parameters the URL leaves out.
+    let setting = |name: &str| params.get(name).or_else(|| settings::get(name));
+    if let Some(v) = setting("variant").as_deref().and_then(Variant::parse) {
+        rules = v.apply(rules);
+    }
+    if let Some(n) = params.get("win").and_then(|n| n.parse().ok()) {
+        rules.win_len = n;
+    }
+    // `?weights=openFour:60000,blockThree:1200` retunes the heuristic (see `Weights`).
+    if let Some(w) = params.get("weights").and_then(|w| rules.weights.with_overrides(&w)) {
+        rules.weights = w;
+    }
+    rules.bounds = params
+        .get("board")
+        .and_then(|n| n.parse().ok())
+        .map(Bounds::square);
+    let rules = rules.validated();
+
+    let game = Game::with_rules(rules);
+
     // Shared UI/application state.
-    let app = Rc::new(RefCell::new(App::new(canvas.clone(), ctx, Game::new())));
+    let a11y = A11y::new(&doc)?;
+    let grid_layer = Layer::new(&doc, &canvas)?;
+    // `?offscreen=off` keeps the stone layer on the main thread (see `layers`).
+    let offscreen = params.get("offscreen").as_deref() != Some("off");
+    let stone_layer = match offscreen.then(|| Layer::offscreen(&doc, &canvas)).flatten() {
+        Some(layer) => layer,
+        None => Layer::new(&doc, &canvas)?,
+    };
+    let layers = (grid_layer, stone_layer);
+    let app = Rc::new(RefCell::new(App::new(canvas.clone(), ctx, layers, game, a11y)));
     App::attach_listeners(&app);
 
+    // Online play: `?ws=<relay url>` joins a relay room, `?rtc=host|join` pairs two browsers
+    // directly. `&side=black|white` picks the local colour (default: Black, or White when joining).
+    let remote: Option<(Box<dyn Transport>, Color)> = match (params.get("ws"), params.get("rtc")) {
+        (Some(url), _) => Some((Box::new(WsTransport::connect(&url)?), Color::Black)),
+        (None, Some(role)) if role == "host" => {
+            Some((Box::new(RtcTransport::host()?), Color::Black))
+        }
+        (None, Some(role)) if role == "join" => {
+            Some((Box::new(RtcTransport::join()?), Color::White))
+        }
+        _ => None,
+    };
+    // `?clock=5` (minutes) or `?clock=3+2` (minutes + increment seconds) enables clocks.
+    if let Some(tc) = params.get("clock").as_deref().and_then(TimeControl::parse) {
+        app.borrow_mut().clock = Some(Clock::new(tc));
+    }
+    // `?engine=mcts` switches the AI to tree search.
+    if let Some(backend) = params.get("engine").as_deref().and_then(Backend::parse) {
+        for ai in &mut app.borrow_mut().ai {
+            ai.backend = backend;
+        }
+    }
+    // `?workers=2` sets how many helper workers search alongside the AI where the
+    // page is cross-origin isolated (one fewer than the cores by default); 0 turns them off.
+    let helpers = params.get("workers").and_then(|n| n.parse().ok());
+    if let Some(pool) = smp::Pool::start(helpers) {
+        let mut app = app.borrow_mut();
+        app.tt = pool.tt();
+        app.smp = Some(pool);
+    }
+    // `?style=aggressive|defensive` picks the AI's personality.
+    if let Some(style) = params.get("style").as_deref().and_then(Style::parse) {
+        for ai in &mut app.borrow_mut().ai {
+            ai.style = style;
+        }
+    }
+    // `?think=<ms>` sets how long the AI thinks per move.
+    if let Some(ms) = params.get("think").and_then(|n| n.parse::<u32>().ok()) {
+        for ai in &mut app.borrow_mut().ai {
+            ai.think_ms = Some(clamp_think_ms(ms));
+        }
+    }
+    // `?taps=2` asks for a confirming tap before each stone, for small touch screens.
+    if params.get("taps").as_deref() == Some("2") {
+        app.borrow_mut().two_tap = true;
+    }
+    // `?wheel=scroll,invert,2x` adapts the wheel to trackpads (see `WheelPrefs::parse`).
+    if let Some(prefs) = setting("wheel").as_deref().and_then(WheelPrefs::parse) {
+        app.borrow_mut().wheel = prefs;
+    }
+    // `?contrast=high|normal` overrides the browser's `prefers-contrast`.
+    match params.get("contrast").as_deref() {
+        Some("high") => app.borrow_mut().theme = &theme::HIGH_CONTRAST,
+        Some("normal") => app.borrow_mut().theme = theme::scheme_theme(),
+        _ => {}
+    }
+    // `?theme=wood` draws a wooden goban with black and white stones.
+    if params.get("theme").as_deref() == Some("wood") {
+        app.borrow_mut().theme = &theme::WOOD;
+    }
+    // A theme picked in the settings panel, unless the URL picks one.
+    if params.get("contrast").is_none() && params.get("theme").is_none() {
+        match settings::get("theme").as_deref() {
+            Some("contrast") => app.borrow_mut().theme = &theme::HIGH_CONTRAST,
+            Some("wood") => app.borrow_mut().theme = &theme::WOOD,
+            _ => {}
+        }
+    }
+    // `?animations=off` stills camera moves, pan glides and confetti; they
+    // start off when the browser asks for reduced motion.
+    if let Some(on) = setting("animations") {
+        app.borrow_mut().animations = on != "off";
+    }
+    // `?evalbar=on` shows who is ahead in a bar down the left edge.
+    if setting("evalbar").as_deref() == Some("on") {
+        app.borrow_mut().show_eval_bar = true;
+    }
+    // `?imagesize=small|large` sets how big saved board images are.
+    if let Some(size) = setting("imagesize").as_deref().and_then(ImageSize::parse) {
+        app.borrow_mut().image_size = size;
+    }
+    // `?present=on` starts in presentation mode, for showing a game to a room.
+    if params.get("present").as_deref() == Some("on") {
+        app.borrow_mut().set_presenting(true);
+    }
+    // `?stones=marked` tells the sides apart by shape, not just colour.
+    if params.get("stones").as_deref() == Some("marked") {
+        app.borrow_mut().stone_marks = true;
+    }
+    // `?stars=8` spaces the star points (every 8 cells by default); `off` hides them.
+    if let Some(stars) = params.get("stars") {
+        app.borrow_mut().star_spacing = stars.parse().unwrap_or(0);
+    }
+    // `?follow=on` pans to each stone played off screen.
+    if params.get("follow").as_deref() == Some("on") {
+        app.borrow_mut().follow = true;
+    }
+    // `?black=Ann&white=Bo` names the players.
+    for side in [Color::Black, Color::White] {
+        let name = params.get(if side == Color::Black { "black" } else { "white" });
+        app.borrow_mut().set_name(side, name.as_deref().unwrap_or(""));
+    }
+    // `?handicap=1..3` weakens the AI for beginners.
+    if let Some(level) = setting("handicap").and_then(|n| n.parse::<u8>().ok()) {
+        for ai in &mut app.borrow_mut().ai {
+            ai.handicap = level.min(MAX_HANDICAP);
+        }
+    }
+    if let Some((transport, default_side)) = remote {
+        let local = match params.get("side").as_deref() {
+            Some("white") => Color::White,
+            Some("black") => Color::Black,
+            _ => default_side,
+        };
+        app.borrow_mut().connect_remote(transport, local);
+    }
+    // `?tournament=<a>:<b>` plays two AI specs (see `AiConfig::parse`) against each other.
+    if let Some(spec) = params.get("tournament") {
+        let (a, b) = spec.split_once(':').unwrap_or((&spec, &spec));
+        if let (Some(a), Some(b)) = (AiConfig::parse(a), AiConfig::parse(b)) {
+            app.borrow_mut().start_tournament([a, b]);
+        }
+    }
+
     {
         let mut a = app.borrow_mut();
         a.resize();
+        // A shared game link (`#m=...`, after any rules of its own) restores its position.
+        let hash = window().unwrap().location().hash().unwrap_or_default();
+        let hash = hash.strip_prefix('#').filter(|h| !h.is_empty());
+        if let Some(hash) = hash.filter(|_| a.tournament.is_none()) {
+            a.open_link(hash);
+        }
         a.render();
         if a.is_ai_turn() {
-            a.queue_ai_soon(120.0);
+            a.queue_ai_soon(AI_START_MS);
         }
     }
 
@@ -58,7 +436,9 @@ pub fn start() -> Result<(), JsValue> {
 /* ---------- Model ---------- */
 
 /// The side (owner of a stone or current player).
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[wasm_bindgen]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Color {
     Black,
     White,
@@ -73,11 +453,41 @@ impl Color {
 }
 
 /// Integer grid point. Keys the sparse board and frontier.
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 struct Pt {
     x: i32,
     y: i32,
 }
+/// A fast, unkeyed hash for `Pt` (FxHash-style). Board lookups are the
+/// heuristic's inner loop, and grid points need no protection from collisions.
+#[derive(Default)]
+struct PtHasher(u64);
+
+impl std::hash::Hasher for PtHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_u32(b as u32);
+        }
+    }
+
+    #[inline]
+    fn write_i32(&mut self, n: i32) {
+        self.write_u32(n as u32);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, n: u32) {
+        self.0 = (self.0.rotate_left(5) ^ n as u64).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+type PtMap<V> = HashMap<Pt, V, std::hash::BuildHasherDefault<PtHasher>>;
+type PtSet = HashSet<Pt, std::hash::BuildHasherDefault<PtHasher>>;
+
 impl Pt {
     #[inline]
     fn new(x: i32, y: i32) -> Self {
@@ -99,65 +509,386 @@ const DIRS: [Pt; 4] = [
     Pt { x: 1, y: -1 },
 ];
 
+/// The winning run of stones, from `start` to `end` along `dir`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct WinLine {
+    start: Pt,
+    end: Pt,
+    dir: Pt,
+}
+
+/// Inclusive rectangle of playable points on a bounded board.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+struct Bounds {
+    min: Pt,
+    max: Pt,
+}
+
+impl Bounds {
+    /// An `n`×`n` board roughly centred on the origin.
+    fn square(n: i32) -> Self {
+        let lo = -(n / 2);
+        Self {
+            min: Pt::new(lo, lo),
+            max: Pt::new(lo + n - 1, lo + n - 1),
+        }
+    }
+
+    fn contains(&self, p: Pt) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x) && (self.min.y..=self.max.y).contains(&p.y)
+    }
+
+    fn size(&self) -> i32 {
+        self.max.x - self.min.x + 1
+    }
+}
+
+/// Captured pairs that win a Pente game.
+const CAPTURES_TO_WIN: u32 = 5;
+
+/// All eight neighbour directions (captures run both ways along each line).
+const DIRS8: [Pt; 8] = [
+    Pt { x: 1, y: 0 },
+    Pt { x: -1, y: 0 },
+    Pt { x: 0, y: 1 },
+    Pt { x: 0, y: -1 },
+    Pt { x: 1, y: 1 },
+    Pt { x: -1, y: -1 },
+    Pt { x: 1, y: -1 },
+    Pt { x: -1, y: 1 },
+];
+
+/// Rule options fixed for the lifetime of a game; `schema` has them as JSON.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Rules {
+    /// `None` is the infinite board.
+    bounds: Option<Bounds>,
+    /// Stones in a row needed to win.
+    win_len: i32,
+    /// Pente: flanking exactly two enemy stones removes them, and
+    /// `CAPTURES_TO_WIN` captured pairs also wins.
+    captures: bool,
+    /// Stones per turn after Black's single opening stone (Connect6: 2).
+    stones_per_turn: u8,
+    /// The AI's heuristic rather than a rule, but carried with the rules so every
+    /// copy of a game (replays, analysis, search nodes) scores points alike.
+    #[serde(skip)]
+    weights: Weights,
+}
+
+impl Rules {
+    /// Clamp user-supplied options to something playable: a win length of 3..=9
+    /// and a board that fits at least one winning line.
+    fn validated(mut self) -> Self {
+        self.win_len = self.win_len.clamp(3, 9);
+        self.stones_per_turn = self.stones_per_turn.clamp(1, 2);
+        if self.bounds.is_some_and(|b| b.size() < self.win_len.max(5)) {
+            self.bounds = None;
+        }
+        self
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            bounds: None,
+            win_len: 5,
+            captures: false,
+            stones_per_turn: 1,
+            weights: Weights::default(),
+        }
+    }
+}
+
+/// Named rule sets; the board size is chosen separately.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Variant {
+    Standard,
+    Pente,
+    Connect6,
+}
+
+impl Variant {
+    fn parse(name: &str) -> Option<Variant> {
+        match name {
+            "standard" | "gomoku" => Some(Variant::Standard),
+            "pente" => Some(Variant::Pente),
+            "connect6" => Some(Variant::Connect6),
+            _ => None,
+        }
+    }
+
+    /// The name `parse` takes.
+    fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::Pente => "pente",
+            Variant::Connect6 => "connect6",
+        }
+    }
+
+    fn label(self) -> Msg {
+        match self {
+            Variant::Standard => Msg::VariantStandard,
+            Variant::Pente => Msg::VariantPente,
+            Variant::Connect6 => Msg::VariantConnect6,
+        }
+    }
+
+    /// The variant `rules` is closest to.
+    fn of(rules: Rules) -> Variant {
+        match (rules.captures, rules.stones_per_turn) {
+            (true, _) => Variant::Pente,
+            (false, 2) => Variant::Connect6,
+            _ => Variant::Standard,
+        }
+    }
+
+    fn next(self) -> Variant {
+        match self {
+            Variant::Standard => Variant::Pente,
+            Variant::Pente => Variant::Connect6,
+            Variant::Connect6 => Variant::Standard,
+        }
+    }
+
+    /// `rules` with this variant's win length, captures and stones per turn.
+    fn apply(self, rules: Rules) -> Rules {
+        let (win_len, captures, stones_per_turn) = match self {
+            Variant::Standard => (5, false, 1),
+            Variant::Pente => (5, true, 1),
+            Variant::Connect6 => (6, false, 2),
+        };
+        Rules {
+            win_len,
+            captures,
+            stones_per_turn,
+            ..rules
+        }
+    }
+}
+
+/// How the game was decided.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum WinReason {
+    FiveInRow,
+    /// Pente: `CAPTURES_TO_WIN` pairs taken.
+    Captures,
+    /// The loser's clock ran out.
+    Time,
+}
+
 #[derive(Clone)]
 struct Game {
-    cells: HashMap<Pt, Color>,
+    rules: Rules,
+    cells: PtMap<Color>,
     player: Color,
     winner: Option<Color>,
+    win_reason: Option<WinReason>,
+    win_line: Option<WinLine>,
     last_move: Option<Pt>,
-    frontier: HashSet<Pt>,
+    /// Every stone in play order; `moves[i]` is move number `i + 1`.
+    moves: Vec<Pt>,
+    /// Pairs captured by Black and White.
+    captures: [u32; 2],
+    /// Stones removed by the most recent move.
+    last_captured: Vec<Pt>,
+    /// Stones `player` may still place before the turn passes (Connect6).
+    stones_left: u8,
+    /// XOR of `zobrist` over every stone on the board.
+    hash: u64,
+    frontier: PtSet,
+}
+
+/// A pseudo-random 64-bit code for a stone, for position hashing (splitmix64).
+fn zobrist(p: Pt, c: Color) -> u64 {
+    let mut z = ((p.x as u32 as u64) << 32 | p.y as u32 as u64) ^ (c as u64 + 1) << 62;
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
 
 impl Game {
     fn new() -> Self {
+        Self::with_rules(Rules::default())
+    }
+
+    fn with_rules(rules: Rules) -> Self {
         let mut g = Self {
-            cells: HashMap::new(),
+            rules,
+            cells: PtMap::default(),
             player: Color::Black,
             winner: None,
+            win_reason: None,
+            win_line: None,
             last_move: None,
-            frontier: HashSet::new(),
+            moves: Vec::new(),
+            captures: [0; 2],
+            last_captured: Vec::new(),
+            stones_left: 1,
+            hash: 0,
+            frontier: PtSet::default(),
         };
         g.rebuild_frontier();
         g
     }
 
+    /// Replay `moves` from the empty board; illegal entries are skipped.
+    fn from_moves(rules: Rules, moves: &[Pt]) -> Self {
+        let mut g = Self::with_rules(rules);
+        for &p in moves {
+            g.play(p);
+        }
+        g
+    }
+
+    /// Replay `moves` as `(x, y)` pairs for a test, which fails on an illegal one.
+    #[cfg(test)]
+    fn from_legal_moves(rules: Rules, moves: &[(i32, i32)]) -> Self {
+        let moves: Vec<Pt> = moves.iter().map(|&(x, y)| Pt::new(x, y)).collect();
+        let game = Self::from_moves(rules, &moves);
+        assert_eq!(game.moves, moves, "every move should be legal");
+        game
+    }
+
     fn reset(&mut self) {
         self.cells.clear();
         self.player = Color::Black;
         self.winner = None;
+        self.win_reason = None;
+        self.win_line = None;
         self.last_move = None;
+        self.moves.clear();
+        self.captures = [0; 2];
+        self.last_captured.clear();
+        self.stones_left = 1;
+        self.hash = 0;
         self.frontier.clear();
         self.rebuild_frontier();
     }
 
+    /// Identifies the position for the search's transposition table: the stones,
+    /// who is to move (and with how many stones left), and the capture counts.
+    fn key(&self) -> u64 {
+        let turn = (self.player as u64) << 8 | self.stones_left as u64;
+        let caps = (self.captures[0] as u64) << 16 | (self.captures[1] as u64) << 24;
+        self.hash ^ (turn | caps).wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
     #[inline]
     fn color_at(&self, p: Pt) -> Option<&Color> {
         self.cells.get(&p)
     }
 
+    /// The smallest rectangle holding every stone, if there are any.
+    fn stone_extent(&self) -> Option<Bounds> {
+        let mut stones = self.cells.keys();
+        let first = *stones.next()?;
+        Some(stones.fold(Bounds { min: first, max: first }, |b, p| Bounds {
+            min: Pt::new(b.min.x.min(p.x), b.min.y.min(p.y)),
+            max: Pt::new(b.max.x.max(p.x), b.max.y.max(p.y)),
+        }))
+    }
+
+    #[inline]
+    fn in_bounds(&self, p: Pt) -> bool {
+        self.rules.bounds.is_none_or(|b| b.contains(p))
+    }
+
+    /// An on-board point with no stone. Off-board points count as blocked.
     #[inline]
     fn is_empty(&self, p: Pt) -> bool {
-        !self.cells.contains_key(&p)
+        !self.cells.contains_key(&p) && self.in_bounds(p)
     }
 
     fn playable(&self, p: Pt) -> bool {
         self.winner.is_none() && self.is_empty(p)
     }
 
+    /// Whether `p` would end the side to move's turn with the opponent able to
+    /// win on the spot, say by leaving their four (or open four) unanswered.
+    /// When their turn is two stones, a stone that makes a four wins too.
+    fn leaves_win(&self, p: Pt) -> bool {
+        let mut next = self.clone();
+        if !next.play(p) || next.winner.is_some() || next.player == self.player {
+            return false;
+        }
+        let opp = next.player;
+        let two = next.stones_left >= 2;
+        next.frontier.iter().any(|&q| {
+            next.is_empty(q)
+                && match threats::classify(&next, q, opp) {
+                    Threat::Five => true,
+                    Threat::Four | Threat::FourThree | Threat::DoubleFour | Threat::OpenFour => two,
+                    _ => false,
+                }
+        })
+    }
+
     fn play(&mut self, p: Pt) -> bool {
         if !self.playable(p) {
             return false;
         }
         self.cells.insert(p, self.player);
+        self.hash ^= zobrist(p, self.player);
         self.last_move = Some(p);
-        if self.check_win(p, self.player) {
+        self.moves.push(p);
+        self.last_captured.clear();
+        if self.rules.captures {
+            for pair in self.captures_at(p, self.player) {
+                for q in pair {
+                    self.cells.remove(&q);
+                    self.hash ^= zobrist(q, self.player.other());
+                    self.last_captured.push(q);
+                }
+                self.captures[self.player as usize] += 1;
+            }
+        }
+        if let Some(line) = self.check_win(p, self.player) {
             self.winner = Some(self.player);
+            self.win_reason = Some(WinReason::FiveInRow);
+            self.win_line = Some(line);
+        } else if self.captures[self.player as usize] >= CAPTURES_TO_WIN {
+            self.winner = Some(self.player);
+            self.win_reason = Some(WinReason::Captures);
+        }
+        self.stones_left -= 1;
+        if self.stones_left == 0 {
+            self.player = self.player.other();
+            self.stones_left = self.rules.stones_per_turn;
         }
-        self.player = self.player.other();
         self.rebuild_frontier();
         true
     }
 
+    /// Enemy pairs that `who` playing at `p` would flank: `p X X who` along any direction.
+    fn captures_at(&self, p: Pt, who: Color) -> Vec<[Pt; 2]> {
+        let opp = who.other();
+        DIRS8
+            .iter()
+            .filter_map(|d| {
+                let a = p.add(d.x, d.y);
+                let b = p.add(2 * d.x, 2 * d.y);
+                let c = p.add(3 * d.x, 3 * d.y);
+                (self.color_at(a) == Some(&opp)
+                    && self.color_at(b) == Some(&opp)
+                    && self.color_at(c) == Some(&who))
+                .then_some([a, b])
+            })
+            .collect()
+    }
+
+    /// End the game in `loser`'s opponent's favour because `loser` ran out of time.
+    fn lose_on_time(&mut self, loser: Color) {
+        if self.winner.is_none() {
+            self.winner = Some(loser.other());
+            self.win_reason = Some(WinReason::Time);
+        }
+    }
+
     fn rebuild_frontier(&mut self) {
         self.frontier.clear();
         if self.cells.is_empty() {
@@ -172,7 +903,7 @@ impl Game {
             for dx in -2..=2 {
                 for dy in -2..=2 {
                     let q = p.add(dx, dy);
-                    if !self.cells.contains_key(&q) {
+                    if self.is_empty(q) {
                         self.frontier.insert(q);
                     }
                 }
@@ -180,16 +911,19 @@ impl Game {
         }
     }
 
-    fn check_win(&self, p: Pt, who: Color) -> bool {
+    fn check_win(&self, p: Pt, who: Color) -> Option<WinLine> {
         for d in DIRS {
-            let mut count = 1;
-            count += self.ray(p, d, who);
-            count += self.ray(p, Pt::new(-d.x, -d.y), who);
-            if count >= 5 {
-                return true;
+            let fwd = self.ray(p, d, who);
+            let back = self.ray(p, Pt::new(-d.x, -d.y), who);
+            if 1 + fwd + back >= self.rules.win_len {
+                return Some(WinLine {
+                    start: p.add(-d.x * back, -d.y * back),
+                    end: p.add(d.x * fwd, d.y * fwd),
+                    dir: d,
+                });
             }
         }
-        false
+        None
     }
 
     fn ray(&self, mut p: Pt, d: Pt, who: Color) -> i32 {
@@ -202,76 +936,176 @@ impl Game {
         c
     }
 
-    fn line_len_open(&self, p: Pt, d: Pt, who: Color) -> (i32, i32) {
-        let mut a = 0;
-        let mut q = p.add(d.x, d.y);
-        while self.color_at(q) == Some(&who) {
-            a += 1;
-            q = q.add(d.x, d.y);
+    fn score_point(&self, p: Pt, who: Color) -> i32 {
+        self.score_point_styled(p, who, Style::Balanced)
+    }
+
+    /// `score_point` with its attacking half (what `who` builds) and defending half
+    /// (what `who` takes from the opponent) weighted by `style`.
+    fn score_point_styled(&self, p: Pt, who: Color, style: Style) -> i32 {
+        if !self.is_empty(p) {
+            return i32::MIN / 4;
+        }
+        self.score_shapes(p, who, &patterns::shapes(self, p, who), style)
+    }
+
+    /// `score_point_styled` for empty `p`, whose `patterns::shapes` are known.
+    fn score_shapes(&self, p: Pt, who: Color, shapes: &[(Shape, Shape); 4], style: Style) -> i32 {
+        let w = &self.rules.weights;
+        let (mut attack, mut defend) = (0, 0);
+        for &(mine, theirs) in shapes {
+            attack += w.attack(mine);
+            defend += w.defence(theirs);
+        }
+        if self.rules.captures {
+            let (take, deny) = self.capture_weights(p, who);
+            attack += take;
+            defend += deny;
+        }
+        let (wa, wd) = style.weights();
+        (attack as i64 * wa as i64 / 100 + defend as i64 * wd as i64 / 100) as i32
+    }
+
+    /// What captures at `p` are worth to `who`: taking pairs (more as the count
+    /// nears a win), and denying the opponent theirs.
+    fn capture_weights(&self, p: Pt, who: Color) -> (i32, i32) {
+        let taken = self.captures[who as usize] as i32;
+        let lost = self.captures[who.other() as usize] as i32;
+        let w = &self.rules.weights;
+        (
+            w.captures(self.captures_at(p, who).len() as i32, taken),
+            w.denied_captures(self.captures_at(p, who.other()).len() as i32, lost),
+        )
+    }
+
+    /// Static assessment from Black's point of view: the side to move's best point
+    /// against the opponent's, or a decisive score once someone has won.
+    fn evaluate(&self) -> i32 {
+        if let Some(w) = self.winner {
+            return if w == Color::Black { 1_000_000 } else { -1_000_000 };
         }
-        let mut b = 0;
-        let mut r = p.add(-d.x, -d.y);
-        while self.color_at(r) == Some(&who) {
-            b += 1;
-            r = r.add(-d.x, -d.y);
+        let best = |who: Color| self.best_move(who).map_or(0, |(_, s)| s);
+        let rel = best(self.player) - best(self.player.other());
+        match self.player {
+            Color::Black => rel,
+            Color::White => -rel,
         }
-        (a, b)
     }
 
-    fn open_ends(&self, p: Pt, d: Pt, who: Color) -> i32 {
-        let mut open = 0;
-        let mut q = p.add(d.x, d.y);
-        while self.color_at(q) == Some(&who) {
-            q = q.add(d.x, d.y);
+    /// Static assessment from `side`'s point of view, weighing its own best point
+    /// against the opponent's as `style` prefers. Balanced agrees with `evaluate`.
+    fn evaluate_for(&self, side: Color, style: Style) -> i32 {
+        if let Some(w) = self.winner {
+            return if w == side { 1_000_000 } else { -1_000_000 };
         }
-        if self.is_empty(q) {
-            open += 1;
+        let best = |who: Color| self.best_move(who).map_or(0, |(_, s)| s) as i64;
+        let (wa, wd) = style.weights();
+        ((best(side) * wa as i64 - best(side.other()) * wd as i64) / 100) as i32
+    }
+
+    /// How many turns the side to move takes within the next `plies` stones,
+    /// the current one included, when the last of them is theirs.
+    fn own_turns_in(&self, plies: u32) -> u32 {
+        let (mut turns, mut mine, mut left) = (1, true, self.stones_left);
+        for _ in 1..plies {
+            left -= 1;
+            if left == 0 {
+                (mine, left) = (!mine, self.rules.stones_per_turn);
+                turns += mine as u32;
+            }
         }
-        let mut r = p.add(-d.x, -d.y);
-        while self.color_at(r) == Some(&who) {
-            r = r.add(-d.x, -d.y);
+        turns
+    }
+
+    /// The AI's move: a book reply in the opening, the first step of a forced win if
+    /// the solver proves one, otherwise whatever `ai`'s backend picks among
+    /// threat-bearing moves.
+    fn ai_move(&self, ai: AiConfig, rng: &mut Rng, tt: &mut Tt) -> Option<Pt> {
+        self.ai_line(ai, rng, tt).1.first().copied()
+    }
+
+    /// `ai_move` followed by the continuation the AI expects (its principal
+    /// variation), where it has one, and how it was found. The line is empty if
+    /// there is nothing to play.
+    fn ai_line(&self, ai: AiConfig, rng: &mut Rng, tt: &mut Tt) -> (Source, Vec<Pt>) {
+        if let Some(found) = self.ai_shortcut(ai, rng) {
+            return found;
         }
-        if self.is_empty(r) {
-            open += 1;
+        let (depth, nodes) = (ai.depth(), ai.nodes());
+        match ai.backend {
+            Backend::AlphaBeta => {
+                let best = search::search(self, ai.style, depth, nodes, rng, tt);
+                self.searched_line(ai, best, rng, tt)
+            }
+            Backend::Mcts => {
+                let line = mcts::search(self, ai.style, ai.iterations(), rng);
+                self.line_or_fallback(ai, line, rng)
+            }
         }
-        open
     }
 
-    fn score_point(&self, p: Pt, who: Color) -> i32 {
-        if !self.is_empty(p) {
-            return i32::MIN / 4;
+    /// The moves `ai_line` plays without searching: from the book, a handicap
+    /// slip, or a forced win the solver found.
+    fn ai_shortcut(&self, ai: AiConfig, rng: &mut Rng) -> Option<(Source, Vec<Pt>)> {
+        if let Some(p) = book::lookup(self, rng) {
+            return Some((Source::Book, vec![p]));
         }
-        let mut s = 0;
-        for d in DIRS {
-            let (a, b) = self.line_len_open(p, d, who);
-            let len = a + 1 + b;
-            let open = self.open_ends(p, d, who);
-            s += match (len, open) {
-                (l, _) if l >= 5 => 1_000_000,
-                (4, 2) => 50_000,
-                (4, 1) => 20_000,
-                (3, 2) => 10_000,
-                (3, 1) => 1_000,
-                (2, 2) => 500,
-                (2, 1) => 100,
-                (1, 2) => 50,
-                _ => 10,
-            };
+        if let Some(p) = self.handicap_move(ai.handicap, rng) {
+            return Some((Source::Handicap, vec![p]));
+        }
+        let line = solver::solve(self)?;
+        let turns = self.own_turns_in(line.len() as u32);
+        Some((Source::ForcedWin { turns }, line))
+    }
 
-            let opp = who.other();
-            let (oa, ob) = self.line_len_open(p, d, opp);
-            let olen = oa + 1 + ob;
-            let oopen = self.open_ends(p, d, opp);
-            s += match (olen, oopen) {
-                (l, _) if l >= 5 => 900_000,
-                (4, 2) => 40_000,
-                (4, 1) => 15_000,
-                (3, 2) => 8_000,
-                (3, 1) => 800,
-                _ => 0,
-            };
+    /// `ai_line` once the alpha-beta search has picked `best`.
+    fn searched_line(
+        &self,
+        ai: AiConfig,
+        best: Option<(Pt, i32)>,
+        rng: &mut Rng,
+        tt: &mut Tt,
+    ) -> (Source, Vec<Pt>) {
+        let line = match best {
+            Some((p, score)) => {
+                let line = search::principal_variation(self, ai.style, p, tt, PV_LEN);
+                if let Some(plies) = search::win_plies(score) {
+                    let turns = self.own_turns_in(plies);
+                    return (Source::ForcedWin { turns }, line);
+                }
+                line
+            }
+            None => Vec::new(),
+        };
+        self.line_or_fallback(ai, line, rng)
+    }
+
+    fn line_or_fallback(&self, ai: AiConfig, line: Vec<Pt>, rng: &mut Rng) -> (Source, Vec<Pt>) {
+        if !line.is_empty() {
+            return (Source::Search, line);
+        }
+        let fallback = self.varied_best_move(self.player, ai.style, rng);
+        (Source::Search, fallback.map(|(p, _)| vec![p]).unwrap_or_default())
+    }
+
+    /// Sometimes, a deliberately weaker move: one of the next few candidates by
+    /// `score_point`, as long as it keeps at least a third of the best one's score.
+    /// Positions with a four or an open three on the board are always played
+    /// properly, so the slips look like missed chances rather than blunders.
+    fn handicap_move(&self, level: u8, rng: &mut Rng) -> Option<Pt> {
+        if level == 0 || rng.below(100) >= 25 * level as usize {
+            return None;
         }
-        s
+        let mut ranked = threats::candidates(self);
+        let top = *ranked.first()?;
+        if top.mine >= Threat::Four || top.theirs >= Threat::Four {
+            return None;
+        }
+        ranked.sort_by_key(|c| std::cmp::Reverse(c.score));
+        let k = (1 + rng.below(level as usize)).min(ranked.len() - 1);
+        Some(ranked[k])
+            .filter(|c| c.score >= ranked[0].score / 3)
+            .map(|c| c.p)
     }
 
     fn best_move(&self, who: Color) -> Option<(Pt, i32)> {
@@ -286,477 +1120,5473 @@ impl Game {
         }
         best
     }
-}
 
-/* ---------- App / UI ---------- */
+    /// Like `best_move` with `style`'s weighting, but chosen at random among every
+    /// point within `near_equal` of the top score, so games against the AI vary.
+    fn varied_best_move(&self, who: Color, style: Style, rng: &mut Rng) -> Option<(Pt, i32)> {
+        let scored: Vec<(Pt, i32)> = self
+            .frontier
+            .iter()
+            .map(|&p| (p, self.score_point_styled(p, who, style)))
+            .collect();
+        let top = scored.iter().map(|&(_, s)| s).max()?;
+        let near: Vec<(Pt, i32)> = scored
+            .into_iter()
+            .filter(|&(_, s)| s >= top - near_equal(top))
+            .collect();
+        Some(near[rng.below(near.len())])
+    }
+}
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
-enum Controller {
-    Human,
-    AI,
+/// How far below the best score a move still counts as just as good when the
+/// AI picks at random among its top choices: 2%, and at least a few points.
+fn near_equal(best: i32) -> i32 {
+    (best.abs() / 50).max(20)
 }
 
-struct App {
-    canvas: HtmlCanvasElement,
-    ctx: CanvasRenderingContext2d,
-    game: Game,
+/* ---------- AI configuration ---------- */
 
-    ctrl_black: Controller,
-    ctrl_white: Controller,
+/// How the AI picks moves once no forced win is found.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+enum Backend {
+    /// Iterative-deepening alpha-beta (`search`).
+    #[default]
+    AlphaBeta,
+    /// Monte Carlo tree search (`mcts`).
+    Mcts,
+}
 
-    cell_px: f64,
-    cam_x: f64,
-    cam_y: f64,
-    view_w: f64,
-    view_h: f64,
+impl Backend {
+    fn parse(name: &str) -> Option<Backend> {
+        match name {
+            "alphabeta" | "minimax" => Some(Backend::AlphaBeta),
+            "mcts" => Some(Backend::Mcts),
+            _ => None,
+        }
+    }
+}
 
-    want_ai: bool,
-    next_ai_at_ms: f64,
+/// How the AI trades building its own lines against breaking the opponent's.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Style {
+    #[default]
+    Balanced,
+    Aggressive,
+    Defensive,
+}
 
-    btn_black: (f64, f64, f64, f64),
-    btn_white: (f64, f64, f64, f64),
+impl Style {
+    fn parse(name: &str) -> Option<Style> {
+        match name {
+            "balanced" => Some(Style::Balanced),
+            "aggressive" => Some(Style::Aggressive),
+            "defensive" => Some(Style::Defensive),
+            _ => None,
+        }
+    }
 
-    dirty: bool,
-}
+    /// The name shown in the HUD, translated.
+    fn label(self) -> String {
+        t(match self {
+            Style::Balanced => Msg::Balanced,
+            Style::Aggressive => Msg::Aggressive,
+            Style::Defensive => Msg::Defensive,
+        })
+    }
 
-impl App {
-    fn new(canvas: HtmlCanvasElement, ctx: CanvasRenderingContext2d, game: Game) -> Self {
-        Self {
-            canvas,
-            ctx,
-            game,
-            ctrl_black: Controller::Human,
-            ctrl_white: Controller::AI,
-            cell_px: 36.0,
-            cam_x: 0.0,
-            cam_y: 0.0,
-            view_w: 0.0,
-            view_h: 0.0,
-            want_ai: false,
-            next_ai_at_ms: 0.0,
-            btn_black: (0.0, 0.0, 0.0, 0.0),
-            btn_white: (0.0, 0.0, 0.0, 0.0),
-            dirty: true,
+    fn next(self) -> Style {
+        match self {
+            Style::Balanced => Style::Aggressive,
+            Style::Aggressive => Style::Defensive,
+            Style::Defensive => Style::Balanced,
         }
     }
 
-    fn attach_listeners(app: &Rc<RefCell<App>>) {
-        // Pointer
-        {
-            let app_rc = Rc::clone(app);
-            let closure = Closure::<dyn FnMut(PointerEvent)>::new(move |e: PointerEvent| {
-                e.prevent_default();
-                app_rc.borrow_mut().on_pointer_down(e);
-            });
-            app.borrow()
-                .canvas
-                .add_event_listener_with_callback("pointerdown", closure.as_ref().unchecked_ref())
-                .unwrap();
-            closure.forget();
-        }
-        // Wheel
-        {
-            let app_rc = Rc::clone(app);
-            let closure = Closure::<dyn FnMut(WheelEvent)>::new(move |e: WheelEvent| {
-                e.prevent_default();
-                app_rc.borrow_mut().on_wheel(e);
-            });
-            app.borrow()
-                .canvas
-                .add_event_listener_with_callback("wheel", closure.as_ref().unchecked_ref())
-                .unwrap();
-            closure.forget();
-        }
-        // Keyboard
-        {
-            let app_rc = Rc::clone(app);
-            let doc = window().unwrap().document().unwrap();
-            let closure = Closure::<dyn FnMut(KeyboardEvent)>::new(move |e: KeyboardEvent| {
-                app_rc.borrow_mut().on_key(e);
-            });
-            doc.add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())
-                .unwrap();
-            closure.forget();
-        }
-        // Resize
-        {
-            let app_rc = Rc::clone(app);
-            let closure = Closure::<dyn FnMut()>::new(move || {
-                let mut a = app_rc.borrow_mut();
-                a.resize();
-                a.render();
-            });
-            window()
-                .unwrap()
-                .add_event_listener_with_callback("resize", closure.as_ref().unchecked_ref())
-                .unwrap();
-            closure.forget();
+    /// Percent weights on attack and defence in `score_point_styled` and `evaluate_for`.
+    fn weights(self) -> (i32, i32) {
+        match self {
+            Style::Balanced => (100, 100),
+            Style::Aggressive => (140, 70),
+            Style::Defensive => (75, 135),
         }
     }
 
-    async fn raf_loop(app: Rc<RefCell<App>>) {
-        loop {
-            {
-                let mut a = app.borrow_mut();
-                a.maybe_ai_step();
-                a.render();
-            }
-            let _ =
-                wasm_bindgen_futures::JsFuture::from(js_sys::Promise::new(&mut |resolve, _| {
-                    window()
-                        .unwrap()
-                        .request_animation_frame(resolve.unchecked_ref())
-                        .unwrap();
-                }))
-                    .await;
+    /// How much worse than even a drawn (full-board) game looks to this style.
+    fn contempt(self) -> i32 {
+        match self {
+            Style::Balanced => 0,
+            Style::Aggressive => 5_000,
+            Style::Defensive => -2_000,
         }
     }
+}
 
-    fn resize(&mut self) {
-        let rect = self
-            .canvas
-            .unchecked_ref::<Element>()
-            .get_bounding_client_rect();
-        self.view_w = rect.width();
-        self.view_h = rect.height();
+/// Longest expected line read back from the search.
+const PV_LEN: usize = 8;
 
-        let dpr = window().unwrap().device_pixel_ratio();
-        self.canvas.set_width((self.view_w * dpr) as u32);
-        self.canvas.set_height((self.view_h * dpr) as u32);
+/// Highest handicap level; each level makes a deliberate slip likelier and deeper.
+const MAX_HANDICAP: u8 = 3;
 
-        let _ = self.ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
-        let _ = self.ctx.scale(dpr, dpr);
+/// Per-side engine settings.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+struct AiConfig {
+    backend: Backend,
+    style: Style,
+    /// 0 plays full strength; at level `n` the AI plays one of its top `n + 1`
+    /// quiet moves instead of the best `25·n`% of the time.
+    handicap: u8,
+    /// Alpha-beta search depth in plies; `None` uses `search::MAX_DEPTH`.
+    max_depth: Option<u32>,
+    /// Thinking time per move; `None` uses `DEFAULT_THINK_MS`.
+    think_ms: Option<u32>,
+}
 
-        self.dirty = true;
-    }
+/// Deepest alpha-beta search a configuration may ask for.
+const DEPTH_LIMIT: u32 = 6;
 
-    fn screen_to_cell_f64(&self, sx: f64, sy: f64) -> (f64, f64) {
-        let x = (sx - self.view_w / 2.0) / self.cell_px + self.cam_x;
-        let y = (sy - self.view_h / 2.0) / self.cell_px + self.cam_y;
-        (x, y)
+/// Thinking times offered by the HUD's Time buttons, shortest first; a time set
+/// from the URL or JS may fall anywhere between the first and last.
+const THINK_PRESETS_MS: [u32; 7] = [50, 120, 250, 500, 1000, 2000, 4000];
+const DEFAULT_THINK_MS: u32 = 120;
+
+fn clamp_think_ms(ms: u32) -> u32 {
+    ms.clamp(THINK_PRESETS_MS[0], THINK_PRESETS_MS[THINK_PRESETS_MS.len() - 1])
+}
+
+/// `0:42`, `12:05`, `1:02:09`.
+fn format_elapsed(ms: f64) -> String {
+    let s = (ms / 1000.0) as i64;
+    if s < 3600 {
+        format!("{}:{:02}", s / 60, s % 60)
+    } else {
+        format!("{}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
     }
-    fn screen_to_cell(&self, sx: f64, sy: f64) -> Pt {
-        let (x, y) = self.screen_to_cell_f64(sx, sy);
-        Pt::new(x.round() as i32, y.round() as i32)
+}
+
+/// `250 ms`, `1 s`, `2.5 s`.
+fn format_think_ms(ms: u32) -> String {
+    if ms < 1000 {
+        format!("{ms} ms")
+    } else {
+        format!("{} s", ms as f64 / 1000.0)
     }
+}
 
-    fn is_human(&self, side: Color) -> bool {
-        match side {
-            Color::Black => self.ctrl_black == Controller::Human,
-            Color::White => self.ctrl_white == Controller::Human,
+impl AiConfig {
+    /// Parse a comma-separated spec such as `"mcts,aggressive"` or `"d2,h1,500ms"`:
+    /// a backend, a style, `d<plies>`, `h<handicap>` and `<n>ms`, each optional.
+    fn parse(spec: &str) -> Option<AiConfig> {
+        let mut ai = AiConfig::default();
+        for token in spec.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            if let Some(backend) = Backend::parse(token) {
+                ai.backend = backend;
+            } else if let Some(style) = Style::parse(token) {
+                ai.style = style;
+            } else if let Some(ms) = token.strip_suffix("ms") {
+                ai.think_ms = Some(clamp_think_ms(ms.parse().ok()?));
+            } else if let Some(d) = token.strip_prefix('d') {
+                ai.max_depth = Some(d.parse().ok().filter(|d| (1..=DEPTH_LIMIT).contains(d))?);
+            } else if let Some(h) = token.strip_prefix('h') {
+                ai.handicap = h.parse().ok().filter(|&h| h <= MAX_HANDICAP)?;
+            } else {
+                return None;
+            }
         }
+        Some(ai)
     }
-    fn is_ai(&self, side: Color) -> bool {
-        !self.is_human(side)
+
+    fn depth(&self) -> u32 {
+        self.max_depth.unwrap_or(search::MAX_DEPTH)
     }
-    fn is_ai_turn(&self) -> bool {
-        self.game.winner.is_none() && self.is_ai(self.game.player)
+
+    fn think_ms(&self) -> u32 {
+        self.think_ms.unwrap_or(DEFAULT_THINK_MS)
     }
 
-    fn queue_ai_soon(&mut self, delay_ms: f64) {
-        let now = window().unwrap().performance().unwrap().now();
-        self.want_ai = true;
-        self.next_ai_at_ms = now + delay_ms;
+    /// Positions the alpha-beta search may visit in `think_ms`.
+    fn nodes(&self) -> u32 {
+        self.think_ms() * search::NODES_PER_MS
     }
-    fn maybe_ai_step(&mut self) {
-        if !self.is_ai_turn() || !self.want_ai {
-            return;
+
+    /// Tree search iterations that fit in `think_ms`.
+    fn iterations(&self) -> u32 {
+        self.think_ms() * mcts::ITERATIONS_PER_SEC / 1000
+    }
+
+    /// Pill text for an AI-controlled side.
+    fn label(&self) -> String {
+        let mut label = t(Msg::Ai);
+        if self.backend == Backend::Mcts {
+            label.push_str(" (MCTS)");
+        } else if let Some(d) = self.max_depth {
+            label.push_str(&format!(" d{d}"));
         }
-        let now = window().unwrap().performance().unwrap().now();
-        if now < self.next_ai_at_ms {
-            return;
+        if self.style != Style::Balanced {
+            label.push_str(&format!(" · {}", self.style.label()));
         }
-        if let Some((ai_p, _)) = self.game.best_move(self.game.player) {
-            self.game.play(ai_p);
-            self.dirty = true;
-            if self.is_ai_turn() {
-                self.queue_ai_soon(120.0);
-            } else {
-                self.want_ai = false;
-            }
-        } else {
-            self.want_ai = false;
+        if self.handicap > 0 {
+            label.push_str(&format!(" −{}", self.handicap));
         }
+        if self.think_ms() != DEFAULT_THINK_MS {
+            label.push_str(&format!(" · {}", format_think_ms(self.think_ms())));
+        }
+        label
     }
+}
 
-    fn on_pointer_down(&mut self, e: PointerEvent) {
-        let rect = self
-            .canvas
-            .unchecked_ref::<Element>()
-            .get_bounding_client_rect();
-        let sx = e.client_x() as f64 - rect.left();
-        let sy = e.client_y() as f64 - rect.top();
+/* ---------- JS API ---------- */
 
-        if self.game.winner.is_some() {
-            self.game.reset();
-            self.dirty = true;
-            if self.is_ai_turn() {
-                self.queue_ai_soon(120.0);
-            } else {
-                self.want_ai = false;
-            }
-            return;
-        }
+/// Rules + AI without the bundled canvas UI, for embedding in other front-ends.
+///
+/// ```js
+/// const g = new GomokuEngine();
+/// g.play(0, 0);
+/// const [x, y] = g.bestMove();
+/// ```
+#[wasm_bindgen]
+pub struct GomokuEngine {
+    game: Game,
+    ai: AiConfig,
+    rng: Rng,
+    tt: Tt,
+}
 
-        // Toggle pills first.
-        if self.hit_btn(self.btn_black, sx, sy) {
-            self.ctrl_black = if self.ctrl_black == Controller::Human {
-                Controller::AI
-            } else {
-                Controller::Human
-            };
-            self.dirty = true;
-            if self.game.player == Color::Black && self.is_ai(Color::Black) {
-                self.queue_ai_soon(80.0);
-            }
-            return;
-        }
-        if self.hit_btn(self.btn_white, sx, sy) {
-            self.ctrl_white = if self.ctrl_white == Controller::Human {
-                Controller::AI
-            } else {
-                Controller::Human
-            };
-            self.dirty = true;
-            if self.game.player == Color::White && self.is_ai(Color::White) {
-                self.queue_ai_soon(80.0);
-            }
-            return;
+#[wasm_bindgen]
+impl GomokuEngine {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GomokuEngine {
+        GomokuEngine {
+            game: Game::new(),
+            ai: AiConfig::default(),
+            rng: Rng::from_entropy(),
+            tt: Tt::default(),
         }
+    }
 
-        // Board play (human-only)
-        if !self.is_human(self.game.player) {
-            return;
-        }
-        let p = self.screen_to_cell(sx, sy);
-        if self.game.play(p) {
-            self.dirty = true;
-            if self.is_ai_turn() {
-                self.queue_ai_soon(120.0);
-            }
+    /// A game needing `win_len` in a row, optionally on a `board_size`×`board_size` board
+    /// and with Pente captures.
+    #[wasm_bindgen(js_name = withRules)]
+    pub fn with_rules(
+        win_len: i32,
+        board_size: Option<i32>,
+        captures: Option<bool>,
+    ) -> GomokuEngine {
+        let rules = Rules {
+            win_len,
+            bounds: board_size.map(Bounds::square),
+            captures: captures.unwrap_or(false),
+            ..Rules::default()
+        };
+        GomokuEngine {
+            game: Game::with_rules(rules.validated()),
+            ai: AiConfig::default(),
+            rng: Rng::from_entropy(),
+            tt: Tt::default(),
         }
     }
 
-    fn hit_btn(&self, btn: (f64, f64, f64, f64), sx: f64, sy: f64) -> bool {
-        let (x, y, w, h) = btn;
-        sx >= x && sx <= x + w && sy >= y && sy <= y + h
+    /// A named rule set: `"standard"`, `"pente"` or `"connect6"`.
+    #[wasm_bindgen(js_name = withVariant)]
+    pub fn with_variant(variant: &str, board_size: Option<i32>) -> Result<GomokuEngine, JsValue> {
+        let v = Variant::parse(variant).ok_or("unknown variant")?;
+        let rules = Rules {
+            bounds: board_size.map(Bounds::square),
+            ..v.apply(Rules::default())
+        };
+        Ok(GomokuEngine {
+            game: Game::with_rules(rules.validated()),
+            ai: AiConfig::default(),
+            rng: Rng::from_entropy(),
+            tt: Tt::default(),
+        })
     }
 
-    fn on_wheel(&mut self, e: WheelEvent) {
-        let rect = self
-            .canvas
-            .unchecked_ref::<Element>()
-            .get_bounding_client_rect();
-        let sx = e.client_x() as f64 - rect.left();
-        let sy = e.client_y() as f64 - rect.top();
+    /// The game as JSON: its rules and moves, in the versioned form `fromJson`
+    /// reads (see `schema`).
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.game).unwrap_or_default()
+    }
 
-        let dx = e.delta_x();
-        let dy = e.delta_y();
+    /// An engine at the game in `json`, as `toJson` writes it. Fails on a
+    /// later schema version or a move that is not legal under its rules.
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<GomokuEngine, JsValue> {
+        let game: Game = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Ok(GomokuEngine {
+            game,
+            ai: AiConfig::default(),
+            rng: Rng::from_entropy(),
+            tt: Tt::default(),
+        })
+    }
 
-        if e.shift_key() || dx.abs() > dy.abs() {
-            let pan_cells = dx / self.cell_px.max(1.0);
-            self.cam_x += pan_cells;
-            self.dirty = true;
-            return;
-        }
+    /// The game as a Gomocup `.psq` file (see `psq`).
+    #[wasm_bindgen(js_name = toPsq)]
+    pub fn to_psq(&self) -> String {
+        psq::write(self.game.rules.bounds, &self.game.moves, &[])
+    }
 
-        let zoom_step = 1.1_f64;
-        let old = self.cell_px;
-        let mut new = if dy < 0.0 { old * zoom_step } else { old / zoom_step };
-        new = new.clamp(12.0, 80.0);
-        if (new - old).abs() < f64::EPSILON {
-            return;
+    /// An engine at the game in a `.psq` file, as standard gomoku on its board.
+    /// Fails if it is not one or a move is not legal.
+    #[wasm_bindgen(js_name = fromPsq)]
+    pub fn from_psq(text: &str) -> Result<GomokuEngine, JsValue> {
+        let (size, moves) = psq::parse(text).ok_or("not a .psq file")?;
+        let rules = Rules {
+            bounds: Some(Bounds::square(size)),
+            ..Rules::default()
+        };
+        let mut game = Game::with_rules(rules.validated());
+        if !moves.iter().all(|&p| game.play(p)) {
+            return Err("illegal move".into());
         }
+        Ok(GomokuEngine {
+            game,
+            ai: AiConfig::default(),
+            rng: Rng::from_entropy(),
+            tt: Tt::default(),
+        })
+    }
 
-        let (cell_x, cell_y) = self.screen_to_cell_f64(sx, sy);
-        self.cell_px = new;
-        self.cam_x = cell_x - (sx - self.view_w / 2.0) / self.cell_px;
-        self.cam_y = cell_y - (sy - self.view_h / 2.0) / self.cell_px;
+    /// Choose how `bestMove` searches: `"alphabeta"` (default) or `"mcts"`.
+    #[wasm_bindgen(js_name = setEngine)]
+    pub fn set_engine(&mut self, name: &str) -> Result<(), JsValue> {
+        self.ai.backend = Backend::parse(name).ok_or("unknown engine")?;
+        Ok(())
+    }
 
-        self.dirty = true;
+    /// The heuristic's pattern weights as a plain object, e.g. `{ five: 1000000,
+    /// openFour: 50000, … }`.
+    pub fn weights(&self) -> js_sys::Object {
+        let obj = js_sys::Object::new();
+        for (name, value) in self.game.rules.weights.entries() {
+            let _ = js_sys::Reflect::set(&obj, &name.into(), &value.into());
+        }
+        obj
     }
 
-    fn on_key(&mut self, e: KeyboardEvent) {
-        match e.key().as_str() {
-            "ArrowLeft" => {
-                self.cam_x -= 3.0;
-                self.dirty = true;
-            }
-            "ArrowRight" => {
-                self.cam_x += 3.0;
-                self.dirty = true;
-            }
-            "ArrowUp" => {
-                self.cam_y -= 3.0;
-                self.dirty = true;
-            }
-            "ArrowDown" => {
-                self.cam_y += 3.0;
-                self.dirty = true;
+    /// Override some of the weights from an object shaped like `weights()`'s;
+    /// others keep their values. Unknown names and non-integers are rejected.
+    #[wasm_bindgen(js_name = setWeights)]
+    pub fn set_weights(&mut self, config: &js_sys::Object) -> Result<(), JsValue> {
+        let mut weights = self.game.rules.weights;
+        for key in js_sys::Object::keys(config).iter() {
+            let name = key.as_string().unwrap_or_default();
+            let value = js_sys::Reflect::get(config, &key)?
+                .as_f64()
+                .filter(|v| v.fract() == 0.0 && v.abs() <= i32::MAX as f64)
+                .ok_or_else(|| format!("weight {name} must be an integer"))?;
+            if !weights.set(&name, value as i32) {
+                return Err(format!("unknown weight {name}").into());
             }
-            "-" => {
-                self.cell_px = (self.cell_px * 0.9).max(12.0);
-                self.dirty = true;
-            }
-            "+" | "=" => {
-                self.cell_px = (self.cell_px * 1.1).min(80.0);
-                self.dirty = true;
-            }
-            "r" | "R" => {
-                self.game.reset();
-                self.dirty = true;
-                if self.is_ai_turn() {
-                    self.queue_ai_soon(120.0);
-                } else {
-                    self.want_ai = false;
-                }
-            }
-            _ => {}
         }
+        self.game.rules.weights = weights;
+        // Scores stored under the old weights no longer apply.
+        self.tt = Tt::default();
+        Ok(())
     }
 
-    fn render(&mut self) {
-        if !self.dirty {
-            return;
-        }
-        self.dirty = false;
-        let w = self.view_w;
-        let h = self.view_h;
+    /// Playstyle for `bestMove`: `"balanced"` (default), `"aggressive"` or `"defensive"`.
+    #[wasm_bindgen(js_name = setStyle)]
+    pub fn set_style(&mut self, name: &str) -> Result<(), JsValue> {
+        self.ai.style = Style::parse(name).ok_or("unknown style")?;
+        Ok(())
+    }
 
-        // background
-        self.ctx.set_fill_style_str("#0b0d11");
-        self.ctx.fill_rect(0.0, 0.0, w, h);
+    /// Time `bestMove` may think for, in milliseconds (50 to 4000; default 120).
+    #[wasm_bindgen(js_name = setThinkTime)]
+    pub fn set_think_time(&mut self, ms: u32) {
+        self.ai.think_ms = Some(clamp_think_ms(ms));
+    }
 
-        // grid
-        self.ctx.set_stroke_style_str("#20242b");
-        self.ctx.set_line_width(1.0);
-        let half_w = (w / 2.0) / self.cell_px;
-        let half_h = (h / 2.0) / self.cell_px;
-        let min_x = (self.cam_x - half_w - 1.0).floor() as i32;
-        let max_x = (self.cam_x + half_w + 1.0).ceil() as i32;
-        let min_y = (self.cam_y - half_h - 1.0).floor() as i32;
-        let max_y = (self.cam_y + half_h + 1.0).ceil() as i32;
+    /// Weaken `bestMove` for beginners: 0 (full strength) to 3.
+    #[wasm_bindgen(js_name = setHandicap)]
+    pub fn set_handicap(&mut self, level: u8) {
+        self.ai.handicap = level.min(MAX_HANDICAP);
+    }
 
-        for gx in min_x..=max_x {
-            let sx = (gx as f64 - self.cam_x) * self.cell_px + w / 2.0;
-            self.ctx.begin_path();
-            self.ctx.move_to(sx, 0.0);
-            self.ctx.line_to(sx, h);
-            self.ctx.stroke();
-        }
-        for gy in min_y..=max_y {
-            let sy = (gy as f64 - self.cam_y) * self.cell_px + h / 2.0;
-            self.ctx.begin_path();
-            self.ctx.move_to(0.0, sy);
-            self.ctx.line_to(w, sy);
-            self.ctx.stroke();
+    /// Place a stone for the side to move. Returns `false` if the move is illegal.
+    pub fn play(&mut self, x: i32, y: i32) -> bool {
+        self.game.play(Pt::new(x, y))
+    }
+
+    /// The engine's choice for the side to move as `[x, y]`, or `undefined` once the game is over.
+    #[wasm_bindgen(js_name = bestMove)]
+    pub fn best_move(&mut self) -> Option<Vec<i32>> {
+        if self.game.winner.is_some() {
+            return None;
         }
+        self.game
+            .ai_move(self.ai, &mut self.rng, &mut self.tt)
+            .map(|p| vec![p.x, p.y])
+    }
 
-        // stones
-        for (&p, &c) in self.game.cells.iter() {
-            let sx = (p.x as f64 - self.cam_x) * self.cell_px + w / 2.0;
-            let sy = (p.y as f64 - self.cam_y) * self.cell_px + h / 2.0;
-            if sx < -self.cell_px
-                || sx > w + self.cell_px
-                || sy < -self.cell_px
-                || sy > h + self.cell_px
-            {
-                continue;
-            }
-            let r = self.cell_px * 0.4;
-            self.ctx.begin_path();
-            let _ = self.ctx.arc(sx, sy, r, 0.0, std::f64::consts::TAU);
-            match c {
-                Color::Black => self.ctx.set_fill_style_str("#e6edf3"),
-                Color::White => self.ctx.set_fill_style_str("#38bdf8"),
+    /// The engine's `n` best moves for the side to move, best first, each as
+    /// `{ x, y, score, line }`: the search score from the mover's side and the
+    /// expected continuation as flat `x, y` pairs. Empty once the game is over.
+    #[wasm_bindgen(js_name = topMoves)]
+    pub fn top_moves(&self, n: usize) -> js_sys::Array {
+        let out = js_sys::Array::new();
+        if self.game.winner.is_some() {
+            return out;
+        }
+        let ai = self.ai;
+        let lines = search::top_lines(&self.game, ai.style, ai.depth(), ai.nodes(), n, PV_LEN);
+        for (score, line) in lines {
+            let rest: Vec<i32> = line[1..].iter().flat_map(|p| [p.x, p.y]).collect();
+            let obj = js_sys::Object::new();
+            for (key, value) in [
+                ("x", line[0].x.into()),
+                ("y", line[0].y.into()),
+                ("score", score.into()),
+                ("line", js_sys::Int32Array::from(rest.as_slice()).into()),
+            ] {
+                let _ = js_sys::Reflect::set(&obj, &key.into(), &value);
             }
-            self.ctx.fill();
+            out.push(&obj);
         }
+        out
+    }
 
-        // HUD: controller pills only; no turn text.
-        self.draw_controller_pills();
+    pub fn winner(&self) -> Option<Color> {
+        self.game.winner
+    }
 
-        // Winner overlay (centered) unchanged
-        if let Some(winner) = self.game.winner {
-            let msg = match winner {
-                Color::Black => "You win!",
-                Color::White => "AI wins!",
-            };
-            let sub = "Click or Press R to play again";
+    /// Side to move.
+    pub fn player(&self) -> Color {
+        self.game.player
+    }
 
-            let w2 = w / 2.0;
-            let h2 = h / 2.0;
+    /// All stones as a flat array of `x, y, color` triples.
+    pub fn cells(&self) -> Vec<i32> {
+        let mut out = Vec::with_capacity(self.game.cells.len() * 3);
+        for (&p, &c) in self.game.cells.iter() {
+            out.extend_from_slice(&[p.x, p.y, c as i32]);
+        }
+        out
+    }
 
-            self.ctx
-                .set_font("bold 36px ui-sans-serif, system-ui, -apple-system");
-            let msg_w = self
-                .ctx
-                .measure_text(msg)
-                .ok()
-                .map(|m| m.width())
-                .unwrap_or(0.0);
+    pub fn reset(&mut self) {
+        self.game.reset();
+    }
+}
 
-            self.ctx
-                .set_font("16px ui-sans-serif, system-ui, -apple-system");
-            let sub_w = self
+impl Default for GomokuEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/* ---------- App / UI ---------- */
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Controller {
+    Human,
+    AI,
+    /// Moves arrive over the network (see `net`).
+    Remote,
+}
+
+fn now_ms() -> f64 {
+    window().unwrap().performance().unwrap().now()
+}
+
+/// Whether the browser asks for less motion (`prefers-reduced-motion: reduce`).
+fn prefers_reduced_motion() -> bool {
+    window()
+        .and_then(|w| w.match_media("(prefers-reduced-motion: reduce)").ok().flatten())
+        .is_some_and(|m| m.matches())
+}
+
+/// Screen distance (CSS px) a pointer must travel before a press becomes a pan.
+const DRAG_THRESHOLD_PX: f64 = 6.0;
+
+/// How long a press must be held still before the magnifier opens.
+const LONG_PRESS_MS: f64 = 450.0;
+/// Player names are cut to this many characters, to keep the pills short.
+const MAX_NAME_CHARS: usize = 24;
+/// Radius of the magnifier (CSS px), its zoom, and its offset above the finger.
+const LOUPE_RADIUS_PX: f64 = 60.0;
+const LOUPE_ZOOM: f64 = 2.0;
+const LOUPE_LIFT_PX: f64 = 100.0;
+
+/// Two taps this close in time and space, neither of which would play, zoom in.
+const DOUBLE_TAP_MS: f64 = 300.0;
+const DOUBLE_TAP_PX: f64 = 24.0;
+/// Zoom factor of a double tap.
+const DOUBLE_TAP_ZOOM: f64 = 2.0;
+/// How long an animated camera move (zoom, recentre, jump to a move) takes.
+const CAMERA_TWEEN_MS: f64 = 250.0;
+/// Zoom factor of one `+`/`-` press.
+const KEY_ZOOM: f64 = 1.1;
+/// The zoom range, in CSS px per cell.
+const MIN_CELL_PX: f64 = 4.0;
+const MAX_CELL_PX: f64 = 80.0;
+/// Below this zoom the grid is one faded path and stones are squares filled a
+/// colour at a time, so a screenful of thousands of cells stays cheap.
+const LOD_CELL_PX: f64 = 10.0;
+
+/// A tap that would not have played or pressed anything, waiting for a second.
+#[derive(Copy, Clone, Debug)]
+struct IdleTap {
+    pos: (f64, f64),
+    ms: f64,
+}
+
+/// Where the view is centred, in cells, and its zoom.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Camera {
+    x: f64,
+    y: f64,
+    px: f64,
+}
+
+/// Star points fall on every eighth line unless `?stars=` says otherwise.
+const DEFAULT_STAR_SPACING: i32 = 8;
+
+/// What the grid layer was drawn for: the camera and view size, theme, board
+/// edge and star spacing.
+type GridKey = ((f64, f64, f64, f64, f64), usize, Option<Bounds>, i32);
+/// What the stone layer was drawn for: the camera and view size, theme and
+/// White's marks. Changes of position alone are patched in cell by cell.
+type StoneKey = ((f64, f64, f64, f64, f64), usize, bool);
+
+/// More changed cells than this in one frame, as after a long replay jump, are
+/// drawn by redrawing the stone layer whole.
+const MAX_PATCHED_CELLS: usize = 48;
+
+/// Longest side of the minimap's map, and its padding inside the box, in CSS px.
+const MINIMAP_PX: f64 = 120.0;
+const MINIMAP_PAD: f64 = 6.0;
+
+/// Where the minimap was last drawn: its box, and the cell at the map's
+/// top-left corner and the px per cell, to turn a tap back into a cell.
+#[derive(Copy, Clone, Debug)]
+struct Minimap {
+    rect: (f64, f64, f64, f64),
+    origin: (f64, f64),
+    scale: f64,
+}
+
+impl Minimap {
+    fn cell_at(&self, sx: f64, sy: f64) -> (f64, f64) {
+        (
+            self.origin.0 + (sx - self.rect.0 - MINIMAP_PAD) / self.scale,
+            self.origin.1 + (sy - self.rect.1 - MINIMAP_PAD) / self.scale,
+        )
+    }
+}
+
+/// An eased camera move. The centre moves linearly in step with `1 / px`, so a
+/// zoom around a point keeps that point still throughout.
+#[derive(Copy, Clone, Debug)]
+struct CameraTween {
+    from: Camera,
+    to: Camera,
+    start_ms: f64,
+}
+
+impl CameraTween {
+    /// The camera at `now`, and whether the move is over.
+    fn at(&self, now: f64) -> (Camera, bool) {
+        let t = ((now - self.start_ms) / CAMERA_TWEEN_MS).clamp(0.0, 1.0);
+        let eased = 1.0 - (1.0 - t).powi(3);
+        let lerp = |a: f64, b: f64| a + (b - a) * eased;
+        let cam = Camera {
+            x: lerp(self.from.x, self.to.x),
+            y: lerp(self.from.y, self.to.y),
+            px: 1.0 / lerp(1.0 / self.from.px, 1.0 / self.to.px),
+        };
+        (cam, t >= 1.0)
+    }
+}
+
+/// A released pan keeps gliding when it was moving faster than this (CSS px per
+/// ms) within the last `GLIDE_RELEASE_MS`; its speed then decays with time
+/// constant `GLIDE_TAU_MS` until below `GLIDE_STOP`.
+const GLIDE_MIN_SPEED: f64 = 0.3;
+const GLIDE_RELEASE_MS: f64 = 60.0;
+const GLIDE_TAU_MS: f64 = 325.0;
+const GLIDE_STOP: f64 = 0.02;
+
+/// The camera coasting after a flick, in screen px per ms.
+#[derive(Copy, Clone, Debug)]
+struct Glide {
+    velocity: (f64, f64),
+    last_ms: f64,
+}
+
+/// How far (CSS px) two fingers must spread or close before they zoom; until
+/// then, moving together, they only pan.
+const PINCH_ZOOM_PX: f64 = 24.0;
+
+/// A two-finger gesture: the fingers' distance when it began, and whether it
+/// has been told apart from a two-finger pan as a pinch.
+#[derive(Copy, Clone, Debug)]
+struct Pinch {
+    start_dist: f64,
+    zooming: bool,
+}
+
+/// Zoom per pixel of wheel travel; a 100 px mouse notch zooms by 10%.
+const WHEEL_ZOOM_PER_PX: f64 = 0.000_953;
+
+/// How the mouse wheel and trackpad move the camera.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct WheelPrefs {
+    /// Multiplies the zoom per unit of wheel travel.
+    sensitivity: f64,
+    /// Zoom in on wheel-down instead of wheel-up.
+    invert: bool,
+    /// The plain wheel pans and Ctrl+wheel zooms, instead of the other way round.
+    scroll: bool,
+}
+
+impl Default for WheelPrefs {
+    fn default() -> Self {
+        WheelPrefs {
+            sensitivity: 1.0,
+            invert: false,
+            scroll: false,
+        }
+    }
+}
+
+impl WheelPrefs {
+    /// Comma-separated `zoom` or `scroll`, `invert`, and a speed such as `2x`.
+    fn parse(spec: &str) -> Option<WheelPrefs> {
+        let mut prefs = WheelPrefs::default();
+        for token in spec.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            match token {
+                "zoom" => prefs.scroll = false,
+                "scroll" => prefs.scroll = true,
+                "invert" => prefs.invert = true,
+                _ => {
+                    let speed: f64 = token.strip_suffix('x')?.parse().ok()?;
+                    prefs.sensitivity = speed.clamp(0.1, 10.0);
+                }
+            }
+        }
+        Some(prefs)
+    }
+}
+
+/// A row of the settings panel.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Setting {
+    Theme,
+    Difficulty,
+    Sound,
+    Variant,
+    Wheel,
+    Animations,
+    EvalBar,
+    ImageSize,
+}
+
+impl Setting {
+    const ALL: [Setting; 8] = [
+        Setting::Theme,
+        Setting::Difficulty,
+        Setting::Sound,
+        Setting::Variant,
+        Setting::Wheel,
+        Setting::Animations,
+        Setting::EvalBar,
+        Setting::ImageSize,
+    ];
+
+    fn label(self) -> Msg {
+        match self {
+            Setting::Theme => Msg::SettingTheme,
+            Setting::Difficulty => Msg::SettingDifficulty,
+            Setting::Sound => Msg::SettingSound,
+            Setting::Variant => Msg::SettingVariant,
+            Setting::Wheel => Msg::SettingWheel,
+            Setting::Animations => Msg::SettingAnimations,
+            Setting::EvalBar => Msg::SettingEvalBar,
+            Setting::ImageSize => Msg::SettingImageSize,
+        }
+    }
+}
+
+type PointerHandler = fn(&mut App, PointerEvent);
+
+/// How long a captured stone takes to fade off the board.
+const FADE_MS: f64 = 400.0;
+
+/// A captured stone shrinking and fading out.
+#[derive(Copy, Clone, Debug)]
+struct Fade {
+    p: Pt,
+    color: Color,
+    start_ms: f64,
+}
+
+/// Pause before the AI's search, long enough for the move it answers to be drawn
+/// first; the thinking time itself is spent inside the search.
+const AI_START_MS: f64 = 30.0;
+
+/// A restart asked for again within this long goes ahead.
+const RESTART_CONFIRM_MS: f64 = 2000.0;
+
+/// An alpha-beta AI move being searched an iteration per frame, in the
+/// position with `key`, so the HUD can show how far it has got.
+struct Thinking {
+    search: search::Deepening,
+    key: u64,
+    start_ms: f64,
+}
+
+/// Human replies pondered per position, likeliest first.
+const PONDER_REPLIES: usize = 6;
+
+/// The longest side of a saved board image.
+const MAX_IMAGE_PX: f64 = 4096.0;
+/// The size of a cell in a recorded video, and the longest side it may have.
+const VIDEO_CELL_PX: f64 = 40.0;
+const MAX_VIDEO_PX: f64 = 1280.0;
+
+/// What a button in the saves menu does, to the slot it belongs to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum SlotAction {
+    Save(usize),
+    Load(usize),
+    Delete(usize),
+}
+
+/// A QR code shown over the board for another device to scan.
+struct Shared {
+    code: qr::Qr,
+    caption: String,
+    /// The WebRTC signalling it holds, handed over as text when it closes.
+    pairing: Option<Pairing>,
+}
+
+/// What a button on the history screen does, to the game it belongs to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum HistoryAction {
+    Replay(usize),
+    Export(usize),
+}
+
+/// How large saved board images are drawn: the size of a cell.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum ImageSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl ImageSize {
+    fn parse(name: &str) -> Option<ImageSize> {
+        match name {
+            "small" => Some(ImageSize::Small),
+            "medium" => Some(ImageSize::Medium),
+            "large" => Some(ImageSize::Large),
+            _ => None,
+        }
+    }
+
+    /// As stored in the settings; the default is left out.
+    fn name(self) -> &'static str {
+        match self {
+            ImageSize::Small => "small",
+            ImageSize::Medium => "",
+            ImageSize::Large => "large",
+        }
+    }
+
+    fn cell_px(self) -> f64 {
+        match self {
+            ImageSize::Small => 24.0,
+            ImageSize::Medium => 48.0,
+            ImageSize::Large => 96.0,
+        }
+    }
+}
+
+/// Presentation mode's HUD text size, and the smallest cells it zooms out to
+/// on entering.
+const PRESENT_TEXT_ZOOM: f64 = 1.6;
+const PRESENT_CELL_PX: f64 = 56.0;
+
+/// How deep, and over how many positions, the eval bar searches each position.
+const EVAL_BAR_DEPTH: u32 = 2;
+const EVAL_BAR_NODES: u32 = 2_000;
+/// The eval bar's width.
+const EVAL_BAR_PX: f64 = 8.0;
+
+/// Height of a row in the replay's move list, before text scaling.
+const MOVE_LIST_ROW_PX: f64 = 18.0;
+
+/// How long a hint marker stays on the board.
+const HINT_MS: f64 = 3000.0;
+
+/// The engine's suggestion for the human to move, pulsing until it expires.
+#[derive(Copy, Clone, Debug)]
+struct Hint {
+    p: Pt,
+    start_ms: f64,
+}
+
+/// How long the AI's expected line stays on the board.
+const PV_MS: f64 = 4000.0;
+
+/// Moves ranked by the `K` overlay, badged in these colours, best first.
+const TOP_LINES: usize = 3;
+const RANK_COLORS: [&str; TOP_LINES] = ["#22c55e", "#38bdf8", "#a78bfa"];
+/// Moves listed from each ranked line, the ranked move included.
+const TOP_LINE_LEN: usize = 4;
+
+/// The line the AI expected when it moved, starting with that move.
+#[derive(Clone, Debug)]
+struct Pv {
+    moves: Vec<(Pt, Color)>,
+    start_ms: f64,
+}
+
+impl Pv {
+    /// `line` as played out from `game`, with who plays each move.
+    fn new(game: &Game, line: &[Pt], start_ms: f64) -> Self {
+        let mut g = game.clone();
+        let mut moves = Vec::with_capacity(line.len());
+        for &p in line {
+            let mover = g.player;
+            if !g.play(p) {
+                break;
+            }
+            moves.push((p, mover));
+        }
+        Pv { moves, start_ms }
+    }
+}
+
+/// How long a blunder warning waits for the confirming second tap.
+const CONFIRM_MS: f64 = 3000.0;
+
+/// A human move held back because it leaves the opponent a win; tapping the
+/// same cell again before it expires plays it anyway.
+#[derive(Copy, Clone, Debug)]
+struct Confirm {
+    p: Pt,
+    start_ms: f64,
+}
+
+/// An active press on the canvas; becomes a pan once it exceeds `DRAG_THRESHOLD_PX`,
+/// or a magnifier (`loupe`) if held still for `LONG_PRESS_MS` instead.
+#[derive(Copy, Clone, Debug)]
+struct Drag {
+    pointer_id: i32,
+    start: (f64, f64),
+    last: (f64, f64),
+    start_ms: f64,
+    /// When `last` was recorded, and the smoothed pan speed up to then.
+    last_ms: f64,
+    velocity: (f64, f64),
+    panning: bool,
+    loupe: bool,
+}
+
+struct App {
+    canvas: HtmlCanvasElement,
+    ctx: CanvasRenderingContext2d,
+    /// Beneath the board canvas: the background and grid, then the stones.
+    grid_layer: Layer<GridKey>,
+    stone_layer: Layer<StoneKey>,
+    /// The position the stone layer shows (its key and move count, and its
+    /// stones), so a new one only repaints the cells that differ.
+    drawn_position: Option<((u64, usize), PtMap<Color>)>,
+    /// Draws the stones instead of the stone layer in very large games. `None`
+    /// until first needed, `Some(None)` where WebGL is unavailable.
+    gl_stones: Option<Option<GlStones>>,
+    game: Game,
+
+    ctrl_black: Controller,
+    ctrl_white: Controller,
+    /// Engine settings for Black and White when they are AI-controlled.
+    ai: [AiConfig; 2],
+    rng: Rng,
+    /// Shared by every alpha-beta search, including pondering.
+    tt: Tt,
+    /// Helper workers searching the AI's positions with it, where the page
+    /// can share memory with them.
+    smp: Option<smp::Pool>,
+    /// Likely human replies still to ponder in the current position; `None`
+    /// until the position is first pondered.
+    ponder_queue: Option<Vec<Pt>>,
+    /// The AI move being searched, if any. MCTS moves are found within one
+    /// frame and never show here.
+    thinking: Option<Thinking>,
+
+    cell_px: f64,
+    cam_x: f64,
+    cam_y: f64,
+    view_w: f64,
+    view_h: f64,
+
+    want_ai: bool,
+    next_ai_at_ms: f64,
+
+    btn_black: (f64, f64, f64, f64),
+    btn_white: (f64, f64, f64, f64),
+    btn_style: (f64, f64, f64, f64),
+    btn_think_less: (f64, f64, f64, f64),
+    btn_think_more: (f64, f64, f64, f64),
+    btn_sound: (f64, f64, f64, f64),
+    btn_settings: (f64, f64, f64, f64),
+    /// Fullscreen on/off; zero-sized where the page cannot go fullscreen.
+    btn_fullscreen: (f64, f64, f64, f64),
+    btn_fit: (f64, f64, f64, f64),
+    /// The bottom toolbar's buttons as last drawn.
+    toolbar: Vec<(Action, (f64, f64, f64, f64))>,
+    /// When each of the game's moves was played, and the card summing it up
+    /// after a win with that card's buttons as last drawn.
+    move_times: Vec<f64>,
+    summary: Option<Summary>,
+    card_buttons: Vec<(Action, (f64, f64, f64, f64))>,
+
+    drag: Option<Drag>,
+    /// Last known position of every pointer currently pressed on the canvas.
+    pointers: HashMap<i32, (f64, f64)>,
+    pinch: Option<Pinch>,
+    wheel: WheelPrefs,
+    gamepad: Gamepad,
+    /// Space is down, turning left-button presses into pans.
+    space_held: bool,
+    /// Mouse/pen position while hovering the canvas, for the ghost-stone preview.
+    hover: Option<(f64, f64)>,
+    /// Cell picked with Shift+arrows for keyboard play, once the keys are used.
+    cursor: Option<Pt>,
+    last_tap: Option<IdleTap>,
+    camera_tween: Option<CameraTween>,
+    /// The minimap as last drawn; `None` while there are no stones.
+    minimap: Option<Minimap>,
+    /// Confetti and the rest of the flourish for the live game's win.
+    celebration: Option<Celebration>,
+    glide: Option<Glide>,
+
+    fades: Vec<Fade>,
+    hint: Option<Hint>,
+    pv: Option<Pv>,
+    confirm: Option<Confirm>,
+    /// Why the AI played its last move, until the next move.
+    why: Option<String>,
+    /// A side the AI has proved can force a win, and in how many more of its
+    /// turns; until the next move.
+    forced_win: Option<(Color, u32)>,
+    /// `search::top_lines` for the position with this key.
+    top_lines: Option<(u64, Vec<ScoredLine>)>,
+
+    show_coords: bool,
+    show_move_numbers: bool,
+    /// Tint frontier cells by `score_point` for the side to move.
+    show_heatmap: bool,
+    /// Show the line the AI expects after each of its moves.
+    show_pv: bool,
+    /// Rank the engine's top moves for the position on screen.
+    show_top_lines: bool,
+    /// The bar down the left edge showing who is ahead, and its reading for
+    /// the position with that key, in `[-1, 1]` with Black up.
+    show_eval_bar: bool,
+    eval_bar: Option<(u64, f64)>,
+    /// The controls overlay (`?`), closed by any key or tap.
+    show_help: bool,
+    /// The share overlay (`F8` or the toolbar button): a QR code of the game's
+    /// link, made when it opens, or of a WebRTC offer or answer; any tap
+    /// closes it.
+    share: Option<Shared>,
+    /// The saved-games menu (`F9` or the toolbar's Saves button): the slots as
+    /// read when it opened, and its buttons as last drawn.
+    saves: Option<Vec<Option<Save>>>,
+    save_buttons: Vec<(SlotAction, (f64, f64, f64, f64))>,
+    /// The saves menu's button for the history screen.
+    btn_history: (f64, f64, f64, f64),
+    /// The finished games (`Shift+F9` or the saves menu's History button),
+    /// and its buttons as last drawn.
+    history: Option<History>,
+    history_buttons: Vec<(HistoryAction, (f64, f64, f64, f64))>,
+    /// A RenjuNet library opened to pick a game to replay from (see `rif`).
+    library: Option<Library>,
+    /// Each library row's Replay button, by record.
+    library_buttons: Vec<(usize, (f64, f64, f64, f64))>,
+    /// How large `F4` saves the board.
+    image_size: ImageSize,
+    /// The video `Shift+F4` is recording, if any.
+    recording: Option<Recording>,
+    /// Presentation mode (`F2`): large text and stones, the last move in a
+    /// banner, and none of the small buttons.
+    presenting: bool,
+    /// The `F3` diagnostics panel, when the frames of the last second were
+    /// drawn, and how long the last one took.
+    show_diagnostics: bool,
+    /// Dot every frontier cell (`Shift+F3`).
+    show_frontier: bool,
+    frame_times: VecDeque<f64>,
+    render_ms: f64,
+    /// The settings panel (`.` or the gear button), and its rows as last drawn.
+    show_settings: bool,
+    settings_rows: Vec<(Setting, (f64, f64, f64, f64))>,
+    /// Tween the camera, glide after a flick and throw confetti for a win.
+    animations: bool,
+    /// Passing messages above the minimap.
+    toasts: Toasts,
+    /// When a restart was last asked for, awaiting a second ask to confirm.
+    restart_asked_ms: Option<f64>,
+    /// Explain each AI move in the HUD.
+    show_why: bool,
+    /// Ask before playing a move that hands the opponent a win.
+    blunder_alerts: bool,
+    /// Place stones with two taps: one to pick the cell, one to confirm.
+    two_tap: bool,
+    /// The cell picked by the first of two taps.
+    selected: Option<Pt>,
+    btn_place: (f64, f64, f64, f64),
+
+    /// When set, the board shows this replay instead of the live game.
+    replay: Option<Replay>,
+    clock: Option<Clock>,
+    /// Index into `TimeControl::PRESETS` last chosen from the keyboard.
+    time_preset: Option<usize>,
+    /// Clock readouts as last drawn, to redraw only when they change.
+    clock_label: String,
+    /// The side and whole second last ticked for under time pressure.
+    last_tick: Option<(Color, u64)>,
+
+    net: Option<Box<dyn Transport>>,
+    /// Connection status as of the last frame, to redraw the HUD when it changes.
+    net_status: Option<NetStatus>,
+
+    /// When set, both sides are AI and finished games give way to the next.
+    tournament: Option<Tournament>,
+
+    /// Per-move evaluations shown as a graph alongside the replay.
+    analysis: Option<Analysis>,
+    graph_rect: (f64, f64, f64, f64),
+    /// The replay's move list: its box and each shown row's move count, as
+    /// last drawn; the first row shown; and the replay position it was last
+    /// scrolled to show.
+    move_list_rect: (f64, f64, f64, f64),
+    move_list_rows: Vec<(usize, (f64, f64, f64, f64))>,
+    move_list_scroll: usize,
+    move_list_cursor: Option<usize>,
+
+    /// The board as seen by screen readers.
+    a11y: A11y,
+    theme: &'static Theme,
+    audio: Audio,
+    /// Ring White's stones so they differ from Black's in shape too.
+    stone_marks: bool,
+    /// Pan to each new stone that lands off screen.
+    follow: bool,
+    /// Cells between star points, as landmarks on the grid; 0 for none.
+    star_spacing: i32,
+    /// Black's and White's players, if named, shown instead of the colours.
+    names: [Option<String>; 2],
+    /// When the live game's first stone and its win came, for the HUD's
+    /// elapsed time, and the whole seconds last shown.
+    game_started_ms: Option<f64>,
+    game_ended_ms: Option<f64>,
+    shown_elapsed_s: i64,
+
+    dirty: bool,
+}
+
+impl App {
+    fn new(
+        canvas: HtmlCanvasElement,
+        ctx: CanvasRenderingContext2d,
+        layers: (Layer<GridKey>, Layer<StoneKey>),
+        game: Game,
+        a11y: A11y,
+    ) -> Self {
+        Self {
+            canvas,
+            ctx,
+            grid_layer: layers.0,
+            stone_layer: layers.1,
+            drawn_position: None,
+            gl_stones: None,
+            game,
+            ctrl_black: Controller::Human,
+            ctrl_white: Controller::AI,
+            ai: [AiConfig::default(); 2],
+            rng: Rng::from_entropy(),
+            tt: Tt::default(),
+            smp: None,
+            ponder_queue: None,
+            thinking: None,
+            cell_px: 36.0,
+            cam_x: 0.0,
+            cam_y: 0.0,
+            view_w: 0.0,
+            view_h: 0.0,
+            want_ai: false,
+            next_ai_at_ms: 0.0,
+            btn_black: (0.0, 0.0, 0.0, 0.0),
+            btn_white: (0.0, 0.0, 0.0, 0.0),
+            btn_style: (0.0, 0.0, 0.0, 0.0),
+            btn_think_less: (0.0, 0.0, 0.0, 0.0),
+            btn_think_more: (0.0, 0.0, 0.0, 0.0),
+            btn_sound: (0.0, 0.0, 0.0, 0.0),
+            btn_settings: (0.0, 0.0, 0.0, 0.0),
+            btn_fullscreen: (0.0, 0.0, 0.0, 0.0),
+            btn_fit: (0.0, 0.0, 0.0, 0.0),
+            toolbar: Vec::new(),
+            move_times: Vec::new(),
+            summary: None,
+            card_buttons: Vec::new(),
+            drag: None,
+            pointers: HashMap::new(),
+            pinch: None,
+            wheel: WheelPrefs::default(),
+            gamepad: Gamepad::default(),
+            space_held: false,
+            hover: None,
+            cursor: None,
+            last_tap: None,
+            camera_tween: None,
+            minimap: None,
+            celebration: None,
+            glide: None,
+            fades: Vec::new(),
+            hint: None,
+            pv: None,
+            confirm: None,
+            why: None,
+            forced_win: None,
+            top_lines: None,
+            show_coords: false,
+            show_move_numbers: false,
+            show_heatmap: false,
+            show_pv: false,
+            show_top_lines: false,
+            show_eval_bar: false,
+            eval_bar: None,
+            show_diagnostics: false,
+            presenting: false,
+            image_size: ImageSize::Medium,
+            recording: None,
+            show_frontier: false,
+            frame_times: VecDeque::new(),
+            render_ms: 0.0,
+            show_help: false,
+            share: None,
+            saves: None,
+            save_buttons: Vec::new(),
+            btn_history: (0.0, 0.0, 0.0, 0.0),
+            history: None,
+            history_buttons: Vec::new(),
+            library: None,
+            library_buttons: Vec::new(),
+            show_settings: false,
+            settings_rows: Vec::new(),
+            animations: !prefers_reduced_motion(),
+            toasts: Toasts::default(),
+            restart_asked_ms: None,
+            show_why: true,
+            blunder_alerts: true,
+            two_tap: false,
+            selected: None,
+            btn_place: (0.0, 0.0, 0.0, 0.0),
+            replay: None,
+            clock: None,
+            time_preset: None,
+            clock_label: String::new(),
+            last_tick: None,
+            net: None,
+            net_status: None,
+            tournament: None,
+            analysis: None,
+            graph_rect: (0.0, 0.0, 0.0, 0.0),
+            move_list_rect: (0.0, 0.0, 0.0, 0.0),
+            move_list_rows: Vec::new(),
+            move_list_scroll: 0,
+            move_list_cursor: None,
+            a11y,
+            theme: if theme::prefers_contrast() {
+                &theme::HIGH_CONTRAST
+            } else {
+                theme::scheme_theme()
+            },
+            stone_marks: false,
+            follow: false,
+            names: [None, None],
+            game_started_ms: None,
+            game_ended_ms: None,
+            shown_elapsed_s: 0,
+            star_spacing: DEFAULT_STAR_SPACING,
+            audio: Audio::default(),
+            dirty: true,
+        }
+    }
+
+    fn attach_listeners(app: &Rc<RefCell<App>>) {
+        // Pointer
+        let pointer_handlers: [(&str, PointerHandler); 5] = [
+            ("pointerdown", App::on_pointer_down),
+            ("pointermove", App::on_pointer_move),
+            ("pointerup", App::on_pointer_up),
+            ("pointercancel", App::on_pointer_cancel),
+            ("pointerleave", App::on_pointer_leave),
+        ];
+        for (name, handler) in pointer_handlers {
+            let app_rc = Rc::clone(app);
+            let closure = Closure::<dyn FnMut(PointerEvent)>::new(move |e: PointerEvent| {
+                e.prevent_default();
+                handler(&mut app_rc.borrow_mut(), e);
+            });
+            app.borrow()
+                .canvas
+                .add_event_listener_with_callback(name, closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+        }
+        // A held touch would otherwise open the browser's context menu over the magnifier.
+        {
+            let closure = Closure::<dyn FnMut(web_sys::Event)>::new(|e: web_sys::Event| {
+                e.prevent_default();
+            });
+            app.borrow()
+                .canvas
+                .add_event_listener_with_callback("contextmenu", closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+        }
+        // Screen-reader grid: activating a cell's button plays there.
+        {
+            let app_rc = Rc::clone(app);
+            let closure = Closure::<dyn FnMut(web_sys::Event)>::new(move |e: web_sys::Event| {
+                if let Some(p) = a11y::clicked_cell(&e) {
+                    app_rc.borrow_mut().on_a11y_cell(p);
+                }
+            });
+            app.borrow()
+                .a11y
+                .grid()
+                .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+        }
+        // Wheel
+        {
+            let app_rc = Rc::clone(app);
+            let closure = Closure::<dyn FnMut(WheelEvent)>::new(move |e: WheelEvent| {
+                e.prevent_default();
+                app_rc.borrow_mut().on_wheel(e);
+            });
+            app.borrow()
+                .canvas
+                .add_event_listener_with_callback("wheel", closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+        }
+        // A `.psq`, RenjuNet or position text file dropped on the board loads it.
+        {
+            let closure = Closure::<dyn FnMut(DragEvent)>::new(|e: DragEvent| e.prevent_default());
+            app.borrow()
+                .canvas
+                .add_event_listener_with_callback("dragover", closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+
+            let app_rc = Rc::clone(app);
+            let closure = Closure::<dyn FnMut(DragEvent)>::new(move |e: DragEvent| {
+                e.prevent_default();
+                let Some(file) = e.data_transfer().and_then(|d| d.files()).and_then(|f| f.get(0))
+                else {
+                    return;
+                };
+                let app_rc = Rc::clone(&app_rc);
+                wasm_bindgen_futures::spawn_local(async move {
+                    let text = wasm_bindgen_futures::JsFuture::from(file.text()).await;
+                    if let Some(text) = text.ok().and_then(|t| t.as_string()) {
+                        app_rc.borrow_mut().paste_position(&text);
+                    }
+                });
+            });
+            app.borrow()
+                .canvas
+                .add_event_listener_with_callback("drop", closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+        }
+        // Keyboard
+        {
+            let app_rc = Rc::clone(app);
+            let doc = window().unwrap().document().unwrap();
+            let closure = Closure::<dyn FnMut(KeyboardEvent)>::new(move |e: KeyboardEvent| {
+                app_rc.borrow_mut().on_key(e);
+            });
+            doc.add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+
+            let app_rc = Rc::clone(app);
+            let closure = Closure::<dyn FnMut(KeyboardEvent)>::new(move |e: KeyboardEvent| {
+                if e.key() == " " {
+                    app_rc.borrow_mut().set_space_held(false);
+                }
+            });
+            doc.add_event_listener_with_callback("keyup", closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+        }
+        // Clipboard: Ctrl+C copies the position, Ctrl+V loads one.
+        {
+            let doc = window().unwrap().document().unwrap();
+            let app_rc = Rc::clone(app);
+            let closure =
+                Closure::<dyn FnMut(ClipboardEvent)>::new(move |e: ClipboardEvent| {
+                    if let Some(data) = e.clipboard_data() {
+                        let text = {
+                            let game = &app_rc.borrow().game;
+                            codec::encode_position(&game.rules, &game.moves)
+                        };
+                        if data.set_data("text/plain", &text).is_ok() {
+                            e.prevent_default();
+                            app_rc.borrow_mut().toast(t(Msg::PositionCopied));
+                        }
+                    }
+                });
+            doc.add_event_listener_with_callback("copy", closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+
+            let app_rc = Rc::clone(app);
+            let closure =
+                Closure::<dyn FnMut(ClipboardEvent)>::new(move |e: ClipboardEvent| {
+                    if let Some(text) = e.clipboard_data().and_then(|d| d.get_data("text").ok()) {
+                        e.prevent_default();
+                        app_rc.borrow_mut().paste_position(&text);
+                    }
+                });
+            doc.add_event_listener_with_callback("paste", closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+        }
+        // Resize, including on entering or leaving fullscreen (by `F`, the
+        // button, or the browser's own Escape).
+        {
+            let app_rc = Rc::clone(app);
+            let closure = Closure::<dyn FnMut()>::new(move || {
+                let mut a = app_rc.borrow_mut();
+                a.resize();
+                a.render();
+            });
+            window()
+                .unwrap()
+                .add_event_listener_with_callback("resize", closure.as_ref().unchecked_ref())
+                .unwrap();
+            window()
+                .unwrap()
+                .document()
+                .unwrap()
+                .add_event_listener_with_callback(
+                    "fullscreenchange",
+                    closure.as_ref().unchecked_ref(),
+                )
+                .unwrap();
+            closure.forget();
+        }
+    }
+
+    async fn raf_loop(app: Rc<RefCell<App>>) {
+        loop {
+            {
+                let mut a = app.borrow_mut();
+                a.poll_network();
+                a.poll_history();
+                a.poll_gamepad();
+                a.tick_clock();
+                a.maybe_ai_step();
+                a.maybe_tournament_step();
+                a.maybe_ponder();
+                a.maybe_replay_step();
+                a.maybe_record_step();
+                a.maybe_long_press();
+                a.maybe_top_lines();
+                a.maybe_eval_bar();
+                a.maybe_relabel();
+                a.maybe_reskin();
+                a.maybe_rescheme();
+                a.animate();
+                let (drawn, start) = (a.dirty, now_ms());
+                a.render();
+                if drawn {
+                    a.frame_drawn(start);
+                }
+            }
+            let _ =
+                wasm_bindgen_futures::JsFuture::from(js_sys::Promise::new(&mut |resolve, _| {
+                    window()
+                        .unwrap()
+                        .request_animation_frame(resolve.unchecked_ref())
+                        .unwrap();
+                }))
+                    .await;
+        }
+    }
+
+    fn resize(&mut self) {
+        let rect = self
+            .canvas
+            .unchecked_ref::<Element>()
+            .get_bounding_client_rect();
+        self.view_w = rect.width();
+        self.view_h = rect.height();
+
+        let dpr = window().unwrap().device_pixel_ratio();
+        self.canvas.set_width((self.view_w * dpr) as u32);
+        self.canvas.set_height((self.view_h * dpr) as u32);
+
+        let _ = self.ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+        let _ = self.ctx.scale(dpr, dpr);
+        self.grid_layer.resize(self.view_w, self.view_h, dpr);
+        self.stone_layer.resize(self.view_w, self.view_h, dpr);
+        if let Some(Some(gl)) = self.gl_stones.as_mut() {
+            gl.resize(self.view_w, self.view_h, dpr);
+        }
+
+        self.dirty = true;
+    }
+
+    /// Put the page in fullscreen, or leave it: the whole page, since the
+    /// board is drawn on stacked canvases. The resize follows from
+    /// `fullscreenchange`; a refusal (no gesture, or no support) is ignored.
+    fn toggle_fullscreen(&self) {
+        let Some(doc) = window().and_then(|w| w.document()) else {
+            return;
+        };
+        if doc.fullscreen_element().is_some() {
+            doc.exit_fullscreen();
+        } else if let Some(page) = doc.document_element() {
+            let _ = page.request_fullscreen();
+        }
+    }
+
+    fn screen_to_cell_f64(&self, sx: f64, sy: f64) -> (f64, f64) {
+        let x = (sx - self.view_w / 2.0) / self.cell_px + self.cam_x;
+        let y = (sy - self.view_h / 2.0) / self.cell_px + self.cam_y;
+        (x, y)
+    }
+    fn screen_to_cell(&self, sx: f64, sy: f64) -> Pt {
+        let (x, y) = self.screen_to_cell_f64(sx, sy);
+        Pt::new(x.round() as i32, y.round() as i32)
+    }
+    fn cell_to_screen(&self, p: Pt) -> (f64, f64) {
+        (
+            (p.x as f64 - self.cam_x) * self.cell_px + self.view_w / 2.0,
+            (p.y as f64 - self.cam_y) * self.cell_px + self.view_h / 2.0,
+        )
+    }
+
+    /// The position being displayed: the replay view if one is open, else the live game.
+    fn shown_game(&self) -> &Game {
+        self.replay.as_ref().map_or(&self.game, Replay::view)
+    }
+
+    fn controller(&self, side: Color) -> Controller {
+        match side {
+            Color::Black => self.ctrl_black,
+            Color::White => self.ctrl_white,
+        }
+    }
+    fn is_human(&self, side: Color) -> bool {
+        self.controller(side) == Controller::Human
+    }
+    fn is_ai(&self, side: Color) -> bool {
+        self.controller(side) == Controller::AI
+    }
+    fn is_ai_turn(&self) -> bool {
+        self.game.winner.is_none() && self.is_ai(self.game.player)
+    }
+
+    fn queue_ai_soon(&mut self, delay_ms: f64) {
+        let now = now_ms();
+        self.want_ai = true;
+        self.next_ai_at_ms = now + delay_ms;
+    }
+    fn maybe_ai_step(&mut self) {
+        if !self.is_ai_turn() || !self.want_ai || self.replay.is_some() {
+            if self.thinking.take().is_some() {
+                self.stop_helpers();
+            }
+            return;
+        }
+        let now = now_ms();
+        if now < self.next_ai_at_ms {
+            return;
+        }
+        let ai = self.ai[self.game.player as usize];
+        let key = self.game.key();
+        let (source, line) = match self.thinking.take() {
+            Some(mut th) if th.key == key => {
+                self.dirty = true;
+                if th.search.step(&self.game, &mut self.tt) {
+                    self.thinking = Some(th);
+                    return;
+                }
+                self.stop_helpers();
+                let best = th.search.best(&mut self.rng);
+                self.game.searched_line(ai, best, &mut self.rng, &mut self.tt)
+            }
+            _ => match self.game.ai_shortcut(ai, &mut self.rng) {
+                Some(found) => found,
+                None if ai.backend == Backend::AlphaBeta => {
+                    let (depth, nodes) = (ai.depth(), ai.nodes());
+                    let search =
+                        search::Deepening::new(&self.game, ai.style, depth, nodes, &mut self.tt);
+                    if let Some(pool) = &self.smp {
+                        pool.help(&self.game, ai.style, depth, nodes);
+                    }
+                    self.thinking = Some(Thinking {
+                        search,
+                        key,
+                        start_ms: now,
+                    });
+                    self.dirty = true;
+                    return;
+                }
+                None => {
+                    let line = mcts::search(&self.game, ai.style, ai.iterations(), &mut self.rng);
+                    self.game.line_or_fallback(ai, line, &mut self.rng)
+                }
+            },
+        };
+        if let Some(&ai_p) = line.first() {
+            let pv = Pv::new(&self.game, &line, now);
+            let mover = self.game.player;
+            let why = explain::explain(&self.game, ai_p, mover, source);
+            self.play_move(ai_p);
+            self.forced_win = match source {
+                Source::ForcedWin { turns } if self.game.winner.is_none() => {
+                    // The turn just started counts unless it has ended.
+                    let left = turns - (self.game.player != mover) as u32;
+                    Some((mover, left)).filter(|&(_, n)| n > 0)
+                }
+                _ => None,
+            };
+            self.pv = Some(pv).filter(|pv| self.show_pv && pv.moves.len() > 1);
+            self.why = Some(why);
+            self.send(&NetMsg::Move(ai_p));
+            if self.is_ai_turn() {
+                self.queue_ai_soon(AI_START_MS);
+            } else {
+                self.want_ai = false;
+            }
+        } else {
+            self.want_ai = false;
+        }
+    }
+
+    fn stop_helpers(&self) {
+        if let Some(pool) = &self.smp {
+            pool.stop();
+        }
+    }
+
+    /// Keep the `K` overlay's ranking current for the position on screen, judged
+    /// with the side to move's AI settings.
+    fn maybe_top_lines(&mut self) {
+        if !self.show_top_lines {
+            return;
+        }
+        let game = self.shown_game();
+        let key = game.key();
+        if self.top_lines.as_ref().is_some_and(|&(k, _)| k == key) {
+            return;
+        }
+        let lines = if game.winner.is_some() {
+            Vec::new()
+        } else {
+            let ai = self.ai[game.player as usize];
+            let nodes = ai.nodes();
+            search::top_lines(game, ai.style, ai.depth(), nodes, TOP_LINES, TOP_LINE_LEN)
+        };
+        self.top_lines = Some((key, lines));
+        self.dirty = true;
+    }
+
+    /// Keep the eval bar's reading current for the position on screen, from a
+    /// shallow search so it costs little on every move.
+    fn maybe_eval_bar(&mut self) {
+        if !self.show_eval_bar {
+            return;
+        }
+        let game = self.shown_game();
+        let key = game.key();
+        if self.eval_bar.is_some_and(|(k, _)| k == key) {
+            return;
+        }
+        let eval = match game.winner {
+            Some(Color::Black) => 1.0,
+            Some(Color::White) => -1.0,
+            None => {
+                let game = game.clone();
+                let best = search::search(
+                    &game,
+                    Style::Balanced,
+                    EVAL_BAR_DEPTH,
+                    EVAL_BAR_NODES,
+                    &mut self.rng,
+                    &mut self.tt,
+                );
+                let score = match best {
+                    Some((_, s)) if game.player == Color::Black => s,
+                    Some((_, s)) => -s,
+                    None => game.evaluate(),
+                };
+                analysis::squash(score)
+            }
+        };
+        self.eval_bar = Some((key, eval));
+        self.dirty = true;
+    }
+
+    /// While a human plays the alpha-beta AI, search their likeliest replies so
+    /// the AI's answer starts from a warm transposition table: in the helper
+    /// workers where there are any, or else one reply per frame, each slice
+    /// small enough to share the main thread with rendering.
+    fn maybe_ponder(&mut self) {
+        let opp = self.game.player.other();
+        if self.replay.is_some()
+            || self.game.winner.is_some()
+            || !self.is_human(self.game.player)
+            || !self.is_ai(opp)
+            || self.ai[opp as usize].backend != Backend::AlphaBeta
+        {
+            return;
+        }
+        let style = self.ai[opp as usize].style;
+        let game = &self.game;
+        let queue = self.ponder_queue.get_or_insert_with(|| {
+            let mut replies: Vec<Pt> = threats::candidates(game)
+                .into_iter()
+                .take(PONDER_REPLIES)
+                .map(|c| c.p)
+                .collect();
+            if let Some(pool) = &self.smp {
+                // Handed over whole; nothing is left for the frames.
+                pool.ponder(game, style, &replies);
+                replies.clear();
+            }
+            replies.reverse();
+            replies
+        });
+        let Some(p) = queue.pop() else {
+            return;
+        };
+        let mut next = self.game.clone();
+        if next.play(p) {
+            search::ponder(&next, style, search::PONDER_NODE_LIMIT, &mut self.tt);
+        }
+    }
+
+    /// Hand both sides to `players` and keep score over games; not while online.
+    fn start_tournament(&mut self, players: [AiConfig; 2]) {
+        if self.net.is_some() {
+            return;
+        }
+        self.ctrl_black = Controller::AI;
+        self.ctrl_white = Controller::AI;
+        self.tournament = Some(Tournament::new(players));
+        self.next_tournament_game();
+    }
+
+    fn stop_tournament(&mut self) {
+        self.tournament = None;
+        self.ctrl_black = Controller::Human;
+        self.want_ai = self.is_ai_turn();
+        self.dirty = true;
+    }
+
+    /// Seat the current round's configurations and start the game.
+    fn next_tournament_game(&mut self) {
+        let Some(t) = &self.tournament else {
+            return;
+        };
+        self.ai = [t.config(Color::Black), t.config(Color::White)];
+        self.reset_game();
+    }
+
+    /// Score a tournament game once it ends, and start the next after a pause.
+    fn maybe_tournament_step(&mut self) {
+        let Some(t) = self.tournament.as_mut() else {
+            return;
+        };
+        let now = now_ms();
+        if t.record(&self.game, now) {
+            self.want_ai = false;
+            self.dirty = true;
+        } else if t.next_game_due(now) {
+            self.next_tournament_game();
+        }
+    }
+
+    /// Whether a move can be taken back: not against a remote player or in a
+    /// tournament, nor after a loss on time.
+    fn can_undo(&self) -> bool {
+        self.replay.is_none()
+            && self.tournament.is_none()
+            && self.net.is_none()
+            && !self.game.moves.is_empty()
+            && self.game.win_reason != Some(WinReason::Time)
+            && (self.is_human(Color::Black) || self.is_human(Color::White))
+    }
+
+    /// Take back moves to the start of the latest turn a human had, so the AI's
+    /// reply goes with the move it answered. The clock is left as it is.
+    fn undo(&mut self) {
+        if !self.can_undo() {
+            return;
+        }
+        // Who played each move.
+        let rules = self.game.rules;
+        let mut replayed = Game::with_rules(rules);
+        let mut movers = Vec::with_capacity(self.game.moves.len());
+        for &p in &self.game.moves {
+            movers.push(replayed.player);
+            replayed.play(p);
+        }
+        let keep = (0..movers.len())
+            .rev()
+            .find(|&n| {
+                let turn_start = n == 0 || movers[n - 1] != movers[n];
+                turn_start && self.is_human(movers[n])
+            })
+            .unwrap_or(0);
+        self.game = Game::from_moves(rules, &self.game.moves[..keep]);
+        self.fades.clear();
+        self.game_changed();
+        self.a11y.announce(&t(Msg::MoveUndone));
+        self.toast(t(Msg::MoveUndone));
+        if self.is_ai_turn() {
+            self.queue_ai_soon(AI_START_MS);
+        } else {
+            self.want_ai = false;
+        }
+    }
+
+    /// Open the replay at the live game's last move.
+    fn open_replay(&mut self) {
+        self.replay = Some(Replay::new(&self.game).with_times(self.move_offsets()));
+        self.dirty = true;
+    }
+
+    /// When each live move was played, in ms after the first; empty if that
+    /// is not known for every move, as after loading a game.
+    fn move_offsets(&self) -> Vec<f64> {
+        match self.move_times.first() {
+            Some(&first) if self.move_times.len() == self.game.moves.len() => {
+                self.move_times.iter().map(|t| t - first).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Show `text` for a moment above the minimap (see `toast`).
+    fn toast(&mut self, text: String) {
+        self.toasts.push(text);
+        self.dirty = true;
+    }
+
+    /// Restart if asked a second time within `RESTART_CONFIRM_MS`, or at once
+    /// if there is nothing to lose; otherwise toast `again`, saying how to confirm.
+    fn confirm_restart(&mut self, again: String) {
+        let now = now_ms();
+        let armed = self.restart_asked_ms.take();
+        if self.game.moves.is_empty() || armed.is_some_and(|at| now - at < RESTART_CONFIRM_MS) {
+            self.restart();
+        } else {
+            self.restart_asked_ms = Some(now);
+            self.toast(again);
+        }
+    }
+
+    /// Start a fresh game here and for any connected peer.
+    fn restart(&mut self) {
+        self.reset_game();
+        self.send(&NetMsg::Reset(self.game.rules));
+    }
+
+    /// Start a fresh game, closing any replay, and hand the move to the AI if it owns Black.
+    fn reset_game(&mut self) {
+        self.game.reset();
+        if let Some(clock) = self.clock.as_mut() {
+            clock.reset();
+        }
+        self.replay = None;
+        self.analysis = None;
+        self.game_changed();
+        self.a11y.announce(&t(Msg::NewGame));
+        // Back over the middle of the board, where the first stones go.
+        self.tween_camera(Camera {
+            x: 0.0,
+            y: 0.0,
+            ..self.camera_goal()
+        });
+        if self.is_ai_turn() {
+            self.queue_ai_soon(AI_START_MS);
+        } else {
+            self.want_ai = false;
+        }
+    }
+
+    /// Play `p` in the live game for the side to move, charging their clock.
+    fn play_move(&mut self, p: Pt) -> bool {
+        let mover = self.game.player;
+        if !self.game.play(p) {
+            return false;
+        }
+        self.move_times.truncate(self.game.moves.len() - 1);
+        self.move_times.push(now_ms());
+        let mut said = tf(Msg::Plays, &[&self.player_name(mover), &a11y::coords(p)]);
+        match self.game.last_captured.len() {
+            0 => {}
+            n => said = format!("{said}, {}", tf(Msg::Capturing, &[&n])),
+        }
+        self.audio.play(Effect::Stone);
+        if self.game.winner.is_some() {
+            said = format!("{said}. {}", tf(Msg::Wins, &[&self.player_name(mover)]));
+            self.audio.play(Effect::Win);
+            haptics::buzz(self.end_buzz(mover));
+        } else {
+            haptics::buzz(Buzz::Stone);
+        }
+        self.a11y.announce(&said);
+        if self.follow && self.replay.is_none() {
+            self.reveal(p);
+        }
+        let now = now_ms();
+        self.fades
+            .extend(self.game.last_captured.iter().map(|&q| Fade {
+                p: q,
+                color: mover.other(),
+                start_ms: now,
+            }));
+        // The increment is per turn, not per stone.
+        if let Some(clock) = self.clock.as_mut().filter(|_| self.game.player != mover) {
+            clock.on_move(mover);
+        }
+        self.game_changed();
+        true
+    }
+
+    /// How a win by `winner` feels here: a loss only to a human beaten by a
+    /// side they do not also play.
+    fn end_buzz(&self, winner: Color) -> Buzz {
+        if self.is_human(winner.other()) && !self.is_human(winner) {
+            Buzz::Loss
+        } else {
+            Buzz::Win
+        }
+    }
+
+    /// Run the side to move's clock; it starts with the first stone and
+    /// pauses while a replay is open.
+    fn tick_clock(&mut self) {
+        let running = (!self.game.moves.is_empty()
+            && self.game.winner.is_none()
+            && self.replay.is_none())
+        .then_some(self.game.player);
+        let Some(lost) = self.clock.as_mut().map(|c| c.tick(now_ms(), running)) else {
+            return;
+        };
+        if let Some(loser) = lost {
+            self.game.lose_on_time(loser);
+            self.a11y
+                .announce(&tf(Msg::WinsOnTime, &[&self.player_name(loser.other())]));
+            self.audio.play(Effect::Win);
+            haptics::buzz(self.end_buzz(loser.other()));
+            self.want_ai = false;
+            self.dirty = true;
+        }
+        let Some(clock) = self.clock.as_ref() else {
+            return;
+        };
+        // Tick once a second while the side to move is short of time.
+        let tick = running
+            .map(|side| (side, clock.remaining_ms(side)))
+            .filter(|&(_, ms)| ms > 0.0 && ms < audio::TICK_BELOW_MS)
+            .map(|(side, ms)| (side, (ms / 1000.0).ceil() as u64));
+        if tick.is_some() && tick != self.last_tick {
+            self.audio.play(Effect::Tick);
+        }
+        self.last_tick = tick;
+        let label = self.clock_text(Color::Black) + &self.clock_text(Color::White);
+        if label != self.clock_label {
+            self.clock_label = label;
+            self.dirty = true;
+        }
+    }
+
+    fn clock_text(&self, side: Color) -> String {
+        self.clock
+            .as_ref()
+            .map(|c| clock::format_ms(c.remaining_ms(side)))
+            .unwrap_or_default()
+    }
+
+    /// Call after every move or reset of the live game.
+    fn game_changed(&mut self) {
+        self.thinking = None;
+        self.hint = None;
+        self.pv = None;
+        self.why = None;
+        self.forced_win = None;
+        self.confirm = None;
+        self.selected = None;
+        self.ponder_queue = None;
+        // Elapsed time runs from the first stone, and restarts with a new game.
+        if self.game.moves.is_empty() {
+            self.game_started_ms = None;
+        } else if self.game_started_ms.is_none() {
+            self.game_started_ms = Some(now_ms());
+        }
+        self.dirty = true;
+        self.sync_url();
+    }
+
+    /// How long the live game has gone on, stopping at its win.
+    fn elapsed_ms(&self, now: f64) -> f64 {
+        self.game_started_ms
+            .map_or(0.0, |start| self.game_ended_ms.unwrap_or(now) - start)
+    }
+
+    /// Mirror the rules and move list into `location.hash` so the URL is a
+    /// shareable game link (see `codec::encode_link`).
+    fn sync_url(&self) {
+        let hash = codec::encode_link(&self.game.rules, &self.game.moves);
+        let url = if hash.is_empty() {
+            // Drop the hash entirely, keeping path and query.
+            let loc = window().unwrap().location();
+            format!(
+                "{}{}",
+                loc.pathname().unwrap_or_default(),
+                loc.search().unwrap_or_default()
+            )
+        } else {
+            format!("#{hash}")
+        };
+        if let Ok(history) = window().unwrap().history() {
+            let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&url));
+        }
+    }
+
+    /// Play `local` here while the other side is `Controller::Remote` over `transport`.
+    fn connect_remote(&mut self, transport: Box<dyn Transport>, local: Color) {
+        self.net = Some(transport);
+        let (mine, theirs) = match local {
+            Color::Black => (&mut self.ctrl_black, &mut self.ctrl_white),
+            Color::White => (&mut self.ctrl_white, &mut self.ctrl_black),
+        };
+        *mine = Controller::Human;
+        *theirs = Controller::Remote;
+        self.want_ai = false;
+        self.dirty = true;
+    }
+
+    fn send(&self, msg: &NetMsg) {
+        if let Some(net) = &self.net {
+            net.send(msg);
+        }
+    }
+
+    fn poll_network(&mut self) {
+        let Some(net) = &self.net else {
+            return;
+        };
+        let status = Some(net.status());
+        let msgs = net.drain();
+        if let Some(pairing) = net.take_pairing() {
+            self.show_pairing(pairing);
+        }
+        if status != self.net_status {
+            match status {
+                Some(NetStatus::Open) => self.toast(t(Msg::OpponentConnected)),
+                Some(NetStatus::Closed) => self.toast(t(Msg::OpponentDisconnected)),
+                _ => {}
+            }
+            self.net_status = status;
+            self.dirty = true;
+        }
+        for msg in msgs {
+            self.on_net_msg(msg);
+        }
+    }
+
+    fn on_net_msg(&mut self, msg: NetMsg) {
+        match msg {
+            NetMsg::Hello if self.net.as_ref().is_some_and(|n| n.answers_hello()) => {
+                self.send_sync();
+            }
+            NetMsg::Hello => {}
+            NetMsg::Move(p) => {
+                if self.controller(self.game.player) == Controller::Remote && self.play_move(p) {
+                    if self.is_ai_turn() {
+                        self.queue_ai_soon(AI_START_MS);
+                    }
+                } else {
+                    // Out of turn or illegal here: we have diverged, so share our view.
+                    self.send_sync();
+                }
+            }
+            NetMsg::Reset(rules) => {
+                self.game = Game::with_rules(self.peer_rules(rules));
+                self.reset_game();
+            }
+            NetMsg::Sync(rules, moves) => {
+                let rules = self.peer_rules(rules);
+                if rules == self.game.rules && moves == self.game.moves {
+                    return;
+                }
+                let mut game = Game::with_rules(rules);
+                if !moves.iter().all(|&p| game.play(p)) {
+                    // Not a game we can follow: keep ours rather than half of theirs.
+                    self.toast(t(Msg::SyncRefused));
+                    return;
+                }
+                self.game = game;
+                self.game_changed();
+                self.zoom_to_fit();
+                if self.is_ai_turn() {
+                    self.queue_ai_soon(AI_START_MS);
+                }
+            }
+        }
+    }
+
+    /// Send the peer our rules and moves, for it to adopt.
+    fn send_sync(&self) {
+        self.send(&NetMsg::Sync(self.game.rules, self.game.moves.clone()));
+    }
+
+    /// A peer's rules as ours, keeping our own AI weights.
+    fn peer_rules(&self, rules: Rules) -> Rules {
+        Rules {
+            weights: self.game.rules.weights,
+            ..rules
+        }
+        .validated()
+    }
+
+    /// Redraw, and relabel the screen-reader grid, after the locale changes.
+    fn maybe_relabel(&mut self) {
+        if i18n::take_changed() {
+            self.a11y.relabel();
+            self.dirty = true;
+        }
+    }
+
+    /// Switch between the dark and light themes when the colour scheme, or the
+    /// browser's preference, changes; other themes are left as they are.
+    fn maybe_rescheme(&mut self) {
+        if theme::take_scheme_changed() && self.theme.follows_scheme() {
+            self.theme = theme::scheme_theme();
+            self.dirty = true;
+        }
+    }
+
+    /// Redraw the layers after the skin changes or one of its images loads,
+    /// on the main thread if it has stone images, which a worker cannot draw.
+    fn maybe_reskin(&mut self) {
+        if skin::take_changed() {
+            let stone_images = [Color::Black, Color::White]
+                .into_iter()
+                .any(|c| skin::image(skin::Part::stone(c)).is_some());
+            let doc = window().and_then(|w| w.document());
+            if let Some(doc) = doc.filter(|_| stone_images && self.stone_layer.is_offscreen()) {
+                if let Ok(mut layer) = self.stone_layer.bring_home(&doc) {
+                    let dpr = window().map_or(1.0, |w| w.device_pixel_ratio());
+                    layer.resize(self.view_w, self.view_h, dpr);
+                    self.stone_layer = layer;
+                    self.drawn_position = None;
+                }
+            }
+            self.grid_layer.invalidate();
+            self.stone_layer.invalidate();
+            self.dirty = true;
+        }
+    }
+
+    /// Keep redrawing while any animation is in flight.
+    fn animate(&mut self) {
+        let now = now_ms();
+        match (self.game.winner, &self.celebration) {
+            (Some(_), None) if self.animations => self.celebration = Some(Celebration::new(now)),
+            (None, Some(_)) => self.celebration = None,
+            _ => {}
+        }
+        match (self.game.winner, self.game_ended_ms) {
+            (Some(_), None) => {
+                self.game_ended_ms = Some(now);
+                self.archive_game();
+            }
+            (None, Some(_)) => self.game_ended_ms = None,
+            _ => {}
+        }
+        match (self.game.winner, &self.summary) {
+            (Some(_), None) if self.tournament.is_none() => {
+                let duration = self.elapsed_ms(now);
+                self.summary = Some(Summary::new(&self.game, &self.move_times, duration));
+            }
+            (None, Some(_)) => self.summary = None,
+            _ => {}
+        }
+        // The HUD's elapsed time ticks over.
+        let elapsed_s = (self.elapsed_ms(now) / 1000.0) as i64;
+        if elapsed_s != self.shown_elapsed_s {
+            self.shown_elapsed_s = elapsed_s;
+            self.dirty = true;
+        }
+        // Diagnostics time every frame, so every frame is drawn.
+        if self.show_diagnostics {
+            self.dirty = true;
+        }
+        let toasting = self.toasts.step(now);
+        if self.fades.is_empty()
+            && !toasting
+            && self.hint.is_none()
+            && self.pv.is_none()
+            && self.confirm.is_none()
+            && self.camera_tween.is_none()
+            && self.glide.is_none()
+            && !self.celebration.as_ref().is_some_and(|c| c.running(now))
+        {
+            return;
+        }
+        if let Some(g) = self.glide.as_mut() {
+            let dt = now - g.last_ms;
+            self.cam_x -= g.velocity.0 * dt / self.cell_px;
+            self.cam_y -= g.velocity.1 * dt / self.cell_px;
+            let decay = (-dt / GLIDE_TAU_MS).exp();
+            g.velocity = (g.velocity.0 * decay, g.velocity.1 * decay);
+            g.last_ms = now;
+            if g.velocity.0.hypot(g.velocity.1) < GLIDE_STOP {
+                self.glide = None;
+            }
+        }
+        if let Some(tween) = self.camera_tween {
+            let (cam, done) = tween.at(now);
+            self.set_camera(cam);
+            if done {
+                self.camera_tween = None;
+            }
+        }
+        self.fades.retain(|f| now - f.start_ms < FADE_MS);
+        self.hint = self.hint.filter(|h| now - h.start_ms < HINT_MS);
+        self.pv = self.pv.take().filter(|pv| now - pv.start_ms < PV_MS);
+        self.confirm = self.confirm.filter(|c| now - c.start_ms < CONFIRM_MS);
+        self.dirty = true;
+    }
+
+    /// Standard → Pente → Connect6 → Standard; starts a new game.
+    fn cycle_variant(&mut self) {
+        let rules = Variant::of(self.game.rules).next().apply(self.game.rules);
+        self.game = Game::with_rules(rules.validated());
+        self.restart();
+    }
+
+    /// Handicap off → 1 → … → MAX_HANDICAP → off, for both sides.
+    fn cycle_handicap(&mut self) {
+        let next = (self.ai[0].handicap + 1) % (MAX_HANDICAP + 1);
+        for ai in &mut self.ai {
+            ai.handicap = next;
+        }
+        self.dirty = true;
+    }
+
+    /// What `setting` is now, as the settings panel shows it.
+    fn setting_value(&self, setting: Setting) -> Msg {
+        let on_off = |on: bool| if on { Msg::On } else { Msg::Off };
+        match setting {
+            Setting::Theme if self.theme.is_high_contrast() => Msg::ThemeContrast,
+            Setting::Theme if std::ptr::eq(self.theme, &theme::WOOD) => Msg::ThemeWood,
+            Setting::Theme => match theme::scheme() {
+                theme::Scheme::Auto => Msg::ThemeAuto,
+                theme::Scheme::Light => Msg::ThemeLight,
+                theme::Scheme::Dark => Msg::ThemeDark,
+            },
+            Setting::Difficulty => match self.ai[0].handicap {
+                0 => Msg::DifficultyHard,
+                1 => Msg::DifficultyMedium,
+                2 => Msg::DifficultyEasy,
+                _ => Msg::DifficultyBeginner,
+            },
+            Setting::Sound => on_off(!audio::muted()),
+            Setting::Variant => Variant::of(self.game.rules).label(),
+            Setting::Wheel if self.wheel.scroll => Msg::WheelScroll,
+            Setting::Wheel => Msg::WheelZoom,
+            Setting::Animations => on_off(self.animations),
+            Setting::EvalBar => on_off(self.show_eval_bar),
+            Setting::ImageSize => match self.image_size {
+                ImageSize::Small => Msg::ImageSmall,
+                ImageSize::Medium => Msg::ImageMedium,
+                ImageSize::Large => Msg::ImageLarge,
+            },
+        }
+    }
+
+    /// Step `setting` to its next value, and remember the panel's settings.
+    fn cycle_setting(&mut self, setting: Setting) {
+        match setting {
+            // Auto → Light → Dark → High contrast → Wood → Auto.
+            Setting::Theme => match self.setting_value(Setting::Theme) {
+                Msg::ThemeAuto => {
+                    theme::set_scheme(theme::Scheme::Light);
+                    self.theme = theme::scheme_theme();
+                }
+                Msg::ThemeLight => {
+                    theme::set_scheme(theme::Scheme::Dark);
+                    self.theme = theme::scheme_theme();
+                }
+                Msg::ThemeDark => self.theme = &theme::HIGH_CONTRAST,
+                Msg::ThemeContrast => self.theme = &theme::WOOD,
+                _ => {
+                    theme::set_scheme(theme::Scheme::Auto);
+                    self.theme = theme::scheme_theme();
+                }
+            },
+            Setting::Difficulty => self.cycle_handicap(),
+            Setting::Sound => audio::toggle_muted(),
+            Setting::Variant => self.cycle_variant(),
+            Setting::Wheel => self.wheel.scroll = !self.wheel.scroll,
+            Setting::Animations => {
+                self.animations = !self.animations;
+                self.celebration = None;
+            }
+            Setting::EvalBar => self.show_eval_bar = !self.show_eval_bar,
+            Setting::ImageSize => {
+                self.image_size = match self.image_size {
+                    ImageSize::Small => ImageSize::Medium,
+                    ImageSize::Medium => ImageSize::Large,
+                    ImageSize::Large => ImageSize::Small,
+                }
+            }
+        }
+        self.save_settings();
+        self.toast(t(Msg::Saved));
+    }
+
+    /// Store what the settings panel covers (see `settings`), leaving out what
+    /// is as it would be anyway.
+    fn save_settings(&self) {
+        let theme = if self.theme.is_high_contrast() {
+            "contrast"
+        } else if std::ptr::eq(self.theme, &theme::WOOD) {
+            "wood"
+        } else {
+            ""
+        };
+        let handicap = match self.ai[0].handicap {
+            0 => String::new(),
+            n => n.to_string(),
+        };
+        let variant = match Variant::of(self.game.rules) {
+            Variant::Standard => "",
+            v => v.name(),
+        };
+        let animations = match self.animations {
+            on if on != prefers_reduced_motion() => "",
+            true => "on",
+            false => "off",
+        };
+        settings::save(&[
+            ("theme", theme.to_string()),
+            ("handicap", handicap),
+            ("variant", variant.to_string()),
+            ("wheel", if self.wheel.scroll { "scroll" } else { "" }.to_string()),
+            ("animations", animations.to_string()),
+            ("evalbar", if self.show_eval_bar { "on" } else { "" }.to_string()),
+            ("imagesize", self.image_size.name().to_string()),
+        ]);
+    }
+
+    /// The next shorter or longer of `THINK_PRESETS_MS`, for both sides.
+    fn step_think_time(&mut self, longer: bool) {
+        let now = self.ai[0].think_ms();
+        let next = if longer {
+            THINK_PRESETS_MS.iter().find(|&&ms| ms > now)
+        } else {
+            THINK_PRESETS_MS.iter().rev().find(|&&ms| ms < now)
+        };
+        if let Some(&ms) = next {
+            for ai in &mut self.ai {
+                ai.think_ms = Some(ms);
+            }
+            self.dirty = true;
+        }
+    }
+
+    /// Balanced → aggressive → defensive, for both sides.
+    fn cycle_style(&mut self) {
+        let next = self.ai[0].style.next();
+        for ai in &mut self.ai {
+            ai.style = next;
+        }
+        self.dirty = true;
+    }
+
+    /// Whether the hint button applies: a human is to move in a live, unfinished game.
+    fn can_hint(&self) -> bool {
+        self.replay.is_none() && self.game.winner.is_none() && self.is_human(self.game.player)
+    }
+
+    /// Mark the engine's choice for the human to move, without playing it.
+    fn show_hint(&mut self) {
+        if !self.can_hint() {
+            return;
+        }
+        let ai = self.ai[self.game.player as usize];
+        if let Some(p) = self.game.ai_move(ai, &mut self.rng, &mut self.tt) {
+            self.hint = Some(Hint {
+                p,
+                start_ms: now_ms(),
+            });
+            self.dirty = true;
+        }
+    }
+
+    fn maybe_replay_step(&mut self) {
+        if let Some(replay) = self.replay.as_mut() {
+            if replay.tick(now_ms()) {
+                self.reveal_replay_move();
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Pointer position in canvas-local CSS pixels.
+    fn pointer_pos(&self, e: &PointerEvent) -> (f64, f64) {
+        let rect = self
+            .canvas
+            .unchecked_ref::<Element>()
+            .get_bounding_client_rect();
+        (
+            e.client_x() as f64 - rect.left(),
+            e.client_y() as f64 - rect.top(),
+        )
+    }
+
+    fn on_pointer_down(&mut self, e: PointerEvent) {
+        self.audio.unlock();
+        let pos = self.pointer_pos(&e);
+        self.camera_tween = None;
+        self.glide = None;
+        let _ = self.canvas.set_pointer_capture(e.pointer_id());
+        self.pointers.insert(e.pointer_id(), pos);
+        match self.drag.as_mut() {
+            // A second finger turns the press into a pinch; never treat it as a tap.
+            Some(drag) => {
+                drag.panning = true;
+                drag.loupe = false;
+                self.dirty = true;
+            }
+            None => {
+                // Middle-button and Space+left presses pan from the start.
+                let pan_only = e.button() == 1 || (self.space_held && e.button() == 0);
+                self.drag = Some(Drag {
+                    pointer_id: e.pointer_id(),
+                    start: pos,
+                    last: pos,
+                    start_ms: now_ms(),
+                    last_ms: now_ms(),
+                    velocity: (0.0, 0.0),
+                    panning: pan_only,
+                    loupe: false,
+                })
+            }
+        }
+    }
+
+    fn on_pointer_move(&mut self, e: PointerEvent) {
+        let (sx, sy) = self.pointer_pos(&e);
+        if e.pointer_type() != "touch" {
+            let cell = |a: &App| a.hover.map(|(hx, hy)| a.screen_to_cell(hx, hy));
+            let before = cell(self);
+            self.hover = Some((sx, sy));
+            if cell(self) != before {
+                self.dirty = true;
+            }
+        }
+        if !self.pointers.contains_key(&e.pointer_id()) {
+            return;
+        }
+        let before = self.pinch_geometry();
+        self.pointers.insert(e.pointer_id(), (sx, sy));
+        if let Some(drag) = self.drag.as_mut().filter(|d| d.pointer_id == e.pointer_id()) {
+            let (lx, ly) = std::mem::replace(&mut drag.last, (sx, sy));
+            // The magnifier follows the finger instead of panning.
+            if drag.loupe {
+                self.dirty = true;
+                return;
+            }
+            if !drag.panning {
+                let (x0, y0) = drag.start;
+                drag.panning = (sx - x0).hypot(sy - y0) > DRAG_THRESHOLD_PX;
+            }
+            if drag.panning && before.is_none() {
+                self.cam_x -= (sx - lx) / self.cell_px;
+                self.cam_y -= (sy - ly) / self.cell_px;
+                self.dirty = true;
+                let now = now_ms();
+                let dt = (now - drag.last_ms).max(1.0);
+                // Weighted towards the latest movement, to follow the flick.
+                let (vx, vy) = drag.velocity;
+                let k = 0.8;
+                drag.velocity = (
+                    k * (sx - lx) / dt + (1.0 - k) * vx,
+                    k * (sy - ly) / dt + (1.0 - k) * vy,
+                );
+                drag.last_ms = now;
+            }
+        }
+
+        if let (Some((m0, d0)), Some((m1, d1))) = (before, self.pinch_geometry()) {
+            // Follow the midpoint, then, once the fingers have clearly spread or
+            // closed rather than moved together, scale around it.
+            self.cam_x -= (m1.0 - m0.0) / self.cell_px;
+            self.cam_y -= (m1.1 - m0.1) / self.cell_px;
+            let pinch = self.pinch.get_or_insert(Pinch {
+                start_dist: d0,
+                zooming: false,
+            });
+            pinch.zooming |= (d1 - pinch.start_dist).abs() > PINCH_ZOOM_PX;
+            if pinch.zooming && d0 > 0.0 {
+                self.zoom_at(m1.0, m1.1, self.cell_px * d1 / d0);
+            }
+            self.dirty = true;
+        }
+    }
+
+    fn on_pointer_up(&mut self, e: PointerEvent) {
+        if self.pointers.remove(&e.pointer_id()).is_none() {
+            return;
+        }
+        if self.pointers.len() < 2 {
+            self.pinch = None;
+        }
+        let Some(drag) = self.drag.filter(|d| d.pointer_id == e.pointer_id()) else {
+            return;
+        };
+        // Hand the pan over to a finger that is still down.
+        self.drag = self.pointers.iter().next().map(|(&id, &pos)| Drag {
+            pointer_id: id,
+            start: pos,
+            last: pos,
+            start_ms: now_ms(),
+            last_ms: now_ms(),
+            velocity: (0.0, 0.0),
+            panning: true,
+            loupe: false,
+        });
+        if drag.loupe {
+            let (sx, sy) = self.pointer_pos(&e);
+            self.place_at(self.screen_to_cell(sx, sy));
+            self.dirty = true;
+        } else if drag.panning {
+            let (vx, vy) = drag.velocity;
+            let fresh = now_ms() - drag.last_ms < GLIDE_RELEASE_MS;
+            if self.animations && self.drag.is_none() && fresh && vx.hypot(vy) > GLIDE_MIN_SPEED {
+                self.glide = Some(Glide {
+                    velocity: drag.velocity,
+                    last_ms: now_ms(),
+                });
+            }
+        } else {
+            let (sx, sy) = self.pointer_pos(&e);
+            if !self.is_idle_tap(sx, sy) {
+                self.last_tap = None;
+                self.on_tap(sx, sy);
+                return;
+            }
+            let now = now_ms();
+            let double = self.last_tap.take().is_some_and(|t| {
+                now - t.ms < DOUBLE_TAP_MS
+                    && (sx - t.pos.0).hypot(sy - t.pos.1) < DOUBLE_TAP_PX
+            });
+            if double {
+                let factor = if e.shift_key() { 1.0 / DOUBLE_TAP_ZOOM } else { DOUBLE_TAP_ZOOM };
+                self.tween_zoom_at(sx, sy, factor);
+            } else {
+                self.last_tap = Some(IdleTap { pos: (sx, sy), ms: now });
+                self.on_tap(sx, sy);
+            }
+        }
+    }
+
+    /// Whether a tap at `(sx, sy)` would neither press a button nor play, so a
+    /// second one can zoom instead.
+    fn is_idle_tap(&self, sx: f64, sy: f64) -> bool {
+        if self.show_settings
+            || self.saves.is_some()
+            || self.history.is_some()
+            || self.library.is_some()
+        {
+            return false;
+        }
+        let buttons = [
+            self.btn_black,
+            self.btn_white,
+            self.btn_style,
+            self.btn_think_less,
+            self.btn_think_more,
+            self.btn_sound,
+            self.btn_settings,
+            self.btn_fullscreen,
+            self.btn_fit,
+            self.minimap.map_or((0.0, 0.0, 0.0, 0.0), |m| m.rect),
+            self.btn_place,
+            self.graph_rect,
+            self.move_list_rect,
+        ];
+        let tools = self.toolbar.iter().chain(&self.card_buttons);
+        let mut buttons = buttons.iter().chain(tools.map(|(_, b)| b));
+        if buttons.any(|&b| self.hit_btn(b, sx, sy)) {
+            return false;
+        }
+        if self.replay.is_some() || self.tournament.is_some() {
+            return true;
+        }
+        let p = self.screen_to_cell(sx, sy);
+        self.game.winner.is_none() && !(self.is_human(self.game.player) && self.game.playable(p))
+    }
+
+    fn on_pointer_cancel(&mut self, e: PointerEvent) {
+        self.pointers.remove(&e.pointer_id());
+        if self.pointers.len() < 2 {
+            self.pinch = None;
+        }
+        if self.drag.is_some_and(|d| d.pointer_id == e.pointer_id()) {
+            self.drag = None;
+            self.dirty = true;
+        }
+    }
+
+    /// Open the magnifier once a single press on the board has been held still
+    /// long enough, while a human is to move.
+    fn maybe_long_press(&mut self) {
+        if self.show_settings
+            || self.saves.is_some()
+            || self.history.is_some()
+            || self.library.is_some()
+        {
+            return;
+        }
+        let can_place = self.replay.is_none()
+            && self.tournament.is_none()
+            && self.game.winner.is_none()
+            && self.is_human(self.game.player);
+        let Some(drag) = self.drag.as_mut() else {
+            return;
+        };
+        if drag.loupe || drag.panning || now_ms() - drag.start_ms < LONG_PRESS_MS {
+            return;
+        }
+        // Held on a pill: rename that side. The press is used up, so its
+        // release does not also switch the side between human and AI.
+        let (sx, sy) = drag.start;
+        let pill = if self.hit_btn(self.btn_black, sx, sy) {
+            Some(Color::Black)
+        } else if self.hit_btn(self.btn_white, sx, sy) {
+            Some(Color::White)
+        } else {
+            None
+        };
+        if let Some(side) = pill {
+            self.drag = None;
+            self.rename(side);
+            return;
+        }
+        if !can_place {
+            return;
+        }
+        let Some(drag) = self.drag.as_mut() else {
+            return;
+        };
+        drag.loupe = true;
+        self.dirty = true;
+    }
+
+    fn on_pointer_leave(&mut self, _e: PointerEvent) {
+        if self.hover.take().is_some() {
+            self.dirty = true;
+        }
+    }
+
+    /// Midpoint and distance of the two lowest-id active pointers, if at least two are down.
+    fn pinch_geometry(&self) -> Option<((f64, f64), f64)> {
+        let mut ids: Vec<i32> = self.pointers.keys().copied().collect();
+        if ids.len() < 2 {
+            return None;
+        }
+        ids.sort_unstable();
+        let (ax, ay) = self.pointers[&ids[0]];
+        let (bx, by) = self.pointers[&ids[1]];
+        Some((((ax + bx) / 2.0, (ay + by) / 2.0), (bx - ax).hypot(by - ay)))
+    }
+
+    /// A press that was released without panning: toggle pills or place a stone.
+    fn on_tap(&mut self, sx: f64, sy: f64) {
+        if self.show_help || self.share.is_some() {
+            self.show_help = false;
+            self.close_share();
+            self.dirty = true;
+            return;
+        }
+        // In the settings panel a row changes its setting, and a tap elsewhere closes it.
+        if self.show_settings {
+            match self.settings_rows.iter().find(|&&(_, b)| self.hit_btn(b, sx, sy)) {
+                Some(&(setting, _)) => self.cycle_setting(setting),
+                None => self.show_settings = false,
+            }
+            self.dirty = true;
+            return;
+        }
+        // On the history screen a button does its thing, and a tap elsewhere closes it.
+        if self.history.is_some() {
+            match self.history_buttons.iter().find(|&&(_, b)| self.hit_btn(b, sx, sy)) {
+                Some(&(action, _)) => self.use_history(action),
+                None => self.history = None,
+            }
+            self.dirty = true;
+            return;
+        }
+        // In a RenjuNet library a Replay button replays its game, and a tap
+        // elsewhere closes it.
+        if self.library.is_some() {
+            match self.library_buttons.iter().find(|&&(_, b)| self.hit_btn(b, sx, sy)) {
+                Some(&(i, _)) => self.replay_record(i),
+                None => self.library = None,
+            }
+            self.dirty = true;
+            return;
+        }
+        // In the saves menu a button does its thing, and a tap elsewhere closes it.
+        if self.saves.is_some() {
+            if self.hit_btn(self.btn_history, sx, sy) {
+                self.saves = None;
+                self.open_history();
+                return;
+            }
+            match self.save_buttons.iter().find(|&&(_, b)| self.hit_btn(b, sx, sy)) {
+                Some(&(action, _)) => self.use_slot(action),
+                None => self.saves = None,
+            }
+            self.dirty = true;
+            return;
+        }
+        if self.hit_btn(self.btn_settings, sx, sy) {
+            self.show_settings = true;
+            self.dirty = true;
+            return;
+        }
+        if self.hit_btn(self.btn_sound, sx, sy) {
+            audio::toggle_muted();
+            self.dirty = true;
+            return;
+        }
+        if self.hit_btn(self.btn_fullscreen, sx, sy) {
+            self.toggle_fullscreen();
+            return;
+        }
+        if self.hit_btn(self.btn_fit, sx, sy) {
+            self.zoom_to_fit();
+            return;
+        }
+        let mut tools = self.toolbar.iter().chain(&self.card_buttons);
+        if let Some(&(action, _)) = tools.find(|&&(_, b)| self.hit_btn(b, sx, sy)) {
+            self.use_tool(action);
+            return;
+        }
+        if let Some(m) = self.minimap.filter(|m| self.hit_btn(m.rect, sx, sy)) {
+            let (x, y) = m.cell_at(sx, sy);
+            self.tween_camera(Camera {
+                x,
+                y,
+                ..self.camera_goal()
+            });
+            return;
+        }
+        if self.replay.is_some() {
+            if let Some(&(n, _)) = self.move_list_rows.iter().find(|&&(_, b)| self.hit_btn(b, sx, sy)) {
+                self.jump_to_move(n);
+                return;
+            }
+            if self.analysis.is_some() && self.hit_btn(self.graph_rect, sx, sy) {
+                let (gx, _, gw, _) = self.graph_rect;
+                if let Some(replay) = self.replay.as_mut() {
+                    let n = ((sx - gx) / gw * replay.len() as f64).round();
+                    replay.seek(n.max(0.0) as usize);
+                    self.reveal_replay_move();
+                    self.dirty = true;
+                }
+            }
+            return;
+        }
+        // The tournament plays itself; `G` hands the board back.
+        if self.tournament.is_some() {
+            return;
+        }
+        if self.game.winner.is_some() {
+            self.confirm_restart(t(Msg::ConfirmRestartTap));
+            return;
+        }
+
+        // Toggle pills first; a remote side cannot be taken over.
+        if self.hit_btn(self.btn_black, sx, sy) {
+            self.ctrl_black = match self.ctrl_black {
+                Controller::Human => Controller::AI,
+                Controller::AI => Controller::Human,
+                Controller::Remote => return,
+            };
+            self.dirty = true;
+            if self.game.player == Color::Black && self.is_ai(Color::Black) {
+                self.queue_ai_soon(80.0);
+            }
+            return;
+        }
+        if self.hit_btn(self.btn_white, sx, sy) {
+            self.ctrl_white = match self.ctrl_white {
+                Controller::Human => Controller::AI,
+                Controller::AI => Controller::Human,
+                Controller::Remote => return,
+            };
+            self.dirty = true;
+            if self.game.player == Color::White && self.is_ai(Color::White) {
+                self.queue_ai_soon(80.0);
+            }
+            return;
+        }
+        if self.hit_btn(self.btn_style, sx, sy) {
+            self.cycle_style();
+            return;
+        }
+        if self.hit_btn(self.btn_think_less, sx, sy) {
+            self.step_think_time(false);
+            return;
+        }
+        if self.hit_btn(self.btn_think_more, sx, sy) {
+            self.step_think_time(true);
+            return;
+        }
+
+        // Board play; in two-tap mode the first tap only picks the cell.
+        if let Some(p) = self.selected.filter(|_| self.hit_btn(self.btn_place, sx, sy)) {
+            self.place_at(p);
+            return;
+        }
+        let p = self.screen_to_cell(sx, sy);
+        if self.two_tap && self.selected != Some(p) {
+            if self.is_human(self.game.player) && self.game.playable(p) {
+                self.selected = Some(p);
+                self.dirty = true;
+            }
+            return;
+        }
+        self.place_at(p);
+    }
+
+    /// A human's stone on `p`, from a tap or the keyboard cursor: asked about
+    /// first if it hands the opponent a win, then played and sent.
+    fn place_at(&mut self, p: Pt) {
+        if !self.is_human(self.game.player) {
+            self.audio.play(Effect::Invalid);
+            haptics::buzz(Buzz::Invalid);
+            return;
+        }
+        let confirmed = self.confirm.is_some_and(|c| c.p == p);
+        if self.blunder_alerts && !confirmed && self.game.leaves_win(p) {
+            self.confirm = Some(Confirm {
+                p,
+                start_ms: now_ms(),
+            });
+            self.dirty = true;
+            return;
+        }
+        self.confirm = None;
+        if self.play_move(p) {
+            self.send(&NetMsg::Move(p));
+            if self.is_ai_turn() {
+                self.queue_ai_soon(AI_START_MS);
+            }
+        } else {
+            self.audio.play(Effect::Invalid);
+            haptics::buzz(Buzz::Invalid);
+        }
+    }
+
+    /// A cell's button activated in the screen-reader grid: play there, as the
+    /// keyboard cursor would, and say so if the move was held back.
+    fn on_a11y_cell(&mut self, p: Pt) {
+        if self.replay.is_some() || self.tournament.is_some() || self.game.winner.is_some() {
+            return;
+        }
+        self.place_at(p);
+        if self.confirm.is_some_and(|c| c.p == p) {
+            let opp = self.player_name(self.game.player.other());
+            self.a11y.announce(&tf(Msg::LeavesWinActivate, &[&opp]));
+        }
+    }
+
+    /// `side`'s name, or its colour if it has none.
+    fn player_name(&self, side: Color) -> String {
+        self.names[side as usize]
+            .clone()
+            .unwrap_or_else(|| i18n::color(side))
+    }
+
+    /// Name `side`'s player; an empty name goes back to the colour.
+    fn set_name(&mut self, side: Color, name: &str) {
+        let name: String = name.trim().chars().take(MAX_NAME_CHARS).collect();
+        self.names[side as usize] = Some(name).filter(|n| !n.is_empty());
+        self.dirty = true;
+    }
+
+    /// Ask for a new name for `side`'s player with the browser's prompt.
+    fn rename(&mut self, side: Color) {
+        let message = tf(Msg::RenamePrompt, &[&i18n::color(side)]);
+        let current = self.names[side as usize].clone().unwrap_or_default();
+        if let Ok(Some(name)) = window()
+            .unwrap()
+            .prompt_with_message_and_default(&message, &current)
+        {
+            self.set_name(side, &name);
+        }
+    }
+
+    fn hit_btn(&self, btn: (f64, f64, f64, f64), sx: f64, sy: f64) -> bool {
+        let (x, y, w, h) = btn;
+        sx >= x && sx <= x + w && sy >= y && sy <= y + h
+    }
+
+    fn on_wheel(&mut self, e: WheelEvent) {
+        self.camera_tween = None;
+        self.glide = None;
+        let rect = self
+            .canvas
+            .unchecked_ref::<Element>()
+            .get_bounding_client_rect();
+        let sx = e.client_x() as f64 - rect.left();
+        let sy = e.client_y() as f64 - rect.top();
+
+        // Line and page deltas (Firefox, some mice) in pixels.
+        let unit = match e.delta_mode() {
+            WheelEvent::DOM_DELTA_LINE => 16.0,
+            WheelEvent::DOM_DELTA_PAGE => self.view_h,
+            _ => 1.0,
+        };
+        let dx = e.delta_x() * unit;
+        let dy = e.delta_y() * unit;
+        let prefs = self.wheel;
+
+        // On the history screen, the wheel scrolls the games.
+        if let Some(history) = self.history.as_mut() {
+            let rows = if dy > 0.0 { 1 } else { -1 };
+            history.scroll = history.scroll.saturating_add_signed(rows);
+            self.dirty = true;
+            return;
+        }
+        // And likewise in a RenjuNet library.
+        if let Some(library) = self.library.as_mut() {
+            let rows = if dy > 0.0 { 1 } else { -1 };
+            library.scroll = library.scroll.saturating_add_signed(rows);
+            self.dirty = true;
+            return;
+        }
+
+        // Over the replay's move list, the wheel scrolls the list.
+        if self.replay.is_some() && self.hit_btn(self.move_list_rect, sx, sy) {
+            let rows = (dy / MOVE_LIST_ROW_PX).round().clamp(-6.0, 6.0);
+            let rows = if rows == 0.0 && dy != 0.0 { dy.signum() } else { rows };
+            self.move_list_scroll = (self.move_list_scroll as f64 + rows).max(0.0) as usize;
+            self.dirty = true;
+            return;
+        }
+
+        // Trackpad pinches arrive as Ctrl+wheel, so Ctrl always zooms.
+        if prefs.scroll && !e.ctrl_key() {
+            let (dx, dy) = if e.shift_key() && dx == 0.0 { (dy, 0.0) } else { (dx, dy) };
+            self.cam_x += dx / self.cell_px.max(1.0);
+            self.cam_y += dy / self.cell_px.max(1.0);
+            self.dirty = true;
+            return;
+        }
+        if !prefs.scroll && (e.shift_key() || dx.abs() > dy.abs()) {
+            let pan_cells = dx / self.cell_px.max(1.0);
+            self.cam_x += pan_cells;
+            self.dirty = true;
+            return;
+        }
+
+        // Proportional to the travel, so a trackpad's many small deltas zoom as
+        // smoothly as a mouse's notches; capped so one event cannot jump far.
+        let travel = dy.clamp(-200.0, 200.0) * if prefs.invert { -1.0 } else { 1.0 };
+        let factor = (-travel * WHEEL_ZOOM_PER_PX * prefs.sensitivity).exp();
+        self.zoom_at(sx, sy, self.cell_px * factor);
+    }
+
+    /// Set the zoom to `new_px` (clamped), keeping the cell under screen point `(sx, sy)` fixed.
+    fn zoom_at(&mut self, sx: f64, sy: f64, new_px: f64) {
+        let new = new_px.clamp(MIN_CELL_PX, MAX_CELL_PX);
+        if (new - self.cell_px).abs() < f64::EPSILON {
+            return;
+        }
+
+        let (cell_x, cell_y) = self.screen_to_cell_f64(sx, sy);
+        self.cell_px = new;
+        self.cam_x = cell_x - (sx - self.view_w / 2.0) / self.cell_px;
+        self.cam_y = cell_y - (sy - self.view_h / 2.0) / self.cell_px;
+
+        self.dirty = true;
+    }
+
+    fn camera(&self) -> Camera {
+        Camera {
+            x: self.cam_x,
+            y: self.cam_y,
+            px: self.cell_px,
+        }
+    }
+
+    fn set_camera(&mut self, cam: Camera) {
+        self.cam_x = cam.x;
+        self.cam_y = cam.y;
+        self.cell_px = cam.px;
+        self.dirty = true;
+    }
+
+    /// Where the camera is headed: the end of the move in flight, if any.
+    fn camera_goal(&self) -> Camera {
+        self.camera_tween.map_or(self.camera(), |t| t.to)
+    }
+
+    /// Animate the camera from where it is to `to` (zoom clamped).
+    fn tween_camera(&mut self, to: Camera) {
+        let to = Camera {
+            px: to.px.clamp(MIN_CELL_PX, MAX_CELL_PX),
+            ..to
+        };
+        self.glide = None;
+        if !self.animations {
+            self.camera_tween = None;
+            self.set_camera(to);
+            self.dirty = true;
+            return;
+        }
+        self.camera_tween = Some(CameraTween {
+            from: self.camera(),
+            to,
+            start_ms: now_ms(),
+        });
+        self.dirty = true;
+    }
+
+    /// Animate a zoom by `factor` around screen point `(sx, sy)`. It starts from
+    /// the goal of any move in flight, so quick repeats add up.
+    fn tween_zoom_at(&mut self, sx: f64, sy: f64, factor: f64) {
+        let goal = self.camera_goal();
+        let px = (goal.px * factor).clamp(MIN_CELL_PX, MAX_CELL_PX);
+        let (dx, dy) = (sx - self.view_w / 2.0, sy - self.view_h / 2.0);
+        self.tween_camera(Camera {
+            x: goal.x + dx / goal.px - dx / px,
+            y: goal.y + dy / goal.px - dy / px,
+            px,
+        });
+    }
+
+    /// Animate the view over `p` if the cell is off screen or nearly so.
+    fn reveal(&mut self, p: Pt) {
+        let goal = self.camera_goal();
+        let margin = 2.0;
+        let half_w = (self.view_w / 2.0 / goal.px - margin).max(0.0);
+        let half_h = (self.view_h / 2.0 / goal.px - margin).max(0.0);
+        let (x, y) = (p.x as f64, p.y as f64);
+        if (x - goal.x).abs() > half_w || (y - goal.y).abs() > half_h {
+            self.tween_camera(Camera { x, y, ..goal });
+        }
+    }
+
+    /// Animate the view to show every stone with a couple of cells to spare,
+    /// as close up as that allows.
+    fn zoom_to_fit(&mut self) {
+        let Some(stones) = self.shown_game().stone_extent() else {
+            return;
+        };
+        let margin = 2.0;
+        let cols = (stones.max.x - stones.min.x) as f64 + 1.0 + 2.0 * margin;
+        let rows = (stones.max.y - stones.min.y) as f64 + 1.0 + 2.0 * margin;
+        self.tween_camera(Camera {
+            x: (stones.min.x + stones.max.x) as f64 / 2.0,
+            y: (stones.min.y + stones.max.y) as f64 / 2.0,
+            px: (self.view_w / cols).min(self.view_h / rows),
+        });
+    }
+
+    /// Animate the view back over the last move, or the middle of an empty board.
+    fn recenter(&mut self) {
+        let p = self.shown_game().last_move.unwrap_or(Pt::new(0, 0));
+        self.tween_camera(Camera {
+            x: p.x as f64,
+            y: p.y as f64,
+            ..self.camera_goal()
+        });
+    }
+
+    /// Show the replay after `n` moves, centred on the last of them.
+    fn jump_to_move(&mut self, n: usize) {
+        let Some(replay) = self.replay.as_mut() else {
+            return;
+        };
+        replay.seek(n);
+        if let Some(p) = replay.view().last_move {
+            self.tween_camera(Camera {
+                x: p.x as f64,
+                y: p.y as f64,
+                ..self.camera_goal()
+            });
+        }
+        self.dirty = true;
+    }
+
+    /// Enter or leave presentation mode; entering zooms in to at least
+    /// `PRESENT_CELL_PX` so the stones read from the back of a room.
+    fn set_presenting(&mut self, on: bool) {
+        self.presenting = on;
+        theme::set_text_zoom(if on { PRESENT_TEXT_ZOOM } else { 1.0 });
+        if on {
+            let goal = self.camera_goal();
+            self.tween_camera(Camera {
+                px: goal.px.max(PRESENT_CELL_PX),
+                ..goal
+            });
+        }
+        self.dirty = true;
+    }
+
+    /// After a replay jumps, bring the last stone shown into view.
+    fn reveal_replay_move(&mut self) {
+        if let Some(p) = self.replay.as_ref().and_then(|r| r.view().last_move) {
+            self.reveal(p);
+        }
+    }
+
+    /// Move the keyboard cursor a cell, starting from the middle of the view,
+    /// and scroll just enough to keep it a cell away from the edges.
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let p = match self.cursor {
+            Some(c) => c.add(dx, dy),
+            None => Pt::new(self.cam_x.round() as i32, self.cam_y.round() as i32),
+        };
+        if !self.game.in_bounds(p) {
+            return;
+        }
+        self.cursor = Some(p);
+        let (sx, sy) = self.cell_to_screen(p);
+        let m = self.cell_px;
+        self.cam_x += ((sx - (self.view_w - m)).max(0.0) - (m - sx).max(0.0)) / self.cell_px;
+        self.cam_y += ((sy - (self.view_h - m)).max(0.0) - (m - sy).max(0.0)) / self.cell_px;
+        self.dirty = true;
+    }
+
+    /// Apply this frame's gamepad input: the same camera moves and cursor play
+    /// as the keyboard's.
+    fn poll_gamepad(&mut self) {
+        let Some(pad) = self.gamepad.poll(now_ms()) else {
+            return;
+        };
+        if pad.pan != (0.0, 0.0) {
+            self.glide = None;
+            self.cam_x += pad.pan.0 / self.cell_px;
+            self.cam_y += pad.pan.1 / self.cell_px;
+            self.dirty = true;
+        }
+        if pad.zoom != 1.0 {
+            self.camera_tween = None;
+            self.zoom_at(self.view_w / 2.0, self.view_h / 2.0, self.cell_px * pad.zoom);
+        }
+        for (dx, dy) in pad.steps {
+            self.move_cursor(dx, dy);
+        }
+        if pad.cancel {
+            self.cursor = None;
+            self.dirty = true;
+        }
+        if pad.place && self.replay.is_none() && self.tournament.is_none() {
+            match self.cursor {
+                _ if self.game.winner.is_some() => {
+                    self.confirm_restart(tf(Msg::ConfirmRestartKey, &[&"A"]))
+                }
+                Some(p) => self.place_at(p),
+                None => self.move_cursor(0, 0),
+            }
+        }
+    }
+
+    /// Space doubles as a pan modifier, so its preview is hidden while it is down.
+    fn set_space_held(&mut self, held: bool) {
+        if self.space_held != held {
+            self.space_held = held;
+            self.dirty = true;
+        }
+    }
+
+    fn on_key(&mut self, e: KeyboardEvent) {
+        // Keys pressed on the screen-reader grid's buttons are theirs.
+        if self.a11y.owns(&e) {
+            return;
+        }
+        // Ctrl and Cmd chords are the browser's; copy and paste come back as
+        // their own events.
+        if e.ctrl_key() || e.meta_key() {
+            return;
+        }
+        self.audio.unlock();
+        if e.key() == " " {
+            self.set_space_held(true);
+        }
+        if self.replay.is_some() && self.on_replay_key(&e) {
+            return;
+        }
+        let action = keys::action_for(&e);
+        if self.show_help && matches!(action, Some(Action::Help | Action::HideCursor)) {
+            self.show_help = false;
+            self.dirty = true;
+            return;
+        }
+        if self.share.is_some() && matches!(action, Some(Action::Share | Action::HideCursor)) {
+            self.close_share();
+            self.dirty = true;
+            return;
+        }
+        if self.history.is_some() && matches!(action, Some(Action::History | Action::HideCursor)) {
+            self.history = None;
+            self.dirty = true;
+            return;
+        }
+        if self.library.is_some() && action == Some(Action::HideCursor) {
+            self.library = None;
+            self.dirty = true;
+            return;
+        }
+        if self.saves.is_some() && matches!(action, Some(Action::Saves | Action::HideCursor)) {
+            self.saves = None;
+            self.dirty = true;
+            return;
+        }
+        if self.show_settings && matches!(action, Some(Action::Settings | Action::HideCursor)) {
+            self.show_settings = false;
+            self.dirty = true;
+            return;
+        }
+        let Some(action) = action else {
+            return;
+        };
+        if matches!(
+            action,
+            Action::PanLeft | Action::PanRight | Action::PanUp | Action::PanDown
+        ) {
+            self.camera_tween = None;
+        }
+        match action {
+            Action::CursorLeft | Action::CursorRight | Action::CursorUp | Action::CursorDown => {
+                let (dx, dy) = match action {
+                    Action::CursorLeft => (-1, 0),
+                    Action::CursorRight => (1, 0),
+                    Action::CursorUp => (0, -1),
+                    _ => (0, 1),
+                };
+                e.prevent_default();
+                self.move_cursor(dx, dy);
+            }
+            Action::PanLeft => {
+                self.cam_x -= 3.0;
+                self.dirty = true;
+            }
+            Action::PanRight => {
+                self.cam_x += 3.0;
+                self.dirty = true;
+            }
+            Action::PanUp => {
+                self.cam_y -= 3.0;
+                self.dirty = true;
+            }
+            Action::PanDown => {
+                self.cam_y += 3.0;
+                self.dirty = true;
+            }
+            Action::ZoomOut => self.tween_zoom_at(self.view_w / 2.0, self.view_h / 2.0, 1.0 / KEY_ZOOM),
+            Action::ZoomIn => self.tween_zoom_at(self.view_w / 2.0, self.view_h / 2.0, KEY_ZOOM),
+            Action::Coords => {
+                self.show_coords = !self.show_coords;
+                self.dirty = true;
+            }
+            Action::MoveNumbers => {
+                self.show_move_numbers = !self.show_move_numbers;
+                self.dirty = true;
+            }
+            Action::Heatmap => {
+                self.show_heatmap = !self.show_heatmap;
+                self.dirty = true;
+            }
+            Action::BlunderAlerts => {
+                self.blunder_alerts = !self.blunder_alerts;
+                self.confirm = None;
+                self.dirty = true;
+            }
+            Action::Contrast => {
+                self.theme = if self.theme.is_high_contrast() {
+                    theme::scheme_theme()
+                } else {
+                    &theme::HIGH_CONTRAST
+                };
+                self.dirty = true;
+            }
+            Action::Mute => {
+                audio::toggle_muted();
+                self.dirty = true;
+            }
+            Action::Haptics => haptics::toggle(),
+            Action::ColorScheme => {
+                // Flip what is on screen, which is then kept over the browser's preference.
+                let light = std::ptr::eq(theme::scheme_theme(), &theme::LIGHT);
+                theme::set_scheme(if light { theme::Scheme::Dark } else { theme::Scheme::Light });
+            }
+            Action::StoneMarks => {
+                self.stone_marks = !self.stone_marks;
+                self.dirty = true;
+            }
+            Action::Follow => {
+                self.follow = !self.follow;
+                if let Some(p) = self.game.last_move.filter(|_| self.follow) {
+                    self.reveal(p);
+                }
+            }
+            Action::Explain => {
+                self.show_why = !self.show_why;
+                self.dirty = true;
+            }
+            Action::TwoTap => {
+                self.two_tap = !self.two_tap;
+                self.selected = None;
+                self.dirty = true;
+            }
+            Action::ExpectedLine => {
+                self.show_pv = !self.show_pv;
+                self.pv = None;
+                self.dirty = true;
+            }
+            Action::TopMoves => {
+                self.show_top_lines = !self.show_top_lines;
+                self.top_lines = None;
+                self.dirty = true;
+            }
+            Action::Replay => self.open_replay(),
+            Action::Undo => self.undo(),
+            Action::CopyLink => self.copy_link(),
+            Action::Analysis if self.game.winner.is_some() => {
+                self.replay = Some(Replay::new(&self.game).with_times(self.move_offsets()));
+                self.analysis = Some(Analysis::new(&self.game));
+                self.dirty = true;
+            }
+            Action::Clock => {
+                // Off → each preset → off; clocks restart from full time.
+                self.time_preset = match self.time_preset {
+                    None => Some(0),
+                    Some(i) if i + 1 < TimeControl::PRESETS.len() => Some(i + 1),
+                    Some(_) => None,
+                };
+                self.clock = self.time_preset.map(|i| Clock::new(TimeControl::PRESETS[i]));
+                self.dirty = true;
+            }
+            Action::Board => {
+                // Infinite → 15×15 → 19×19 → infinite; starts a new game.
+                let size = self.game.rules.bounds.map(|b| b.size());
+                let next = match size {
+                    None => Some(15),
+                    Some(15) => Some(19),
+                    _ => None,
+                };
+                let mut rules = self.game.rules;
+                rules.bounds = next.map(Bounds::square);
+                self.game = Game::with_rules(rules);
+                self.restart();
+            }
+            Action::Variant => self.cycle_variant(),
+            Action::Hint => self.show_hint(),
+            Action::Engine => {
+                // Alpha-beta ↔ MCTS, for both sides.
+                let next = match self.ai[0].backend {
+                    Backend::AlphaBeta => Backend::Mcts,
+                    Backend::Mcts => Backend::AlphaBeta,
+                };
+                for ai in &mut self.ai {
+                    ai.backend = next;
+                }
+                self.dirty = true;
+            }
+            Action::Style => self.cycle_style(),
+            Action::Handicap => self.cycle_handicap(),
+            Action::Tournament => match self.tournament {
+                Some(_) => self.stop_tournament(),
+                None => self.start_tournament(self.ai),
+            },
+            Action::Restart => {
+                self.confirm_restart(tf(Msg::ConfirmRestartKey, &[&keys::label(&[Action::Restart])]))
+            }
+            Action::Place => {
+                if let Some(p) = self.cursor.filter(|_| self.replay.is_none()) {
+                    e.prevent_default();
+                    if self.tournament.is_none() && self.game.winner.is_none() {
+                        self.place_at(p);
+                    }
+                }
+            }
+            Action::HideCursor => {
+                self.cursor = None;
+                self.dirty = true;
+                // Most browsers leave fullscreen on Escape themselves, before
+                // the page sees the key; this covers the rest.
+                if let Some(doc) = window().and_then(|w| w.document()) {
+                    if doc.fullscreen_element().is_some() {
+                        doc.exit_fullscreen();
+                    }
+                }
+            }
+            Action::Fullscreen => self.toggle_fullscreen(),
+            Action::ZoomToFit => self.zoom_to_fit(),
+            Action::Recenter => self.recenter(),
+            Action::Analysis => {}
+            Action::Help => {
+                self.show_help = true;
+                self.dirty = true;
+            }
+            Action::Settings => {
+                self.show_settings = true;
+                self.dirty = true;
+            }
+            Action::SaveImage => {
+                e.prevent_default();
+                self.save_image();
+            }
+            Action::SaveVideo => {
+                e.prevent_default();
+                self.save_video();
+            }
+            Action::Share => {
+                e.prevent_default();
+                self.open_share();
+            }
+            Action::ExportPsq => {
+                e.prevent_default();
+                self.save_psq();
+            }
+            Action::CopyMoves => {
+                e.prevent_default();
+                self.copy_moves();
+            }
+            Action::ExportReplay => {
+                e.prevent_default();
+                self.save_replay();
+            }
+            Action::Saves => {
+                e.prevent_default();
+                self.open_saves();
+            }
+            Action::History => {
+                e.prevent_default();
+                self.open_history();
+            }
+            Action::Presentation => {
+                e.prevent_default();
+                self.set_presenting(!self.presenting);
+            }
+            Action::Diagnostics => {
+                e.prevent_default();
+                self.show_diagnostics = !self.show_diagnostics;
+                self.frame_times.clear();
+                self.dirty = true;
+            }
+            Action::FrontierDots => {
+                e.prevent_default();
+                self.show_frontier = !self.show_frontier;
+                self.dirty = true;
+            }
+            // Only looked up in replay mode, by `on_replay_key`.
+            Action::ReplayBack
+            | Action::ReplayForward
+            | Action::ReplayStart
+            | Action::ReplayEnd
+            | Action::ReplayAutoplay
+            | Action::ReplaySlower
+            | Action::ReplayFaster
+            | Action::ReplayExit => {}
+        }
+    }
+
+    /// Replay-mode bindings. Returns `true` if the key was consumed.
+    fn on_replay_key(&mut self, e: &KeyboardEvent) -> bool {
+        let Some(replay) = self.replay.as_mut() else {
+            return false;
+        };
+        let Some(action) = keys::replay_action_for(e) else {
+            return false;
+        };
+        match action {
+            Action::ReplayBack => {
+                replay.step_back();
+            }
+            Action::ReplayForward => {
+                replay.step_forward();
+            }
+            Action::ReplayStart => {
+                replay.seek(0);
+            }
+            Action::ReplayEnd => {
+                replay.seek(replay.len());
+            }
+            Action::ReplayAutoplay => {
+                e.prevent_default();
+                replay.toggle_autoplay(now_ms());
+            }
+            Action::ReplaySlower => replay.slower(),
+            Action::ReplayFaster => replay.faster(),
+            Action::ReplayExit => {
+                self.replay = None;
+                self.analysis = None;
+                if self.is_ai_turn() {
+                    self.queue_ai_soon(AI_START_MS);
+                }
+            }
+            _ => return false,
+        }
+        self.reveal_replay_move();
+        self.dirty = true;
+        true
+    }
+
+    /// Keep the view centre over a bounded board. Every camera change marks the
+    /// frame dirty, so doing this once per rendered frame covers all of them.
+    fn clamp_camera(&mut self) {
+        if let Some(b) = self.game.rules.bounds {
+            self.cam_x = self.cam_x.clamp(b.min.x as f64, b.max.x as f64);
+            self.cam_y = self.cam_y.clamp(b.min.y as f64, b.max.y as f64);
+        }
+    }
+
+    /// The cells on screen, with one to spare at each edge, as
+    /// `(min_x, max_x, min_y, max_y)`; only those on the board if it is bounded.
+    fn visible_cells(&self) -> (i32, i32, i32, i32) {
+        let half_w = (self.view_w / 2.0) / self.cell_px;
+        let half_h = (self.view_h / 2.0) / self.cell_px;
+        let mut min_x = (self.cam_x - half_w - 1.0).floor() as i32;
+        let mut max_x = (self.cam_x + half_w + 1.0).ceil() as i32;
+        let mut min_y = (self.cam_y - half_h - 1.0).floor() as i32;
+        let mut max_y = (self.cam_y + half_h + 1.0).ceil() as i32;
+        if let Some(b) = self.game.rules.bounds {
+            min_x = min_x.max(b.min.x);
+            max_x = max_x.min(b.max.x);
+            min_y = min_y.max(b.min.y);
+            max_y = max_y.min(b.max.y);
+        }
+        (min_x, max_x, min_y, max_y)
+    }
+
+    /// Run `draw` with `self.ctx` pointed at a layer's context instead of the
+    /// board canvas's, so the drawing helpers serve every canvas.
+    fn draw_on(&mut self, ctx: CanvasRenderingContext2d, draw: impl FnOnce(&App)) {
+        let board = std::mem::replace(&mut self.ctx, ctx);
+        draw(self);
+        self.ctx = board;
+    }
+
+    /// Background, grid lines, the edge of a bounded board and star points.
+    fn draw_grid(&self) {
+        let (w, h) = (self.view_w, self.view_h);
+        let theme = self.theme;
+        if let Some(img) = skin::image(skin::Part::Board) {
+            self.draw_board_image(&img);
+        } else {
+            self.ctx.set_fill_style_str(theme.background);
+            self.ctx.fill_rect(0.0, 0.0, w, h);
+            if let Some(grain) = theme.board_grain {
+                self.draw_grain(grain);
+            }
+        }
+
+        self.ctx.set_stroke_style_str(theme.grid);
+        self.ctx.set_line_width(theme.grid_width);
+        let (min_x, max_x, min_y, max_y) = self.visible_cells();
+        // On a bounded board, lines stop at the edge.
+        let (mut x0, mut x1, mut y0, mut y1) = (0.0, w, 0.0, h);
+        if let Some(b) = self.game.rules.bounds {
+            (x0, y0) = self.cell_to_screen(b.min);
+            (x1, y1) = self.cell_to_screen(b.max);
+        }
+
+        let lod = self.cell_px < LOD_CELL_PX;
+        if lod {
+            self.ctx.save();
+            self.ctx
+                .set_global_alpha(0.3 + 0.7 * self.cell_px / LOD_CELL_PX);
+            self.ctx.begin_path();
+        }
+        for gx in min_x..=max_x {
+            let sx = (gx as f64 - self.cam_x) * self.cell_px + w / 2.0;
+            if !lod {
+                self.ctx.begin_path();
+            }
+            self.ctx.move_to(sx, y0.max(0.0));
+            self.ctx.line_to(sx, y1.min(h));
+            if !lod {
+                self.ctx.stroke();
+            }
+        }
+        for gy in min_y..=max_y {
+            let sy = (gy as f64 - self.cam_y) * self.cell_px + h / 2.0;
+            if !lod {
+                self.ctx.begin_path();
+            }
+            self.ctx.move_to(x0.max(0.0), sy);
+            self.ctx.line_to(x1.min(w), sy);
+            if !lod {
+                self.ctx.stroke();
+            }
+        }
+        if lod {
+            self.ctx.stroke();
+            self.ctx.restore();
+        }
+        if self.game.rules.bounds.is_some() {
+            self.ctx.set_stroke_style_str(theme.edge);
+            self.ctx.set_line_width(2.0 * theme.grid_width);
+            self.ctx.stroke_rect(x0, y0, x1 - x0, y1 - y0);
+        }
+        self.draw_star_points(min_x, max_x, min_y, max_y);
+    }
+
+    /// Bring the stone layer up to the position shown: whole if `restyled`
+    /// (the camera or look changed) or much has changed, else by repainting
+    /// just the cells whose stones differ from what it shows.
+    fn sync_stone_layer(&mut self, restyled: bool) {
+        if self.sync_gl_stones(restyled) {
+            return;
+        }
+        let shown = self.shown_game();
+        let position = (shown.key(), shown.moves.len());
+        let changed: Option<Vec<Pt>> = match &self.drawn_position {
+            _ if restyled => None,
+            Some((key, _)) if *key == position => return,
+            Some((_, drawn)) => {
+                let mut changed: Vec<Pt> = shown
+                    .cells
+                    .iter()
+                    .filter(|&(p, c)| drawn.get(p) != Some(c))
+                    .map(|(&p, _)| p)
+                    .collect();
+                changed.extend(drawn.keys().filter(|p| !shown.cells.contains_key(p)));
+                Some(changed).filter(|c| c.len() <= MAX_PATCHED_CELLS)
+            }
+            None => None,
+        };
+        let (look, view) = (self.look(), self.view());
+        match changed {
+            Some(cells) => {
+                let cells = cells.into_iter().map(|p| (p, shown.color_at(p).copied())).collect();
+                self.stone_layer.patch_stones(look, view, cells);
+            }
+            None => {
+                let stones = shown
+                    .cells
+                    .iter()
+                    .filter(|&(&p, _)| view.shows(p))
+                    .map(|(&p, &c)| (p, c))
+                    .collect();
+                self.stone_layer.redraw_stones(look, view, stones);
+            }
+        }
+        self.drawn_position = Some((position, shown.cells.clone()));
+    }
+
+    /// Hand the stones to the WebGL layer while the position shown has over
+    /// `WEBGL_STONES` of them, and back to the stone layer after. `true` while
+    /// WebGL draws them.
+    fn sync_gl_stones(&mut self, restyled: bool) -> bool {
+        let shown = self.replay.as_ref().map_or(&self.game, Replay::view);
+        let big = shown.cells.len() > WEBGL_STONES;
+        if big && self.gl_stones.is_none() {
+            let doc = window().and_then(|w| w.document());
+            let dpr = window().map_or(1.0, |w| w.device_pixel_ratio());
+            let made = doc.and_then(|d| GlStones::new(&d, &self.canvas));
+            self.gl_stones = Some(made.map(|mut gl| {
+                gl.resize(self.view_w, self.view_h, dpr);
+                gl
+            }));
+        }
+        let Some(Some(gl)) = self.gl_stones.as_mut() else {
+            return false;
+        };
+        if !big {
+            if gl.hide() {
+                // Redraw the stone layer whole, having cleared it below.
+                self.drawn_position = None;
+            }
+            return false;
+        }
+        let from_2d = self.drawn_position.is_some();
+        if gl.show((shown.key(), shown.moves.len()), &shown.cells) || restyled || from_2d {
+            gl.draw(
+                (self.cam_x, self.cam_y),
+                self.cell_px,
+                (self.view_w, self.view_h),
+                self.theme.stones,
+            );
+        }
+        if from_2d {
+            self.stone_layer.clear(self.view_w, self.view_h);
+            self.drawn_position = None;
+        }
+        true
+    }
+
+    fn render(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+        self.clamp_camera();
+        let w = self.view_w;
+        let h = self.view_h;
+
+        let theme = self.theme;
+
+        // The grid and the stones are on layers of their own, redrawn only
+        // when what they show has changed.
+        let cam = (self.cam_x, self.cam_y, self.cell_px, w, h);
+        let theme_id = theme as *const Theme as usize;
+        let grid_key = (cam, theme_id, self.game.rules.bounds, self.star_spacing);
+        if self.grid_layer.stale(grid_key) {
+            if let Some(ctx) = self.grid_layer.ctx().cloned() {
+                self.draw_on(ctx, App::draw_grid);
+            }
+        }
+        let restyled = self.stone_layer.stale((cam, theme_id, self.stone_marks));
+        self.sync_stone_layer(restyled);
+        self.ctx.clear_rect(0.0, 0.0, w, h);
+        let (min_x, max_x, min_y, max_y) = self.visible_cells();
+
+        // On empty cells only, so it can go over the stones' layer.
+        if self.show_heatmap {
+            self.draw_heatmap();
+        }
+        if self.show_frontier {
+            self.draw_frontier();
+        }
+
+        // captured stones fading out
+        let now = now_ms();
+        for f in &self.fades {
+            let t = ((now - f.start_ms) / FADE_MS).clamp(0.0, 1.0);
+            let (sx, sy) = self.cell_to_screen(f.p);
+            self.ctx.save();
+            self.ctx.set_global_alpha(1.0 - t);
+            let _ = self.ctx.translate(sx, sy);
+            let _ = self.ctx.scale(1.0 - 0.5 * t, 1.0 - 0.5 * t);
+            self.draw_stone(0.0, 0.0, f.color);
+            self.ctx.restore();
+        }
+
+        // ghost stone under the cursor when a click would place there
+        let loupe = self.drag.filter(|d| d.loupe).map(|d| d.last);
+        if let Some((lx, ly)) = loupe {
+            self.draw_ghost_stone(self.screen_to_cell(lx, ly));
+        } else if let Some((hx, hy)) = self.hover {
+            let p = self.screen_to_cell(hx, hy);
+            let panning = self.drag.is_some_and(|d| d.panning);
+            if !panning && !self.space_held {
+                self.draw_ghost_stone(p);
+                if self.show_heatmap {
+                    self.draw_cell_scores(p, hx, hy);
+                }
+            }
+        }
+        // two-tap placement: the picked cell, enlarged, and its confirm button
+        match self.selected.filter(|_| self.replay.is_none()) {
+            Some(p) => self.draw_selection(p),
+            None => self.btn_place = (0.0, 0.0, 0.0, 0.0),
+        }
+        // keyboard cursor: a square on its cell, over the same preview
+        if let Some(p) = self.cursor.filter(|_| self.replay.is_none()) {
+            self.draw_ghost_stone(p);
+            let (sx, sy) = self.cell_to_screen(p);
+            let half = self.cell_px * 0.45;
+            self.ctx.set_stroke_style_str("#38bdf8");
+            self.ctx.set_line_width((self.cell_px * 0.06).max(1.5));
+            self.ctx
+                .stroke_rect(sx - half, sy - half, 2.0 * half, 2.0 * half);
+        }
+
+        // hint: a pulsing ring on the suggested cell
+        if let Some(hint) = self.hint {
+            self.draw_pulse_ring(hint.p, now - hint.start_ms, "#facc15");
+        }
+        // blunder warning: a red ring where the held-back move would go
+        if let Some(c) = self.confirm {
+            self.draw_pulse_ring(c.p, now - c.start_ms, "#ef4444");
+        }
+
+        if let Some(pv) = self.pv.as_ref().filter(|_| self.replay.is_none()) {
+            self.draw_pv(pv, now);
+        }
+        if let Some((_, lines)) = &self.top_lines {
+            self.draw_rank_badges(lines);
+        }
+        if let Some(p) = self.game.last_move.filter(|_| self.forced_win.is_some()) {
+            self.draw_forcing_outline(p);
+        }
+
+        // last-move marker: a dark ring inset on the most recent stone
+        // (move numbers, when shown, highlight the last number instead)
+        if self.show_move_numbers {
+            self.draw_move_numbers();
+        } else if let Some(p) = self.shown_game().last_move {
+            let (sx, sy) = self.cell_to_screen(p);
+            self.ctx.begin_path();
+            let _ = self
+                .ctx
+                .arc(sx, sy, self.cell_px * 0.18, 0.0, std::f64::consts::TAU);
+            self.ctx.set_stroke_style_str(theme.background);
+            self.ctx.set_line_width((self.cell_px * 0.06).max(1.5));
+            self.ctx.stroke();
+        }
+
+        // winning line, pulsing while the live game's win is celebrated
+        if let Some(line) = self.shown_game().win_line {
+            let pulse = match (&self.celebration, &self.replay) {
+                (Some(c), None) => c.pulse(now_ms()),
+                _ => 0.0,
+            };
+            self.draw_win_line(line, pulse);
+        }
+
+        if self.show_coords {
+            self.draw_axis_labels(min_x, max_x, min_y, max_y);
+        }
+        if let Some((_, eval)) = self.eval_bar.filter(|_| self.show_eval_bar) {
+            self.draw_eval_bar(eval);
+        }
+
+        // HUD: controller pills, the game's progress under them, and the AI's
+        // search while it thinks.
+        self.draw_controller_pills();
+        self.draw_progress_strip();
+        if let Some(th) = &self.thinking {
+            self.draw_thinking(th);
+        }
+        if self.presenting {
+            self.toolbar.clear();
+            self.minimap = None;
+            if self.replay.is_none() {
+                self.draw_last_move_banner();
+            }
+        } else {
+            self.draw_toolbar();
+            self.draw_minimap();
+        }
+        let mut panel_y = 12.0;
+        if let Some(t) = &self.tournament {
+            panel_y = self.draw_panel(&t.table(), panel_y) + 8.0;
+        }
+        if let Some((_, lines)) = &self.top_lines {
+            panel_y = self.draw_panel(&self.top_lines_table(lines), panel_y) + 8.0;
+        }
+        if self.show_diagnostics {
+            panel_y = self.draw_panel(&self.diagnostics(), panel_y) + 8.0;
+        }
+        if self.replay.is_some() {
+            self.draw_move_list(panel_y);
+        } else {
+            self.move_list_rect = (0.0, 0.0, 0.0, 0.0);
+            self.move_list_rows.clear();
+            self.move_list_cursor = None;
+        }
+
+        if let Some(rec) = &self.recording {
+            let (shown, total) = rec.progress();
+            self.draw_status_line(&tf(Msg::Recording, &[&shown, &total]), theme.muted_text);
+        } else if self.confirm.is_some() {
+            let opp = self.player_name(self.game.player.other());
+            self.draw_status_line(&tf(Msg::LeavesWin, &[&opp]), "#fca5a5");
+        } else if let Some(why) =
+            self.why.as_ref().filter(|_| self.show_why && self.replay.is_none())
+        {
+            self.draw_status_line(&tf(Msg::AiSays, &[why]), theme.muted_text);
+        }
+        if let Some(replay) = &self.replay {
+            self.draw_replay_bar(replay);
+            if let Some(analysis) = &self.analysis {
+                self.graph_rect = self.draw_eval_graph(analysis, replay.cursor());
+            }
+        }
+
+        // Winner overlay (centered): confetti, then the message fading in
+        // and rising into place, on the game's summary card unless in a
+        // tournament.
+        self.card_buttons.clear();
+        if let Some(winner) = self.game.winner.filter(|_| self.replay.is_none()) {
+            let now = now_ms();
+            let fade = self.celebration.as_ref().map_or(1.0, |c| c.message_alpha(now));
+            if let Some(c) = &self.celebration {
+                c.draw(&self.ctx, now, w, h);
+            }
+            let reason = match self.game.win_reason {
+                Some(WinReason::Time) => Msg::GameWonOnTime,
+                Some(WinReason::Captures) => Msg::GameWonByCaptures,
+                _ => Msg::GameWon,
+            };
+            let msg = tf(reason, &[&self.player_name(winner)]);
+            let (msg, sub) = match &self.tournament {
+                Some(tour) => (
+                    tf(Msg::WinsAs, &[&tour.name(winner), &i18n::color(winner)]),
+                    t(Msg::NextGame),
+                ),
+                None => (msg, t(Msg::PlayAgain)),
+            };
+
+            if self.summary.is_some() {
+                self.draw_summary_card(&msg, &sub, fade);
+            } else {
+                let w2 = w / 2.0;
+                let h2 = h / 2.0 + 12.0 * (1.0 - fade).powi(2);
+                self.ctx.save();
+                self.ctx.set_global_alpha(fade);
+
+                self.ctx.set_font(&theme.font(36.0, true));
+                let msg_w = self
+                    .ctx
+                    .measure_text(&msg)
+                    .ok()
+                    .map(|m| m.width())
+                    .unwrap_or(0.0);
+
+                self.ctx.set_font(&theme.font(16.0, false));
+                let sub_w = self
+                    .ctx
+                    .measure_text(&sub)
+                    .ok()
+                    .map(|m| m.width())
+                    .unwrap_or(0.0);
+
+                let s = theme.text_scale();
+                let pad = 24.0;
+                let box_w = msg_w.max(sub_w) + pad * 2.0;
+                let box_h = (36.0 + 8.0 + 16.0) * s + pad * 2.0;
+
+                self.ctx.set_fill_style_str(theme.panel);
+                self.ctx
+                    .fill_rect(w2 - box_w / 2.0, h2 - box_h / 2.0, box_w, box_h);
+
+                self.ctx.set_text_align("center");
+                self.ctx.set_text_baseline("middle");
+
+                self.ctx.set_fill_style_str(theme.text);
+                self.ctx.set_font(&theme.font(36.0, true));
+                let _ = self.ctx.fill_text(&msg, w2, h2 - 10.0 * s);
+
+                self.ctx.set_fill_style_str(theme.muted_text);
+                self.ctx.set_font(&theme.font(16.0, false));
+                let _ = self.ctx.fill_text(&sub, w2, h2 + 24.0 * s);
+
+                self.ctx.restore();
+            }
+        }
+
+        // Build timestamp HUD
+        if !self.presenting {
+            let ts = env!("BUILD_TS_UNIX");
+            self.ctx.set_text_align("left");
+            self.ctx.set_text_baseline("alphabetic");
+            self.ctx.set_font(&theme.font(14.0, false));
+            let _ = self.ctx.fill_text(ts, 12.0, h - 22.0);
+        }
+
+        if self.show_settings {
+            self.draw_settings();
+        }
+        if self.saves.is_some() {
+            self.draw_saves();
+        }
+        if self.history.is_some() {
+            self.draw_history();
+        }
+        if self.library.is_some() {
+            self.draw_library();
+        }
+        if self.show_help {
+            self.draw_help();
+        }
+        if self.share.is_some() {
+            self.draw_share();
+        }
+        // Over the panels, so a setting's "Saved" shows while the panel is open.
+        let bottom = self.minimap.map_or(h - 12.0, |m| m.rect.1 - 8.0);
+        self.toasts.draw(&self.ctx, theme, now_ms(), w - 12.0, bottom);
+
+        // Last, so it magnifies the finished frame.
+        if let Some(at) = loupe {
+            self.draw_loupe(at);
+        }
+
+        let centre = self
+            .cursor
+            .unwrap_or_else(|| Pt::new(self.cam_x.round() as i32, self.cam_y.round() as i32));
+        let playable = self.replay.is_none()
+            && self.tournament.is_none()
+            && self.game.winner.is_none()
+            && self.is_human(self.game.player);
+        let shown = self.replay.as_ref().map_or(&self.game, Replay::view);
+        self.a11y.sync(shown, centre, playable);
+    }
+
+    /// The frame around `(fx, fy)`, magnified in a circle above the finger
+    /// (below it near the top edge), with a crosshair on the cell under it.
+    fn draw_loupe(&self, (fx, fy): (f64, f64)) {
+        let r = LOUPE_RADIUS_PX;
+        let lift = LOUPE_LIFT_PX;
+        let cy = if fy - lift - r >= 0.0 { fy - lift } else { fy + lift };
+        let cx = fx.clamp(r, (self.view_w - r).max(r));
+        // The source rectangle is in backing-store pixels.
+        let dpr = self.canvas.width() as f64 / self.view_w.max(1.0);
+        let src = r / LOUPE_ZOOM;
+        self.ctx.save();
+        self.ctx.begin_path();
+        let _ = self.ctx.arc(cx, cy, r, 0.0, std::f64::consts::TAU);
+        self.ctx.clip();
+        self.ctx.set_fill_style_str(self.theme.background);
+        self.ctx.fill_rect(cx - r, cy - r, 2.0 * r, 2.0 * r);
+        // Every layer, bottom up; the board canvas holds what is drawn so far.
+        let gl = match &self.gl_stones {
+            Some(Some(gl)) if !gl.canvas.hidden() => Some(&gl.canvas),
+            _ => None,
+        };
+        let layers = [&self.grid_layer.canvas, &self.stone_layer.canvas];
+        for canvas in layers.into_iter().chain(gl).chain([&self.canvas]) {
+            let _ = self
+                .ctx
+                .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    canvas,
+                    (fx - src) * dpr,
+                    (fy - src) * dpr,
+                    2.0 * src * dpr,
+                    2.0 * src * dpr,
+                    cx - r,
+                    cy - r,
+                    2.0 * r,
+                    2.0 * r,
+                );
+        }
+        // The crosshair marks the finger's point; the square, its cell.
+        let (px, py) = self.cell_to_screen(self.screen_to_cell(fx, fy));
+        let (ox, oy) = (cx + (px - fx) * LOUPE_ZOOM, cy + (py - fy) * LOUPE_ZOOM);
+        let half = self.cell_px * LOUPE_ZOOM / 2.0;
+        self.ctx.set_stroke_style_str("#38bdf8");
+        self.ctx.set_line_width(1.5);
+        self.ctx.stroke_rect(ox - half, oy - half, 2.0 * half, 2.0 * half);
+        self.ctx.begin_path();
+        self.ctx.move_to(cx - r, cy);
+        self.ctx.line_to(cx + r, cy);
+        self.ctx.move_to(cx, cy - r);
+        self.ctx.line_to(cx, cy + r);
+        self.ctx.set_stroke_style_str("rgba(248,250,252,0.6)");
+        self.ctx.set_line_width(1.0);
+        self.ctx.stroke();
+        self.ctx.restore();
+        self.ctx.begin_path();
+        let _ = self.ctx.arc(cx, cy, r, 0.0, std::f64::consts::TAU);
+        self.ctx.set_stroke_style_str("#94a3b8");
+        self.ctx.set_line_width(2.0);
+        self.ctx.stroke();
+    }
+
+    /// Grid coordinates along the top (x) and left (y) edges, thinned out as the zoom shrinks.
+    fn draw_axis_labels(&self, min_x: i32, max_x: i32, min_y: i32, max_y: i32) {
+        let min_gap_px = 28.0;
+        let step = [1, 2, 5, 10, 20, 50, 100]
+            .into_iter()
+            .find(|&s| s as f64 * self.cell_px >= min_gap_px)
+            .unwrap_or(100);
+
+        self.ctx.set_fill_style_str(self.theme.labels);
+        self.ctx.set_font(&self.theme.font(10.0, false));
+
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("top");
+        for gx in (min_x..=max_x).filter(|g| g.rem_euclid(step) == 0) {
+            let (sx, _) = self.cell_to_screen(Pt::new(gx, 0));
+            let _ = self.ctx.fill_text(&gx.to_string(), sx, 4.0);
+        }
+
+        // Keep clear of the controller pills, the strip under them, the toolbar,
+        // the build stamp and the eval bar.
+        let x = if self.show_eval_bar { EVAL_BAR_PX + 4.0 } else { 4.0 };
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("middle");
+        for gy in (min_y..=max_y).filter(|g| g.rem_euclid(step) == 0) {
+            let (_, sy) = self.cell_to_screen(Pt::new(0, gy));
+            if sy < 88.0 || sy > self.view_h - 80.0 {
+                continue;
+            }
+            let _ = self.ctx.fill_text(&gy.to_string(), x, sy);
+        }
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    fn draw_move_numbers(&self) {
+        let font_px = (self.cell_px * 0.38).floor();
+        if font_px < 7.0 {
+            return;
+        }
+        self.ctx
+            .set_font(&format!("bold {font_px}px ui-sans-serif, system-ui, -apple-system"));
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("middle");
+        let game = self.shown_game();
+        let last = game.moves.len();
+        // With captures a point can be played more than once; only its latest stone is labelled.
+        let mut latest: HashMap<Pt, usize> = HashMap::new();
+        for (i, &p) in game.moves.iter().enumerate() {
+            latest.insert(p, i);
+        }
+        for (p, i) in latest {
+            if game.is_empty(p) {
+                continue;
+            }
+            let (sx, sy) = self.cell_to_screen(p);
+            if sx < -self.cell_px
+                || sx > self.view_w + self.cell_px
+                || sy < -self.cell_px
+                || sy > self.view_h + self.cell_px
+            {
+                continue;
+            }
+            self.ctx
+                .set_fill_style_str(if i + 1 == last { "#dc2626" } else { "#0b0d11" });
+            let _ = self.ctx.fill_text(&(i + 1).to_string(), sx, sy);
+        }
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// The AI's expected continuation as numbered ghost stones joined by arrows
+    /// from the move it played, fading out over the last second.
+    fn draw_pv(&self, pv: &Pv, now: f64) {
+        let fade = ((PV_MS - (now - pv.start_ms)) / 1000.0).clamp(0.0, 1.0);
+        let r = self.cell_px * 0.3;
+        self.ctx.save();
+        self.ctx.set_global_alpha(0.6 * fade);
+        self.ctx.set_stroke_style_str("#facc15");
+        self.ctx.set_line_width((self.cell_px * 0.05).max(1.5));
+        for pair in pv.moves.windows(2) {
+            let (ax, ay) = self.cell_to_screen(pair[0].0);
+            let (bx, by) = self.cell_to_screen(pair[1].0);
+            let len = (bx - ax).hypot(by - ay);
+            if len <= 2.0 * r {
+                continue;
+            }
+            let (ux, uy) = ((bx - ax) / len, (by - ay) / len);
+            let (tx, ty) = (bx - ux * r, by - uy * r);
+            self.ctx.begin_path();
+            self.ctx.move_to(ax + ux * r, ay + uy * r);
+            self.ctx.line_to(tx, ty);
+            // Arrowhead: two short strokes back from the tip.
+            let head = r * 0.6;
+            for side in [-1.0, 1.0] {
+                let (c, s) = (0.866, 0.5 * side);
+                self.ctx.move_to(tx, ty);
+                self.ctx.line_to(
+                    tx - head * (ux * c - uy * s),
+                    ty - head * (uy * c + ux * s),
+                );
+            }
+            self.ctx.stroke();
+        }
+
+        let font_px = (self.cell_px * 0.38).floor();
+        self.ctx
+            .set_font(&format!("bold {font_px}px ui-sans-serif, system-ui, -apple-system"));
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("middle");
+        for (i, &(p, c)) in pv.moves.iter().enumerate().skip(1) {
+            let (sx, sy) = self.cell_to_screen(p);
+            self.ctx.set_global_alpha(0.4 * fade);
+            self.draw_stone(sx, sy, c);
+            if font_px >= 7.0 {
+                self.ctx.set_global_alpha(0.9 * fade);
+                self.ctx.set_fill_style_str("#0b0d11");
+                let _ = self.ctx.fill_text(&i.to_string(), sx, sy);
+            }
+        }
+        self.ctx.restore();
+    }
+
+    /// Translucent tint on every frontier cell, cold to hot by how much the
+    /// heuristic likes it for the side to move. Scores span several orders of
+    /// magnitude, so the gradient is logarithmic and relative to the best cell.
+    fn draw_heatmap(&self) {
+        let game = self.shown_game();
+        if game.winner.is_some() {
+            return;
+        }
+        let scores: Vec<(Pt, i32)> = game
+            .frontier
+            .iter()
+            .map(|&p| (p, game.score_point(p, game.player)))
+            .collect();
+        let Some(max) = scores.iter().map(|&(_, s)| s).max() else {
+            return;
+        };
+        let log_max = (max.max(2) as f64).ln();
+        let half = self.cell_px / 2.0;
+        for (p, score) in scores {
+            let (sx, sy) = self.cell_to_screen(p);
+            if sx < -self.cell_px
+                || sx > self.view_w + self.cell_px
+                || sy < -self.cell_px
+                || sy > self.view_h + self.cell_px
+            {
+                continue;
+            }
+            let t = ((score.max(1) as f64).ln() / log_max).clamp(0.0, 1.0);
+            // Blue (240°) for the least promising cells through to red (0°) for the best.
+            let hue = 240.0 * (1.0 - t);
+            self.ctx
+                .set_fill_style_str(&format!("hsla({hue:.0}, 85%, 55%, {:.2})", 0.15 + 0.4 * t));
+            self.ctx
+                .fill_rect(sx - half, sy - half, self.cell_px, self.cell_px);
+        }
+    }
+
+    /// `score_point` for both sides on the empty cell `p`, in a box beside the
+    /// mouse at (`mx`, `my`).
+    fn draw_cell_scores(&self, p: Pt, mx: f64, my: f64) {
+        let game = self.shown_game();
+        if game.cells.contains_key(&p) || !game.in_bounds(p) {
+            return;
+        }
+        let text = [Color::Black, Color::White]
+            .iter()
+            .map(|&c| format!("{} {}", i18n::color(c), game.score_point(p, c)))
+            .collect::<Vec<_>>()
+            .join(" · ");
+        let theme = self.theme;
+        self.ctx.set_font(&theme.font(11.0, false));
+        let tw = self
+            .ctx
+            .measure_text(&text)
+            .ok()
+            .map(|m| m.width())
+            .unwrap_or(0.0);
+        let (bw, bh) = (tw + 12.0, 18.0 * theme.text_scale());
+        // Below right of the pointer, or flipped to stay on screen.
+        let x = if mx + 16.0 + bw > self.view_w { mx - 16.0 - bw } else { mx + 16.0 };
+        let y = if my + 16.0 + bh > self.view_h { my - 16.0 - bh } else { my + 16.0 };
+        self.ctx.set_fill_style_str(theme.panel);
+        self.ctx.fill_rect(x, y, bw, bh);
+        self.ctx.set_fill_style_str(theme.text);
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("middle");
+        let _ = self.ctx.fill_text(&text, x + 6.0, y + bh / 2.0);
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// Presentation mode's bottom-centre banner naming the last move and who
+    /// played it.
+    fn draw_last_move_banner(&self) {
+        let Some(p) = self.game.last_move else {
+            return;
+        };
+        let Some(&mover) = self.game.cells.get(&p) else {
+            return;
+        };
+        let text = tf(Msg::Plays, &[&self.player_name(mover), &a11y::coords(p)]);
+        let theme = self.theme;
+        self.ctx.set_font(&theme.font(24.0, true));
+        let tw = self
+            .ctx
+            .measure_text(&text)
+            .ok()
+            .map(|m| m.width())
+            .unwrap_or(0.0);
+        let bh = 44.0 * theme.text_scale();
+        let dot = bh * 0.3;
+        let bw = tw + 2.0 * dot + 48.0;
+        let x = (self.view_w - bw) / 2.0;
+        let y = self.view_h - bh - 24.0;
+        self.ctx.set_fill_style_str(theme.panel);
+        self.ctx.fill_rect(x, y, bw, bh);
+        self.ctx.begin_path();
+        let _ = self.ctx.arc(x + 16.0 + dot, y + bh / 2.0, dot, 0.0, std::f64::consts::TAU);
+        self.ctx.set_fill_style_str(theme.stones[mover as usize]);
+        self.ctx.fill();
+        self.ctx.set_stroke_style_str(theme.pill_border);
+        self.ctx.set_line_width(1.0);
+        self.ctx.stroke();
+        self.ctx.set_fill_style_str(theme.text);
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("middle");
+        let _ = self.ctx.fill_text(&text, x + 2.0 * dot + 32.0, y + bh / 2.0);
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// The winner's `msg` over the summary's rows, buttons to replay the game,
+    /// copy its link or start another, and `sub` beneath, faded in by `fade`.
+    fn draw_summary_card(&mut self, msg: &str, sub: &str, fade: f64) {
+        let Some(summary) = &self.summary else {
+            return;
+        };
+        let names = [self.player_name(Color::Black), self.player_name(Color::White)];
+        let mut rows = vec![tf(
+            Msg::SummaryMoves,
+            &[&summary.moves, &format_elapsed(summary.duration_ms)],
+        )];
+        let averages: Vec<String> = (0..2)
+            .filter_map(|i| Some(format!("{} {:.1} s", names[i], summary.average_ms[i]? / 1000.0)))
+            .collect();
+        if !averages.is_empty() {
+            rows.push(tf(Msg::SummaryAverage, &[&averages.join(" · ")]));
+        }
+        let threats = (0..2)
+            .map(|i| {
+                let (made, stopped) = (summary.threats_made[i], summary.threats_blocked[i]);
+                format!("{} {made} / {stopped}", names[i])
+            })
+            .collect::<Vec<_>>()
+            .join(" · ");
+        rows.push(tf(Msg::SummaryThreats, &[&threats]));
+
+        let theme = self.theme;
+        let s = theme.text_scale();
+        let (pad, gap, row_h, pill_h) = (24.0, 12.0, 22.0 * s, 26.0 * s);
+        let width = |ctx: &CanvasRenderingContext2d, text: &str| {
+            ctx.measure_text(text).ok().map(|m| m.width()).unwrap_or(0.0)
+        };
+        self.ctx.set_font(&theme.font(13.0, false));
+        let buttons: Vec<(Action, String, f64)> = [
+            (Action::Replay, Msg::ReplayButton),
+            (Action::CopyLink, Msg::CopyLinkButton),
+            (Action::SaveImage, Msg::SaveImageButton),
+            (Action::Restart, Msg::RestartButton),
+        ]
+        .into_iter()
+        .map(|(action, label)| {
+            let label = t(label);
+            let w = width(&self.ctx, &label) + 20.0;
+            (action, label, w)
+        })
+        .collect();
+        let buttons_w =
+            buttons.iter().map(|b| b.2).sum::<f64>() + 10.0 * (buttons.len() - 1) as f64;
+        self.ctx.set_font(&theme.font(36.0, true));
+        let mut text_w = width(&self.ctx, msg);
+        self.ctx.set_font(&theme.font(16.0, false));
+        for text in rows.iter().map(String::as_str).chain([sub]) {
+            text_w = text_w.max(width(&self.ctx, text));
+        }
+
+        let (w, h) = (self.view_w, self.view_h);
+        let box_w = text_w.max(buttons_w) + pad * 2.0;
+        let rows_h = rows.len() as f64 * row_h;
+        let box_h = 36.0 * s + rows_h + pill_h + 16.0 * s + 3.0 * gap + pad * 2.0;
+        let x0 = w / 2.0 - box_w / 2.0;
+        let mut y = h / 2.0 + 12.0 * (1.0 - fade).powi(2) - box_h / 2.0;
+        self.ctx.save();
+        self.ctx.set_global_alpha(fade);
+        self.ctx.set_fill_style_str(theme.panel);
+        self.ctx.fill_rect(x0, y, box_w, box_h);
+        y += pad;
+
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("middle");
+        self.ctx.set_fill_style_str(theme.text);
+        self.ctx.set_font(&theme.font(36.0, true));
+        let _ = self.ctx.fill_text(msg, w / 2.0, y + 18.0 * s);
+        y += 36.0 * s + gap;
+        self.ctx.set_font(&theme.font(16.0, false));
+        for row in &rows {
+            let _ = self.ctx.fill_text(row, w / 2.0, y + row_h / 2.0);
+            y += row_h;
+        }
+        y += gap;
+
+        self.ctx.set_font(&theme.font(13.0, false));
+        let mut x = w / 2.0 - buttons_w / 2.0;
+        for (action, label, bw) in buttons {
+            let rect = (x, y, bw, pill_h);
+            draw_pill(&self.ctx, theme, rect, &label, false, true);
+            self.card_buttons.push((action, rect));
+            x += bw + 10.0;
+        }
+        y += pill_h + gap;
+
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("middle");
+        self.ctx.set_fill_style_str(theme.muted_text);
+        self.ctx.set_font(&theme.font(16.0, false));
+        let _ = self.ctx.fill_text(sub, w / 2.0, y + 8.0 * s);
+        self.ctx.restore();
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// The eval bar down the left edge: Black's share filling from the bottom,
+    /// White's from the top, with a mark at even.
+    fn draw_eval_bar(&self, eval: f64) {
+        let theme = self.theme;
+        let h = self.view_h;
+        let black_h = h * (1.0 + eval) / 2.0;
+        self.ctx.set_fill_style_str(theme.stones[Color::White as usize]);
+        self.ctx.fill_rect(0.0, 0.0, EVAL_BAR_PX, h - black_h);
+        self.ctx.set_fill_style_str(theme.stones[Color::Black as usize]);
+        self.ctx.fill_rect(0.0, h - black_h, EVAL_BAR_PX, black_h);
+        self.ctx.set_fill_style_str(theme.pill_border);
+        self.ctx.fill_rect(0.0, h / 2.0 - 1.0, EVAL_BAR_PX, 2.0);
+        self.ctx.fill_rect(EVAL_BAR_PX, 0.0, 1.0, h);
+    }
+
+    /// A faint dot on every cell of the frontier of the game on screen.
+    fn draw_frontier(&self) {
+        let game = self.shown_game();
+        let r = (self.cell_px * 0.08).max(1.5);
+        self.ctx.set_fill_style_str(self.theme.muted_text);
+        self.ctx.set_global_alpha(0.6);
+        self.ctx.begin_path();
+        for &p in &game.frontier {
+            let (sx, sy) = self.cell_to_screen(p);
+            if sx < -r || sx > self.view_w + r || sy < -r || sy > self.view_h + r {
+                continue;
+            }
+            self.ctx.move_to(sx + r, sy);
+            let _ = self.ctx.arc(sx, sy, r, 0.0, std::f64::consts::TAU);
+        }
+        self.ctx.fill();
+        self.ctx.set_global_alpha(1.0);
+    }
+
+    /// Rows of text in a box at the top right, from `y` down; returns its bottom.
+    fn draw_panel(&self, rows: &[String], y: f64) -> f64 {
+        self.ctx.set_font(&self.theme.font(13.0, false));
+        let tw = rows
+            .iter()
+            .filter_map(|r| self.ctx.measure_text(r).ok())
+            .map(|m| m.width())
+            .fold(0.0, f64::max);
+        let line_h = 18.0 * self.theme.text_scale();
+        let (bw, bh) = (tw + 24.0, rows.len() as f64 * line_h + 12.0);
+        let x = self.view_w - bw - 12.0;
+        self.ctx.set_fill_style_str(self.theme.panel);
+        self.ctx.fill_rect(x, y, bw, bh);
+        self.ctx.set_fill_style_str(self.theme.text);
+        self.ctx.set_text_baseline("middle");
+        for (i, row) in rows.iter().enumerate() {
+            let _ = self
+                .ctx
+                .fill_text(row, x + 12.0, y + 6.0 + line_h * (i as f64 + 0.5));
+        }
+        self.ctx.set_text_baseline("alphabetic");
+        y + bh
+    }
+
+    /// The replay's moves in a scrolling list at the right, from `top` down to
+    /// the minimap, the current one highlighted. A row's hitbox seeks to it.
+    fn draw_move_list(&mut self, top: f64) {
+        self.move_list_rect = (0.0, 0.0, 0.0, 0.0);
+        self.move_list_rows.clear();
+        let Some(replay) = &self.replay else {
+            return;
+        };
+        let theme = self.theme;
+        let row_h = MOVE_LIST_ROW_PX * theme.text_scale();
+        let bottom = self.minimap.map_or(self.view_h - 12.0, |m| m.rect.1 - 8.0);
+        let shown = ((bottom - top - 12.0) / row_h).floor().min(replay.len() as f64);
+        if shown < 3.0 {
+            return;
+        }
+        let shown = shown as usize;
+        // Follow the position when it moves, otherwise keep the reader's scroll.
+        let cursor = replay.cursor();
+        if self.move_list_cursor != Some(cursor) {
+            self.move_list_cursor = Some(cursor);
+            let row = cursor.saturating_sub(1);
+            if row < self.move_list_scroll {
+                self.move_list_scroll = row;
+            } else if row >= self.move_list_scroll + shown {
+                self.move_list_scroll = row + 1 - shown;
+            }
+        }
+        self.move_list_scroll = self.move_list_scroll.min(replay.len() - shown);
+
+        let rows: Vec<(usize, Color, String)> = replay
+            .moves()
+            .enumerate()
+            .skip(self.move_list_scroll)
+            .take(shown)
+            .map(|(i, (p, c))| (i + 1, c, format!("{}. {}", i + 1, a11y::coords(p))))
+            .collect();
+        self.ctx.set_font(&theme.font(12.0, false));
+        let tw = rows
+            .iter()
+            .filter_map(|(_, _, r)| self.ctx.measure_text(r).ok())
+            .map(|m| m.width())
+            .fold(0.0, f64::max);
+        let dot = row_h * 0.28;
+        let bw = tw + 4.0 * dot + 24.0;
+        let bh = shown as f64 * row_h + 12.0;
+        let x = self.view_w - bw - 12.0;
+        self.move_list_rect = (x, top, bw, bh);
+        self.ctx.set_fill_style_str(theme.panel);
+        self.ctx.fill_rect(x, top, bw, bh);
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("middle");
+        for (i, (n, c, text)) in rows.into_iter().enumerate() {
+            let y = top + 6.0 + i as f64 * row_h;
+            if n == cursor {
+                self.ctx.set_fill_style_str(theme.pill_ai);
+                self.ctx.fill_rect(x + 4.0, y, bw - 8.0, row_h);
+            }
+            let cy = y + row_h / 2.0;
+            self.ctx.begin_path();
+            let _ = self.ctx.arc(x + 12.0 + dot, cy, dot, 0.0, std::f64::consts::TAU);
+            self.ctx.set_fill_style_str(theme.stones[c as usize]);
+            self.ctx.fill();
+            self.ctx.set_stroke_style_str(theme.pill_border);
+            self.ctx.set_line_width(1.0);
+            self.ctx.stroke();
+            self.ctx.set_fill_style_str(if n <= cursor { theme.text } else { theme.muted_text });
+            let _ = self.ctx.fill_text(&text, x + 12.0 + 3.0 * dot, cy);
+            self.move_list_rows.push((n, (x, y, bw, row_h)));
+        }
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// Record a frame drawn from `start_ms` until now, for the diagnostics.
+    fn frame_drawn(&mut self, start_ms: f64) {
+        let now = now_ms();
+        self.render_ms = now - start_ms;
+        if self.show_diagnostics {
+            self.frame_times.push_back(now);
+            while self.frame_times.front().is_some_and(|&at| now - at > 1000.0) {
+                self.frame_times.pop_front();
+            }
+        }
+    }
+
+    /// The diagnostics panel's rows: frame rate and render time, the game's
+    /// size, and the wasm heap.
+    fn diagnostics(&self) -> Vec<String> {
+        let memory = wasm_bindgen::memory()
+            .dyn_into::<js_sys::WebAssembly::Memory>()
+            .map(|m| m.buffer().unchecked_into::<js_sys::ArrayBuffer>().byte_length())
+            .unwrap_or(0);
+        let fps = self.frame_times.len();
+        let render = format!("{:.1}", self.render_ms);
+        let mib = format!("{:.1}", memory as f64 / (1024.0 * 1024.0));
+        vec![
+            tf(Msg::DiagFrames, &[&fps, &render]),
+            tf(Msg::DiagGame, &[&self.game.cells.len(), &self.game.frontier.len()]),
+            tf(Msg::DiagMemory, &[&mib]),
+        ]
+    }
+
+    /// The settings panel over a dimmed board: a button per setting showing its
+    /// value, which a tap steps on. The rows' hitboxes go in `settings_rows`.
+    fn draw_settings(&mut self) {
+        let theme = self.theme;
+        let (w, h) = (self.view_w, self.view_h);
+        self.ctx.set_fill_style_str("rgba(0,0,0,0.6)");
+        self.ctx.fill_rect(0.0, 0.0, w, h);
+
+        let rows: Vec<(Setting, String)> = Setting::ALL
+            .iter()
+            .map(|&s| (s, tf(Msg::Pill, &[&t(s.label()), &t(self.setting_value(s))])))
+            .collect();
+        let (title, hint) = (t(Msg::SettingsTitle), t(Msg::SettingsHint));
+        self.ctx.set_font(&theme.font(13.0, false));
+        let text_w = rows
+            .iter()
+            .map(|(_, r)| r.as_str())
+            .chain([hint.as_str()])
+            .filter_map(|r| self.ctx.measure_text(r).ok())
+            .map(|m| m.width())
+            .fold(0.0, f64::max);
+        let pad = 20.0;
+        let row_h = 30.0 * theme.text_scale();
+        let gap = 8.0;
+        let bw = (text_w + 20.0 + 2.0 * pad).min(w - 16.0);
+        let bh = 2.0 * pad + 48.0 * theme.text_scale() + rows.len() as f64 * (row_h + gap);
+        let (x, y) = ((w - bw) / 2.0, ((h - bh) / 2.0).max(0.0));
+        self.ctx.set_fill_style_str(theme.pill_ai);
+        self.ctx.fill_rect(x, y, bw, bh);
+        self.ctx.set_stroke_style_str(theme.pill_border);
+        self.ctx.set_line_width(1.0);
+        self.ctx.stroke_rect(x, y, bw, bh);
+
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("middle");
+        self.ctx.set_fill_style_str(theme.text);
+        self.ctx.set_font(&theme.font(16.0, true));
+        let _ = self.ctx.fill_text(&title, x + pad, y + pad + 8.0 * theme.text_scale());
+        self.ctx.set_fill_style_str(theme.muted_text);
+        self.ctx.set_font(&theme.font(12.0, false));
+        let _ = self.ctx.fill_text(&hint, x + pad, y + pad + 30.0 * theme.text_scale());
+
+        self.ctx.set_font(&theme.font(13.0, false));
+        self.settings_rows.clear();
+        let mut ry = y + pad + 48.0 * theme.text_scale();
+        for (setting, text) in &rows {
+            let rect = (x + pad, ry, bw - 2.0 * pad, row_h);
+            draw_pill(&self.ctx, theme, rect, text, false, false);
+            self.settings_rows.push((*setting, rect));
+            ry += row_h + gap;
+        }
+    }
+
+    /// The saves menu over a dimmed board: a row a slot, with a thumbnail of
+    /// its position, its name and when it was saved, and its buttons.
+    fn draw_saves(&mut self) {
+        let Some(slots) = &self.saves else {
+            return;
+        };
+        let theme = self.theme;
+        let s = theme.text_scale();
+        let (w, h) = (self.view_w, self.view_h);
+        self.ctx.set_fill_style_str("rgba(0,0,0,0.6)");
+        self.ctx.fill_rect(0.0, 0.0, w, h);
+
+        let (pad, gap, row_h, pill_h) = (20.0, 8.0, 56.0 * s, 26.0 * s);
+        let bw = (480.0 * s).min(w - 16.0);
+        let bh = 2.0 * pad + 32.0 * s + saves::SLOTS as f64 * (row_h + gap);
+        let (x, y) = ((w - bw) / 2.0, ((h - bh) / 2.0).max(0.0));
+        self.ctx.set_fill_style_str(theme.pill_ai);
+        self.ctx.fill_rect(x, y, bw, bh);
+        self.ctx.set_stroke_style_str(theme.pill_border);
+        self.ctx.set_line_width(1.0);
+        self.ctx.stroke_rect(x, y, bw, bh);
+
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("middle");
+        self.ctx.set_fill_style_str(theme.text);
+        self.ctx.set_font(&theme.font(16.0, true));
+        let _ = self.ctx.fill_text(&t(Msg::SavesTitle), x + pad, y + pad + 8.0 * s);
+        self.ctx.set_font(&theme.font(12.0, false));
+        let label = t(Msg::HistoryButton);
+        let lw = self.ctx.measure_text(&label).ok().map_or(40.0, |m| m.width()) + 20.0;
+        let rect = (x + bw - pad - lw, y + pad + 8.0 * s - pill_h / 2.0, lw, pill_h);
+        draw_pill(&self.ctx, theme, rect, &label, false, false);
+        self.btn_history = rect;
+
+        let locale = i18n::tag();
+        let mut buttons = Vec::new();
+        let mut ry = y + pad + 32.0 * s;
+        for (i, slot) in slots.iter().enumerate() {
+            let thumb = (x + pad, ry, row_h, row_h);
+            self.ctx.set_fill_style_str(theme.panel);
+            self.ctx.fill_rect(thumb.0, thumb.1, thumb.2, thumb.3);
+            self.ctx.set_stroke_style_str(theme.pill_border);
+            self.ctx.stroke_rect(thumb.0, thumb.1, thumb.2, thumb.3);
+
+            // Every slot can be saved to; only a full one loaded or emptied.
+            let mut actions = vec![(SlotAction::Save(i), Msg::SaveButton)];
+            if slot.is_some() {
+                actions.push((SlotAction::Load(i), Msg::LoadButton));
+                actions.push((SlotAction::Delete(i), Msg::DeleteButton));
+            }
+            self.ctx.set_font(&theme.font(12.0, false));
+            let mut bx = x + bw - pad;
+            for (action, msg) in actions.into_iter().rev() {
+                let label = t(msg);
+                let lw = self.ctx.measure_text(&label).ok().map_or(40.0, |m| m.width()) + 20.0;
+                bx -= lw;
+                let rect = (bx, ry + (row_h - pill_h) / 2.0, lw, pill_h);
+                draw_pill(&self.ctx, theme, rect, &label, false, false);
+                buttons.push((action, rect));
+                bx -= 8.0;
+            }
+
+            let tx = thumb.0 + row_h + 12.0;
+            match slot {
+                Some(save) => {
+                    self.draw_thumbnail(&save.game, thumb);
+                    let date = js_sys::Date::new(&JsValue::from_f64(save.saved_ms));
+                    let when = String::from(date.to_locale_string(&locale, &JsValue::UNDEFINED));
+                    self.ctx.set_fill_style_str(theme.text);
+                    self.ctx.set_font(&theme.font(14.0, true));
+                    let _ = self.ctx.fill_text(&save.name, tx, ry + row_h * 0.32);
+                    self.ctx.set_fill_style_str(theme.muted_text);
+                    self.ctx.set_font(&theme.font(12.0, false));
+                    let info = tf(Msg::SlotInfo, &[&save.game.moves.len(), &when]);
+                    let _ = self.ctx.fill_text(&info, tx, ry + row_h * 0.7);
+                }
+                None => {
+                    self.ctx.set_fill_style_str(theme.muted_text);
+                    self.ctx.set_font(&theme.font(13.0, false));
+                    let _ = self.ctx.fill_text(&t(Msg::EmptySlot), tx, ry + row_h / 2.0);
+                }
+            }
+            ry += row_h + gap;
+        }
+        self.ctx.set_text_baseline("alphabetic");
+        self.save_buttons = buttons;
+    }
+
+    /// The history screen over a dimmed board: a row for each finished game
+    /// that fits, newest first, with who won and when and its buttons.
+    fn draw_history(&mut self) {
+        let Some(history) = &self.history else {
+            return;
+        };
+        let theme = self.theme;
+        let s = theme.text_scale();
+        let (w, h) = (self.view_w, self.view_h);
+        self.ctx.set_fill_style_str("rgba(0,0,0,0.6)");
+        self.ctx.fill_rect(0.0, 0.0, w, h);
+
+        let (pad, gap, row_h, pill_h) = (20.0, 8.0, 44.0 * s, 26.0 * s);
+        let top = pad + 32.0 * s;
+        let fit = ((h - 16.0 - top - pad) / (row_h + gap)).floor().max(1.0) as usize;
+        let entries = history.entries.as_deref();
+        let rows = entries.map_or(1, |e| e.len().clamp(1, fit));
+        let bw = (560.0 * s).min(w - 16.0);
+        let bh = top + pad + rows as f64 * (row_h + gap);
+        let (x, y) = ((w - bw) / 2.0, ((h - bh) / 2.0).max(0.0));
+        self.ctx.set_fill_style_str(theme.pill_ai);
+        self.ctx.fill_rect(x, y, bw, bh);
+        self.ctx.set_stroke_style_str(theme.pill_border);
+        self.ctx.set_line_width(1.0);
+        self.ctx.stroke_rect(x, y, bw, bh);
+
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("middle");
+        self.ctx.set_fill_style_str(theme.text);
+        self.ctx.set_font(&theme.font(16.0, true));
+        let _ = self.ctx.fill_text(&t(Msg::HistoryTitle), x + pad, y + pad + 8.0 * s);
+
+        let mut buttons = Vec::new();
+        let scroll = entries.map_or(0, |e| history.scroll.min(e.len().saturating_sub(fit)));
+        let list = match entries {
+            None | Some([]) => {
+                let note = match entries {
+                    None => t(Msg::HistoryLoading),
+                    Some(_) => t(Msg::HistoryEmpty),
+                };
+                self.ctx.set_fill_style_str(theme.muted_text);
+                self.ctx.set_font(&theme.font(13.0, false));
+                let _ = self.ctx.fill_text(&note, x + pad, y + top + row_h / 2.0);
+                &[][..]
+            }
+            Some(list) => list,
+        };
+        let player = |c: Controller| {
+            t(match c {
+                Controller::Human => Msg::Human,
+                Controller::AI => Msg::Ai,
+                Controller::Remote => Msg::Remote,
+            })
+        };
+        let locale = i18n::tag();
+        let mut ry = y + top;
+        for (i, entry) in list.iter().enumerate().skip(scroll).take(fit) {
+            self.ctx.set_font(&theme.font(12.0, false));
+            let mut bx = x + bw - pad;
+            for (action, msg) in [
+                (HistoryAction::Export(i), Msg::ExportButton),
+                (HistoryAction::Replay(i), Msg::ReplayButton),
+            ] {
+                let label = t(msg);
+                let lw = self.ctx.measure_text(&label).ok().map_or(40.0, |m| m.width()) + 20.0;
+                bx -= lw;
+                let rect = (bx, ry + (row_h - pill_h) / 2.0, lw, pill_h);
+                draw_pill(&self.ctx, theme, rect, &label, false, false);
+                buttons.push((action, rect));
+                bx -= 8.0;
+            }
+
+            let players = tf(
+                Msg::HistoryPlayers,
+                &[&player(entry.players[0]), &player(entry.players[1])],
+            );
+            let result = format!("{} · {players}", tf(Msg::Wins, &[&i18n::color(entry.winner)]));
+            let date = js_sys::Date::new(&JsValue::from_f64(entry.date_ms));
+            let when = String::from(date.to_locale_string(&locale, &JsValue::UNDEFINED));
+            let variant = t(Variant::of(entry.rules).label());
+            let info = tf(Msg::HistoryInfo, &[&when, &variant, &entry.moves.len()]);
+            self.ctx.set_fill_style_str(theme.text);
+            self.ctx.set_font(&theme.font(14.0, true));
+            let _ = self.ctx.fill_text(&result, x + pad, ry + row_h * 0.3);
+            self.ctx.set_fill_style_str(theme.muted_text);
+            self.ctx.set_font(&theme.font(12.0, false));
+            let _ = self.ctx.fill_text(&info, x + pad, ry + row_h * 0.72);
+            ry += row_h + gap;
+        }
+        self.ctx.set_text_baseline("alphabetic");
+        self.history_buttons = buttons;
+        if let Some(history) = self.history.as_mut() {
+            history.scroll = scroll;
+        }
+    }
+
+    /// The open RenjuNet library: a row a game with its players, result, date
+    /// and length, and a button to replay it.
+    fn draw_library(&mut self) {
+        let Some(library) = &self.library else {
+            return;
+        };
+        let theme = self.theme;
+        let s = theme.text_scale();
+        let (w, h) = (self.view_w, self.view_h);
+        self.ctx.set_fill_style_str("rgba(0,0,0,0.6)");
+        self.ctx.fill_rect(0.0, 0.0, w, h);
+
+        let (pad, gap, row_h, pill_h) = (20.0, 8.0, 44.0 * s, 26.0 * s);
+        let top = pad + 32.0 * s;
+        let fit = ((h - 16.0 - top - pad) / (row_h + gap)).floor().max(1.0) as usize;
+        let records = &library.records;
+        let rows = records.len().clamp(1, fit);
+        let bw = (560.0 * s).min(w - 16.0);
+        let bh = top + pad + rows as f64 * (row_h + gap);
+        let (x, y) = ((w - bw) / 2.0, ((h - bh) / 2.0).max(0.0));
+        self.ctx.set_fill_style_str(theme.pill_ai);
+        self.ctx.fill_rect(x, y, bw, bh);
+        self.ctx.set_stroke_style_str(theme.pill_border);
+        self.ctx.set_line_width(1.0);
+        self.ctx.stroke_rect(x, y, bw, bh);
+
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("middle");
+        self.ctx.set_fill_style_str(theme.text);
+        self.ctx.set_font(&theme.font(16.0, true));
+        let _ = self.ctx.fill_text(&t(Msg::LibraryTitle), x + pad, y + pad + 8.0 * s);
+        if records.is_empty() {
+            self.ctx.set_fill_style_str(theme.muted_text);
+            self.ctx.set_font(&theme.font(13.0, false));
+            let _ = self.ctx.fill_text(&t(Msg::LibraryEmpty), x + pad, y + top + row_h / 2.0);
+        }
+
+        let mut buttons = Vec::new();
+        let scroll = library.scroll.min(records.len().saturating_sub(fit));
+        let label = t(Msg::ReplayButton);
+        let mut ry = y + top;
+        for (i, record) in records.iter().enumerate().skip(scroll).take(fit) {
+            self.ctx.set_font(&theme.font(12.0, false));
+            let lw = self.ctx.measure_text(&label).ok().map_or(40.0, |m| m.width()) + 20.0;
+            let rect = (x + bw - pad - lw, ry + (row_h - pill_h) / 2.0, lw, pill_h);
+            draw_pill(&self.ctx, theme, rect, &label, false, false);
+            buttons.push((i, rect));
+
+            let players = tf(Msg::HistoryPlayers, &[&record.black, &record.white]);
+            let result = match record.black_result {
+                Some(r) if r > 0.75 => "1–0",
+                Some(r) if r > 0.25 => "½–½",
+                Some(_) => "0–1",
+                None => "?",
+            };
+            let info = tf(Msg::HistoryInfo, &[&result, &record.date, &record.moves.len()]);
+            self.ctx.set_fill_style_str(theme.text);
+            self.ctx.set_font(&theme.font(14.0, true));
+            let _ = self.ctx.fill_text(&players, x + pad, ry + row_h * 0.3);
+            self.ctx.set_fill_style_str(theme.muted_text);
+            self.ctx.set_font(&theme.font(12.0, false));
+            let _ = self.ctx.fill_text(&info, x + pad, ry + row_h * 0.72);
+            ry += row_h + gap;
+        }
+        self.ctx.set_text_baseline("alphabetic");
+        self.library_buttons = buttons;
+        if let Some(library) = self.library.as_mut() {
+            library.scroll = scroll;
+        }
+    }
+
+    /// `game`'s stones scaled into the square `rect`, as on the minimap.
+    fn draw_thumbnail(&self, game: &Game, (x, y, side, _): (f64, f64, f64, f64)) {
+        let Some(stones) = game.stone_extent() else {
+            return;
+        };
+        let cols = (stones.max.x - stones.min.x + 1).max(stones.max.y - stones.min.y + 1) as f64;
+        let scale = (side - 8.0) / cols;
+        let (cx, cy) = (
+            (stones.min.x + stones.max.x) as f64 / 2.0,
+            (stones.min.y + stones.max.y) as f64 / 2.0,
+        );
+        let dot = (scale * 0.8).max(2.0);
+        for (&p, &c) in &game.cells {
+            let sx = x + side / 2.0 + (p.x as f64 - cx) * scale;
+            let sy = y + side / 2.0 + (p.y as f64 - cy) * scale;
+            self.ctx.set_fill_style_str(self.theme.stones[c as usize]);
+            self.ctx.fill_rect(sx - dot / 2.0, sy - dot / 2.0, dot, dot);
+        }
+    }
+
+    /// Every control with its current keys, centred over a dimmed board.
+    fn draw_help(&self) {
+        use keys::label;
+        let rows: Vec<(String, Msg)> = vec![
+            (t(Msg::TapClick), Msg::HelpPlace),
+            (label(&[Action::TwoTap]), Msg::HelpTwoTap),
+            (t(Msg::LongPress), Msg::HelpLongPress),
+            (
+                label(&[
+                    Action::CursorLeft,
+                    Action::CursorRight,
+                    Action::CursorUp,
+                    Action::CursorDown,
+                ]),
+                Msg::HelpCursor,
+            ),
+            (label(&[Action::Place]), Msg::HelpPlaceCursor),
+            (label(&[Action::HideCursor]), Msg::HelpHideCursor),
+            (t(Msg::Drag), Msg::HelpPan),
+            (
+                label(&[Action::PanLeft, Action::PanRight, Action::PanUp, Action::PanDown]),
+                Msg::HelpPan,
+            ),
+            (t(Msg::WheelPinch), Msg::HelpWheel),
+            (t(Msg::DoubleTap), Msg::HelpDoubleTap),
+            (label(&[Action::ZoomIn, Action::ZoomOut]), Msg::HelpZoom),
+            (label(&[Action::ZoomToFit]), Msg::HelpZoomToFit),
+            (label(&[Action::Recenter]), Msg::HelpRecenter),
+            (label(&[Action::Restart]), Msg::HelpRestart),
+            (label(&[Action::Undo]), Msg::HelpUndo),
+            (label(&[Action::Hint]), Msg::HelpHint),
+            (label(&[Action::Replay]), Msg::HelpReplay),
+            (label(&[Action::Analysis]), Msg::HelpAnalysis),
+            (label(&[Action::Coords]), Msg::HelpCoords),
+            (label(&[Action::MoveNumbers]), Msg::HelpMoveNumbers),
+            (label(&[Action::Heatmap]), Msg::HelpHeatmap),
+            (label(&[Action::Mute]), Msg::HelpMute),
+            (label(&[Action::Haptics]), Msg::HelpHaptics),
+            (label(&[Action::Fullscreen]), Msg::HelpFullscreen),
+            (label(&[Action::ExpectedLine]), Msg::HelpExpectedLine),
+            (label(&[Action::TopMoves]), Msg::HelpTopMoves),
+            (label(&[Action::Explain]), Msg::HelpExplain),
+            (label(&[Action::BlunderAlerts]), Msg::HelpBlunderAlerts),
+            (label(&[Action::Contrast]), Msg::HelpContrast),
+            (label(&[Action::ColorScheme]), Msg::HelpColorScheme),
+            (label(&[Action::StoneMarks]), Msg::HelpStoneMarks),
+            (label(&[Action::Follow]), Msg::HelpFollow),
+            (label(&[Action::Board]), Msg::HelpBoard),
+            (label(&[Action::Variant]), Msg::HelpVariant),
+            (label(&[Action::Clock]), Msg::HelpClock),
+            (label(&[Action::Engine]), Msg::HelpEngine),
+            (label(&[Action::Style]), Msg::HelpStyle),
+            (label(&[Action::Handicap]), Msg::HelpHandicap),
+            (label(&[Action::Tournament]), Msg::HelpTournament),
+            (label(&[Action::Settings]), Msg::HelpSettings),
+            (label(&[Action::SaveImage]), Msg::HelpSaveImage),
+            (label(&[Action::SaveVideo]), Msg::HelpSaveVideo),
+            (label(&[Action::Share]), Msg::HelpShare),
+            (label(&[Action::ExportPsq]), Msg::HelpExportPsq),
+            (label(&[Action::CopyMoves]), Msg::HelpCopyMoves),
+            (label(&[Action::ExportReplay]), Msg::HelpExportReplay),
+            (label(&[Action::Saves]), Msg::HelpSaves),
+            (label(&[Action::History]), Msg::HelpHistory),
+            ("Ctrl+C / Ctrl+V".to_string(), Msg::HelpCopyPaste),
+            (label(&[Action::Presentation]), Msg::HelpPresentation),
+            (label(&[Action::Diagnostics]), Msg::HelpDiagnostics),
+            (label(&[Action::FrontierDots]), Msg::HelpFrontierDots),
+            (label(&[Action::Help]), Msg::HelpHelp),
+        ];
+        let [step, play, speed, exit] = [
+            label(&[Action::ReplayBack, Action::ReplayForward]),
+            label(&[Action::ReplayAutoplay]),
+            label(&[Action::ReplaySlower, Action::ReplayFaster]),
+            label(&[Action::ReplayExit, Action::Replay]),
+        ];
+        let text = |m| match m {
+            Msg::HelpReplay => tf(m, &[&step, &play, &speed, &exit]),
+            _ => t(m),
+        };
+        let rows: Vec<(String, String)> = rows.into_iter().map(|(k, m)| (k, text(m))).collect();
+        let (w, h) = (self.view_w, self.view_h);
+        self.ctx.set_fill_style_str("rgba(0,0,0,0.6)");
+        self.ctx.fill_rect(0.0, 0.0, w, h);
+
+        let line_h = ((h - 96.0) / rows.len() as f64).clamp(12.0, 20.0);
+        let font_px = (line_h * 0.7).floor();
+        let bold = format!("bold {font_px}px ui-sans-serif, system-ui, -apple-system");
+        let plain = format!("{font_px}px ui-sans-serif, system-ui, -apple-system");
+        let width = |font: &str, texts: &mut dyn Iterator<Item = &str>| {
+            self.ctx.set_font(font);
+            texts
+                .filter_map(|t| self.ctx.measure_text(t).ok())
+                .map(|m| m.width())
+                .fold(0.0, f64::max)
+        };
+        let key_w = width(&bold, &mut rows.iter().map(|r| r.0.as_str()));
+        let text_w = width(&plain, &mut rows.iter().map(|r| r.1.as_str()));
+        let pad = 20.0;
+        let bw = key_w + text_w + 3.0 * pad;
+        let bh = (rows.len() + 2) as f64 * line_h + pad;
+        let (x, y) = ((w - bw) / 2.0, ((h - bh) / 2.0).max(0.0));
+        self.ctx.set_fill_style_str(self.theme.pill_ai);
+        self.ctx.fill_rect(x, y, bw, bh);
+        self.ctx.set_stroke_style_str(self.theme.pill_border);
+        self.ctx.set_line_width(1.0);
+        self.ctx.stroke_rect(x, y, bw, bh);
+
+        self.ctx.set_text_baseline("middle");
+        self.ctx.set_font(&bold);
+        self.ctx.set_fill_style_str(self.theme.text);
+        let _ = self.ctx.fill_text(&t(Msg::Controls), x + pad, y + pad / 2.0 + line_h / 2.0);
+        for (i, (keys, what)) in rows.iter().enumerate() {
+            let ry = y + pad / 2.0 + line_h * (i as f64 + 2.0);
+            self.ctx.set_font(&bold);
+            self.ctx.set_fill_style_str("#facc15");
+            let _ = self.ctx.fill_text(keys, x + pad, ry);
+            self.ctx.set_font(&plain);
+            self.ctx.set_fill_style_str(self.theme.muted_text);
+            let _ = self.ctx.fill_text(what, x + 2.0 * pad + key_w, ry);
+        }
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// The `K` overlay's ranking as panel rows: each move, its score on the
+    /// evaluation graph's scale, and the reply line expected after it.
+    fn top_lines_table(&self, lines: &[ScoredLine]) -> Vec<String> {
+        let side = i18n::color(self.shown_game().player);
+        let mut rows = vec![tf(Msg::TopMovesFor, &[&side])];
+        if lines.is_empty() {
+            rows.push(t(Msg::GameOver));
+        }
+        let cell = |p: &Pt| format!("{},{}", p.x, p.y);
+        for (i, (score, line)) in lines.iter().enumerate() {
+            let rest: Vec<String> = line.iter().skip(1).map(cell).collect();
+            rows.push(format!(
+                "{}. {}  {:+.2}  {}",
+                i + 1,
+                cell(&line[0]),
+                analysis::squash(*score),
+                rest.join(" ")
+            ));
+        }
+        rows
+    }
+
+    /// The first of two taps: a larger preview stone on `p` inside a ring, and a
+    /// ✓ button beside it (above, unless that would leave the screen).
+    fn draw_selection(&mut self, p: Pt) {
+        let (sx, sy) = self.cell_to_screen(p);
+        self.ctx.save();
+        self.ctx.set_global_alpha(0.6);
+        let _ = self.ctx.translate(sx, sy);
+        let _ = self.ctx.scale(1.4, 1.4);
+        self.draw_stone(0.0, 0.0, self.game.player);
+        self.ctx.restore();
+        self.ctx.begin_path();
+        let _ = self
+            .ctx
+            .arc(sx, sy, self.cell_px * 0.75, 0.0, std::f64::consts::TAU);
+        self.ctx.set_stroke_style_str("#22c55e");
+        self.ctx.set_line_width(2.0);
+        self.ctx.stroke();
+
+        let r = (self.cell_px * 0.45).max(16.0);
+        let gap = self.cell_px * 0.75 + r + 6.0;
+        let by = if sy - gap - r >= 0.0 { sy - gap } else { sy + gap };
+        self.ctx.begin_path();
+        let _ = self.ctx.arc(sx, by, r, 0.0, std::f64::consts::TAU);
+        self.ctx.set_fill_style_str("#16a34a");
+        self.ctx.fill();
+        self.ctx.set_fill_style_str("#f0fdf4");
+        self.ctx
+            .set_font(&format!("bold {:.0}px ui-sans-serif, system-ui, -apple-system", r));
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("middle");
+        let _ = self.ctx.fill_text("✓", sx, by);
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("alphabetic");
+        self.btn_place = (sx - r, by - r, 2.0 * r, 2.0 * r);
+    }
+
+    /// The side to move's stone, faint, on `p` if a human could play there now.
+    fn draw_ghost_stone(&self, p: Pt) {
+        if self.replay.is_some() || !self.is_human(self.game.player) || !self.game.playable(p) {
+            return;
+        }
+        let (sx, sy) = self.cell_to_screen(p);
+        self.ctx.set_global_alpha(0.35);
+        self.draw_stone(sx, sy, self.game.player);
+        self.ctx.set_global_alpha(1.0);
+    }
+
+    /// A square around the stone that started a proven win.
+    fn draw_forcing_outline(&self, p: Pt) {
+        if self.replay.is_some() {
+            return;
+        }
+        let (sx, sy) = self.cell_to_screen(p);
+        let half = self.cell_px * 0.48;
+        self.ctx.set_stroke_style_str("#f97316");
+        self.ctx.set_line_width((self.cell_px * 0.06).max(1.5));
+        self.ctx
+            .stroke_rect(sx - half, sy - half, 2.0 * half, 2.0 * half);
+    }
+
+    /// A numbered badge, coloured by rank, on each of the `K` overlay's moves.
+    fn draw_rank_badges(&self, lines: &[ScoredLine]) {
+        let font_px = (self.cell_px * 0.4).floor();
+        self.ctx
+            .set_font(&format!("bold {font_px}px ui-sans-serif, system-ui, -apple-system"));
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("middle");
+        for (i, (_, line)) in lines.iter().enumerate() {
+            let (sx, sy) = self.cell_to_screen(line[0]);
+            self.ctx.begin_path();
+            let _ = self
+                .ctx
+                .arc(sx, sy, self.cell_px * 0.32, 0.0, std::f64::consts::TAU);
+            self.ctx.set_global_alpha(0.85);
+            self.ctx.set_fill_style_str(RANK_COLORS[i]);
+            self.ctx.fill();
+            self.ctx.set_global_alpha(1.0);
+            if font_px >= 7.0 {
+                self.ctx.set_fill_style_str("#0b0d11");
+                let _ = self.ctx.fill_text(&(i + 1).to_string(), sx, sy);
+            }
+        }
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// A ring on `p` pulsing once a second, `age_ms` into its life.
+    fn draw_pulse_ring(&self, p: Pt, age_ms: f64, color: &str) {
+        let pulse = 0.5 + 0.5 * (age_ms / 1000.0 * std::f64::consts::TAU).sin();
+        let (sx, sy) = self.cell_to_screen(p);
+        self.ctx.begin_path();
+        let _ = self.ctx.arc(
+            sx,
+            sy,
+            self.cell_px * (0.3 + 0.12 * pulse),
+            0.0,
+            std::f64::consts::TAU,
+        );
+        self.ctx.set_global_alpha(0.5 + 0.5 * pulse);
+        self.ctx.set_stroke_style_str(color);
+        self.ctx.set_line_width((self.cell_px * 0.08).max(2.0));
+        self.ctx.stroke();
+        self.ctx.set_global_alpha(1.0);
+    }
+
+    /// Bottom-centre message: why the AI moved, or a warning.
+    fn draw_status_line(&self, text: &str, color: &str) {
+        self.ctx.set_font(&self.theme.font(13.0, false));
+        let tw = self
+            .ctx
+            .measure_text(text)
+            .ok()
+            .map(|m| m.width())
+            .unwrap_or(0.0);
+        let (bw, bh) = (tw + 24.0, 28.0 * self.theme.text_scale());
+        let x = (self.view_w - bw) / 2.0;
+        let y = self.view_h - bh - 12.0;
+        self.ctx.set_fill_style_str(self.theme.panel);
+        self.ctx.fill_rect(x, y, bw, bh);
+        self.ctx.set_fill_style_str(color);
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("middle");
+        let _ = self.ctx.fill_text(text, self.view_w / 2.0, y + bh / 2.0);
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// Under the controller pills: the move number and each side's stones on
+    /// the board, for the game on screen, and the live game's elapsed time.
+    fn draw_progress_strip(&self) {
+        let game = self.shown_game();
+        let mut counts = [0; 2];
+        for &c in game.cells.values() {
+            counts[c as usize] += 1;
+        }
+        let mut text = format!(
+            "{} · {} {} · {} {}",
+            tf(Msg::MoveCount, &[&game.moves.len()]),
+            self.player_name(Color::Black),
+            counts[0],
+            self.player_name(Color::White),
+            counts[1],
+        );
+        if self.replay.is_none() {
+            text = format!("{text} · {}", format_elapsed(self.elapsed_ms(now_ms())));
+        }
+        let theme = self.theme;
+        self.ctx.set_font(&theme.font(11.0, false));
+        let tw = self
+            .ctx
+            .measure_text(&text)
+            .ok()
+            .map(|m| m.width())
+            .unwrap_or(0.0);
+        let (x, y) = (12.0, self.btn_black.1 + self.btn_black.3 + 6.0);
+        let bh = 20.0 * theme.text_scale();
+        self.ctx.set_fill_style_str(theme.panel);
+        self.ctx.fill_rect(x, y, tw + 16.0, bh);
+        self.ctx.set_fill_style_str(theme.muted_text);
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("middle");
+        let _ = self.ctx.fill_text(&text, x + 8.0, y + bh / 2.0);
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// A spinner and the search's depth and positions under the progress strip,
+    /// over a bar filling as the position budget is spent.
+    fn draw_thinking(&self, th: &Thinking) {
+        let (done, max_depth, nodes, limit) = th.search.progress();
+        let depth = (done + 1).min(max_depth);
+        let text = tf(Msg::Thinking, &[&depth, &max_depth, &nodes]);
+        let theme = self.theme;
+        self.ctx.set_font(&theme.font(11.0, false));
+        let tw = self
+            .ctx
+            .measure_text(&text)
+            .ok()
+            .map(|m| m.width())
+            .unwrap_or(0.0);
+        let bh = 20.0 * theme.text_scale();
+        let (x, y) = (12.0, self.btn_black.1 + self.btn_black.3 + 12.0 + bh);
+        let bw = tw + bh + 16.0;
+        self.ctx.set_fill_style_str(theme.panel);
+        self.ctx.fill_rect(x, y, bw, bh);
+        let spent = if limit == 0 { 1.0 } else { (nodes as f64 / limit as f64).min(1.0) };
+        self.ctx.set_fill_style_str(theme.pill_ai);
+        self.ctx.fill_rect(x, y + bh - 2.0, bw * spent, 2.0);
+        // A three-quarter ring turning once a second.
+        let angle = (now_ms() - th.start_ms) / 1000.0 * std::f64::consts::TAU;
+        let r = bh * 0.3;
+        self.ctx.set_stroke_style_str(theme.muted_text);
+        self.ctx.set_line_width(2.0);
+        self.ctx.begin_path();
+        let _ = self.ctx.arc(
+            x + 8.0 + r,
+            y + bh / 2.0,
+            r,
+            angle,
+            angle + 1.5 * std::f64::consts::PI,
+        );
+        self.ctx.stroke();
+        self.ctx.set_fill_style_str(theme.muted_text);
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("middle");
+        let _ = self.ctx.fill_text(&text, x + bh + 8.0, y + bh / 2.0);
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// Bottom-centre status strip while a replay is open.
+    fn draw_replay_bar(&self, replay: &Replay) {
+        let state = t(if replay.is_autoplay() { Msg::Playing } else { Msg::Paused });
+        let text = tf(
+            Msg::ReplayBar,
+            &[
+                &replay.cursor(),
+                &replay.len(),
+                &state,
+                &format!("{:.1}", replay.interval_ms() / 1000.0),
+            ],
+        );
+        self.ctx.set_font(&self.theme.font(13.0, false));
+        let tw = self
+            .ctx
+            .measure_text(&text)
+            .ok()
+            .map(|m| m.width())
+            .unwrap_or(0.0);
+        let (bw, bh) = (tw + 24.0, 28.0 * self.theme.text_scale());
+        let x = (self.view_w - bw) / 2.0;
+        let y = self.view_h - bh - 12.0;
+        self.ctx.set_fill_style_str(self.theme.panel);
+        self.ctx.fill_rect(x, y, bw, bh);
+        self.ctx.set_fill_style_str(self.theme.text);
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("middle");
+        let _ = self.ctx.fill_text(&text, self.view_w / 2.0, y + bh / 2.0);
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// Evaluation-over-time graph above the replay bar; returns its hitbox.
+    /// Up is good for Black, down for White; the current move is marked.
+    fn draw_eval_graph(&self, analysis: &Analysis, cursor: usize) -> (f64, f64, f64, f64) {
+        let evals = analysis.evals();
+        let gw = (self.view_w - 24.0).min(480.0);
+        let gh = 80.0;
+        let gx = (self.view_w - gw) / 2.0;
+        let gy = self.view_h - 40.0 - 12.0 - gh;
+        let mid = gy + gh / 2.0;
+        let span = (evals.len().max(2) - 1) as f64;
+        let px = |i: usize| gx + i as f64 / span * gw;
+        let py = |v: f64| mid - v * (gh / 2.0 - 4.0);
+
+        self.ctx.set_fill_style_str(self.theme.panel);
+        self.ctx.fill_rect(gx, gy, gw, gh);
+        self.ctx.set_stroke_style_str("#374151");
+        self.ctx.set_line_width(1.0);
+        self.ctx.begin_path();
+        self.ctx.move_to(gx, mid);
+        self.ctx.line_to(gx + gw, mid);
+        self.ctx.stroke();
+
+        self.ctx.set_stroke_style_str("#38bdf8");
+        self.ctx.set_line_width(1.5);
+        self.ctx.begin_path();
+        for (i, &v) in evals.iter().enumerate() {
+            if i == 0 {
+                self.ctx.move_to(px(i), py(v));
+            } else {
+                self.ctx.line_to(px(i), py(v));
+            }
+        }
+        self.ctx.stroke();
+
+        let cx = px(cursor.min(evals.len() - 1));
+        self.ctx.set_stroke_style_str("#fbbf24");
+        self.ctx.begin_path();
+        self.ctx.move_to(cx, gy);
+        self.ctx.line_to(cx, gy + gh);
+        self.ctx.stroke();
+
+        (gx, gy, gw, gh)
+    }
+
+    fn draw_stone(&self, sx: f64, sy: f64, c: Color) {
+        stones::stone(&self.ctx, &self.look(), self.cell_px, (sx, sy), c);
+    }
+
+    /// What stones look like in the theme, with White's marks if they are on.
+    fn look(&self) -> stones::Look<'static> {
+        stones::Look::of(self.theme, self.stone_marks)
+    }
+
+    fn view(&self) -> stones::View {
+        stones::View {
+            cam: (self.cam_x, self.cam_y),
+            cell_px: self.cell_px,
+            size: (self.view_w, self.view_h),
+        }
+    }
+
+    /// The skin's board image tiled at its own size over the view, anchored to
+    /// the origin cell so it moves with the grid when panning.
+    fn draw_board_image(&self, img: &web_sys::HtmlImageElement) {
+        let (w, h) = (self.view_w, self.view_h);
+        let Ok(Some(pattern)) = self.ctx.create_pattern_with_html_image_element(img, "repeat") else {
+            return;
+        };
+        let (ox, oy) = self.cell_to_screen(Pt::new(0, 0));
+        let dx = ox.rem_euclid(img.natural_width() as f64);
+        let dy = oy.rem_euclid(img.natural_height() as f64);
+        self.ctx.save();
+        let _ = self.ctx.translate(dx, dy);
+        self.ctx.set_fill_style_canvas_pattern(&pattern);
+        self.ctx.fill_rect(-dx, -dy, w, h);
+        self.ctx.restore();
+    }
+
+    /// Wavy grain lines across the board, fixed to cells so they move with it.
+    /// They are spaced a fixed fraction of a cell, doubled until a few px
+    /// apart, so zooming out keeps every other line rather than shifting them.
+    fn draw_grain(&self, color: &str) {
+        let (w, h) = (self.view_w, self.view_h);
+        let mut spacing = 0.3;
+        let mut stride = 1;
+        while spacing * self.cell_px < 5.0 {
+            spacing *= 2.0;
+            stride *= 2;
+        }
+        let (_, _, min_y, max_y) = self.visible_cells();
+        let first = (min_y as f64 / spacing).floor() as i64;
+        let last = (max_y as f64 / spacing).ceil() as i64;
+        self.ctx.set_stroke_style_str(color);
+        for row in first..=last {
+            // The same line at every zoom: seeded by its index at the finest spacing.
+            let seed = ((row * stride) as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            let seed = seed ^ (seed >> 29);
+            let phase = (seed % 628) as f64 / 100.0;
+            let wave = 0.2 + ((seed >> 10) % 100) as f64 / 100.0 * 0.4;
+            self.ctx.set_line_width(0.6 + ((seed >> 20) % 10) as f64 / 10.0);
+            self.ctx.begin_path();
+            let mut sx = 0.0;
+            while sx <= w + 16.0 {
+                let (x, _) = self.screen_to_cell_f64(sx, 0.0);
+                let y = row as f64 * spacing
+                    + spacing * wave * ((x * 0.35 + phase).sin() + 0.5 * (x * 1.3 + phase * 2.0).sin());
+                let sy = (y - self.cam_y) * self.cell_px + h / 2.0;
+                if sx == 0.0 {
+                    self.ctx.move_to(sx, sy);
+                } else {
+                    self.ctx.line_to(sx, sy);
+                }
+                sx += 16.0;
+            }
+            self.ctx.stroke();
+        }
+    }
+
+    /// Dots where lines a multiple of `star_spacing` apart cross, within the
+    /// given cells, and a ring around the origin among them.
+    fn draw_star_points(&self, min_x: i32, max_x: i32, min_y: i32, max_y: i32) {
+        let n = self.star_spacing;
+        if n <= 0 {
+            return;
+        }
+        let r = (self.cell_px * 0.09).max(1.5);
+        self.ctx.set_fill_style_str(self.theme.edge);
+        for gx in (min_x..=max_x).filter(|x| x.rem_euclid(n) == 0) {
+            for gy in (min_y..=max_y).filter(|y| y.rem_euclid(n) == 0) {
+                let (sx, sy) = self.cell_to_screen(Pt::new(gx, gy));
+                self.ctx.begin_path();
+                let _ = self.ctx.arc(sx, sy, r, 0.0, std::f64::consts::TAU);
+                self.ctx.fill();
+            }
+        }
+        let origin = Pt::new(0, 0);
+        if (min_x..=max_x).contains(&origin.x) && (min_y..=max_y).contains(&origin.y) {
+            let (sx, sy) = self.cell_to_screen(origin);
+            self.ctx.set_stroke_style_str(self.theme.edge);
+            self.ctx.set_line_width((self.cell_px * 0.05).max(1.0));
+            self.ctx.begin_path();
+            let _ = self
                 .ctx
-                .measure_text(sub)
-                .ok()
-                .map(|m| m.width())
-                .unwrap_or(0.0);
+                .arc(sx, sy, self.cell_px * 0.25, 0.0, std::f64::consts::TAU);
+            self.ctx.stroke();
+        }
+    }
 
-            let pad = 24.0;
-            let box_w = msg_w.max(sub_w) + pad * 2.0;
-            let box_h = 36.0 + 8.0 + 16.0 + pad * 2.0;
+    /// Bottom-right overview of the stones and, outlined, the part of the board
+    /// on screen; stores where it went for `on_tap`.
+    fn draw_minimap(&mut self) {
+        let Some(stones) = self.shown_game().stone_extent() else {
+            self.minimap = None;
+            return;
+        };
+        let theme = self.theme;
+        let half_w = self.view_w / 2.0 / self.cell_px;
+        let half_h = self.view_h / 2.0 / self.cell_px;
+        let view = (
+            self.cam_x - half_w,
+            self.cam_y - half_h,
+            self.cam_x + half_w,
+            self.cam_y + half_h,
+        );
+        // The stones with a cell to spare, and the view wherever it has gone.
+        let x0 = (stones.min.x as f64 - 1.0).min(view.0);
+        let y0 = (stones.min.y as f64 - 1.0).min(view.1);
+        let x1 = (stones.max.x as f64 + 1.0).max(view.2);
+        let y1 = (stones.max.y as f64 + 1.0).max(view.3);
+        let scale = (MINIMAP_PX / (x1 - x0)).min(MINIMAP_PX / (y1 - y0));
+        let (mw, mh) = ((x1 - x0) * scale, (y1 - y0) * scale);
+        let (bw, bh) = (mw + 2.0 * MINIMAP_PAD, mh + 2.0 * MINIMAP_PAD);
+        let (bx, by) = (self.view_w - bw - 12.0, self.view_h - bh - 12.0);
+        let map = |x: f64, y: f64| {
+            (
+                bx + MINIMAP_PAD + (x - x0) * scale,
+                by + MINIMAP_PAD + (y - y0) * scale,
+            )
+        };
 
-            self.ctx.set_fill_style_str("rgba(0,0,0,0.55)");
-            self.ctx
-                .fill_rect(w2 - box_w / 2.0, h2 - box_h / 2.0, box_w, box_h);
+        self.ctx.set_fill_style_str(theme.panel);
+        self.ctx.fill_rect(bx, by, bw, bh);
+        self.ctx.set_stroke_style_str(theme.pill_border);
+        self.ctx.set_line_width(1.0);
+        self.ctx.stroke_rect(bx, by, bw, bh);
 
-            self.ctx.set_text_align("center");
-            self.ctx.set_text_baseline("middle");
+        let dot = (scale * 0.8).max(2.0);
+        for (&p, &c) in self.shown_game().cells.iter() {
+            let (sx, sy) = map(p.x as f64, p.y as f64);
+            self.ctx.set_fill_style_str(theme.stones[c as usize]);
+            self.ctx.fill_rect(sx - dot / 2.0, sy - dot / 2.0, dot, dot);
+        }
 
-            self.ctx.set_fill_style_str("#e6edf3");
-            self.ctx
-                .set_font("bold 36px ui-sans-serif, system-ui, -apple-system");
-            let _ = self.ctx.fill_text(msg, w2, h2 - 10.0);
+        let (vx, vy) = map(view.0, view.1);
+        self.ctx.set_stroke_style_str(theme.text);
+        self.ctx.set_line_width(1.5);
+        self.ctx
+            .stroke_rect(vx, vy, (view.2 - view.0) * scale, (view.3 - view.1) * scale);
 
-            self.ctx.set_fill_style_str("#cbd5e1");
-            self.ctx
-                .set_font("16px ui-sans-serif, system-ui, -apple-system");
-            let _ = self.ctx.fill_text(sub, w2, h2 + 24.0);
+        self.minimap = Some(Minimap {
+            rect: (bx, by, bw, bh),
+            origin: (x0, y0),
+            scale,
+        });
+    }
 
-            self.ctx.set_text_align("left");
-            self.ctx.set_text_baseline("alphabetic");
-        }
+    /// Glowing stroke through the winning stones, overshooting half a cell at
+    /// each end; `pulse` (0 to 1) swells it and brightens the glow.
+    fn draw_win_line(&self, line: WinLine, pulse: f64) {
+        let (x0, y0) = self.cell_to_screen(line.start);
+        let (x1, y1) = self.cell_to_screen(line.end);
+        let len = (line.dir.x as f64).hypot(line.dir.y as f64);
+        let ox = line.dir.x as f64 / len * self.cell_px * 0.5;
+        let oy = line.dir.y as f64 / len * self.cell_px * 0.5;
 
-        // Build timestamp HUD
-        let ts = env!("BUILD_TS_UNIX");
-        self.ctx.set_text_align("left");
-        self.ctx.set_text_baseline("alphabetic");
-        self.ctx
-            .set_font("14px ui-sans-serif, system-ui, -apple-system");
-        let _ = self.ctx.fill_text(ts, 12.0, h - 22.0);
+        self.ctx.save();
+        self.ctx.set_line_cap("round");
+        self.ctx.set_shadow_color("#fbbf24");
+        self.ctx.set_shadow_blur(self.cell_px * (0.5 + 0.5 * pulse));
+        self.ctx.set_stroke_style_str("rgba(251,191,36,0.85)");
+        self.ctx.set_line_width((self.cell_px * 0.18).max(3.0) * (1.0 + 0.6 * pulse));
+        self.ctx.begin_path();
+        self.ctx.move_to(x0 - ox, y0 - oy);
+        self.ctx.line_to(x1 + ox, y1 + oy);
+        self.ctx.stroke();
+        self.ctx.restore();
     }
 
     /// Draw the Human/AI toggle pills and store their hitboxes.
     /// The pill for the **current turn** is highlighted with a bright outline.
     fn draw_controller_pills(&mut self) {
+        let theme = self.theme;
         let pad_x = 12.0;
-        let y = 44.0;
         let gap = 10.0;
-        let pill_h = 26.0;
+        let pill_h = 26.0 * theme.text_scale();
+        // Text baseline; the pills' tops stay level with the default size's.
+        let y = 18.0 + pill_h;
 
-        self.ctx
-            .set_font("12px ui-sans-serif, system-ui, -apple-system");
+        self.ctx.set_font(&theme.font(12.0, false));
 
-        let fmt = |c: Controller| match c {
-            Controller::Human => "Human",
-            Controller::AI => "AI",
+        let fmt = |side: Color| match self.controller(side) {
+            Controller::Human => t(Msg::Human),
+            Controller::AI => self.ai[side as usize].label(),
+            Controller::Remote => t(match self.net_status {
+                Some(NetStatus::Open) => Msg::Remote,
+                Some(NetStatus::Closed) => Msg::RemoteOffline,
+                _ => Msg::RemoteConnecting,
+            }),
         };
 
-        let b_label = format!("Black: {}", fmt(self.ctrl_black));
-        let w_label = format!("White: {}", fmt(self.ctrl_white));
+        let with_clock = |side: Color, label: String| match self.clock {
+            Some(_) => format!("{label} · {}", self.clock_text(side)),
+            None => label,
+        };
+        let with_extras = |side: Color, label: String| {
+            let mut label = if self.game.rules.captures {
+                format!("{label} · {}", tf(Msg::Captures, &[&self.game.captures[side as usize]]))
+            } else {
+                label
+            };
+            // Connect6: which of the two stones this turn is on.
+            let per_turn = self.game.rules.stones_per_turn;
+            if per_turn > 1 && side == self.game.player && !self.game.moves.is_empty() {
+                let placed = per_turn - self.game.stones_left;
+                label = format!("{label} · {}", tf(Msg::StoneOf, &[&(placed + 1), &per_turn]));
+            }
+            if let Some((_, turns)) = self.forced_win.filter(|&(w, _)| w == side) {
+                label = format!("{label} · {}", tf(Msg::WinsIn, &[&turns]));
+            }
+            with_clock(side, label)
+        };
+        let pill = |side: Color| tf(Msg::Pill, &[&self.player_name(side), &fmt(side)]);
+        let b_label = with_extras(Color::Black, pill(Color::Black));
+        let w_label = with_extras(Color::White, pill(Color::White));
 
         let b_w = self
             .ctx
@@ -779,50 +6609,8 @@ impl App {
         self.btn_black = (x0, y - pill_h + 8.0, b_w, pill_h);
         self.btn_white = (x1, y - pill_h + 8.0, w_w, pill_h);
 
-        // Helper: draw pill with fill driven by controller, and outline if current player's pill.
         let draw_pill = |x: f64, text: &str, is_current: bool, is_ai: bool, w: f64| {
-            // Fill indicates Human/AI (subtle)
-            self.ctx
-                .set_fill_style_str(if is_ai { "#111827" } else { "#1f2937" });
-            self.ctx.begin_path();
-            let r = 13.0;
-            let y0 = y - pill_h + 8.0;
-            let x1 = x + w;
-            let y1 = y0 + pill_h;
-            self.ctx.move_to(x + r, y0);
-            self.ctx.line_to(x1 - r, y0);
-            let _ = self
-                .ctx
-                .arc(x1 - r, y0 + r, r, -std::f64::consts::FRAC_PI_2, 0.0);
-            self.ctx.line_to(x1, y1 - r);
-            let _ = self
-                .ctx
-                .arc(x1 - r, y1 - r, r, 0.0, std::f64::consts::FRAC_PI_2);
-            self.ctx.line_to(x + r, y1);
-            let _ = self
-                .ctx
-                .arc(x + r, y1 - r, r, std::f64::consts::FRAC_PI_2, std::f64::consts::PI);
-            self.ctx.line_to(x, y0 + r);
-            let _ = self
-                .ctx
-                .arc(x + r, y0 + r, r, std::f64::consts::PI, 3.0 * std::f64::consts::FRAC_PI_2);
-            self.ctx.close_path();
-            self.ctx.fill();
-
-            // Outline: bright if current turn, muted otherwise.
-            if is_current {
-                self.ctx.set_stroke_style_str("#38bdf8"); // highlight
-                self.ctx.set_line_width(2.0);
-            } else {
-                self.ctx.set_stroke_style_str("#374151");
-                self.ctx.set_line_width(1.0);
-            }
-            self.ctx.stroke();
-
-            self.ctx.set_fill_style_str("#e5e7eb");
-            self.ctx.set_text_align("left");
-            self.ctx.set_text_baseline("alphabetic");
-            let _ = self.ctx.fill_text(text, x + 10.0, y);
+            draw_pill(&self.ctx, theme, (x, y - pill_h + 8.0, w, pill_h), text, is_current, is_ai);
         };
 
         draw_pill(
@@ -839,5 +6627,714 @@ impl App {
             self.ctrl_white == Controller::AI,
             w_w,
         );
+
+        // Presenting keeps to the players.
+        if self.presenting {
+            let none = (0.0, 0.0, 0.0, 0.0);
+            self.btn_style = none;
+            self.btn_think_less = none;
+            self.btn_think_more = none;
+            self.btn_sound = none;
+            self.btn_settings = none;
+            self.btn_fullscreen = none;
+            self.btn_fit = none;
+            return;
+        }
+
+        let mut x2 = x1 + w_w + gap;
+
+        // Style button, while either side is played by the AI.
+        if self.is_ai(Color::Black) || self.is_ai(Color::White) {
+            let label = format!("{} (S)", tf(Msg::StyleButton, &[&self.ai[0].style.label()]));
+            let s_w = self
+                .ctx
+                .measure_text(&label)
+                .ok()
+                .map(|m| m.width())
+                .unwrap_or(100.0)
+                + 20.0;
+            draw_pill(x2, &label, false, true, s_w);
+            self.btn_style = (x2, y - pill_h + 8.0, s_w, pill_h);
+            x2 += s_w + gap;
+
+            // Thinking time: a readout between − and + buttons.
+            let label = tf(Msg::TimeButton, &[&format_think_ms(self.ai[0].think_ms())]);
+            let t_w = self
+                .ctx
+                .measure_text(&label)
+                .ok()
+                .map(|m| m.width())
+                .unwrap_or(80.0)
+                + 20.0;
+            let step_w = 28.0;
+            draw_pill(x2, "−", false, true, step_w);
+            self.btn_think_less = (x2, y - pill_h + 8.0, step_w, pill_h);
+            x2 += step_w + 4.0;
+            draw_pill(x2, &label, false, true, t_w);
+            x2 += t_w + 4.0;
+            draw_pill(x2, "+", false, true, step_w);
+            self.btn_think_more = (x2, y - pill_h + 8.0, step_w, pill_h);
+            x2 += step_w + gap;
+        } else {
+            self.btn_style = (0.0, 0.0, 0.0, 0.0);
+            self.btn_think_less = (0.0, 0.0, 0.0, 0.0);
+            self.btn_think_more = (0.0, 0.0, 0.0, 0.0);
+        }
+
+        // Sound on/off, always available.
+        let label = if audio::muted() { "🔇" } else { "🔊" };
+        let m_w = 36.0 * theme.text_scale();
+        draw_pill(x2, label, false, true, m_w);
+        self.btn_sound = (x2, y - pill_h + 8.0, m_w, pill_h);
+        x2 += m_w + gap;
+
+        // Settings panel, always available.
+        draw_pill(x2, "⚙", self.show_settings, true, m_w);
+        self.btn_settings = (x2, y - pill_h + 8.0, m_w, pill_h);
+        x2 += m_w + gap;
+
+        // Fullscreen, where the page may have it.
+        let doc = window().and_then(|w| w.document());
+        if doc.as_ref().is_some_and(|d| d.fullscreen_enabled()) {
+            let full = doc.is_some_and(|d| d.fullscreen_element().is_some());
+            let label = if full { "⤡" } else { "⛶" };
+            draw_pill(x2, label, false, true, m_w);
+            self.btn_fullscreen = (x2, y - pill_h + 8.0, m_w, pill_h);
+            x2 += m_w + gap;
+        } else {
+            self.btn_fullscreen = (0.0, 0.0, 0.0, 0.0);
+        }
+
+        // Zoom to fit, once there is something to fit.
+        if self.shown_game().cells.is_empty() {
+            self.btn_fit = (0.0, 0.0, 0.0, 0.0);
+        } else {
+            let label = format!("{} (0)", t(Msg::FitButton));
+            let f_w = self
+                .ctx
+                .measure_text(&label)
+                .ok()
+                .map(|m| m.width())
+                .unwrap_or(50.0)
+                + 20.0;
+            draw_pill(x2, &label, false, true, f_w);
+            self.btn_fit = (x2, y - pill_h + 8.0, f_w, pill_h);
+        }
+    }
+
+    /// The toolbar along the bottom left, above the build stamp: buttons for
+    /// the main actions, each shown only while it would do something, with
+    /// their hitboxes kept in `toolbar`.
+    fn draw_toolbar(&mut self) {
+        let theme = self.theme;
+        let pill_h = 26.0 * theme.text_scale();
+        let y0 = self.view_h - 44.0 - pill_h;
+        let tools = [
+            (Action::Undo, Msg::UndoButton, self.can_undo()),
+            (Action::Hint, Msg::HintButton, self.can_hint()),
+            (Action::Replay, Msg::ReplayButton, self.replay.is_none() && !self.game.moves.is_empty()),
+            (Action::Share, Msg::ShareButton, !self.game.moves.is_empty()),
+            (Action::Saves, Msg::SavesButton, self.tournament.is_none()),
+            (Action::Restart, Msg::RestartButton, self.tournament.is_none()),
+        ];
+        self.ctx.set_font(&theme.font(12.0, false));
+        self.toolbar.clear();
+        let mut x = 12.0;
+        for (action, msg, shown) in tools {
+            if !shown {
+                continue;
+            }
+            let label = format!("{} ({})", t(msg), keys::label(&[action]));
+            let w = self
+                .ctx
+                .measure_text(&label)
+                .ok()
+                .map(|m| m.width())
+                .unwrap_or(50.0)
+                + 20.0;
+            let rect = (x, y0, w, pill_h);
+            draw_pill(&self.ctx, theme, rect, &label, false, true);
+            self.toolbar.push((action, rect));
+            x += w + 10.0;
+        }
+    }
+
+    /// Do what the toolbar button for `action` does.
+    fn use_tool(&mut self, action: Action) {
+        match action {
+            Action::Undo => self.undo(),
+            Action::Hint => self.show_hint(),
+            Action::Replay => self.open_replay(),
+            Action::Restart => self.confirm_restart(t(Msg::ConfirmRestartTap)),
+            Action::CopyLink => self.copy_link(),
+            Action::SaveImage => self.save_image(),
+            Action::Share => self.open_share(),
+            Action::Saves => self.open_saves(),
+            _ => {}
+        }
+    }
+
+    /// Draw the board around the stones on screen, a cell beyond them each way,
+    /// to an image `image_size` gives the cells of, and download it as a PNG.
+    fn save_image(&mut self) {
+        let Some(stones) = self.shown_game().stone_extent() else {
+            return;
+        };
+        let frame = framing(stones, self.image_size.cell_px(), MAX_IMAGE_PX);
+        let Some((canvas, ctx)) = offscreen_canvas(frame.3, frame.4) else {
+            return;
+        };
+        let cells = self.shown_game().cells.clone();
+        self.draw_framed(ctx, frame, &cells);
+        let Ok(url) = canvas.to_data_url_with_type("image/png") else {
+            return;
+        };
+        if let Some(link) = window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.create_element("a").ok())
+            .and_then(|a| a.dyn_into::<web_sys::HtmlAnchorElement>().ok())
+        {
+            link.set_href(&url);
+            link.set_download("gomoku.png");
+            link.click();
+        }
+    }
+
+    /// Draw the grid and `cells` on `ctx` with the camera at `frame` (see
+    /// `framing`), then put the camera back.
+    fn draw_framed(
+        &mut self,
+        ctx: CanvasRenderingContext2d,
+        (x, y, px, w, h): (f64, f64, f64, f64, f64),
+        cells: &PtMap<Color>,
+    ) {
+        let view = (self.cam_x, self.cam_y, self.cell_px, self.view_w, self.view_h);
+        (self.cam_x, self.cam_y, self.cell_px, self.view_w, self.view_h) = (x, y, px, w, h);
+        self.draw_on(ctx, |a| {
+            a.draw_grid();
+            for (&p, &c) in cells {
+                let (sx, sy) = a.cell_to_screen(p);
+                a.draw_stone(sx, sy, c);
+            }
+        });
+        (self.cam_x, self.cam_y, self.cell_px, self.view_w, self.view_h) = view;
+    }
+
+    /// Record the game from its first move to its last as a video, unless one
+    /// is being recorded already.
+    fn save_video(&mut self) {
+        if self.recording.is_some() {
+            return;
+        }
+        let Some(stones) = self.game.stone_extent() else {
+            return;
+        };
+        let frame = framing(stones, VIDEO_CELL_PX, MAX_VIDEO_PX);
+        let Some((canvas, ctx)) = offscreen_canvas(frame.3, frame.4) else {
+            return;
+        };
+        match record::Recording::start(&canvas, ctx, frame, &self.game) {
+            Ok(rec) => self.recording = Some(rec),
+            Err(_) => self.toast(t(Msg::VideoUnsupported)),
+        }
+        self.dirty = true;
+    }
+
+    /// Draw the video's next position when it is due, and end it after the last.
+    fn maybe_record_step(&mut self) {
+        let Some(rec) = self.recording.as_mut() else {
+            return;
+        };
+        match rec.step(now_ms()) {
+            Step::Wait => return,
+            Step::Frame => {
+                let (ctx, frame) = (rec.ctx.clone(), rec.frame);
+                let cells = rec.game().cells.clone();
+                self.draw_framed(ctx, frame, &cells);
+            }
+            Step::Done => {
+                if let Some(rec) = self.recording.take() {
+                    rec.finish();
+                }
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Show the game's link as a QR code, for a phone to pick the game up from.
+    fn open_share(&mut self) {
+        self.sync_url();
+        let Ok(href) = window().unwrap().location().href() else {
+            return;
+        };
+        self.share = qr::encode(href.as_bytes()).map(|code| Shared {
+            code,
+            caption: t(Msg::ShareCaption),
+            pairing: None,
+        });
+        self.dirty = true;
+    }
+
+    /// Show a WebRTC offer or answer as a QR code, handing it over as text
+    /// when the overlay closes; straight away if it is too long for one.
+    fn show_pairing(&mut self, pairing: Pairing) {
+        let Some(code) = qr::encode(pairing.qr_text.as_bytes()) else {
+            self.hand_over(pairing);
+            return;
+        };
+        let caption = t(if pairing.wants_reply { Msg::PairOffer } else { Msg::PairAnswer });
+        self.share = Some(Shared {
+            code,
+            caption,
+            pairing: Some(pairing),
+        });
+        self.dirty = true;
+    }
+
+    fn close_share(&mut self) {
+        if let Some(pairing) = self.share.take().and_then(|s| s.pairing) {
+            self.hand_over(pairing);
+        }
+    }
+
+    /// Offer a pairing's code to copy, and for an offer take the answer back.
+    fn hand_over(&mut self, pairing: Pairing) {
+        let window = window().unwrap();
+        if pairing.wants_reply {
+            let answer = window
+                .prompt_with_message_and_default(&t(Msg::RtcSendOffer), &pairing.code)
+                .ok()
+                .flatten();
+            if let Some(net) = &self.net {
+                net.reply(&answer.unwrap_or_default());
+            }
+        } else {
+            let _ = window.prompt_with_message_and_default(&t(Msg::RtcSendAnswer), &pairing.code);
+        }
+    }
+
+    /// The share overlay: the QR code, dark on white whatever the theme so
+    /// cameras read it, in a panel over the dimmed board, captioned below.
+    fn draw_share(&self) {
+        let Some(Shared { code, caption, .. }) = &self.share else {
+            return;
+        };
+        let theme = self.theme;
+        let (w, h) = (self.view_w, self.view_h);
+        self.ctx.set_fill_style_str("rgba(0,0,0,0.6)");
+        self.ctx.fill_rect(0.0, 0.0, w, h);
+
+        let caption_h = 28.0 * theme.text_scale();
+        // Four modules of quiet zone each way, as scanners expect.
+        let modules = (code.size + 8) as f64;
+        let px = ((w.min(h - caption_h) * 0.7) / modules).floor().max(2.0);
+        let side = modules * px;
+        let (x, y) = (((w - side) / 2.0).round(), ((h - side - caption_h) / 2.0).round());
+        self.ctx.set_fill_style_str("#fff");
+        self.ctx.fill_rect(x, y, side, side);
+        self.ctx.set_fill_style_str("#000");
+        for my in 0..code.size {
+            for mx in 0..code.size {
+                if code.dark(mx, my) {
+                    let (cx, cy) = (x + (mx + 4) as f64 * px, y + (my + 4) as f64 * px);
+                    self.ctx.fill_rect(cx, cy, px, px);
+                }
+            }
+        }
+        self.ctx.set_fill_style_str(theme.panel);
+        self.ctx.fill_rect(x, y + side, side, caption_h);
+        self.ctx.set_stroke_style_str(theme.pill_border);
+        self.ctx.set_line_width(1.0);
+        self.ctx.stroke_rect(x, y, side, side + caption_h);
+        self.ctx.set_font(&theme.font(13.0, false));
+        self.ctx.set_fill_style_str(theme.text);
+        self.ctx.set_text_align("center");
+        self.ctx.set_text_baseline("middle");
+        let _ = self.ctx.fill_text(caption, w / 2.0, y + side + caption_h / 2.0);
+        self.ctx.set_text_align("left");
+        self.ctx.set_text_baseline("alphabetic");
+    }
+
+    /// Load the position in `text` (see `codec::encode_position`) as the live
+    /// game, if every move in it is legal under the rules it carries, the game in
+    /// a `.psq` file, as standard gomoku on its board, or a move list (see
+    /// `notation`) on the infinite board. A RenjuNet library opens for picking
+    /// a game to replay instead, and a replay file plays back.
+    fn paste_position(&mut self, text: &str) {
+        if self.tournament.is_some() {
+            return;
+        }
+        if let Some(records) = rif::parse(text) {
+            self.open_library(records);
+            return;
+        }
+        if let Some(file) = schema::ReplayFile::parse(text) {
+            self.open_replay_file(file);
+            return;
+        }
+        let loaded = if let Some((rules, moves)) = codec::decode_position(text, self.game.rules) {
+            let rules = Rules {
+                weights: self.game.rules.weights,
+                ..rules
+            };
+            self.load_game(rules, moves)
+        } else if let Some((size, moves)) = psq::parse(text) {
+            let rules = Rules {
+                bounds: Some(Bounds::square(size)),
+                ..Variant::Standard.apply(self.game.rules)
+            };
+            self.load_game(rules.validated(), moves)
+        } else if let Some(moves) = notation::parse(text) {
+            let rules = Rules {
+                bounds: None,
+                ..self.game.rules
+            };
+            self.load_game(rules, moves)
+        } else {
+            false
+        };
+        self.toast(t(if loaded { Msg::PositionPasted } else { Msg::PasteInvalid }));
+    }
+
+    /// Open the game in a link's hash (see `codec::encode_link`) under the
+    /// rules it carries, or say the link is broken.
+    fn open_link(&mut self, hash: &str) {
+        let loaded = codec::decode_link(hash).is_some_and(|(rules, moves)| {
+            let rules = Rules {
+                weights: self.game.rules.weights,
+                ..rules
+            };
+            self.load_game(rules, moves)
+        });
+        if !loaded {
+            self.toast(t(Msg::PasteInvalid));
+        }
+    }
+
+    /// Make `moves` the live game here and for any connected peer, if every
+    /// one is legal under `rules`.
+    fn load_game(&mut self, rules: Rules, moves: Vec<Pt>) -> bool {
+        let mut game = Game::with_rules(rules);
+        if !moves.iter().all(|&p| game.play(p)) {
+            return false;
+        }
+        self.game = game;
+        if let Some(clock) = self.clock.as_mut() {
+            clock.reset();
+        }
+        self.replay = None;
+        self.analysis = None;
+        self.move_times.clear();
+        self.game_started_ms = None;
+        self.game_changed();
+        self.send_sync();
+        self.zoom_to_fit();
+        if self.is_ai_turn() {
+            self.queue_ai_soon(AI_START_MS);
+        } else {
+            self.want_ai = false;
+        }
+        true
+    }
+
+    /// Replay the only game in a RenjuNet library, or list them to pick from.
+    fn open_library(&mut self, records: Vec<rif::Record>) {
+        self.library = Some(Library { records, scroll: 0 });
+        if self.library.as_ref().is_some_and(|l| l.records.len() == 1) {
+            self.replay_record(0);
+        }
+        self.dirty = true;
+    }
+
+    /// Replay the library's game `i` as standard gomoku on the infinite board.
+    fn replay_record(&mut self, i: usize) {
+        let Some(record) = self.library.as_ref().and_then(|l| l.records.get(i)) else {
+            return;
+        };
+        let rules = Rules {
+            bounds: None,
+            ..Variant::Standard.apply(self.game.rules)
+        };
+        self.replay = Some(Replay::new(&Game::from_moves(rules, &record.moves)));
+        self.analysis = None;
+        self.library = None;
+        self.zoom_to_fit();
+    }
+
+    /// Keep the game just won in the archive (see `archive`).
+    fn archive_game(&self) {
+        let Some(winner) = self.game.winner else {
+            return;
+        };
+        archive::add(&archive::Entry {
+            date_ms: js_sys::Date::now(),
+            moves: self.game.moves.clone(),
+            winner,
+            players: [self.ctrl_black, self.ctrl_white],
+            rules: self.game.rules,
+        });
+    }
+
+    fn open_history(&mut self) {
+        self.history = Some(History::open());
+        self.dirty = true;
+    }
+
+    /// Redraw once the history's list arrives from IndexedDB.
+    fn poll_history(&mut self) {
+        if self.history.as_mut().is_some_and(History::poll) {
+            self.dirty = true;
+        }
+    }
+
+    /// Replay a finished game, under the rules it was played by, or download it.
+    fn use_history(&mut self, action: HistoryAction) {
+        let Some(entries) = self.history.as_ref().and_then(|h| h.entries.as_ref()) else {
+            return;
+        };
+        match action {
+            HistoryAction::Replay(i) => {
+                let Some(entry) = entries.get(i) else {
+                    return;
+                };
+                let rules = Rules {
+                    weights: self.game.rules.weights,
+                    ..entry.rules
+                };
+                self.replay = Some(Replay::new(&Game::from_moves(rules, &entry.moves)));
+                self.analysis = None;
+                self.history = None;
+                self.zoom_to_fit();
+            }
+            HistoryAction::Export(i) => {
+                if let Some(entry) = entries.get(i) {
+                    let _ = entry.export();
+                }
+            }
+        }
+    }
+
+    fn open_saves(&mut self) {
+        if self.tournament.is_none() {
+            self.saves = Some(saves::load(self.game.rules));
+            self.dirty = true;
+        }
+    }
+
+    /// Save the live game to a slot, load it from there, or empty it.
+    fn use_slot(&mut self, action: SlotAction) {
+        let Some(slots) = self.saves.as_mut() else {
+            return;
+        };
+        let window = window().unwrap();
+        match action {
+            SlotAction::Save(slot) => {
+                let current = slots[slot].as_ref().map_or_else(
+                    || tf(Msg::SaveDefaultName, &[&(slot + 1)]),
+                    |s| s.name.clone(),
+                );
+                let message = t(Msg::SaveNamePrompt);
+                let Ok(Some(name)) = window.prompt_with_message_and_default(&message, &current)
+                else {
+                    return;
+                };
+                let name = if name.trim().is_empty() { current } else { name };
+                slots[slot] = Some(Save::new(&name, js_sys::Date::now(), self.game.clone()));
+                saves::store(slots);
+                self.toast(t(Msg::GameSaved));
+            }
+            SlotAction::Load(slot) => {
+                let Some(game) = slots[slot].as_ref().map(|s| &s.game) else {
+                    return;
+                };
+                // The slot's rules, with this page's weights, which saves leave out.
+                let rules = Rules {
+                    weights: self.game.rules.weights,
+                    ..game.rules
+                };
+                let moves = game.moves.clone();
+                self.saves = None;
+                if !self.load_game(rules, moves) {
+                    self.toast(t(Msg::SaveUnplayable));
+                }
+            }
+            SlotAction::Delete(slot) => {
+                let Some(save) = &slots[slot] else {
+                    return;
+                };
+                let message = tf(Msg::DeleteSaveConfirm, &[&save.name]);
+                if window.confirm_with_message(&message) == Ok(true) {
+                    slots[slot] = None;
+                    saves::store(slots);
+                }
+            }
+        }
+    }
+
+    /// Download the live game as `gomoku.psq`, with how long each move took
+    /// when that is known.
+    fn save_psq(&mut self) {
+        let moves = &self.game.moves;
+        let ms: Vec<f64> = if self.move_times.len() == moves.len() {
+            let after = self.move_times.windows(2).map(|w| w[1] - w[0]);
+            std::iter::once(0.0).chain(after).collect()
+        } else {
+            Vec::new()
+        };
+        download_text("gomoku.psq", "text/plain", &psq::write(self.game.rules.bounds, moves, &ms));
+    }
+
+    /// Download the live game as `gomoku-replay.json`, with when each move
+    /// was played, to watch back at its own pace (see `schema::ReplayFile`).
+    fn save_replay(&mut self) {
+        if self.game.moves.is_empty() {
+            return;
+        }
+        let times = self.move_offsets();
+        let first = self.move_times.first().filter(|_| !times.is_empty());
+        let started = js_sys::Date::now() - first.map_or(0.0, |&t| now_ms() - t);
+        let players = [self.ctrl_black, self.ctrl_white];
+        let file = schema::ReplayFile::new(&self.game, started, times, players);
+        let Ok(json) = serde_json::to_string(&file) else {
+            return;
+        };
+        download_text("gomoku-replay.json", "application/json", &json);
+    }
+
+    /// Step through the game in a replay file at the pace it was played.
+    fn open_replay_file(&mut self, file: schema::ReplayFile) {
+        let rules = Rules {
+            weights: self.game.rules.weights,
+            ..file.rules
+        };
+        let mut game = Game::with_rules(rules);
+        if !file.moves.iter().all(|&p| game.play(p)) {
+            self.toast(t(Msg::PasteInvalid));
+            return;
+        }
+        let mut replay = Replay::new(&game).with_times(file.times);
+        replay.toggle_autoplay(now_ms());
+        self.replay = Some(replay);
+        self.analysis = None;
+        self.zoom_to_fit();
+    }
+
+    /// Put the live game's move list on the clipboard (see `notation`).
+    fn copy_moves(&mut self) {
+        let per_turn = usize::from(self.game.rules.stones_per_turn.max(1));
+        let text = notation::write(&self.game.moves, per_turn);
+        let _ = window().unwrap().navigator().clipboard().write_text(&text);
+        self.toast(t(Msg::MovesCopied));
+    }
+
+    /// Put the game's link on the clipboard.
+    fn copy_link(&mut self) {
+        self.sync_url();
+        let window = window().unwrap();
+        let Ok(href) = window.location().href() else {
+            return;
+        };
+        let _ = window.navigator().clipboard().write_text(&href);
+        self.toast(t(Msg::LinkCopied));
+    }
+}
+
+/// The camera framing `stones` with a cell to spare each way, at `px` a cell
+/// or less so neither side passes `max_px`: its centre, cell size and view size.
+fn framing(stones: Bounds, px: f64, max_px: f64) -> (f64, f64, f64, f64, f64) {
+    let cols = (stones.max.x - stones.min.x + 3) as f64;
+    let rows = (stones.max.y - stones.min.y + 3) as f64;
+    let px = px.min(max_px / cols).min(max_px / rows).floor().max(1.0);
+    let x = (stones.min.x + stones.max.x) as f64 / 2.0;
+    let y = (stones.min.y + stones.max.y) as f64 / 2.0;
+    (x, y, px, (cols * px).round(), (rows * px).round())
+}
+
+/// Download `text` as a file called `name`.
+fn download_text(name: &str, mime: &str, text: &str) {
+    let url = format!("data:{mime};charset=utf-8,{}", js_sys::encode_uri_component(text));
+    if let Some(link) = window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.create_element("a").ok())
+        .and_then(|a| a.dyn_into::<web_sys::HtmlAnchorElement>().ok())
+    {
+        link.set_href(&url);
+        link.set_download(name);
+        link.click();
+    }
+}
+
+/// A canvas of `w`×`h` pixels outside the page, and its 2D context.
+fn offscreen_canvas(w: f64, h: f64) -> Option<(HtmlCanvasElement, CanvasRenderingContext2d)> {
+    let doc = window()?.document()?;
+    let canvas = doc.create_element("canvas").ok()?.dyn_into::<HtmlCanvasElement>().ok()?;
+    canvas.set_width(w as u32);
+    canvas.set_height(h as u32);
+    let ctx = canvas.get_context("2d").ok()??;
+    Some((canvas, ctx.dyn_into().ok()?))
+}
+
+/// A HUD pill over `(x, y, w, h)` reading `text`: filled by whether it is
+/// an AI control, and outlined brightly if `is_current`.
+fn draw_pill(
+    ctx: &CanvasRenderingContext2d,
+    theme: &Theme,
+    (x, y0, w, pill_h): (f64, f64, f64, f64),
+    text: &str,
+    is_current: bool,
+    is_ai: bool,
+) {
+    // Fill indicates Human/AI (subtle)
+    ctx.set_fill_style_str(if is_ai { theme.pill_ai } else { theme.pill_human });
+    ctx.begin_path();
+    let r = pill_h / 2.0;
+    let x1 = x + w;
+    let y1 = y0 + pill_h;
+    ctx.move_to(x + r, y0);
+    ctx.line_to(x1 - r, y0);
+    let _ = ctx.arc(x1 - r, y0 + r, r, -std::f64::consts::FRAC_PI_2, 0.0);
+    ctx.line_to(x1, y1 - r);
+    let _ = ctx.arc(x1 - r, y1 - r, r, 0.0, std::f64::consts::FRAC_PI_2);
+    ctx.line_to(x + r, y1);
+    let _ = ctx.arc(x + r, y1 - r, r, std::f64::consts::FRAC_PI_2, std::f64::consts::PI);
+    ctx.line_to(x, y0 + r);
+    let _ = ctx.arc(x + r, y0 + r, r, std::f64::consts::PI, 3.0 * std::f64::consts::FRAC_PI_2);
+    ctx.close_path();
+    ctx.fill();
+
+    // Outline: bright if current turn, muted otherwise.
+    if is_current {
+        ctx.set_stroke_style_str("#38bdf8"); // highlight
+        ctx.set_line_width(2.0);
+    } else {
+        ctx.set_stroke_style_str(theme.pill_border);
+        ctx.set_line_width(1.0);
+    }
+    ctx.stroke();
+
+    ctx.set_fill_style_str(theme.text);
+    ctx.set_text_align("left");
+    ctx.set_text_baseline("alphabetic");
+    let _ = ctx.fill_text(text, x + 10.0, y0 + pill_h - 8.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_win_sees_a_four_left_for_a_two_stone_turn() {
+        let rules = Variant::Connect6.apply(Rules::default());
+        // White has four in a row; Black's second stone goes elsewhere.
+        let moves = [(0, 0), (0, 5), (1, 5), (10, 0), (10, 1), (2, 5), (3, 5), (20, 20)];
+        assert!(Game::from_legal_moves(rules, &moves).leaves_win(Pt::new(21, 21)));
+    }
+
+    #[test]
+    fn leaves_win_needs_a_one_stone_win_when_turns_are_one_stone() {
+        let three = [(0, 0), (0, 5), (1, 0), (1, 5), (10, 10), (2, 5)];
+        assert!(!Game::from_legal_moves(Rules::default(), &three).leaves_win(Pt::new(20, 20)));
+        let four = [(0, 0), (0, 5), (1, 0), (1, 5), (10, 10), (2, 5), (11, 11), (3, 5)];
+        assert!(Game::from_legal_moves(Rules::default(), &four).leaves_win(Pt::new(20, 20)));
     }
 }
@@ -0,0 +1,154 @@
+//! Monte Carlo tree search (UCT), the alternative to the alpha-beta backend.
+//!
+//! The tree only expands threat-pruned candidates. Playouts pick the best
+//! `score_point` among a few random frontier cells, which keeps them gomoku-like
+//! without a full scan per move, and playouts that run out of moves are scored
+//! by the static evaluation. The searching side's playout moves follow its `Style`.
+
+use crate::rng::Rng;
+use crate::threats;
+use crate::{Color, Game, Pt, Style};
+
+/// Iterations run per second of thinking time, roughly, in a browser.
+pub(crate) const ITERATIONS_PER_SEC: u32 = 2_500;
+const PLAYOUT_MOVES: u32 = 16;
+/// Frontier cells sampled per playout move.
+const PLAYOUT_SAMPLES: usize = 4;
+const EXPLORATION: f64 = 1.4;
+/// Visits a reply needs before it counts as part of the expected line.
+const MIN_PV_VISITS: f64 = 3.0;
+
+struct Node {
+    mv: Pt,
+    /// Who played `mv`; results are credited from their point of view.
+    mover: Color,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Candidates not yet expanded, most forcing last.
+    untried: Vec<Pt>,
+    visits: f64,
+    wins: f64,
+}
+
+impl Node {
+    fn new(mv: Pt, mover: Color, parent: Option<usize>, game: &Game) -> Self {
+        let untried = if game.winner.is_some() {
+            Vec::new()
+        } else {
+            threats::candidates(game)
+                .into_iter()
+                .rev()
+                .map(|c| c.p)
+                .collect()
+        };
+        Node {
+            mv,
+            mover,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0.0,
+            wins: 0.0,
+        }
+    }
+}
+
+/// The most visited root move after `iterations`, followed by the most visited
+/// reply to it and so on while replies are well explored. Empty if there is
+/// nothing to play.
+pub(crate) fn search(game: &Game, style: Style, iterations: u32, rng: &mut Rng) -> Vec<Pt> {
+    let root = Node::new(Pt::new(0, 0), game.player.other(), None, game);
+    match root.untried.as_slice() {
+        [] => return Vec::new(),
+        [only] => return vec![*only],
+        _ => {}
+    }
+    let mut rng = Rng::new(rng.next_u64());
+    let mut tree = vec![root];
+    for _ in 0..iterations {
+        let mut g = game.clone();
+        let mut node = 0;
+        // Select
+        while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+            node = best_child(&tree, node);
+            g.play(tree[node].mv);
+        }
+        // Expand
+        if let Some(p) = tree[node].untried.pop() {
+            let mover = g.player;
+            g.play(p);
+            tree.push(Node::new(p, mover, Some(node), &g));
+            let child = tree.len() - 1;
+            tree[node].children.push(child);
+            node = child;
+        }
+        // Simulate, then credit every node on the path.
+        let black = playout(&mut g, (game.player, style), &mut rng);
+        let mut at = Some(node);
+        while let Some(i) = at {
+            let n = &mut tree[i];
+            n.visits += 1.0;
+            n.wins += match n.mover {
+                Color::Black => black,
+                Color::White => 1.0 - black,
+            };
+            at = n.parent;
+        }
+    }
+    let mut line = Vec::new();
+    let mut node = 0;
+    while let Some(&i) = tree[node]
+        .children
+        .iter()
+        .max_by(|&&a, &&b| tree[a].visits.total_cmp(&tree[b].visits))
+    {
+        if !line.is_empty() && tree[i].visits < MIN_PV_VISITS {
+            break;
+        }
+        line.push(tree[i].mv);
+        node = i;
+    }
+    line
+}
+
+fn best_child(tree: &[Node], node: usize) -> usize {
+    let ln_n = tree[node].visits.max(1.0).ln();
+    let uct = |i: usize| {
+        let c = &tree[i];
+        c.wins / c.visits + EXPLORATION * (ln_n / c.visits).sqrt()
+    };
+    *tree[node]
+        .children
+        .iter()
+        .max_by(|&&a, &&b| uct(a).total_cmp(&uct(b)))
+        .unwrap()
+}
+
+/// Play on from `g`, with `styled`'s side choosing moves its way, and return
+/// Black's result in `[0, 1]`.
+fn playout(g: &mut Game, styled: (Color, Style), rng: &mut Rng) -> f64 {
+    for _ in 0..PLAYOUT_MOVES {
+        if g.winner.is_some() {
+            break;
+        }
+        let cells: Vec<Pt> = g.frontier.iter().copied().collect();
+        if cells.is_empty() {
+            break;
+        }
+        let style = if g.player == styled.0 {
+            styled.1
+        } else {
+            Style::Balanced
+        };
+        let p = (0..PLAYOUT_SAMPLES)
+            .map(|_| cells[rng.below(cells.len())])
+            .max_by_key(|&p| g.score_point_styled(p, g.player, style))
+            .unwrap();
+        g.play(p);
+    }
+    match g.winner {
+        Some(Color::Black) => 1.0,
+        Some(Color::White) => 0.0,
+        None => 0.5 + 0.5 * (g.evaluate() as f64 / 20_000.0).tanh(),
+    }
+}
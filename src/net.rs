@@ -0,0 +1,250 @@
+//! Online play: the remote-move protocol and its WebSocket relay transport.
+//!
+//! Any `Transport` carries the same text messages (see `rtc` for the
+//! peer-to-peer one). A WebSocket relay is expected to forward every text frame
+//! to the other clients in the same room; all game logic stays in the
+//! browsers. Messages are single lines:
+//!
+//! - `hello` — sent on connect; the side that already holds the game answers
+//!   with `sync` (see `Transport::answers_hello`).
+//! - `move <x> <y>` — a stone placed by the sender.
+//! - `reset <rules>` — the sender started a new game under `rules`.
+//! - `sync <rules> <moves>` — the sender's rules and full move list (see
+//!   `codec`); receivers adopt both, or refuse a move list that is not legal
+//!   under the rules.
+//!
+//! Rules go as the compact JSON `schema` writes for them, which has no spaces.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use web_sys::{MessageEvent, WebSocket};
+
+use crate::{codec, Pt, Rules};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum NetMsg {
+    Hello,
+    Move(Pt),
+    Reset(Rules),
+    Sync(Rules, Vec<Pt>),
+}
+
+impl NetMsg {
+    pub(crate) fn encode(&self) -> String {
+        match self {
+            NetMsg::Hello => "hello".to_string(),
+            NetMsg::Move(p) => format!("move {} {}", p.x, p.y),
+            NetMsg::Reset(rules) => format!("reset {}", rules_json(rules)),
+            NetMsg::Sync(rules, moves) => {
+                format!("sync {} {}", rules_json(rules), codec::encode_moves(moves))
+            }
+        }
+    }
+
+    pub(crate) fn parse(line: &str) -> Option<NetMsg> {
+        let mut parts = line.split_whitespace();
+        let msg = match parts.next()? {
+            "hello" => NetMsg::Hello,
+            "move" => {
+                let x = parts.next()?.parse().ok()?;
+                let y = parts.next()?.parse().ok()?;
+                NetMsg::Move(Pt::new(x, y))
+            }
+            "reset" => NetMsg::Reset(serde_json::from_str(parts.next()?).ok()?),
+            "sync" => {
+                let rules = serde_json::from_str(parts.next()?).ok()?;
+                NetMsg::Sync(rules, codec::decode_moves(parts.next().unwrap_or(""))?)
+            }
+            _ => return None,
+        };
+        parts.next().is_none().then_some(msg)
+    }
+}
+
+fn rules_json(rules: &Rules) -> String {
+    serde_json::to_string(rules).unwrap_or_default()
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum NetStatus {
+    Connecting,
+    Open,
+    Closed,
+}
+
+/// Inbox and status shared between a transport's JS callbacks and the rAF loop,
+/// so handlers never run while `App` is borrowed.
+#[derive(Clone)]
+pub(crate) struct Link {
+    inbox: Rc<RefCell<VecDeque<NetMsg>>>,
+    status: Rc<Cell<NetStatus>>,
+}
+
+impl Link {
+    pub(crate) fn new() -> Link {
+        Link {
+            inbox: Rc::new(RefCell::new(VecDeque::new())),
+            status: Rc::new(Cell::new(NetStatus::Connecting)),
+        }
+    }
+
+    pub(crate) fn receive(&self, text: &str) {
+        if let Some(msg) = NetMsg::parse(text) {
+            self.inbox.borrow_mut().push_back(msg);
+        }
+    }
+
+    pub(crate) fn set_status(&self, status: NetStatus) {
+        self.status.set(status);
+    }
+}
+
+/// Signalling text to hand the other player by hand before a direct
+/// connection can open (see `rtc`).
+pub(crate) struct Pairing {
+    /// The text to copy across.
+    pub(crate) code: String,
+    /// What a QR code of it holds: a link that joins with it, or the code.
+    pub(crate) qr_text: String,
+    /// Whether the other player sends a code back (see `Transport::reply`).
+    pub(crate) wants_reply: bool,
+}
+
+/// A connection to the other player.
+pub(crate) trait Transport {
+    fn link(&self) -> &Link;
+
+    /// Write one protocol line; only called while `Open`.
+    fn send_text(&self, text: &str);
+
+    /// Whether a peer's `hello` gets our `sync`. Only one side of a pair may
+    /// answer, or each would adopt the other's game and the two would swap.
+    fn answers_hello(&self) -> bool {
+        true
+    }
+
+    /// Signalling waiting to be shown to the player, taken once.
+    fn take_pairing(&self) -> Option<Pairing> {
+        None
+    }
+
+    /// The other player's code for a `Pairing` that wants one; empty gives up.
+    fn reply(&self, _code: &str) {}
+
+    fn status(&self) -> NetStatus {
+        self.link().status.get()
+    }
+
+    /// Send if connected; messages while connecting are dropped (the `sync`
+    /// exchanged on connect brings peers up to date).
+    fn send(&self, msg: &NetMsg) {
+        if self.status() == NetStatus::Open {
+            self.send_text(&msg.encode());
+        }
+    }
+
+    fn drain(&self) -> Vec<NetMsg> {
+        self.link().inbox.borrow_mut().drain(..).collect()
+    }
+}
+
+/// A WebSocket relay connection.
+pub(crate) struct WsTransport {
+    ws: WebSocket,
+    link: Link,
+    _on_open: Closure<dyn FnMut()>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_close: Closure<dyn FnMut()>,
+}
+
+impl WsTransport {
+    pub(crate) fn connect(url: &str) -> Result<WsTransport, JsValue> {
+        let ws = WebSocket::new(url)?;
+        let link = Link::new();
+
+        let on_open = {
+            let ws = ws.clone();
+            let link = link.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                link.set_status(NetStatus::Open);
+                let _ = ws.send_with_str(&NetMsg::Hello.encode());
+            })
+        };
+        let on_message = {
+            let link = link.clone();
+            Closure::<dyn FnMut(MessageEvent)>::new(move |e: MessageEvent| {
+                if let Some(text) = e.data().as_string() {
+                    link.receive(&text);
+                }
+            })
+        };
+        let on_close = {
+            let link = link.clone();
+            Closure::<dyn FnMut()>::new(move || link.set_status(NetStatus::Closed))
+        };
+        ws.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        ws.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        ws.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        ws.set_onerror(Some(on_close.as_ref().unchecked_ref()));
+
+        Ok(WsTransport {
+            ws,
+            link,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_close: on_close,
+        })
+    }
+}
+
+impl Transport for WsTransport {
+    fn link(&self) -> &Link {
+        &self.link
+    }
+
+    fn send_text(&self, text: &str) {
+        let _ = self.ws.send_with_str(text);
+    }
+}
+
+impl Drop for WsTransport {
+    fn drop(&mut self) {
+        self.ws.set_onopen(None);
+        self.ws.set_onmessage(None);
+        self.ws.set_onclose(None);
+        self.ws.set_onerror(None);
+        let _ = self.ws.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bounds, Variant};
+
+    #[test]
+    fn messages_round_trip_with_their_rules() {
+        let rules = Rules {
+            bounds: Some(Bounds::square(15)),
+            ..Variant::Connect6.apply(Rules::default())
+        };
+        for msg in [
+            NetMsg::Hello,
+            NetMsg::Move(Pt::new(-3, 7)),
+            NetMsg::Reset(rules),
+            NetMsg::Sync(rules, vec![Pt::new(0, 0), Pt::new(1, -1)]),
+            NetMsg::Sync(Rules::default(), Vec::new()),
+        ] {
+            assert_eq!(NetMsg::parse(&msg.encode()), Some(msg));
+        }
+    }
+
+    #[test]
+    fn sync_without_rules_is_refused() {
+        assert_eq!(NetMsg::parse("reset"), None);
+        assert_eq!(NetMsg::parse("sync"), None);
+    }
+}
@@ -0,0 +1,81 @@
+//! A move list people can read, for pasting into forums or issues: a move a
+//! line as `12. B +3 -1`, its number, its side and its cell counted from the
+//! first stone, right and down being positive, so the same game reads the
+//! same wherever it was played on the infinite board.
+//!
+//! A `#` line at the top says what the list is; reading one skips `#` lines,
+//! and takes the number and side as optional, so a hand-typed `0 0` / `1 -1`
+//! list loads too. The first stone goes on the origin.
+
+use crate::Pt;
+
+const HEADER: &str = "# gomoku move list: cells from the first stone, x right, y down";
+
+/// `moves` as a move list, Black moving first and `stones_per_turn` stones a
+/// turn after Black's first.
+pub(crate) fn write(moves: &[Pt], stones_per_turn: usize) -> String {
+    let first = moves.first().copied().unwrap_or(Pt::new(0, 0));
+    let mut out = format!("{HEADER}\n");
+    for (i, p) in moves.iter().enumerate() {
+        let turn = i.div_ceil(stones_per_turn);
+        let side = if turn.is_multiple_of(2) { 'B' } else { 'W' };
+        out += &format!("{}. {side} {:+} {:+}\n", i + 1, p.x - first.x, p.y - first.y);
+    }
+    out
+}
+
+/// The moves in a move list, or `None` if a line is not a move or there are
+/// none.
+pub(crate) fn parse(text: &str) -> Option<Vec<Pt>> {
+    let moves = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|line| {
+            let mut fields = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|f| !f.is_empty())
+                .peekable();
+            if fields.peek().is_some_and(|f| f.ends_with('.')) {
+                fields.next();
+            }
+            if fields.peek().is_some_and(|f| matches!(*f, "B" | "W" | "b" | "w")) {
+                fields.next();
+            }
+            let mut n = || fields.next()?.replace('−', "-").parse::<i32>().ok();
+            let p = Pt::new(n()?, n()?);
+            fields.next().is_none().then_some(p)
+        })
+        .collect::<Option<Vec<Pt>>>()?;
+    let first = *moves.first()?;
+    Some(moves.iter().map(|p| p.add(-first.x, -first.y)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_round_trip_from_the_first_stone() {
+        let moves = [Pt::new(5, 5), Pt::new(6, 4), Pt::new(3, 7), Pt::new(5, 6)];
+        let text = write(&moves, 1);
+        assert!(text.contains("\n1. B +0 +0\n2. W +1 -1\n3. B -2 +2\n4. W +0 +1\n"));
+        let back: Vec<Pt> = moves.iter().map(|p| p.add(-5, -5)).collect();
+        assert_eq!(parse(&text), Some(back));
+    }
+
+    #[test]
+    fn sides_follow_two_stone_turns() {
+        let moves = [Pt::new(0, 0), Pt::new(-1, 0), Pt::new(0, -1), Pt::new(1, 1)];
+        let text = write(&moves, 2);
+        assert!(text.contains("\n1. B +0 +0\n2. W -1 +0\n3. W +0 -1\n4. B +1 +1\n"));
+        assert_eq!(parse(&text), Some(moves.to_vec()));
+    }
+
+    #[test]
+    fn bare_pairs_load_and_other_text_does_not() {
+        assert_eq!(parse("0 0\n1, −1\n"), Some(vec![Pt::new(0, 0), Pt::new(1, -1)]));
+        assert_eq!(parse("# nothing\n"), None);
+        assert_eq!(parse("1. B +0 +0 extra"), None);
+    }
+}
@@ -0,0 +1,213 @@
+//! Line shapes read from a precomputed table instead of walking rays.
+//!
+//! The cells along one direction around a point, up to `win_len - 1` either
+//! side, are encoded base 3 (empty, own, blocked) into an index. The table for
+//! each win length maps every such window, with an own stone on the centre, to
+//! the shape that stone makes. Shapes are defined by what one more stone would
+//! do, so broken lines such as `X·XX` are read as threes just like `XXX`:
+//!
+//! - a five is `win_len` in a row through the centre;
+//! - a four has one empty cell completing a five, an open four two or more;
+//! - a three can become a four with one more stone, an open three an open four;
+//! - twos and open ones likewise lead to threes and open twos.
+//!
+//! With the `simd` feature, on a wasm build with `simd128` enabled, the indices
+//! for all four directions and both players are built in one vector.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{Color, Game, Pt, DIRS};
+
+/// What one stone makes along one line, weakest first.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub(crate) enum Shape {
+    None,
+    OpenOne,
+    Two,
+    OpenTwo,
+    Three,
+    OpenThree,
+    Four,
+    OpenFour,
+    Five,
+}
+
+/// Cells read either side of the point, at most. Longer win lengths are judged
+/// within this window, which keeps their tables small.
+const MAX_RADIUS: usize = 5;
+
+const EMPTY: u16 = 0;
+const OWN: u16 = 1;
+const BLOCKED: u16 = 2;
+
+struct Table {
+    radius: usize,
+    shapes: Vec<Shape>,
+}
+
+thread_local! {
+    /// Built on first use, per win length.
+    static TABLES: RefCell<Vec<Option<Rc<Table>>>> = const { RefCell::new(Vec::new()) };
+}
+
+fn table(win_len: i32) -> Rc<Table> {
+    TABLES.with(|tables| {
+        let mut tables = tables.borrow_mut();
+        let i = win_len as usize;
+        if tables.len() <= i {
+            tables.resize(i + 1, None);
+        }
+        tables[i]
+            .get_or_insert_with(|| Rc::new(Table::build(win_len as usize)))
+            .clone()
+    })
+}
+
+/// What `who` and the opponent would each make by playing empty `p`, along
+/// each of `DIRS`.
+pub(crate) fn shapes(game: &Game, p: Pt, who: Color) -> [(Shape, Shape); 4] {
+    let table = table(game.rules.win_len);
+    let r = table.radius as i32;
+    // Digits run from the far end on one side to the far end on the other,
+    // skipping the centre: `who`'s view along each direction, then the
+    // opponent's.
+    let mut windows = Windows::new();
+    for k in (-r..=r).filter(|&k| k != 0) {
+        let mut row = [0; 8];
+        for (i, d) in DIRS.iter().enumerate() {
+            let q = p.add(d.x * k, d.y * k);
+            let (m, t) = match game.color_at(q) {
+                Some(&c) if c == who => (OWN, BLOCKED),
+                Some(_) => (BLOCKED, OWN),
+                None if game.in_bounds(q) => (EMPTY, EMPTY),
+                None => (BLOCKED, BLOCKED),
+            };
+            (row[i], row[i + 4]) = (m, t);
+        }
+        windows.push(row);
+    }
+    let idx = windows.indices();
+    std::array::from_fn(|i| {
+        let at = |j: usize| table.shapes[idx[j] as usize];
+        (at(i), at(i + 4))
+    })
+}
+
+/// Eight window indices built up one base-3 digit at a time. The largest
+/// window, `3^(2 * MAX_RADIUS)` cells, still fits a `u16`.
+#[cfg(not(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128")))]
+struct Windows([u16; 8]);
+
+#[cfg(not(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128")))]
+impl Windows {
+    fn new() -> Self {
+        Windows([0; 8])
+    }
+
+    fn push(&mut self, digits: [u16; 8]) {
+        for (w, d) in self.0.iter_mut().zip(digits) {
+            *w = *w * 3 + d;
+        }
+    }
+
+    fn indices(&self) -> [u16; 8] {
+        self.0
+    }
+}
+
+/// The same, one simd128 lane per window.
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+struct Windows(core::arch::wasm32::v128);
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+impl Windows {
+    fn new() -> Self {
+        Windows(core::arch::wasm32::u16x8_splat(0))
+    }
+
+    fn push(&mut self, d: [u16; 8]) {
+        use core::arch::wasm32::*;
+        let digits = u16x8(d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7]);
+        self.0 = i16x8_add(i16x8_mul(self.0, u16x8_splat(3)), digits);
+    }
+
+    fn indices(&self) -> [u16; 8] {
+        use core::arch::wasm32::u16x8_extract_lane as lane;
+        let v = self.0;
+        [
+            lane::<0>(v),
+            lane::<1>(v),
+            lane::<2>(v),
+            lane::<3>(v),
+            lane::<4>(v),
+            lane::<5>(v),
+            lane::<6>(v),
+            lane::<7>(v),
+        ]
+    }
+}
+
+impl Table {
+    fn build(win_len: usize) -> Table {
+        let radius = (win_len - 1).min(MAX_RADIUS);
+        let cells = 2 * radius;
+        let size = 3usize.pow(cells as u32);
+        // A window's shape depends on the windows with one more own stone, so
+        // fill the table from the fullest windows down.
+        let mut order: Vec<usize> = (0..size).collect();
+        order.sort_by_cached_key(|&i| {
+            std::cmp::Reverse(digits(i, cells).filter(|&d| d == OWN).count())
+        });
+        let mut shapes = vec![Shape::None; size];
+        for idx in order {
+            shapes[idx] = Self::shape(idx, cells, win_len, &shapes);
+        }
+        Table { radius, shapes }
+    }
+
+    /// The shape of window `idx`, given the shapes of every fuller window.
+    fn shape(idx: usize, cells: usize, win_len: usize, shapes: &[Shape]) -> Shape {
+        let line: Vec<u16> = digits(idx, cells).collect();
+        let centre = cells / 2;
+        // Own stones running out from the centre (digit `centre - 1` is adjacent
+        // on one side, `centre` on the other).
+        let before = line[..centre]
+            .iter()
+            .rev()
+            .take_while(|&&d| d == OWN)
+            .count();
+        let after = line[centre..].iter().take_while(|&&d| d == OWN).count();
+        if before + 1 + after >= win_len {
+            return Shape::Five;
+        }
+
+        let mut completions = 0;
+        let mut best = Shape::None;
+        for (pos, _) in line.iter().enumerate().filter(|&(_, &d)| d == EMPTY) {
+            // Digit `pos` counts 3^(cells - 1 - pos); make it own.
+            let next = shapes[idx + 3usize.pow((cells - 1 - pos) as u32)];
+            if next == Shape::Five {
+                completions += 1;
+            }
+            best = best.max(next);
+        }
+        match (completions, best) {
+            (2.., _) => Shape::OpenFour,
+            (1, _) => Shape::Four,
+            (_, Shape::OpenFour) => Shape::OpenThree,
+            (_, Shape::Four) => Shape::Three,
+            (_, Shape::OpenThree) => Shape::OpenTwo,
+            (_, Shape::Three) => Shape::Two,
+            (_, Shape::OpenTwo) => Shape::OpenOne,
+            _ => Shape::None,
+        }
+    }
+}
+
+/// The base-3 digits of `idx`, most significant first.
+fn digits(idx: usize, cells: usize) -> impl Iterator<Item = u16> {
+    (0..cells)
+        .rev()
+        .map(move |k| (idx / 3usize.pow(k as u32) % 3) as u16)
+}
@@ -0,0 +1,102 @@
+//! Gomocup's `.psq` game files, so games from its tournament archives can be
+//! loaded here, and games from here handed to engine developers.
+//!
+//! A file starts with a header such as `Piskvorky 20x20, 11:11, 0`, whose
+//! board size is all that is read, and has a move a line as `x,y,ms`: the
+//! cell counted from 1 at the top left, and the time the move took. Lines
+//! after the moves name the engines and the result, and are skipped. The
+//! board is put on a square `Bounds` of its size, so its cells keep their
+//! places around the origin.
+
+use crate::{Bounds, Pt};
+
+/// The board size of a file whose header does not give one.
+const DEFAULT_SIZE: i32 = 20;
+
+/// The board size and moves of the game in `text`, or `None` if it is not a
+/// `.psq` file or a move is off its board.
+pub(crate) fn parse(text: &str) -> Option<(i32, Vec<Pt>)> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    let header = lines.next()?.strip_prefix("Piskvorky")?;
+    let size = header
+        .split(',')
+        .next()
+        .and_then(|dims| dims.trim().split_once('x'))
+        .and_then(|(w, h)| Some(w.parse::<i32>().ok()?.max(h.parse().ok()?)))
+        .unwrap_or(DEFAULT_SIZE);
+    let origin = Bounds::square(size).min;
+    let mut moves = Vec::new();
+    for line in lines {
+        let mut fields = line.split(',').map(|f| f.trim().parse::<i32>());
+        let (Some(Ok(x)), Some(Ok(y))) = (fields.next(), fields.next()) else {
+            break;
+        };
+        if !(1..=size).contains(&x) || !(1..=size).contains(&y) {
+            return None;
+        }
+        moves.push(origin.add(x - 1, y - 1));
+    }
+    Some((size, moves))
+}
+
+/// `moves` as a `.psq` file, each taking `ms[i]` (0 where not known), on
+/// `board`, or for an infinite board on the smallest square of at least the
+/// usual size that holds them all, centred on them.
+pub(crate) fn write(board: Option<Bounds>, moves: &[Pt], ms: &[f64]) -> String {
+    let (size, origin) = match board {
+        Some(b) => (b.size(), b.min),
+        None => {
+            let (lo, hi) = moves.iter().fold(
+                (Pt::new(i32::MAX, i32::MAX), Pt::new(i32::MIN, i32::MIN)),
+                |(lo, hi), p| {
+                    (Pt::new(lo.x.min(p.x), lo.y.min(p.y)), Pt::new(hi.x.max(p.x), hi.y.max(p.y)))
+                },
+            );
+            if moves.is_empty() {
+                (DEFAULT_SIZE, Bounds::square(DEFAULT_SIZE).min)
+            } else {
+                let (w, h) = (hi.x - lo.x + 1, hi.y - lo.y + 1);
+                let size = w.max(h).max(DEFAULT_SIZE);
+                (size, lo.add(-(size - w) / 2, -(size - h) / 2))
+            }
+        }
+    };
+    let centre = size / 2 + 1;
+    let mut out = format!("Piskvorky {size}x{size}, {centre}:{centre}, 0\n");
+    for (i, p) in moves.iter().enumerate() {
+        let took = ms.get(i).map_or(0, |&t| t.max(0.0).round() as u64);
+        out += &format!("{},{},{took}\n", p.x - origin.x + 1, p.y - origin.y + 1);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn games_round_trip() {
+        let board = Bounds::square(15);
+        let moves = vec![Pt::new(0, 0), Pt::new(1, -1), Pt::new(-7, 7), Pt::new(7, -7)];
+        let text = write(Some(board), &moves, &[1200.0, 340.0]);
+        assert_eq!(parse(&text), Some((15, moves.clone())));
+        let (size, back) = parse(&text).unwrap();
+        assert_eq!(write(Some(Bounds::square(size)), &back, &[1200.0, 340.0]), text);
+    }
+
+    #[test]
+    fn corners_count_from_one_at_the_top_left() {
+        let board = Bounds::square(15);
+        let text = "Piskvorky 15x15, 8:8, 0\n1,1,0\n15,15,0\n8,8,0\nengine\n-1\n";
+        let moves = vec![board.min, board.max, Pt::new(0, 0)];
+        assert_eq!(parse(text), Some((15, moves.clone())));
+        assert!(write(Some(board), &moves, &[]).ends_with("\n1,1,0\n15,15,0\n8,8,0\n"));
+    }
+
+    #[test]
+    fn moves_off_the_board_are_refused() {
+        assert_eq!(parse("Piskvorky 15x15, 8:8, 0\n16,1,0\n"), None);
+        assert_eq!(parse("Piskvorky 15x15, 8:8, 0\n0,1,0\n"), None);
+        assert_eq!(parse("15,1,0\n"), None);
+    }
+}
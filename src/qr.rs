@@ -0,0 +1,508 @@
+//! QR codes for the share overlay: enough of ISO/IEC 18004 to turn a game
+//! link into modules on the canvas. Byte mode only, at error correction level
+//! M, or L when the link is too long for M, in the smallest version it fits.
+
+/// Error correction codewords per block, and blocks, by version (index 0 is
+/// unused), at levels L and M.
+const ECC_PER_BLOCK: [[u8; 41]; 2] = [
+    [
+        0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28,
+        28, 30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26,
+        28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    ],
+];
+const BLOCKS: [[u8; 41]; 2] = [
+    [
+        0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12,
+        13, 14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+    ],
+    [
+        0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21,
+        23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+    ],
+];
+
+#[derive(Clone, Copy)]
+enum Level {
+    L,
+    M,
+}
+
+impl Level {
+    /// The level's two bits in the format information.
+    fn format_bits(self) -> u32 {
+        match self {
+            Level::L => 1,
+            Level::M => 0,
+        }
+    }
+}
+
+pub(crate) struct Qr {
+    /// Modules a side, quiet zone not included.
+    pub(crate) size: i32,
+    modules: Vec<bool>,
+}
+
+impl Qr {
+    pub(crate) fn dark(&self, x: i32, y: i32) -> bool {
+        self.modules[(y * self.size + x) as usize]
+    }
+}
+
+/// `data` as a QR code, or `None` when it is too long for any version.
+pub(crate) fn encode(data: &[u8]) -> Option<Qr> {
+    let (version, level) = [Level::M, Level::L].into_iter().find_map(|level| {
+        (1..=40)
+            .find(|&v| 4 + count_bits(v) + 8 * data.len() <= 8 * data_codewords(v, level))
+            .map(|v| (v, level))
+    })?;
+
+    let mut bits = Vec::new();
+    push_bits(&mut bits, 0b0100, 4);
+    push_bits(&mut bits, data.len() as u32, count_bits(version));
+    for &b in data {
+        push_bits(&mut bits, b as u32, 8);
+    }
+    let capacity = 8 * data_codewords(version, level);
+    let end = (capacity - bits.len()).min(4);
+    push_bits(&mut bits, 0, end);
+    let pad = (8 - bits.len() % 8) % 8;
+    push_bits(&mut bits, 0, pad);
+    let mut codewords: Vec<u8> = bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, &b| acc << 1 | b as u8))
+        .collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() * 8 >= capacity {
+            break;
+        }
+        codewords.push(pad);
+    }
+
+    let mut qr = Builder::new(version);
+    qr.draw_function_patterns(version, level);
+    qr.draw_codewords(&add_ecc(&codewords, version, level));
+    let mask = (0..8)
+        .min_by_key(|&mask| {
+            qr.apply_mask(mask);
+            qr.draw_format(level, mask);
+            let penalty = qr.penalty();
+            qr.apply_mask(mask);
+            penalty
+        })
+        .unwrap_or(0);
+    qr.apply_mask(mask);
+    qr.draw_format(level, mask);
+    Some(Qr {
+        size: qr.size,
+        modules: qr.modules,
+    })
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: u32, len: usize) {
+    bits.extend((0..len).rev().map(|i| value >> i & 1 == 1));
+}
+
+/// Bits the byte count takes in `version`.
+fn count_bits(version: usize) -> usize {
+    if version <= 9 {
+        8
+    } else {
+        16
+    }
+}
+
+/// Modules left for codewords in `version` once the function patterns are drawn.
+fn raw_modules(version: usize) -> usize {
+    let mut n = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let align = version / 7 + 2;
+        n -= (25 * align - 10) * align - 55;
+        if version >= 7 {
+            n -= 36;
+        }
+    }
+    n
+}
+
+fn data_codewords(version: usize, level: Level) -> usize {
+    let l = level as usize;
+    raw_modules(version) / 8 - ECC_PER_BLOCK[l][version] as usize * BLOCKS[l][version] as usize
+}
+
+/// Split `data` into the version's blocks, give each its Reed–Solomon
+/// codewords, and interleave them all.
+fn add_ecc(data: &[u8], version: usize, level: Level) -> Vec<u8> {
+    let l = level as usize;
+    let blocks = BLOCKS[l][version] as usize;
+    let ecc_len = ECC_PER_BLOCK[l][version] as usize;
+    let raw = raw_modules(version) / 8;
+    let short_blocks = blocks - raw % blocks;
+    let short_len = raw / blocks;
+    let divisor = rs_divisor(ecc_len);
+
+    let mut rest = data;
+    let filled: Vec<Vec<u8>> = (0..blocks)
+        .map(|i| {
+            let len = short_len - ecc_len + usize::from(i >= short_blocks);
+            let (block, tail) = rest.split_at(len);
+            rest = tail;
+            let mut out = block.to_vec();
+            // A placeholder, so every block lines up for the interleaving.
+            if i < short_blocks {
+                out.push(0);
+            }
+            out.extend(rs_remainder(block, &divisor));
+            out
+        })
+        .collect();
+
+    let mut out = Vec::with_capacity(raw);
+    for i in 0..=short_len {
+        for (j, block) in filled.iter().enumerate() {
+            if i != short_len - ecc_len || j >= short_blocks {
+                out.push(block[i]);
+            }
+        }
+    }
+    out
+}
+
+/// Multiply in GF(2⁸) modulo x⁸ + x⁴ + x³ + x² + 1.
+fn gf_mul(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= (y as u32 >> i & 1) * x as u32;
+    }
+    z as u8
+}
+
+/// The generator polynomial of `degree`, leading coefficient left out.
+fn rs_divisor(degree: usize) -> Vec<u8> {
+    let mut out = vec![0; degree];
+    out[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            out[j] = gf_mul(out[j], root);
+            if j + 1 < degree {
+                out[j] ^= out[j + 1];
+            }
+        }
+        root = gf_mul(root, 0x02);
+    }
+    out
+}
+
+fn rs_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut out = vec![0; divisor.len()];
+    for &b in data {
+        let factor = b ^ out.remove(0);
+        out.push(0);
+        for (x, &y) in out.iter_mut().zip(divisor) {
+            *x ^= gf_mul(y, factor);
+        }
+    }
+    out
+}
+
+/// A code being drawn: its modules, and which of them are function patterns
+/// that data and masks leave alone.
+struct Builder {
+    size: i32,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl Builder {
+    fn new(version: usize) -> Builder {
+        let size = version as i32 * 4 + 17;
+        let n = (size * size) as usize;
+        Builder {
+            size,
+            modules: vec![false; n],
+            function: vec![false; n],
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y * self.size + x) as usize
+    }
+
+    fn set_function(&mut self, x: i32, y: i32, dark: bool) {
+        let i = self.index(x, y);
+        self.modules[i] = dark;
+        self.function[i] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize, level: Level) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4..=4i32 {
+                for dx in -4..=4i32 {
+                    let (x, y) = (cx + dx, cy + dy);
+                    if (0..size).contains(&x) && (0..size).contains(&y) {
+                        let ring = i32::max(dx.abs(), dy.abs());
+                        self.set_function(x, y, ring != 2 && ring != 4);
+                    }
+                }
+            }
+        }
+        let align = alignment_positions(version, size);
+        let last = align.len().saturating_sub(1);
+        for (i, &cx) in align.iter().enumerate() {
+            for (j, &cy) in align.iter().enumerate() {
+                // Not where the finder patterns are.
+                if (i, j) == (0, 0) || (i, j) == (0, last) || (i, j) == (last, 0) {
+                    continue;
+                }
+                for dy in -2..=2i32 {
+                    for dx in -2..=2i32 {
+                        let ring = i32::max(dx.abs(), dy.abs());
+                        self.set_function(cx + dx, cy + dy, ring != 1);
+                    }
+                }
+            }
+        }
+        // Reserve the format modules; the mask chosen fills them in later.
+        self.draw_format(level, 0);
+        if version >= 7 {
+            let v = version as u32;
+            let mut rem = v;
+            for _ in 0..12 {
+                rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+            }
+            let bits = v << 12 | rem;
+            for i in 0..18 {
+                let dark = bits >> i & 1 == 1;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                self.set_function(a, b, dark);
+                self.set_function(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format(&mut self, level: Level, mask: u32) {
+        let data = level.format_bits() << 3 | mask;
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = (data << 10 | rem) ^ 0x5412;
+        let bit = |i: i32| bits >> i & 1 == 1;
+        let size = self.size;
+        for i in 0..6 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    /// Lay `codewords` out in the zigzag of two-module columns, from the
+    /// bottom right corner.
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vert in 0..size {
+                let y = if upward { size - 1 - vert } else { vert };
+                for x in [right, right - 1] {
+                    let at = self.index(x, y);
+                    if !self.function[at] && i < codewords.len() * 8 {
+                        self.modules[at] = codewords[i / 8] >> (7 - i % 8) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    /// Flip the data modules `mask` picks; doing it twice undoes it.
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let flip = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let at = self.index(x, y);
+                self.modules[at] ^= flip && !self.function[at];
+            }
+        }
+    }
+
+    /// How hard the code is to scan: long runs, 2×2 blocks, finder lookalikes
+    /// and an uneven share of dark modules all count against it.
+    fn penalty(&self) -> u32 {
+        let size = self.size;
+        let at = |x: i32, y: i32| self.modules[self.index(x, y)];
+        let mut penalty = 0;
+        let lines = (0..size).flat_map(|i| {
+            [
+                (0..size).map(|j| at(j, i)).collect::<Vec<_>>(),
+                (0..size).map(|j| at(i, j)).collect::<Vec<_>>(),
+            ]
+        });
+        const FINDER: [bool; 11] = [
+            true, false, true, true, true, false, true, false, false, false, false,
+        ];
+        for line in lines {
+            for run in line.chunk_by(|a, b| a == b) {
+                if run.len() >= 5 {
+                    penalty += run.len() as u32 - 2;
+                }
+            }
+            for window in line.windows(11) {
+                if window == FINDER || window.iter().eq(FINDER.iter().rev()) {
+                    penalty += 40;
+                }
+            }
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let c = at(x, y);
+                if c == at(x + 1, y) && c == at(x, y + 1) && c == at(x + 1, y + 1) {
+                    penalty += 3;
+                }
+            }
+        }
+        let total = (size * size) as u32;
+        let dark = self.modules.iter().filter(|&&d| d).count() as u32;
+        let k = (dark * 20).abs_diff(total * 10).div_ceil(total).saturating_sub(1);
+        penalty + k * 10
+    }
+}
+
+/// Centres of the alignment patterns along each axis, from the top left.
+fn alignment_positions(version: usize, size: i32) -> Vec<i32> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let n = version as i32 / 7 + 2;
+    let step = (version as i32 * 8 + n * 3 + 5) / (n * 4 - 4) * 2;
+    let mut out: Vec<i32> = (0..n - 1).map(|i| size - 7 - i * step).collect();
+    out.push(6);
+    out.reverse();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Level M's format information by mask, from the standard's table.
+    const FORMAT_M: [u32; 8] = [
+        0b101010000010010,
+        0b101000100100101,
+        0b101111001111100,
+        0b101101101001011,
+        0b100010111111001,
+        0b100000011001110,
+        0b100111110010111,
+        0b100101010100000,
+    ];
+
+    #[test]
+    fn reed_solomon_matches_the_worked_example() {
+        // "HELLO WORLD" at 1-M, the example most QR write-ups work through.
+        let data = [32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17];
+        let ecc = [196, 35, 39, 119, 235, 215, 231, 226, 93, 23];
+        assert_eq!(rs_remainder(&data, &rs_divisor(10)), ecc);
+    }
+
+    #[test]
+    fn a_short_link_reads_back_as_version_1_m() {
+        let qr = encode(b"x4d3.io/#m=AA").unwrap();
+        assert_eq!(qr.size, 21);
+        let bit = |(x, y): (i32, i32)| u32::from(qr.dark(x, y));
+        let read = |cells: Vec<(i32, i32)>| {
+            cells.into_iter().enumerate().fold(0, |acc, (i, at)| acc | bit(at) << i)
+        };
+        // Both copies of the format information, bit 0 first.
+        let first = (0..6)
+            .map(|i| (8, i))
+            .chain([(8, 7), (8, 8), (7, 8)])
+            .chain((9..15).map(|i| (14 - i, 8)))
+            .collect();
+        let second = (0..8).map(|i| (20 - i, 8)).chain((8..15).map(|i| (8, 6 + i))).collect();
+        let format = read(first);
+        assert_eq!(read(second), format);
+        let mask = FORMAT_M.iter().position(|&f| f == format).expect("level M") as i32;
+        assert!(qr.dark(8, 13), "the dark module");
+
+        // Unmask the data modules in their zigzag order, and regroup them as bytes.
+        let function = |x: i32, y: i32| {
+            x == 6 || y == 6 || (x < 9 && y < 9) || (x >= 13 && y < 9) || (x < 9 && y >= 13)
+        };
+        let flipped = |x: i32, y: i32| match mask {
+            0 => (x + y) % 2 == 0,
+            1 => y % 2 == 0,
+            2 => x % 3 == 0,
+            3 => (x + y) % 3 == 0,
+            4 => (y / 2 + x / 3) % 2 == 0,
+            5 => (x * y) % 2 + (x * y) % 3 == 0,
+            6 => ((x * y) % 2 + (x * y) % 3) % 2 == 0,
+            _ => ((x + y) % 2 + (x * y) % 3) % 2 == 0,
+        };
+        let mut bits = Vec::new();
+        let (mut right, mut upward) = (20, true);
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..21 {
+                let y = if upward { 20 - vert } else { vert };
+                for x in [right, right - 1] {
+                    if !function(x, y) {
+                        bits.push(qr.dark(x, y) ^ flipped(x, y));
+                    }
+                }
+            }
+            upward = !upward;
+            right -= 2;
+        }
+        let codewords: Vec<u8> = bits
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, &b| acc << 1 | u8::from(b)))
+            .collect();
+        // Byte mode, 13 bytes, the link, the terminator and padding.
+        let data = [
+            0x40, 0xD7, 0x83, 0x46, 0x43, 0x32, 0xE6, 0x96, 0xF2, 0xF2, 0x36, 0xD3, 0xD4, 0x14,
+            0x10, 0xEC,
+        ];
+        let ecc = [0xA8, 0x97, 0xD2, 0x2F, 0xEE, 0xD6, 0x70, 0x3E, 0x08, 0x36];
+        assert_eq!(codewords, [&data[..], &ecc[..]].concat());
+    }
+}
@@ -0,0 +1,144 @@
+//! Recording a game as a video: every position from the empty board to the
+//! last move is drawn in turn on a canvas of its own, which a `MediaRecorder`
+//! captures; when it stops, the video downloads as `gomoku.webm` (or `.mp4`
+//! where the browser only records that).
+//!
+//! The recorder captures in real time, so a recording takes as long as the
+//! video it makes: `MOVE_MS` a move, and `HOLD_MS` on the final position.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    window, Blob, BlobEvent, BlobPropertyBag, CanvasRenderingContext2d, HtmlAnchorElement,
+    HtmlCanvasElement, MediaRecorder, MediaRecorderOptions, Url,
+};
+
+use crate::{Game, Pt};
+
+/// How long each position stays on screen, and the last one.
+const MOVE_MS: f64 = 500.0;
+const HOLD_MS: f64 = 2000.0;
+/// Frames a second captured from the canvas.
+const FRAME_RATE: f64 = 30.0;
+/// Formats to record in, best first, with their file extensions.
+const FORMATS: [(&str, &str); 3] = [
+    ("video/webm;codecs=vp9", "webm"),
+    ("video/webm", "webm"),
+    ("video/mp4", "mp4"),
+];
+
+/// What `Recording::step` wants done this frame.
+pub(crate) enum Step {
+    Wait,
+    /// Draw `Recording::game` on the recorded canvas.
+    Frame,
+    /// The video is over; `finish` it.
+    Done,
+}
+
+pub(crate) struct Recording {
+    pub(crate) ctx: CanvasRenderingContext2d,
+    /// The camera framing every stone: centre, cell size and canvas size.
+    pub(crate) frame: (f64, f64, f64, f64, f64),
+    recorder: MediaRecorder,
+    moves: Vec<Pt>,
+    /// The position after `shown` of `moves`, `None` before the first frame.
+    game: Game,
+    shown: Option<usize>,
+    next_at_ms: f64,
+}
+
+impl Recording {
+    /// Start recording `canvas`, which `ctx` draws on and `frame` frames, for
+    /// the moves of `game`. Fails where the browser cannot record a canvas.
+    pub(crate) fn start(
+        canvas: &HtmlCanvasElement,
+        ctx: CanvasRenderingContext2d,
+        frame: (f64, f64, f64, f64, f64),
+        game: &Game,
+    ) -> Result<Recording, JsValue> {
+        let &(mime, ext) = FORMATS
+            .iter()
+            .find(|(mime, _)| MediaRecorder::is_type_supported(mime))
+            .ok_or("no supported video format")?;
+        let stream = canvas.capture_stream_with_frame_request_rate(FRAME_RATE)?;
+        let options = MediaRecorderOptions::new();
+        options.set_mime_type(mime);
+        let recorder =
+            MediaRecorder::new_with_media_stream_and_media_recorder_options(&stream, &options)?;
+
+        let chunks = js_sys::Array::new();
+        let on_data = {
+            let chunks = chunks.clone();
+            Closure::<dyn FnMut(BlobEvent)>::new(move |e: BlobEvent| {
+                if let Some(data) = e.data() {
+                    chunks.push(&data);
+                }
+            })
+        };
+        recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+        on_data.forget();
+        let on_stop = Closure::<dyn FnMut()>::new(move || {
+            let _ = download(&chunks, mime, ext);
+        });
+        recorder.set_onstop(Some(on_stop.as_ref().unchecked_ref()));
+        on_stop.forget();
+        recorder.start()?;
+
+        Ok(Recording {
+            ctx,
+            frame,
+            recorder,
+            moves: game.moves.clone(),
+            game: Game::with_rules(game.rules),
+            shown: None,
+            next_at_ms: 0.0,
+        })
+    }
+
+    /// The position to draw on the next `Step::Frame`.
+    pub(crate) fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Moves recorded so far, and in all.
+    pub(crate) fn progress(&self) -> (usize, usize) {
+        (self.shown.unwrap_or(0), self.moves.len())
+    }
+
+    pub(crate) fn step(&mut self, now_ms: f64) -> Step {
+        if now_ms < self.next_at_ms {
+            return Step::Wait;
+        }
+        let next = self.shown.map_or(0, |n| n + 1);
+        if next > self.moves.len() {
+            return Step::Done;
+        }
+        if next > 0 {
+            self.game.play(self.moves[next - 1]);
+        }
+        self.shown = Some(next);
+        let stay = if next == self.moves.len() { HOLD_MS } else { MOVE_MS };
+        self.next_at_ms = now_ms + stay;
+        Step::Frame
+    }
+
+    /// Stop recording, which downloads the video.
+    pub(crate) fn finish(self) {
+        let _ = self.recorder.stop();
+    }
+}
+
+/// Save the recorded `chunks` as one video file.
+fn download(chunks: &js_sys::Array, mime: &str, ext: &str) -> Result<(), JsValue> {
+    let kind = BlobPropertyBag::new();
+    kind.set_type(mime);
+    let blob = Blob::new_with_blob_sequence_and_options(chunks, &kind)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+    let doc = window().and_then(|w| w.document()).ok_or("no document")?;
+    let link = doc.create_element("a")?.dyn_into::<HtmlAnchorElement>()?;
+    link.set_href(&url);
+    link.set_download(&format!("gomoku.{ext}"));
+    link.click();
+    Ok(())
+}
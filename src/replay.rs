@@ -0,0 +1,156 @@
+//! Replay: step through a finished or in-progress game without touching the live `Game`.
+
+use crate::{Color, Game, Pt, Rules};
+
+/// Autoplay speeds (ms per move), slowest first.
+const SPEEDS_MS: [f64; 5] = [2000.0, 1000.0, 600.0, 300.0, 120.0];
+
+pub(crate) struct Replay {
+    rules: Rules,
+    moves: Vec<Pt>,
+    /// Who played each of `moves`.
+    movers: Vec<Color>,
+    /// Number of moves applied to `view`.
+    cursor: usize,
+    /// Position after `moves[..cursor]`.
+    view: Game,
+    /// When each of `moves` was played, in ms after the first, if known.
+    times: Option<Vec<f64>>,
+    autoplay: bool,
+    /// An index into `SPEEDS_MS`, or `None` to keep the pace the moves were
+    /// played at (only with `times`).
+    speed: Option<usize>,
+    next_step_at_ms: f64,
+}
+
+impl Replay {
+    /// Start at the current position of `game`.
+    pub(crate) fn new(game: &Game) -> Self {
+        let mut g = Game::with_rules(game.rules);
+        let movers = game
+            .moves
+            .iter()
+            .map(|&p| {
+                let mover = g.player;
+                g.play(p);
+                mover
+            })
+            .collect();
+        Self {
+            rules: game.rules,
+            moves: game.moves.clone(),
+            movers,
+            cursor: game.moves.len(),
+            view: Game::from_moves(game.rules, &game.moves),
+            times: None,
+            autoplay: false,
+            speed: Some(1),
+            next_step_at_ms: 0.0,
+        }
+    }
+
+    /// Autoplay at the pace of `times` (see `Replay::times`), if there is one
+    /// for each move and they never go back.
+    pub(crate) fn with_times(mut self, times: Vec<f64>) -> Self {
+        if times.len() == self.moves.len() && times.windows(2).all(|w| w[0] <= w[1]) {
+            self.times = Some(times);
+            self.speed = None;
+        }
+        self
+    }
+
+    pub(crate) fn view(&self) -> &Game {
+        &self.view
+    }
+
+    pub(crate) fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Every move of the game with who played it, in order.
+    pub(crate) fn moves(&self) -> impl Iterator<Item = (Pt, Color)> + '_ {
+        self.moves.iter().copied().zip(self.movers.iter().copied())
+    }
+
+    pub(crate) fn is_autoplay(&self) -> bool {
+        self.autoplay
+    }
+
+    /// How long autoplay waits before the next move.
+    pub(crate) fn interval_ms(&self) -> f64 {
+        match (self.speed, &self.times) {
+            (Some(i), _) => SPEEDS_MS[i],
+            (None, Some(times)) => match (self.cursor.checked_sub(1), times.get(self.cursor)) {
+                (Some(prev), Some(next)) => next - times[prev],
+                _ => 0.0,
+            },
+            (None, None) => SPEEDS_MS[1],
+        }
+    }
+
+    /// Jump to the position after `n` moves (clamped). Returns `true` if the view changed.
+    pub(crate) fn seek(&mut self, n: usize) -> bool {
+        let n = n.min(self.moves.len());
+        if n == self.cursor {
+            return false;
+        }
+        if n > self.cursor {
+            for &p in &self.moves[self.cursor..n] {
+                self.view.play(p);
+            }
+        } else {
+            self.view = Game::from_moves(self.rules, &self.moves[..n]);
+        }
+        self.cursor = n;
+        true
+    }
+
+    pub(crate) fn step_forward(&mut self) -> bool {
+        self.seek(self.cursor + 1)
+    }
+
+    pub(crate) fn step_back(&mut self) -> bool {
+        self.cursor > 0 && self.seek(self.cursor - 1)
+    }
+
+    /// Toggle autoplay; restarting from the end rewinds to the first move.
+    pub(crate) fn toggle_autoplay(&mut self, now_ms: f64) {
+        self.autoplay = !self.autoplay;
+        if self.autoplay {
+            if self.cursor == self.moves.len() {
+                self.seek(0);
+            }
+            self.next_step_at_ms = now_ms + self.interval_ms();
+        }
+    }
+
+    /// The next fixed speed up; the game's own pace comes before the slowest.
+    pub(crate) fn faster(&mut self) {
+        self.speed = Some(self.speed.map_or(0, |i| (i + 1).min(SPEEDS_MS.len() - 1)));
+    }
+
+    pub(crate) fn slower(&mut self) {
+        self.speed = match self.speed {
+            Some(0) if self.times.is_some() => None,
+            Some(i) => Some(i.saturating_sub(1)),
+            None => None,
+        };
+    }
+
+    /// Advance autoplay if due. Returns `true` if the view changed.
+    pub(crate) fn tick(&mut self, now_ms: f64) -> bool {
+        if !self.autoplay || now_ms < self.next_step_at_ms {
+            return false;
+        }
+        let moved = self.step_forward();
+        if self.cursor == self.moves.len() {
+            self.autoplay = false;
+        }
+        self.next_step_at_ms = now_ms + self.interval_ms();
+        moved
+    }
+}
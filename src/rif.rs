@@ -0,0 +1,174 @@
+//! RenjuNet's game library (`.rif`, an XML export of its database), so the
+//! classic professional games in it can be stepped through in replay.
+//!
+//! The file lists players as `<player id name surname>` and games as
+//! `<game black white bresult date>` elements, each with its moves in a
+//! `<move>` child as space-separated cells like `h8`: a column letter from
+//! `a` at the left of the 15×15 board and a row number from 1 at the bottom.
+//! The board's centre `h8` is put on the origin, so the games sit on the
+//! infinite grid around it.
+
+use std::collections::HashMap;
+
+use crate::{Bounds, Pt};
+
+/// The side of RenjuNet's board.
+const BOARD: i32 = 15;
+
+pub(crate) struct Record {
+    pub(crate) black: String,
+    pub(crate) white: String,
+    /// As it is written in the file, or empty.
+    pub(crate) date: String,
+    /// 1 for a black win, 0.5 a draw, 0 a white win; `None` where unknown.
+    pub(crate) black_result: Option<f64>,
+    pub(crate) moves: Vec<Pt>,
+}
+
+/// The library a `.rif` file opened for picking a game from.
+pub(crate) struct Library {
+    pub(crate) records: Vec<Record>,
+    /// Records scrolled past at the top.
+    pub(crate) scroll: usize,
+}
+
+/// The games with moves in `text`, or `None` if it is not a RenjuNet file or
+/// has no game with moves.
+pub(crate) fn parse(text: &str) -> Option<Vec<Record>> {
+    if !text.contains("<database") {
+        return None;
+    }
+    let mut players = HashMap::new();
+    for tag in tags(text, "player") {
+        let Some(id) = attr(tag, "id") else {
+            continue;
+        };
+        let name = [attr(tag, "name"), attr(tag, "surname")]
+            .into_iter()
+            .flatten()
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        players.insert(id, name);
+    }
+    let player = |tag: &str, side: &str| {
+        let id = attr(tag, side).unwrap_or_default();
+        players.get(&id).cloned().unwrap_or_else(|| format!("#{id}"))
+    };
+    let mut records = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<game ") {
+        rest = &rest[start..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..tag_end];
+        let body_end = rest.find("</game>").unwrap_or(rest.len());
+        let body = rest.get(tag_end..body_end).unwrap_or("");
+        rest = &rest[body_end.max(tag_end)..];
+        let moves = body
+            .split_once("<move>")
+            .and_then(|(_, m)| m.split_once("</move>"))
+            .map(|(m, _)| cells(m))
+            .unwrap_or_default();
+        if moves.is_empty() {
+            continue;
+        }
+        records.push(Record {
+            black: player(tag, "black"),
+            white: player(tag, "white"),
+            date: attr(tag, "date").unwrap_or_default(),
+            black_result: attr(tag, "bresult").and_then(|r| r.parse().ok()),
+            moves,
+        });
+    }
+    (!records.is_empty()).then_some(records)
+}
+
+/// The opening tags `<name ...>` in `text`, without their brackets.
+fn tags<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+    let open = format!("<{name} ");
+    text.match_indices(open.as_str())
+        .filter_map(|(i, _)| {
+            let tag = &text[i..];
+            tag.find('>').map(|end| &tag[..end])
+        })
+        .collect()
+}
+
+/// The value of `key="..."` in `tag`, unescaped.
+fn attr(tag: &str, key: &str) -> Option<String> {
+    let start = tag.find(&format!(" {key}=\""))? + key.len() + 3;
+    let len = tag[start..].find('"')?;
+    Some(
+        tag[start..start + len]
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// The cells in a `<move>` list, up to the first that is not one.
+fn cells(list: &str) -> Vec<Pt> {
+    let origin = Bounds::square(BOARD).min;
+    list.split_whitespace()
+        .map_while(|cell| {
+            let mut chars = cell.chars();
+            let col = chars.next()?.to_ascii_lowercase();
+            let row: i32 = chars.as_str().parse().ok()?;
+            let col = col.is_ascii_lowercase().then(|| i32::from(col as u8 - b'a'))?;
+            ((0..BOARD).contains(&col) && (1..=BOARD).contains(&row))
+                .then(|| origin.add(col, BOARD - row))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY: &str = r#"<?xml version="1.0"?>
+<database>
+  <players>
+    <player id="7" name="Ann" surname="Lee"/>
+    <player id="9" name="Bo &amp; Co" surname=""/>
+  </players>
+  <games>
+    <game id="1" black="7" white="9" bresult="1" date="1999-08-01">
+      <move>h8 i9 g7 j10 f6 e5</move>
+    </game>
+    <game id="2" black="9" white="3" date="">
+      <move></move>
+    </game>
+  </games>
+</database>"#;
+
+    #[test]
+    fn reads_a_game_centred_on_h8() {
+        let records = parse(LIBRARY).unwrap();
+        assert_eq!(records.len(), 1);
+        let game = &records[0];
+        assert_eq!((game.black.as_str(), game.white.as_str()), ("Ann Lee", "Bo & Co"));
+        assert_eq!((game.date.as_str(), game.black_result), ("1999-08-01", Some(1.0)));
+        assert_eq!(game.moves[0], Pt::new(0, 0));
+        assert_eq!(&game.moves[1..3], &[Pt::new(1, -1), Pt::new(-1, 1)]);
+        assert_eq!(game.moves.len(), 6);
+    }
+
+    #[test]
+    fn malformed_files_are_refused() {
+        for text in [
+            "",
+            "h8 i9 g7",
+            "<database>",
+            "<database><game black=\"7",
+            "<database><game black=\"7\"><move>z99 h8</move></game>",
+            "<database></game><game ><move>h8",
+            "<database><game \u{e9}=\"\"><move>\u{e9}8 h8</move>",
+        ] {
+            assert!(parse(text).is_none(), "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! A small seedable PRNG (xorshift64*), enough for playouts and move variety.
+
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // Zero is the one state xorshift never leaves.
+        Rng(seed.max(1))
+    }
+
+    /// Seeded from `Math.random`, for play that differs between games.
+    pub(crate) fn from_entropy() -> Self {
+        Self::new((js_sys::Math::random() * u64::MAX as f64) as u64)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform in `0..n`; `n` must be non-zero.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
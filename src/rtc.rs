@@ -0,0 +1,265 @@
+//! Serverless peer-to-peer play over a WebRTC data channel.
+//!
+//! Signalling is manual: the host is shown an offer to send to their friend,
+//! as a QR code of a link that joins with it or as text to copy, the guest
+//! scans or pastes it and is shown an answer to send back the same way, and
+//! the host pastes that. Both blobs are the session description with all ICE
+//! candidates gathered, base64-encoded so it survives a single-line prompt
+//! and a link's `#offer=`. Once the channel opens it speaks the `net`
+//! protocol, the host's game standing for both.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::Reflect;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    window, MessageEvent, RtcConfiguration, RtcDataChannel, RtcDataChannelEvent,
+    RtcIceGatheringState, RtcPeerConnection, RtcPeerConnectionIceEvent, RtcSdpType,
+    RtcSessionDescriptionInit,
+};
+
+use crate::codec;
+use crate::i18n::{t, Msg};
+use crate::net::{Link, NetMsg, NetStatus, Pairing, Transport};
+
+const STUN_URL: &str = "stun:stun.l.google.com:19302";
+
+pub(crate) struct RtcTransport {
+    pc: RtcPeerConnection,
+    channel: Rc<RefCell<Option<RtcDataChannel>>>,
+    link: Link,
+    host: bool,
+    /// Our offer or answer once it is ready to show.
+    pairing: Rc<RefCell<Option<Pairing>>>,
+    /// Resolves the host's wait for the guest's answer.
+    reply: Rc<RefCell<Option<js_sys::Function>>>,
+}
+
+impl RtcTransport {
+    /// Create the offer and wait for the guest's pasted answer.
+    pub(crate) fn host() -> Result<RtcTransport, JsValue> {
+        let t = Self::new(true)?;
+        let ch = t.pc.create_data_channel("gomoku");
+        wire_channel(&ch, &t.link);
+        *t.channel.borrow_mut() = Some(ch);
+
+        let (pc, link) = (t.pc.clone(), t.link.clone());
+        let (pairing, reply) = (Rc::clone(&t.pairing), Rc::clone(&t.reply));
+        wasm_bindgen_futures::spawn_local(async move {
+            if host_signalling(&pc, &pairing, &reply).await.is_err() {
+                link.set_status(NetStatus::Closed);
+            }
+        });
+        Ok(t)
+    }
+
+    /// Take the host's offer from the link, or ask for it, and show the
+    /// answer to send back.
+    pub(crate) fn join() -> Result<RtcTransport, JsValue> {
+        let t = Self::new(false)?;
+        let on_channel = {
+            let (channel, link) = (Rc::clone(&t.channel), t.link.clone());
+            Closure::<dyn FnMut(RtcDataChannelEvent)>::new(move |e: RtcDataChannelEvent| {
+                let ch = e.channel();
+                wire_channel(&ch, &link);
+                *channel.borrow_mut() = Some(ch);
+            })
+        };
+        t.pc.set_ondatachannel(Some(on_channel.as_ref().unchecked_ref()));
+        on_channel.forget();
+
+        let (pc, link) = (t.pc.clone(), t.link.clone());
+        let (pairing, offer) = (Rc::clone(&t.pairing), offer_in_link());
+        wasm_bindgen_futures::spawn_local(async move {
+            if guest_signalling(&pc, &pairing, offer).await.is_err() {
+                link.set_status(NetStatus::Closed);
+            }
+        });
+        Ok(t)
+    }
+
+    fn new(host: bool) -> Result<RtcTransport, JsValue> {
+        let server = js_sys::Object::new();
+        Reflect::set(&server, &"urls".into(), &STUN_URL.into())?;
+        let config = RtcConfiguration::new();
+        config.set_ice_servers(&js_sys::Array::of1(&server));
+        Ok(RtcTransport {
+            pc: RtcPeerConnection::new_with_configuration(&config)?,
+            channel: Rc::new(RefCell::new(None)),
+            link: Link::new(),
+            host,
+            pairing: Rc::new(RefCell::new(None)),
+            reply: Rc::new(RefCell::new(None)),
+        })
+    }
+}
+
+impl Transport for RtcTransport {
+    fn link(&self) -> &Link {
+        &self.link
+    }
+
+    fn send_text(&self, text: &str) {
+        if let Some(ch) = self.channel.borrow().as_ref() {
+            let _ = ch.send_with_str(text);
+        }
+    }
+
+    fn answers_hello(&self) -> bool {
+        self.host
+    }
+
+    fn take_pairing(&self) -> Option<Pairing> {
+        self.pairing.borrow_mut().take()
+    }
+
+    fn reply(&self, code: &str) {
+        if let Some(resolve) = self.reply.borrow_mut().take() {
+            let _ = resolve.call1(&JsValue::NULL, &code.into());
+        }
+    }
+}
+
+impl Drop for RtcTransport {
+    fn drop(&mut self) {
+        if let Some(ch) = self.channel.borrow_mut().take() {
+            ch.close();
+        }
+        self.pc.close();
+    }
+}
+
+fn wire_channel(ch: &RtcDataChannel, link: &Link) {
+    let on_open = {
+        let (ch, link) = (ch.clone(), link.clone());
+        Closure::<dyn FnMut()>::new(move || {
+            link.set_status(NetStatus::Open);
+            let _ = ch.send_with_str(&NetMsg::Hello.encode());
+        })
+    };
+    let on_message = {
+        let link = link.clone();
+        Closure::<dyn FnMut(MessageEvent)>::new(move |e: MessageEvent| {
+            if let Some(text) = e.data().as_string() {
+                link.receive(&text);
+            }
+        })
+    };
+    let on_close = {
+        let link = link.clone();
+        Closure::<dyn FnMut()>::new(move || link.set_status(NetStatus::Closed))
+    };
+    ch.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+    ch.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    ch.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+    on_open.forget();
+    on_message.forget();
+    on_close.forget();
+}
+
+/// Show the offer, then wait for the answer the player pastes (see `reply`).
+async fn host_signalling(
+    pc: &RtcPeerConnection,
+    pairing: &RefCell<Option<Pairing>>,
+    reply: &RefCell<Option<js_sys::Function>>,
+) -> Result<(), JsValue> {
+    let offer = JsFuture::from(pc.create_offer()).await?;
+    set_local(pc, RtcSdpType::Offer, &offer).await?;
+    let blob = gathered_description(pc).await?;
+    let loc = window().ok_or("no window")?.location();
+    let join = format!("{}{}?rtc=join#offer={blob}", loc.origin()?, loc.pathname()?);
+    let answer = js_sys::Promise::new(&mut |resolve, _| *reply.borrow_mut() = Some(resolve));
+    *pairing.borrow_mut() = Some(Pairing {
+        code: blob,
+        qr_text: join,
+        wants_reply: true,
+    });
+    let answer = JsFuture::from(answer).await?.as_string().unwrap_or_default();
+    if answer.trim().is_empty() {
+        return Err("signalling cancelled".into());
+    }
+    set_remote(pc, RtcSdpType::Answer, &answer).await
+}
+
+async fn guest_signalling(
+    pc: &RtcPeerConnection,
+    pairing: &RefCell<Option<Pairing>>,
+    offer: Option<String>,
+) -> Result<(), JsValue> {
+    let offer = match offer {
+        Some(offer) => offer,
+        None => prompt(&t(Msg::RtcPasteOffer), "")?,
+    };
+    set_remote(pc, RtcSdpType::Offer, &offer).await?;
+    let answer = JsFuture::from(pc.create_answer()).await?;
+    set_local(pc, RtcSdpType::Answer, &answer).await?;
+    let blob = gathered_description(pc).await?;
+    *pairing.borrow_mut() = Some(Pairing {
+        code: blob.clone(),
+        qr_text: blob,
+        wants_reply: false,
+    });
+    Ok(())
+}
+
+/// The offer in a join link's `#offer=`, as the host's QR code holds it.
+fn offer_in_link() -> Option<String> {
+    let hash = window()?.location().hash().ok()?;
+    hash.strip_prefix("#offer=").map(str::to_string)
+}
+
+async fn set_local(
+    pc: &RtcPeerConnection,
+    kind: RtcSdpType,
+    desc: &JsValue,
+) -> Result<(), JsValue> {
+    let sdp = Reflect::get(desc, &"sdp".into())?
+        .as_string()
+        .ok_or("description without sdp")?;
+    let init = RtcSessionDescriptionInit::new(kind);
+    init.set_sdp(&sdp);
+    JsFuture::from(pc.set_local_description(&init)).await?;
+    Ok(())
+}
+
+async fn set_remote(pc: &RtcPeerConnection, kind: RtcSdpType, blob: &str) -> Result<(), JsValue> {
+    let sdp = codec::base64_decode(blob.trim())
+        .and_then(|b| String::from_utf8(b).ok())
+        .ok_or("malformed session description")?;
+    let init = RtcSessionDescriptionInit::new(kind);
+    init.set_sdp(&sdp);
+    JsFuture::from(pc.set_remote_description(&init)).await?;
+    Ok(())
+}
+
+/// Wait until ICE gathering finishes, then return the local description as a paste-able blob.
+async fn gathered_description(pc: &RtcPeerConnection) -> Result<String, JsValue> {
+    if pc.ice_gathering_state() != RtcIceGatheringState::Complete {
+        let done = js_sys::Promise::new(&mut |resolve, _| {
+            let on_candidate = Closure::<dyn FnMut(RtcPeerConnectionIceEvent)>::new(
+                move |e: RtcPeerConnectionIceEvent| {
+                    // A null candidate marks the end of gathering.
+                    if e.candidate().is_none() {
+                        let _ = resolve.call0(&JsValue::NULL);
+                    }
+                },
+            );
+            pc.set_onicecandidate(Some(on_candidate.as_ref().unchecked_ref()));
+            on_candidate.forget();
+        });
+        JsFuture::from(done).await?;
+        pc.set_onicecandidate(None);
+    }
+    let sdp = pc.local_description().ok_or("no local description")?.sdp();
+    Ok(codec::base64_encode(sdp.as_bytes()))
+}
+
+fn prompt(message: &str, default: &str) -> Result<String, JsValue> {
+    window()
+        .unwrap()
+        .prompt_with_message_and_default(message, default)?
+        .ok_or_else(|| "signalling cancelled".into())
+}
@@ -0,0 +1,125 @@
+//! Named save slots, kept in `localStorage`, so several games can be carried
+//! on besides the one in the page's link.
+//!
+//! They are stored a slot a line as `slot<TAB>saved at<TAB>name<TAB>game`,
+//! the time in ms since the epoch and the game as `schema` writes it, so a
+//! slot loads under the rules it was saved with; empty slots are left out.
+//! Slots saved before they kept their rules hold just the moves as a game
+//! link writes them (see `codec`), and are read under the rules given.
+
+use web_sys::{window, Storage};
+
+use crate::{codec, Game, Rules};
+
+const STORAGE_KEY: &str = "gomoku.saves";
+/// Slots in the menu.
+pub(crate) const SLOTS: usize = 5;
+/// Longest name kept, in characters.
+const MAX_NAME_CHARS: usize = 32;
+
+pub(crate) struct Save {
+    pub(crate) name: String,
+    pub(crate) saved_ms: f64,
+    pub(crate) game: Game,
+}
+
+impl Save {
+    /// A save of `game` at `saved_ms`, its name trimmed, shortened and kept
+    /// to one line.
+    pub(crate) fn new(name: &str, saved_ms: f64, game: Game) -> Save {
+        let name = name
+            .trim()
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .take(MAX_NAME_CHARS)
+            .collect();
+        Save {
+            name,
+            saved_ms,
+            game,
+        }
+    }
+}
+
+fn storage() -> Option<Storage> {
+    window()?.local_storage().ok().flatten()
+}
+
+/// Every slot, `None` where empty or unreadable; slots without rules of their
+/// own are read under `legacy`.
+pub(crate) fn load(legacy: Rules) -> Vec<Option<Save>> {
+    let spec = storage()
+        .and_then(|s| s.get_item(STORAGE_KEY).ok().flatten())
+        .unwrap_or_default();
+    parse(&spec, legacy)
+}
+
+fn parse(spec: &str, legacy: Rules) -> Vec<Option<Save>> {
+    let mut slots: Vec<Option<Save>> = (0..SLOTS).map(|_| None).collect();
+    for line in spec.lines() {
+        let mut fields = line.splitn(4, '\t');
+        let (Some(slot), Some(at), Some(name), Some(game)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let (Ok(slot), Ok(at)) = (slot.parse::<usize>(), at.parse()) else {
+            continue;
+        };
+        let game = match game.starts_with('{') {
+            true => serde_json::from_str(game).ok(),
+            false => codec::decode_moves(game).map(|moves| Game::from_moves(legacy, &moves)),
+        };
+        if let (Some(entry), Some(game)) = (slots.get_mut(slot), game) {
+            *entry = Some(Save::new(name, at, game));
+        }
+    }
+    slots
+}
+
+/// Replace the stored slots with `slots`.
+pub(crate) fn store(slots: &[Option<Save>]) {
+    if let Some(s) = storage() {
+        let _ = s.set_item(STORAGE_KEY, &spec(slots));
+    }
+}
+
+fn spec(slots: &[Option<Save>]) -> String {
+    slots
+        .iter()
+        .enumerate()
+        .filter_map(|(i, save)| {
+            let s = save.as_ref()?;
+            let game = serde_json::to_string(&s.game).ok()?;
+            Some(format!("{i}\t{}\t{}\t{game}", s.saved_ms, s.name))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pt, Variant};
+
+    #[test]
+    fn slots_keep_their_rules() {
+        let rules = Variant::Connect6.apply(Rules::default());
+        let moves = [Pt::new(0, 0), Pt::new(1, 1), Pt::new(2, 2), Pt::new(3, 0)];
+        let saved = Save::new("six\tin a row", 1.5e12, Game::from_moves(rules, &moves));
+        let slots = parse(&spec(&[None, Some(saved)]), Rules::default());
+        let back = slots[1].as_ref().unwrap();
+        assert!(slots[0].is_none());
+        assert_eq!((back.name.as_str(), back.saved_ms), ("six in a row", 1.5e12));
+        assert_eq!((back.game.rules, back.game.moves.as_slice()), (rules, &moves[..]));
+    }
+
+    #[test]
+    fn slots_from_before_rules_load_under_the_ones_given() {
+        let moves = [Pt::new(0, 0), Pt::new(1, 0)];
+        let line = format!("2\t1000\told\t{}", codec::encode_moves(&moves));
+        let rules = Variant::Pente.apply(Rules::default());
+        let back = parse(&line, rules).remove(2).unwrap();
+        assert_eq!((back.game.rules, back.game.moves.as_slice()), (rules, &moves[..]));
+    }
+}
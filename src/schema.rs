@@ -0,0 +1,205 @@
+//! The JSON form of a game, as `GomokuEngine::toJson` writes it and `fromJson`
+//! reads it, for saves, the network and outside tools to share:
+//!
+//! ```json
+//! { "version": 1,
+//!   "rules": { "bounds": null, "winLen": 5, "captures": false, "stonesPerTurn": 1 },
+//!   "moves": [{ "x": 0, "y": 0 }, { "x": 1, "y": 1 }],
+//!   "toMove": "black", "winner": null }
+//! ```
+//!
+//! A game is its rules and moves; `toMove` and `winner` are written for
+//! readers' convenience and worked out again from the moves when read, which
+//! must all be legal. Readers take any `version` up to `VERSION` and refuse
+//! later ones, so a new field or meaning bumps it. The AI's weights are not
+//! part of a game here (see `GomokuEngine::weights`).
+//!
+//! A replay file wraps the same rules and moves with when each move was
+//! played, for watching a game back at its own pace:
+//!
+//! ```json
+//! { "version": 1, "format": "gomoku-replay", "started": 1791993600000,
+//!   "players": ["human", "ai"], "winner": "white",
+//!   "rules": { ... }, "moves": [{ "x": 0, "y": 0 }, ...], "times": [0, 950, ...] }
+//! ```
+//!
+//! `started` is the wall-clock time of the first move in ms since the epoch,
+//! and `times` each move's ms after it, or empty where that is not known.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Color, Controller, Game, Pt, Rules};
+
+/// The schema version written, and the latest one read.
+pub(crate) const VERSION: u32 = 1;
+
+/// Marks a replay file, so it can be told from a plain game.
+const REPLAY_FORMAT: &str = "gomoku-replay";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GameOut<'a> {
+    version: u32,
+    rules: &'a Rules,
+    moves: &'a [Pt],
+    to_move: Color,
+    winner: Option<Color>,
+}
+
+#[derive(Deserialize)]
+struct GameIn {
+    version: u32,
+    rules: Rules,
+    moves: Vec<Pt>,
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        GameOut {
+            version: VERSION,
+            rules: &self.rules,
+            moves: &self.moves,
+            to_move: self.player,
+            winner: self.winner,
+        }
+        .serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Game, D::Error> {
+        let doc = GameIn::deserialize(d)?;
+        if doc.version > VERSION {
+            return Err(D::Error::custom(format!(
+                "schema version {} is newer than {VERSION}",
+                doc.version
+            )));
+        }
+        let mut game = Game::with_rules(doc.rules.validated());
+        for (i, &p) in doc.moves.iter().enumerate() {
+            if !game.play(p) {
+                return Err(D::Error::custom(format!(
+                    "move {} at ({}, {}) is not legal",
+                    i + 1,
+                    p.x,
+                    p.y
+                )));
+            }
+        }
+        Ok(game)
+    }
+}
+
+/// A game with its timings, as a replay file holds it (see above).
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ReplayFile {
+    version: u32,
+    format: String,
+    pub(crate) started: f64,
+    pub(crate) players: [Controller; 2],
+    winner: Option<Color>,
+    pub(crate) rules: Rules,
+    pub(crate) moves: Vec<Pt>,
+    pub(crate) times: Vec<f64>,
+}
+
+impl ReplayFile {
+    /// `game`, its first move played at `started` and each at `times` after.
+    pub(crate) fn new(
+        game: &Game,
+        started: f64,
+        times: Vec<f64>,
+        players: [Controller; 2],
+    ) -> Self {
+        ReplayFile {
+            version: VERSION,
+            format: REPLAY_FORMAT.to_string(),
+            started,
+            players,
+            winner: game.winner,
+            rules: game.rules,
+            moves: game.moves.clone(),
+            times,
+        }
+    }
+
+    /// The replay file in `json`, if it is one of a version this reads and
+    /// every move is legal under its rules.
+    pub(crate) fn parse(json: &str) -> Option<ReplayFile> {
+        let file: ReplayFile = serde_json::from_str(json).ok()?;
+        if file.format != REPLAY_FORMAT || file.version > VERSION {
+            return None;
+        }
+        let rules = file.rules.validated();
+        let mut game = Game::with_rules(rules);
+        file.moves
+            .iter()
+            .all(|&p| game.play(p))
+            .then_some(ReplayFile { rules, ..file })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Variant;
+
+    const RULES: &str = r#"{ "bounds": null, "winLen": 5, "captures": false, "stonesPerTurn": 1 }"#;
+
+    fn game_json(version: u32, moves: &str) -> String {
+        format!(r#"{{ "version": {version}, "rules": {RULES}, "moves": [{moves}] }}"#)
+    }
+
+    fn replay_json(version: u32, moves: &str) -> String {
+        format!(
+            r#"{{ "version": {version}, "format": "gomoku-replay", "started": 0,
+                "players": ["human", "ai"], "winner": null, "rules": {RULES},
+                "moves": [{moves}], "times": [0, 950] }}"#
+        )
+    }
+
+    const MOVES: &str = r#"{ "x": 0, "y": 0 }, { "x": 1, "y": -1 }"#;
+    const SAME_CELL_TWICE: &str = r#"{ "x": 0, "y": 0 }, { "x": 0, "y": 0 }"#;
+
+    #[test]
+    fn games_round_trip() {
+        let rules = Variant::Pente.apply(Rules::default());
+        let game = Game::from_moves(rules, &[Pt::new(0, 0), Pt::new(2, 1), Pt::new(-1, 3)]);
+        let back: Game = serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+        assert_eq!((back.rules, back.moves, back.player), (rules, game.moves, game.player));
+    }
+
+    #[test]
+    fn games_are_read_when_valid() {
+        let game: Game = serde_json::from_str(&game_json(VERSION, MOVES)).unwrap();
+        assert_eq!(game.moves, [Pt::new(0, 0), Pt::new(1, -1)]);
+        assert_eq!(game.player, Color::Black);
+    }
+
+    #[test]
+    fn games_are_refused_when_not() {
+        let read = |json: &str| serde_json::from_str::<Game>(json).is_err();
+        assert!(read(&game_json(VERSION + 1, MOVES)), "a later version");
+        assert!(read(&game_json(VERSION, SAME_CELL_TWICE)), "an illegal move");
+        assert!(read(&format!(r#"{{ "version": 1, "moves": [{MOVES}] }}"#)), "no rules");
+        assert!(read(&format!(r#"{{ "rules": {RULES}, "moves": [] }}"#)), "no version");
+    }
+
+    #[test]
+    fn replay_files_are_read_when_valid() {
+        let file = ReplayFile::parse(&replay_json(VERSION, MOVES)).unwrap();
+        assert_eq!(file.moves, [Pt::new(0, 0), Pt::new(1, -1)]);
+        assert_eq!(file.times, [0.0, 950.0]);
+    }
+
+    #[test]
+    fn replay_files_are_refused_when_not() {
+        assert!(ReplayFile::parse(&replay_json(VERSION + 1, MOVES)).is_none());
+        assert!(ReplayFile::parse(&replay_json(VERSION, SAME_CELL_TWICE)).is_none());
+        let plain_game = game_json(VERSION, MOVES);
+        assert!(ReplayFile::parse(&plain_game).is_none());
+        let no_times = replay_json(VERSION, MOVES).replace(r#", "times": [0, 950]"#, "");
+        assert!(ReplayFile::parse(&no_times).is_none());
+    }
+}
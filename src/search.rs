@@ -0,0 +1,703 @@
+//! Iterative-deepening alpha-beta search over threat-pruned candidates.
+//!
+//! Nodes are plain `Game` clones and leaves use `Game::evaluate_for`, so the search
+//! understands every variant the rules do. Whoever moves next is read from the
+//! child position, which keeps Connect6's two-stone turns correct.
+//!
+//! Moves that make a four, and the replies that block one, are searched without
+//! using up depth, so a forcing sequence running past the horizon is played out
+//! rather than cut off halfway.
+//!
+//! Within a node the remembered best move goes first, then forcing moves in the
+//! order `threats::candidates` gives them. Quiet moves follow: those that last
+//! caused a cutoff at the same ply (killers), then the rest by how often they
+//! have caused cutoffs anywhere in this search (history).
+//!
+//! Leaves and drawn positions are judged by the root player's `Style`, so an
+//! aggressive AI also prefers playing on to a full board.
+//!
+//! Each iteration first searches a narrow window around the previous one's
+//! score, which cuts off more of the tree while the evaluation is stable, and
+//! searches again with the full window if the best score lands outside it.
+//!
+//! Results are kept in a transposition table that outlives a single search, so
+//! work done while pondering on the opponent's time is reused once they move.
+//!
+//! Where the page can share memory with workers, the table is one that helper
+//! workers write into as they search the same position (see `smp`); `help` is
+//! their side of it, and they ponder there too, off the main thread.
+
+use std::collections::HashMap;
+
+use crate::rng::Rng;
+use crate::smp::Table;
+use crate::threats::{self, Candidate, Threat};
+use crate::{near_equal, Color, Game, Pt, PtMap, Style};
+
+/// Score of a won position, less the plies needed to reach it.
+const WIN_SCORE: i32 = 100_000_000;
+/// Deepest iteration unless the caller asks for less (or more).
+pub(crate) const MAX_DEPTH: u32 = 4;
+/// Positions visited per millisecond of thinking time, roughly, in a browser.
+/// A move's budget is spent up front; an iteration that runs out is discarded.
+pub(crate) const NODES_PER_MS: u32 = 10;
+/// Positions visited per pondering slice on the main thread, small enough to
+/// fit beside a frame.
+pub(crate) const PONDER_NODE_LIMIT: u32 = 150;
+/// Plies of forcing moves searched beyond the nominal depth along one line.
+const MAX_EXTENSIONS: u32 = 6;
+/// Half-width of the first window tried around the previous iteration's score.
+const ASPIRATION: i32 = 5_000;
+/// Alpha-beta bounds that no score falls outside.
+const FULL: (i32, i32) = (-WIN_SCORE - 1, WIN_SCORE + 1);
+/// Quiet moves remembered per ply for causing a cutoff.
+const KILLERS: usize = 2;
+/// Entries kept before the table is cleared.
+const TT_CAPACITY: usize = 200_000;
+/// Positions a helper visits between checks for a newer job.
+const CANCEL_CHECK: u32 = 256;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Bound {
+    Exact,
+    /// The score is at least this (a beta cutoff).
+    Lower,
+    /// The score is at most this (nothing beat alpha).
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Entry {
+    depth: u32,
+    score: i32,
+    bound: Bound,
+    best: Option<Pt>,
+}
+
+impl Entry {
+    /// The entry in two words for the shared table: the score, then the depth
+    /// (at most 15), bound, best move (if within 2048 cells of the origin) and
+    /// a bit telling a written slot from an empty one.
+    fn pack(self) -> [i32; 2] {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let best = self
+            .best
+            .filter(|p| (-2048..2048).contains(&p.x) && (-2048..2048).contains(&p.y))
+            .map_or(0, |p| 1 << 6 | (p.x as u32 & 0xfff) << 7 | (p.y as u32 & 0xfff) << 19);
+        let info = self.depth.min(15) | bound << 4 | best | 1 << 31;
+        [self.score, info as i32]
+    }
+
+    fn unpack([score, info]: [i32; 2]) -> Option<Entry> {
+        let info = info as u32;
+        if info >> 31 == 0 {
+            return None;
+        }
+        let bound = match info >> 4 & 3 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        // Shift the 12-bit coordinates to the top to sign-extend them.
+        let coord = |shift: u32| ((info << (20 - shift)) as i32) >> 20;
+        let best = (info >> 6 & 1 == 1).then(|| Pt::new(coord(7), coord(19)));
+        Some(Entry {
+            depth: info & 15,
+            score,
+            bound,
+            best,
+        })
+    }
+}
+
+/// Transposition table keyed by `Game::key`.
+#[derive(Default)]
+pub(crate) struct Tt {
+    map: HashMap<u64, Entry>,
+    /// Where entries go instead while helpers search alongside.
+    shared: Option<Table>,
+}
+
+impl Tt {
+    /// A table kept in `table`, shared with other threads.
+    pub(crate) fn shared(table: Table) -> Tt {
+        Tt {
+            map: HashMap::new(),
+            shared: Some(table),
+        }
+    }
+
+    fn get(&self, key: u64) -> Option<Entry> {
+        match &self.shared {
+            Some(t) => t.get(key).and_then(Entry::unpack),
+            None => self.map.get(&key).copied(),
+        }
+    }
+
+    fn put(&mut self, key: u64, entry: Entry) {
+        if let Some(t) = &self.shared {
+            t.put(key, entry.pack());
+            return;
+        }
+        if self.map.len() >= TT_CAPACITY {
+            self.map.clear();
+        }
+        self.map.insert(key, entry);
+    }
+
+    /// In a helper, search for job `job` until a newer one starts.
+    pub(crate) fn watch(&mut self, job: i32) {
+        if let Some(t) = &mut self.shared {
+            t.watch(job);
+        }
+    }
+
+    /// Whether the helper's job has been replaced.
+    pub(crate) fn cancelled(&self) -> bool {
+        self.shared.as_ref().is_some_and(Table::cancelled)
+    }
+}
+
+/// The best move for the side to move and its score from their point of view,
+/// chosen at random among moves that score about as well. Iterations stop at
+/// `depth` plies or when `nodes` positions have been visited.
+pub(crate) fn search(
+    game: &Game,
+    style: Style,
+    depth: u32,
+    nodes: u32,
+    rng: &mut Rng,
+    tt: &mut Tt,
+) -> Option<(Pt, i32)> {
+    let mut d = Deepening::new(game, style, depth, nodes, tt);
+    while d.step(game, tt) {}
+    d.best(rng)
+}
+
+/// A `search` run one iteration at a time, so the caller can draw a frame
+/// between them: the game shows the search going on instead of freezing for
+/// the whole of a long think. An iteration itself is not interrupted.
+pub(crate) struct Deepening {
+    style: Style,
+    max_depth: u32,
+    limit: u32,
+    /// Positions visited so far, and the move ordering learned from them.
+    nodes: u32,
+    killers: Vec<[Option<Pt>; KILLERS]>,
+    history: PtMap<u32>,
+    root: Vec<Candidate>,
+    /// The moves near the best from the deepest iteration that finished.
+    result: Vec<(Pt, i32)>,
+    /// Iterations that finished, and whether any more will run.
+    depth: u32,
+    done: bool,
+}
+
+impl Deepening {
+    /// A search of `game` to `depth` plies or `nodes` positions, not yet started.
+    pub(crate) fn new(game: &Game, style: Style, depth: u32, nodes: u32, tt: &mut Tt) -> Deepening {
+        let mut root = threats::candidates(game);
+        Searcher::new(game, style, nodes, tt).tt_move_first(game, &mut root);
+        Deepening {
+            style,
+            max_depth: depth,
+            limit: nodes,
+            nodes: 0,
+            killers: Vec::new(),
+            history: PtMap::default(),
+            root,
+            result: Vec::new(),
+            depth: 0,
+            done: depth == 0,
+        }
+    }
+
+    /// Run the next iteration on `game`, the position given to `new`; whether
+    /// there are more to run.
+    pub(crate) fn step(&mut self, game: &Game, tt: &mut Tt) -> bool {
+        if self.done {
+            return false;
+        }
+        let mut s = Searcher::new(game, self.style, self.limit, tt);
+        s.max_depth = self.max_depth;
+        s.nodes = self.nodes;
+        s.killers = std::mem::take(&mut self.killers);
+        s.history = std::mem::take(&mut self.history);
+        let depth = self.depth + 1;
+        let more = s.iterate(game, &mut self.root, depth, &mut self.result);
+        if s.nodes <= s.limit {
+            self.depth = depth;
+        }
+        self.nodes = s.nodes.min(s.limit);
+        self.killers = s.killers;
+        self.history = s.history;
+        self.done = !more || depth >= self.max_depth;
+        !self.done
+    }
+
+    /// Iterations finished and planned, and positions visited out of the budget.
+    pub(crate) fn progress(&self) -> (u32, u32, u32, u32) {
+        (self.depth, self.max_depth, self.nodes, self.limit)
+    }
+
+    /// One of the best moves found so far, at random, with its score.
+    pub(crate) fn best(&self, rng: &mut Rng) -> Option<(Pt, i32)> {
+        match self.result.len() {
+            0 => None,
+            n => Some(self.result[rng.below(n)]),
+        }
+    }
+}
+
+/// The line the table expects once `first` is played in `game`: `first`, then
+/// each position's remembered best move, for at most `len` moves. Read it
+/// right after `search` with the same `style`.
+pub(crate) fn principal_variation(
+    game: &Game,
+    style: Style,
+    first: Pt,
+    tt: &Tt,
+    len: usize,
+) -> Vec<Pt> {
+    let salt = salt(game, style);
+    let mut g = game.clone();
+    let mut line = vec![first];
+    g.play(first);
+    while line.len() < len && g.winner.is_none() {
+        match tt.get(g.key() ^ salt).and_then(|e| e.best) {
+            Some(p) if g.play(p) => line.push(p),
+            _ => break,
+        }
+    }
+    line
+}
+
+/// Stones to the end of the game, if `score` proves a win for the side to move.
+pub(crate) fn win_plies(score: i32) -> Option<u32> {
+    (score > WIN_SCORE / 2).then(|| (WIN_SCORE - score) as u32)
+}
+
+/// Mixed into table keys so styles never read each other's scores. Balanced
+/// scores are the same from either side, so they share one slot.
+fn salt(game: &Game, style: Style) -> u64 {
+    match style {
+        Style::Balanced => 0,
+        s => (s as u64 * 2 + game.player as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15),
+    }
+}
+
+/// A helper worker's part in a search of `game` (see `smp`): iterations like
+/// the main thread's, whose only product is what they leave in `tt`. So that
+/// helpers fill the table with different parts of the tree, odd ones start an
+/// iteration deeper, and each tries the root moves from a different one.
+pub(crate) fn help(game: &Game, style: Style, depth: u32, nodes: u32, helper: usize, tt: &mut Tt) {
+    let mut root = threats::candidates(game);
+    if root.is_empty() {
+        return;
+    }
+    let mut s = Searcher::new(game, style, nodes, tt);
+    s.tt_move_first(game, &mut root);
+    let n = root.len();
+    root.rotate_left(helper % n);
+    let mut result = Vec::new();
+    for depth in 1 + helper as u32 % 2..=depth {
+        if !s.iterate(game, &mut root, depth, &mut result) {
+            break;
+        }
+    }
+}
+
+/// A search of `game` within `nodes` whose only product is what it leaves in `tt`.
+pub(crate) fn ponder(game: &Game, style: Style, nodes: u32, tt: &mut Tt) {
+    let mut s = Searcher::new(game, style, nodes, tt);
+    s.deepen(game, threats::candidates(game));
+}
+
+/// A move's score for the side to move and the line expected from it, the move
+/// itself first.
+pub(crate) type ScoredLine = (i32, Vec<Pt>);
+
+/// The best `n` moves for the side to move, best first, with lines of at most
+/// `len` moves. Each is the best of a `search` that leaves out the ones above it.
+/// Those searches get a table of their own, since they score the position as if
+/// the better moves were not there.
+pub(crate) fn top_lines(
+    game: &Game,
+    style: Style,
+    depth: u32,
+    nodes: u32,
+    n: usize,
+    len: usize,
+) -> Vec<ScoredLine> {
+    let mut tt = Tt::default();
+    let mut root = threats::candidates(game);
+    let mut lines = Vec::with_capacity(n);
+    while lines.len() < n {
+        let mut s = Searcher::new(game, style, nodes, &mut tt);
+        s.max_depth = depth;
+        let Some(&(p, score)) = s.deepen(game, root.clone()).first() else {
+            break;
+        };
+        root.retain(|c| c.p != p);
+        lines.push((score, principal_variation(game, style, p, &tt, len)));
+    }
+    lines
+}
+
+/// Wins are never traded for one another, so only heuristic scores get slack.
+fn margin(best: i32) -> i32 {
+    if best.abs() > WIN_SCORE / 2 {
+        0
+    } else {
+        near_equal(best)
+    }
+}
+
+/// Whether a root search in `window` that found `best` scored it exactly, and
+/// the moves within `margin` of it too.
+fn fits(best: i32, (lo, hi): (i32, i32)) -> bool {
+    best - margin(best) > lo && best < hi
+}
+
+/// Win scores count plies from the root; the table stores them from the node.
+fn to_tt(score: i32, ply: i32) -> i32 {
+    match score {
+        s if s > WIN_SCORE / 2 => s + ply,
+        s if s < -WIN_SCORE / 2 => s - ply,
+        s => s,
+    }
+}
+
+fn from_tt(score: i32, ply: i32) -> i32 {
+    match score {
+        s if s > WIN_SCORE / 2 => s - ply,
+        s if s < -WIN_SCORE / 2 => s + ply,
+        s => s,
+    }
+}
+
+struct Searcher<'a> {
+    nodes: u32,
+    limit: u32,
+    max_depth: u32,
+    tt: &'a mut Tt,
+    /// Whose style judges leaves; scores are still from the side to move.
+    side: Color,
+    style: Style,
+    /// See `salt`.
+    salt: u64,
+    /// Extensions taken on the line currently being searched.
+    extended: u32,
+    /// Latest cutoff moves by ply, newest first.
+    killers: Vec<[Option<Pt>; KILLERS]>,
+    /// Cutoffs caused by each point, weighted by the depth remaining.
+    history: PtMap<u32>,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(game: &Game, style: Style, limit: u32, tt: &'a mut Tt) -> Self {
+        Searcher {
+            nodes: 0,
+            limit,
+            max_depth: MAX_DEPTH,
+            tt,
+            side: game.player,
+            style,
+            salt: salt(game, style),
+            extended: 0,
+            killers: Vec::new(),
+            history: PtMap::default(),
+        }
+    }
+
+    fn key(&self, game: &Game) -> u64 {
+        game.key() ^ self.salt
+    }
+
+    /// `score`, given from the root player's point of view, for the side to move.
+    fn for_mover(&self, game: &Game, score: i32) -> i32 {
+        if game.player == self.side {
+            score
+        } else {
+            -score
+        }
+    }
+
+    /// The moves of `root` near the best, from the deepest iteration that finished.
+    fn deepen(&mut self, game: &Game, mut root: Vec<Candidate>) -> Vec<(Pt, i32)> {
+        self.tt_move_first(game, &mut root);
+        let mut result = Vec::new();
+        for depth in 1..=self.max_depth {
+            if !self.iterate(game, &mut root, depth, &mut result) {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Search `root` to `depth`, putting the moves near the best in `result`
+    /// if the iteration finishes; whether a deeper one is worth starting.
+    fn iterate(
+        &mut self,
+        game: &Game,
+        root: &mut [Candidate],
+        depth: u32,
+        result: &mut Vec<(Pt, i32)>,
+    ) -> bool {
+        let prev = result.first().map(|&(_, s)| s);
+        let Some(top) = self.aspirate(game, root, depth, prev) else {
+            return false;
+        };
+        let Some(&(best, score)) = top.first() else {
+            return false;
+        };
+        let entry = Entry {
+            depth,
+            score,
+            bound: Bound::Exact,
+            best: Some(best),
+        };
+        self.tt.put(self.key(game), entry);
+        *result = top;
+        if score.abs() > WIN_SCORE / 2 {
+            return false;
+        }
+        // Search the previous best first next time.
+        if let Some(i) = root.iter().position(|c| c.p == best) {
+            root[..=i].rotate_right(1);
+        }
+        true
+    }
+
+    /// `root` searched in a window around `prev`, and again in full if the best
+    /// score lands outside it.
+    fn aspirate(
+        &mut self,
+        game: &Game,
+        root: &[Candidate],
+        depth: u32,
+        prev: Option<i32>,
+    ) -> Option<Vec<(Pt, i32)>> {
+        if let Some(s) = prev.filter(|s| s.abs() <= WIN_SCORE / 2) {
+            let window = (s - ASPIRATION, s + ASPIRATION);
+            let top = self.root(game, root, depth, window)?;
+            if top.first().is_some_and(|&(_, best)| fits(best, window)) {
+                return Some(top);
+            }
+        }
+        self.root(game, root, depth, FULL)
+    }
+
+    /// Root moves scoring within `margin` of the best, best first, or `None` if
+    /// the node budget ran out. Scores are only exact if the best `fits` the
+    /// `(lo, hi)` window; a move reaching `hi` ends the iteration early.
+    fn root(
+        &mut self,
+        game: &Game,
+        root: &[Candidate],
+        depth: u32,
+        (lo, hi): (i32, i32),
+    ) -> Option<Vec<(Pt, i32)>> {
+        let mut best = -WIN_SCORE - 1;
+        let mut scored = Vec::with_capacity(root.len());
+        for c in root {
+            // Search just below the best so near-equal moves get exact scores;
+            // the window only rises, so anything failing low stays out of reach.
+            let alpha = (best - margin(best)).max(lo);
+            let score = self.child(game, c, depth, alpha, hi, 1)?;
+            best = best.max(score);
+            scored.push((c.p, score));
+            if score >= hi {
+                break;
+            }
+        }
+        scored.retain(|&(_, s)| s == best || s > best - margin(best));
+        scored.sort_by_key(|&(_, s)| std::cmp::Reverse(s));
+        Some(scored)
+    }
+
+    /// Score of playing `c` from `game`, from the mover's point of view.
+    fn child(
+        &mut self,
+        game: &Game,
+        c: &Candidate,
+        depth: u32,
+        alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> Option<i32> {
+        let forcing = c.mine >= Threat::Four || c.theirs == Threat::Five;
+        let extend = forcing && self.extended < MAX_EXTENSIONS;
+        let depth = if extend { depth } else { depth - 1 };
+        self.extended += extend as u32;
+        let mut next = game.clone();
+        next.play(c.p);
+        let score = if next.player == game.player {
+            self.negamax(&next, depth, alpha, beta, ply)
+        } else {
+            self.negamax(&next, depth, -beta, -alpha, ply).map(|s| -s)
+        };
+        self.extended -= extend as u32;
+        score
+    }
+
+    fn negamax(
+        &mut self,
+        game: &Game,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> Option<i32> {
+        self.nodes += 1;
+        if self.nodes > self.limit
+            || self.nodes.is_multiple_of(CANCEL_CHECK) && self.tt.cancelled()
+        {
+            return None;
+        }
+        if let Some(w) = game.winner {
+            let score = WIN_SCORE - ply;
+            return Some(if w == game.player { score } else { -score });
+        }
+        let key = self.key(game);
+        if let Some(e) = self.tt.get(key).filter(|e| e.depth >= depth) {
+            let score = from_tt(e.score, ply);
+            match e.bound {
+                Bound::Exact => return Some(score),
+                Bound::Lower if score >= beta => return Some(score),
+                Bound::Upper if score <= alpha => return Some(score),
+                _ => {}
+            }
+        }
+        if depth == 0 {
+            let eval = game.evaluate_for(self.side, self.style);
+            return Some(self.for_mover(game, eval));
+        }
+        let mut moves = threats::candidates(game);
+        if moves.is_empty() {
+            return Some(self.for_mover(game, -self.style.contempt()));
+        }
+        self.order(game, ply, &mut moves);
+        let alpha_in = alpha;
+        let mut best = -WIN_SCORE - 1;
+        let mut best_move = None;
+        for c in moves {
+            let score = self.child(game, &c, depth, alpha, beta, ply + 1)?;
+            if score > best {
+                best = score;
+                best_move = Some(c.p);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                if is_quiet(&c) {
+                    self.record_cutoff(c.p, depth, ply);
+                }
+                break;
+            }
+        }
+        let bound = if best <= alpha_in {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        let entry = Entry {
+            depth,
+            score: to_tt(best, ply),
+            bound,
+            best: best_move,
+        };
+        self.tt.put(key, entry);
+        Some(best)
+    }
+
+    /// Move ordering at an inner node: see the module docs.
+    fn order(&self, game: &Game, ply: i32, moves: &mut [Candidate]) {
+        let tt_move = self.tt.get(self.key(game)).and_then(|e| e.best);
+        let killers = self.killers.get(ply as usize).copied().unwrap_or_default();
+        moves.sort_by_cached_key(|c| {
+            let quiet = is_quiet(c);
+            let killer = quiet && killers.contains(&Some(c.p));
+            let history = match quiet {
+                true => self.history.get(&c.p).copied().unwrap_or(0),
+                false => 0,
+            };
+            (
+                Some(c.p) != tt_move,
+                quiet,
+                !killer,
+                std::cmp::Reverse(history),
+            )
+        });
+    }
+
+    fn record_cutoff(&mut self, p: Pt, depth: u32, ply: i32) {
+        let ply = ply as usize;
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; KILLERS]);
+        }
+        let slots = &mut self.killers[ply];
+        if slots[0] != Some(p) {
+            slots.rotate_right(1);
+            slots[0] = Some(p);
+        }
+        *self.history.entry(p).or_insert(0) += depth * depth;
+    }
+
+    /// Try the move the table remembers as best here before the others.
+    fn tt_move_first(&self, game: &Game, moves: &mut [Candidate]) {
+        let Some(p) = self.tt.get(self.key(game)).and_then(|e| e.best) else {
+            return;
+        };
+        if let Some(i) = moves.iter().position(|c| c.p == p) {
+            moves[..=i].rotate_right(1);
+        }
+    }
+}
+
+/// A move that neither makes nor stops a threat.
+fn is_quiet(c: &Candidate) -> bool {
+    c.mine == Threat::None && c.theirs == Threat::None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_survive_packing() {
+        for (depth, bound, best) in [
+            (3, Bound::Exact, Some(Pt::new(-2048, 2047))),
+            (15, Bound::Lower, None),
+            (1, Bound::Upper, Some(Pt::new(5, -1))),
+        ] {
+            let e = Entry {
+                depth,
+                score: -WIN_SCORE + 7,
+                bound,
+                best,
+            };
+            let back = Entry::unpack(e.pack()).unwrap();
+            let fields = (back.depth, back.score, back.bound, back.best);
+            assert_eq!(fields, (depth, e.score, bound, best));
+        }
+    }
+
+    #[test]
+    fn far_best_moves_and_deep_entries_pack_safely() {
+        let e = Entry {
+            depth: 40,
+            score: 12,
+            bound: Bound::Exact,
+            best: Some(Pt::new(3000, 0)),
+        };
+        let back = Entry::unpack(e.pack()).unwrap();
+        assert_eq!((back.depth, back.best), (15, None));
+        assert!(Entry::unpack([0, 0]).is_none());
+    }
+}
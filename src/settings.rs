@@ -0,0 +1,90 @@
+//! Choices made in the settings panel, kept in `localStorage` so the next
+//! visit starts with them.
+//!
+//! They are stored as `name:value,…`, each value written as the URL parameter
+//! of the same name takes it (`variant:pente,handicap:2,wheel:scroll`), and
+//! `start` reads them as defaults for those parameters, so a link still has
+//! the last word. Sound and the colour scheme keep their own storage (see
+//! `audio` and `theme`).
+//!
+//! From JS, `settingsJson()` gives them as `{ "version": 1, "settings": {
+//! "variant": "pente", … } }`, and `setSettingsJson` stores such a document
+//! for the next visit, versioned as games are (see `schema`).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use web_sys::{window, Storage};
+
+use crate::schema::VERSION;
+
+const STORAGE_KEY: &str = "gomoku.settings";
+
+fn storage() -> Option<Storage> {
+    window()?.local_storage().ok().flatten()
+}
+
+#[derive(Serialize, Deserialize)]
+struct SettingsDoc {
+    version: u32,
+    settings: BTreeMap<String, String>,
+}
+
+/// Every saved setting, by name.
+fn all() -> BTreeMap<String, String> {
+    let spec = storage()
+        .and_then(|s| s.get_item(STORAGE_KEY).ok().flatten())
+        .unwrap_or_default();
+    spec.split(',')
+        .filter_map(|entry| entry.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// The saved value of `name`, if any.
+pub(crate) fn get(name: &str) -> Option<String> {
+    all().remove(name)
+}
+
+/// Replace the saved settings with `entries`; empty values are left out.
+pub(crate) fn save(entries: &[(&str, String)]) {
+    let spec = entries
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(name, value)| format!("{name}:{value}"))
+        .collect::<Vec<_>>()
+        .join(",");
+    if let Some(s) = storage() {
+        let _ = s.set_item(STORAGE_KEY, &spec);
+    }
+}
+
+/// The saved settings as JSON.
+#[wasm_bindgen(js_name = settingsJson)]
+pub fn settings_json() -> String {
+    let doc = SettingsDoc {
+        version: VERSION,
+        settings: all(),
+    };
+    serde_json::to_string(&doc).unwrap_or_default()
+}
+
+/// Save the settings in `json`, as `settingsJson` writes them, in place of
+/// the current ones; like the panel's, they apply from the next visit. Fails
+/// on a later version, or a name or value with `,` or `:` in it.
+#[wasm_bindgen(js_name = setSettingsJson)]
+pub fn set_settings_json(json: &str) -> Result<(), JsValue> {
+    let doc: SettingsDoc = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    if doc.version > VERSION {
+        return Err(format!("schema version {} is newer than {VERSION}", doc.version).into());
+    }
+    let bad = |text: &String| text.contains([',', ':']);
+    if let Some((name, _)) = doc.settings.iter().find(|(n, v)| bad(n) || bad(v)) {
+        return Err(format!("setting {name} cannot be stored").into());
+    }
+    let entries: Vec<(&str, String)> =
+        doc.settings.iter().map(|(n, v)| (n.as_str(), v.clone())).collect();
+    save(&entries);
+    Ok(())
+}
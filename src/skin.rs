@@ -0,0 +1,117 @@
+//! Images for the stones and the board, set from JS:
+//!
+//! ```js
+//! setSkin({ black: "img/black.png", white: "<svg …>…</svg>", board: "img/wood.jpg" });
+//! clearSkin();
+//! ```
+//!
+//! Each part is an image URL or inline SVG, and any left out is drawn as the
+//! theme has it. Stones are drawn as their image scaled to the stone, and the
+//! board image is tiled under the grid, moving with it. Until an image has
+//! loaded (or if it fails to), its part is drawn as if there were none.
+//!
+//! Stone images are not used when zoomed out far enough for stones to be drawn
+//! as squares, nor on the WebGL layer of very large games.
+
+use std::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlImageElement;
+
+use crate::Color;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Part {
+    Black,
+    White,
+    Board,
+}
+
+impl Part {
+    const ALL: [Part; 3] = [Part::Black, Part::White, Part::Board];
+
+    pub(crate) fn stone(c: Color) -> Part {
+        match c {
+            Color::Black => Part::Black,
+            Color::White => Part::White,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Part::Black => "black",
+            Part::White => "white",
+            Part::Board => "board",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Skin {
+    images: [Option<HtmlImageElement>; 3],
+    /// Set when a part changes or an image finishes loading, until the next
+    /// frame takes it.
+    changed: bool,
+}
+
+thread_local! {
+    static SKIN: RefCell<Skin> = RefCell::default();
+}
+
+/// `part`'s image, once it has loaded.
+pub(crate) fn image(part: Part) -> Option<HtmlImageElement> {
+    SKIN.with(|s| {
+        s.borrow().images[part as usize]
+            .clone()
+            .filter(|img| img.complete() && img.natural_width() > 0)
+    })
+}
+
+/// Whether the skin changed since the last call.
+pub(crate) fn take_changed() -> bool {
+    SKIN.with(|s| std::mem::take(&mut s.borrow_mut().changed))
+}
+
+/// Start loading `source`, a URL or inline SVG.
+fn load(source: &str) -> Result<HtmlImageElement, JsValue> {
+    let img = HtmlImageElement::new()?;
+    let onload = Closure::<dyn FnMut()>::new(|| SKIN.with(|s| s.borrow_mut().changed = true));
+    img.set_onload(Some(onload.as_ref().unchecked_ref()));
+    onload.forget();
+    if source.trim_start().starts_with("<svg") {
+        let encoded = js_sys::encode_uri_component(source);
+        img.set_src(&format!("data:image/svg+xml;charset=utf-8,{encoded}"));
+    } else {
+        img.set_src(source);
+    }
+    Ok(img)
+}
+
+/// Replace the skin with `config`'s `black`, `white` and `board` images.
+#[wasm_bindgen(js_name = setSkin)]
+pub fn set_skin(config: &JsValue) -> Result<(), JsValue> {
+    let mut images: [Option<HtmlImageElement>; 3] = Default::default();
+    for part in Part::ALL {
+        let value = js_sys::Reflect::get(config, &part.name().into())?;
+        if value.is_undefined() || value.is_null() {
+            continue;
+        }
+        let source = value
+            .as_string()
+            .ok_or_else(|| format!("skin part {} is not a string", part.name()))?;
+        images[part as usize] = Some(load(&source)?);
+    }
+    SKIN.with(|s| {
+        let mut s = s.borrow_mut();
+        s.images = images;
+        s.changed = true;
+    });
+    Ok(())
+}
+
+/// Go back to drawing everything as the theme has it.
+#[wasm_bindgen(js_name = clearSkin)]
+pub fn clear_skin() {
+    SKIN.with(|s| *s.borrow_mut() = Skin { changed: true, ..Skin::default() });
+}
@@ -0,0 +1,254 @@
+//! Lazy SMP: helper Web Workers search the same position as the AI, each a
+//! little differently, writing what they find into a transposition table they
+//! share with it. The AI's own search on the main thread then cuts off far
+//! more of its tree, and reaches deeper within the same node budget.
+//!
+//! The table lives in a `SharedArrayBuffer`, which browsers only hand out to
+//! cross-origin isolated pages (served with COOP and COEP headers). Where there
+//! is none, or with `?workers=0`, nothing here starts and the search stays on
+//! the main thread alone.
+//!
+//! While the human thinks, the helpers also ponder their likeliest replies
+//! into the table (see `Pool::ponder`), so the AI's answer starts from it warm
+//! and the page's frames are left to drawing.
+//!
+//! Each helper runs its own instance of this module (see `workers`), so only
+//! the table is shared and no `atomics` build is needed.
+//!
+//! A slot holds its key XORed with its data beside the data itself, so a slot
+//! written by two threads at once reads as a miss rather than a wrong entry.
+//! The first word of the buffer counts jobs: a helper drops its search once a
+//! newer one starts.
+
+use std::cell::RefCell;
+
+use js_sys::{Array, Atomics, Int32Array, SharedArrayBuffer};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Worker;
+
+use crate::search::{self, Tt};
+use crate::{workers, Game, Pt, Rules, Style};
+
+/// Slots in the shared table, a power of two; 16 bytes each.
+const SLOTS: u32 = 1 << 18;
+/// Words before the first slot; the first counts jobs.
+const HEADER: u32 = 4;
+/// Helpers started at most, whatever the core count.
+const MAX_HELPERS: usize = 3;
+/// Positions a helper visits pondering each reply; off the main thread, it
+/// need not fit beside a frame.
+const PONDER_NODES: u32 = 20_000;
+
+/// The transposition table's storage, shared by the threads searching.
+#[derive(Clone)]
+pub(crate) struct Table {
+    words: Int32Array,
+    /// In a helper, the job it is searching for.
+    job: Option<i32>,
+}
+
+impl Table {
+    fn new() -> Table {
+        let buffer = SharedArrayBuffer::new((HEADER + SLOTS * 4) * 4);
+        Table {
+            words: Int32Array::new(&buffer),
+            job: None,
+        }
+    }
+
+    fn slot(key: u64) -> u32 {
+        HEADER + (key as u32 & (SLOTS - 1)) * 4
+    }
+
+    pub(crate) fn get(&self, key: u64) -> Option<[i32; 2]> {
+        let i = Self::slot(key);
+        let w = |n| self.words.get_index(i + n);
+        let data = [w(2), w(3)];
+        let stored = (w(0) ^ data[0]) as u32 as u64 | ((w(1) ^ data[1]) as u32 as u64) << 32;
+        (stored == key).then_some(data)
+    }
+
+    pub(crate) fn put(&self, key: u64, data: [i32; 2]) {
+        let i = Self::slot(key);
+        self.words.set_index(i, key as i32 ^ data[0]);
+        self.words.set_index(i + 1, (key >> 32) as i32 ^ data[1]);
+        self.words.set_index(i + 2, data[0]);
+        self.words.set_index(i + 3, data[1]);
+    }
+
+    /// In a helper, search for `job` from now on.
+    pub(crate) fn watch(&mut self, job: i32) {
+        self.job = Some(job);
+    }
+
+    /// In a helper, whether a newer job has replaced the one it is on.
+    pub(crate) fn cancelled(&self) -> bool {
+        self.job.is_some_and(|job| Atomics::load(&self.words, 0).ok() != Some(job))
+    }
+
+    /// Start a new job, cancelling the last; its number.
+    fn next_job(&self) -> i32 {
+        Atomics::add(&self.words, 0, 1).map_or(0, |n| n.wrapping_add(1))
+    }
+}
+
+/// A search for the helpers to share, sent as JSON.
+#[derive(Serialize, Deserialize)]
+struct Job {
+    rules: Rules,
+    /// The rules' weights, which their JSON leaves out.
+    weights: Vec<(String, i32)>,
+    moves: Vec<Pt>,
+    style: Style,
+    depth: u32,
+    nodes: u32,
+    helper: usize,
+    job: i32,
+    /// Replies to `moves` to ponder one after another, rather than helping
+    /// with a search of `moves` itself.
+    #[serde(default)]
+    replies: Vec<Pt>,
+}
+
+/// The helper workers, and the table they share with the page.
+pub(crate) struct Pool {
+    workers: Vec<Worker>,
+    table: Table,
+}
+
+impl Pool {
+    /// Up to `helpers` workers (one fewer than the cores if `None`), or `None`
+    /// if the page cannot share memory with them.
+    pub(crate) fn start(helpers: Option<usize>) -> Option<Pool> {
+        let global = js_sys::global();
+        let isolated = js_sys::Reflect::get(&global, &"crossOriginIsolated".into())
+            .is_ok_and(|v| v.is_truthy());
+        let has_sab = js_sys::Reflect::has(&global, &"SharedArrayBuffer".into()).unwrap_or(false);
+        if !isolated || !has_sab {
+            return None;
+        }
+        let cores = web_sys::window()?.navigator().hardware_concurrency() as usize;
+        let n = helpers.unwrap_or(cores.saturating_sub(1)).min(MAX_HELPERS);
+        if n == 0 {
+            return None;
+        }
+        let table = Table::new();
+        let buffer = table.words.buffer();
+        let workers = (0..n)
+            .map_while(|_| workers::spawn("smpAttach", "smpHelp", &buffer, &Array::new()))
+            .collect::<Vec<_>>();
+        (!workers.is_empty()).then_some(Pool { workers, table })
+    }
+
+    /// A transposition table backed by the shared one.
+    pub(crate) fn tt(&self) -> Tt {
+        Tt::shared(self.table.clone())
+    }
+
+    /// Have every helper search `game` as `search::Deepening` will on the main
+    /// thread, dropping whatever they were searching.
+    pub(crate) fn help(&self, game: &Game, style: Style, depth: u32, nodes: u32) {
+        let mut job = self.job(game, style, depth, nodes);
+        for (i, worker) in self.workers.iter().enumerate() {
+            job.helper = i + 1;
+            post(worker, &job);
+        }
+    }
+
+    /// Have the helpers ponder each of `replies` to `game`, dealt out among
+    /// them, dropping whatever they were searching. The AI's next search
+    /// cancels what is left.
+    pub(crate) fn ponder(&self, game: &Game, style: Style, replies: &[Pt]) {
+        let mut job = self.job(game, style, 0, PONDER_NODES);
+        let n = self.workers.len();
+        for (i, worker) in self.workers.iter().enumerate() {
+            job.helper = i + 1;
+            job.replies = replies.iter().skip(i).step_by(n).copied().collect();
+            if !job.replies.is_empty() {
+                post(worker, &job);
+            }
+        }
+    }
+
+    /// A job for `game`, cancelling the last.
+    fn job(&self, game: &Game, style: Style, depth: u32, nodes: u32) -> Job {
+        Job {
+            rules: game.rules,
+            weights: game.rules.weights.entries().into_iter().map(|(n, v)| (n.into(), v)).collect(),
+            moves: game.moves.clone(),
+            style,
+            depth,
+            nodes,
+            helper: 0,
+            job: self.table.next_job(),
+            replies: Vec::new(),
+        }
+    }
+
+    /// Have the helpers drop their search, the main thread's having ended.
+    pub(crate) fn stop(&self) {
+        self.table.next_job();
+    }
+}
+
+fn post(worker: &Worker, job: &Job) {
+    if let Ok(json) = serde_json::to_string(job) {
+        let _ = worker.post_message(&json.into());
+    }
+}
+
+thread_local! {
+    /// In a helper, the table it was given.
+    static HELPER_TT: RefCell<Option<Tt>> = const { RefCell::new(None) };
+}
+
+/// Called by a helper worker once started, with the page's table. Not for
+/// embedders.
+#[wasm_bindgen(js_name = smpAttach)]
+pub fn smp_attach(buffer: JsValue) {
+    let Ok(buffer) = buffer.dyn_into::<SharedArrayBuffer>() else {
+        return;
+    };
+    let table = Table {
+        words: Int32Array::new(&buffer),
+        job: None,
+    };
+    HELPER_TT.with(|tt| *tt.borrow_mut() = Some(Tt::shared(table)));
+}
+
+/// Called by a helper worker for each job the page posts. Not for embedders.
+#[wasm_bindgen(js_name = smpHelp)]
+pub fn smp_help(job: &str) {
+    let Ok(job) = serde_json::from_str::<Job>(job) else {
+        return;
+    };
+    HELPER_TT.with(|tt| {
+        let mut tt = tt.borrow_mut();
+        let Some(tt) = tt.as_mut() else {
+            return;
+        };
+        tt.watch(job.job);
+        if tt.cancelled() {
+            return;
+        }
+        let mut rules = job.rules;
+        for (name, value) in &job.weights {
+            rules.weights.set(name, *value);
+        }
+        let game = Game::from_moves(rules, &job.moves);
+        if job.replies.is_empty() {
+            search::help(&game, job.style, job.depth, job.nodes, job.helper, tt);
+        }
+        for &p in &job.replies {
+            let mut next = game.clone();
+            if tt.cancelled() {
+                return;
+            }
+            if next.play(p) {
+                search::ponder(&next, job.style, job.nodes, tt);
+            }
+        }
+    });
+}
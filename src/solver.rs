@@ -0,0 +1,294 @@
+//! Forced-win solver: victory by continuous fours (VCF) and by continuous threats (VCT).
+//!
+//! A four leaves the defender a single reply and an open three only the few empty
+//! cells of its window, so forcing lines can be read far deeper than a full-width
+//! search could. Threats are found by sliding windows over the attacker's lines: a
+//! `win_len` window one stone short is a four, and a `win_len + 1` window with both
+//! ends empty and one inner gap is an open three.
+//!
+//! Refutations are over-approximated (every cell of every new three window is tried),
+//! so a reported win is sound. The solver assumes one stone per turn and that stones
+//! stay put, so it stands aside for Connect6 and Pente.
+
+use std::collections::HashSet;
+
+use crate::{Color, Game, Pt, PtMap, Rules, DIRS};
+
+/// Attacker moves explored along a line of fours.
+const VCF_DEPTH: u32 = 12;
+/// Attacker moves explored along a line of fours and threes.
+const VCT_DEPTH: u32 = 4;
+/// Positions visited per solve, to keep the AI's turn within a frame or two.
+const NODE_LIMIT: u32 = 4_000;
+
+/// A forced win for the side to move, as alternating attacker/defender moves
+/// from the attacker's first to the stone that completes the line; `None` if
+/// none was found within the limits.
+pub(crate) fn solve(game: &Game) -> Option<Vec<Pt>> {
+    if game.winner.is_some() || game.rules.captures || game.rules.stones_per_turn != 1 {
+        return None;
+    }
+    let mut s = Solver {
+        cells: game.cells.clone(),
+        rules: game.rules,
+        attacker: game.player,
+        nodes: 0,
+    };
+    if let Some(&p) = s.all_win_points(s.attacker).first() {
+        return Some(vec![p]);
+    }
+    let blocks = s.all_win_points(s.attacker.other());
+    if let Some(line) = s.attack(VCF_DEPTH, false, &blocks) {
+        return Some(line);
+    }
+    s.nodes = 0;
+    s.attack(VCT_DEPTH, true, &blocks)
+}
+
+struct Solver {
+    cells: PtMap<Color>,
+    rules: Rules,
+    attacker: Color,
+    nodes: u32,
+}
+
+impl Solver {
+    /// Attacker to move; `blocks` are the defender's open win points.
+    fn attack(&mut self, depth: u32, vct: bool, blocks: &[Pt]) -> Option<Vec<Pt>> {
+        self.nodes += 1;
+        if depth == 0 || blocks.len() > 1 || self.nodes > NODE_LIMIT {
+            return None;
+        }
+        let (me, opp) = (self.attacker, self.attacker.other());
+        let candidates = match blocks {
+            [b] => vec![*b],
+            _ if vct => merge(self.four_moves(me), self.three_moves(me)),
+            _ => self.four_moves(me),
+        };
+        for p in candidates {
+            self.cells.insert(p, me);
+            let wins = self.win_points(p, me);
+            let line = match wins.as_slice() {
+                // Two ways to complete: the defender cannot stop both.
+                [w, v, ..] => Some(vec![p, *w, *v]),
+                [w] => {
+                    self.cells.insert(*w, opp);
+                    let blocks = self.win_points(*w, opp);
+                    let rest = self.attack(depth - 1, vct, &blocks);
+                    self.cells.remove(w);
+                    rest.map(|rest| [vec![p, *w], rest].concat())
+                }
+                [] if vct => self.refute_all(p, depth, me, opp),
+                [] => None,
+            };
+            self.cells.remove(&p);
+            if line.is_some() {
+                return line;
+            }
+        }
+        None
+    }
+
+    /// After the attacker's three at `p`, every defence must still lose. Returns the
+    /// line through the first defence tried.
+    fn refute_all(&mut self, p: Pt, depth: u32, me: Color, opp: Color) -> Option<Vec<Pt>> {
+        let defences = self.three_defences(p, me);
+        if defences.is_empty() {
+            return None;
+        }
+        // The defender may also answer with a four of their own.
+        let defences = merge(defences, self.four_moves(opp));
+        let mut first = None;
+        for d in defences {
+            self.cells.insert(d, opp);
+            let blocks = self.win_points(d, opp);
+            let rest = self.attack(depth - 1, true, &blocks);
+            self.cells.remove(&d);
+            let rest = rest?;
+            first.get_or_insert_with(|| [vec![p, d], rest].concat());
+        }
+        first
+    }
+
+    fn is_empty(&self, p: Pt) -> bool {
+        !self.cells.contains_key(&p) && self.rules.bounds.is_none_or(|b| b.contains(p))
+    }
+
+    /// The `len` cells starting at `start` along `d`.
+    fn window(start: Pt, d: Pt, len: i32) -> impl Iterator<Item = Pt> {
+        (0..len).map(move |k| start.add(d.x * k, d.y * k))
+    }
+
+    /// Every `(start, dir)` of a `len`-long window containing `p`.
+    fn windows_through(p: Pt, len: i32) -> impl Iterator<Item = (Pt, Pt)> {
+        DIRS.into_iter()
+            .flat_map(move |d| (0..len).map(move |k| (p.add(-d.x * k, -d.y * k), d)))
+    }
+
+    /// Counts of `who`'s stones in a window, and its empty cells; `None` if the
+    /// window holds an enemy stone or runs off the board.
+    fn scan(&self, start: Pt, d: Pt, len: i32, who: Color) -> Option<(i32, Vec<Pt>)> {
+        let mut mine = 0;
+        let mut empty = Vec::new();
+        for q in Self::window(start, d, len) {
+            match self.cells.get(&q) {
+                Some(&c) if c == who => mine += 1,
+                Some(_) => return None,
+                None if self.is_empty(q) => empty.push(q),
+                None => return None,
+            }
+        }
+        Some((mine, empty))
+    }
+
+    /// Cells where `who` would complete a line through `p`.
+    fn win_points(&self, p: Pt, who: Color) -> Vec<Pt> {
+        let n = self.rules.win_len;
+        let mut out = Vec::new();
+        for (start, d) in Self::windows_through(p, n) {
+            if let Some((mine, empty)) = self.scan(start, d, n, who) {
+                if mine == n - 1 && !out.contains(&empty[0]) {
+                    out.push(empty[0]);
+                }
+            }
+        }
+        out
+    }
+
+    fn all_win_points(&self, who: Color) -> Vec<Pt> {
+        let mut out = Vec::new();
+        for p in self.stones(who) {
+            for q in self.win_points(p, who) {
+                if !out.contains(&q) {
+                    out.push(q);
+                }
+            }
+        }
+        out
+    }
+
+    /// Cells where `who` would make a four.
+    fn four_moves(&self, who: Color) -> Vec<Pt> {
+        let n = self.rules.win_len;
+        let mut out = HashSet::new();
+        for p in self.stones(who) {
+            for (start, d) in Self::windows_through(p, n) {
+                if let Some((mine, empty)) = self.scan(start, d, n, who) {
+                    if mine == n - 2 {
+                        out.extend(empty);
+                    }
+                }
+            }
+        }
+        Self::sorted(out)
+    }
+
+    /// Cells where `who` would make an open three.
+    fn three_moves(&self, who: Color) -> Vec<Pt> {
+        let n = self.rules.win_len;
+        let mut out = HashSet::new();
+        for p in self.stones(who) {
+            for (start, d) in Self::windows_through(p, n + 1) {
+                if let Some(inner) = self.open_inner(start, d, who) {
+                    if inner.0 == n - 3 {
+                        out.extend(inner.1);
+                    }
+                }
+            }
+        }
+        Self::sorted(out)
+    }
+
+    /// Cells that might stop the open threes through `p`: both ends and the gap of each.
+    fn three_defences(&self, p: Pt, who: Color) -> Vec<Pt> {
+        let n = self.rules.win_len;
+        let mut out = HashSet::new();
+        for (start, d) in Self::windows_through(p, n + 1) {
+            if let Some((mine, gaps)) = self.open_inner(start, d, who) {
+                if mine == n - 2 {
+                    out.extend(gaps);
+                    out.insert(start);
+                    out.insert(start.add(d.x * n, d.y * n));
+                }
+            }
+        }
+        Self::sorted(out)
+    }
+
+    /// For a `win_len + 1` window with both ends empty, the stone count and gaps
+    /// of its inner cells.
+    fn open_inner(&self, start: Pt, d: Pt, who: Color) -> Option<(i32, Vec<Pt>)> {
+        let n = self.rules.win_len;
+        let end = start.add(d.x * n, d.y * n);
+        if !self.is_empty(start) || !self.is_empty(end) {
+            return None;
+        }
+        self.scan(start.add(d.x, d.y), d, n - 1, who)
+    }
+
+    fn stones(&self, who: Color) -> Vec<Pt> {
+        self.cells
+            .iter()
+            .filter(|&(_, &c)| c == who)
+            .map(|(&p, _)| p)
+            .collect()
+    }
+
+    /// A stable order, so the same position is solved the same way.
+    fn sorted(set: HashSet<Pt>) -> Vec<Pt> {
+        let mut v: Vec<Pt> = set.into_iter().collect();
+        v.sort_by_key(|p| (p.y, p.x));
+        v
+    }
+}
+
+/// `a` followed by the cells of `b` not already in it.
+fn merge(mut a: Vec<Pt>, b: Vec<Pt>) -> Vec<Pt> {
+    for p in b {
+        if !a.contains(&p) {
+            a.push(p);
+        }
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Black's `black` and White's `white` stones, played alternately, with
+    /// Black to move.
+    fn position(black: &[(i32, i32)], white: &[(i32, i32)]) -> Game {
+        assert_eq!(black.len(), white.len());
+        let moves: Vec<(i32, i32)> = black.iter().zip(white).flat_map(|(&b, &w)| [b, w]).collect();
+        Game::from_legal_moves(Rules::default(), &moves)
+    }
+
+    #[test]
+    fn finds_a_win_by_continuous_fours() {
+        // E.g. a four at (4,3) forces (3,3), and (4,4) then makes two fours at once.
+        let black = [(1, 4), (2, 4), (3, 4), (4, 1), (4, 2), (5, 3), (6, 3), (7, 3)];
+        let white = [(0, 4), (4, 0), (8, 3), (20, 20), (23, 20), (20, 23), (26, 26), (29, 20)];
+        let mut game = position(&black, &white);
+        let line = solve(&game).expect("a VCF");
+        assert!(line.len() >= 3 && line.len() % 2 == 1, "{line:?}");
+        for pair in line.chunks(2) {
+            assert!(game.play(pair[0]));
+            if let [_, reply] = *pair {
+                // Any other reply loses at once: the attacker's move was a four.
+                let mut elsewhere = game.clone();
+                assert!(elsewhere.play(Pt::new(-30, -30)));
+                assert_eq!(solve(&elsewhere).map(|l| l.len()), Some(1), "{line:?}");
+                assert!(game.play(reply));
+            }
+        }
+        assert_eq!(game.winner, Some(Color::Black));
+    }
+
+    #[test]
+    fn quiet_positions_have_no_forced_win() {
+        let black = [(0, 0), (3, 1), (-2, 4)];
+        let white = [(1, 1), (-1, -2), (4, 4)];
+        assert_eq!(solve(&position(&black, &white)), None);
+    }
+}
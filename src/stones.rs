@@ -0,0 +1,189 @@
+//! Painting stones on a 2D context, for the board canvas, the stone layer and
+//! the worker that draws the stone layer where it is offscreen (see `layers`).
+//! Everything a stone's look depends on comes in as values, so a worker with
+//! no game or theme of its own paints them the same.
+
+use serde::{Deserialize, Serialize};
+use web_sys::CanvasRenderingContext2d;
+
+use crate::theme::Theme;
+use crate::{skin, Color, Pt, LOD_CELL_PX};
+
+/// What stones look like: the theme's part in it, and White's marks.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub(crate) struct Look<'a> {
+    #[serde(borrow)]
+    pub(crate) stones: [&'a str; 2],
+    #[serde(borrow)]
+    pub(crate) outline: Option<&'a str>,
+    pub(crate) shaded: bool,
+    /// A band inside White's stones, in the background colour.
+    pub(crate) marks: bool,
+    pub(crate) background: &'a str,
+}
+
+impl Look<'static> {
+    pub(crate) fn of(theme: &'static Theme, marks: bool) -> Self {
+        Look {
+            stones: theme.stones,
+            outline: theme.stone_outline,
+            shaded: theme.shaded_stones,
+            marks,
+            background: theme.background,
+        }
+    }
+}
+
+/// Where the camera is: the cell it centres on, the size of a cell and the
+/// view's size, all in CSS px.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub(crate) struct View {
+    pub(crate) cam: (f64, f64),
+    pub(crate) cell_px: f64,
+    pub(crate) size: (f64, f64),
+}
+
+impl View {
+    pub(crate) fn screen(self, p: Pt) -> (f64, f64) {
+        (
+            (p.x as f64 - self.cam.0) * self.cell_px + self.size.0 / 2.0,
+            (p.y as f64 - self.cam.1) * self.cell_px + self.size.1 / 2.0,
+        )
+    }
+
+    /// Whether a stone at `p` would show.
+    pub(crate) fn shows(self, p: Pt) -> bool {
+        let (sx, sy) = self.screen(p);
+        let m = self.cell_px;
+        (-m..=self.size.0 + m).contains(&sx) && (-m..=self.size.1 + m).contains(&sy)
+    }
+}
+
+/// A stone of colour `c` centred on `(sx, sy)`, in a cell `cell_px` across.
+pub(crate) fn stone(
+    ctx: &CanvasRenderingContext2d,
+    look: &Look,
+    cell_px: f64,
+    (sx, sy): (f64, f64),
+    c: Color,
+) {
+    let r = cell_px * 0.4;
+    ctx.begin_path();
+    let _ = ctx.arc(sx, sy, r, 0.0, std::f64::consts::TAU);
+    ctx.set_fill_style_str(look.stones[c as usize]);
+    if let Some(img) = skin::image(skin::Part::stone(c)) {
+        let (x, y, d) = (sx - r, sy - r, 2.0 * r);
+        let _ = ctx.draw_image_with_html_image_element_and_dw_and_dh(&img, x, y, d, d);
+    } else if look.shaded {
+        // Small enough to stay inside the cell, which the stone layer's
+        // patches of single cells rely on.
+        ctx.save();
+        ctx.set_shadow_color("rgba(0,0,0,0.45)");
+        ctx.set_shadow_blur(cell_px * 0.06);
+        ctx.set_shadow_offset_x(cell_px * 0.03);
+        ctx.set_shadow_offset_y(cell_px * 0.03);
+        ctx.fill();
+        ctx.restore();
+        shade(ctx, sx, sy, r);
+    } else {
+        ctx.fill();
+    }
+    if let Some(outline) = look.outline {
+        ctx.set_stroke_style_str(outline);
+        ctx.set_line_width((cell_px * 0.06).max(2.0));
+        ctx.stroke();
+    }
+    // A dark band inside White's stones, clear of the centre where move
+    // numbers and the last-move marker go.
+    if look.marks && c == Color::White {
+        ctx.begin_path();
+        let _ = ctx.arc(sx, sy, r * 0.65, 0.0, std::f64::consts::TAU);
+        ctx.set_stroke_style_str(look.background);
+        ctx.set_line_width((r * 0.2).max(1.5));
+        ctx.stroke();
+    }
+}
+
+/// Light from the top left over the stone just filled at `(sx, sy)`: a
+/// highlight fading into the stone's own colour, and a darker rim.
+fn shade(ctx: &CanvasRenderingContext2d, sx: f64, sy: f64, r: f64) {
+    let (hx, hy) = (sx - r * 0.35, sy - r * 0.35);
+    let Ok(g) = ctx.create_radial_gradient(hx, hy, r * 0.05, sx, sy, r) else {
+        return;
+    };
+    let _ = g.add_color_stop(0.0, "rgba(255,255,255,0.55)");
+    let _ = g.add_color_stop(0.45, "rgba(255,255,255,0)");
+    let _ = g.add_color_stop(0.85, "rgba(0,0,0,0.08)");
+    let _ = g.add_color_stop(1.0, "rgba(0,0,0,0.3)");
+    ctx.set_fill_style_canvas_gradient(&g);
+    ctx.fill();
+}
+
+/// `stones` on a cleared canvas: as plain squares, one path and fill per
+/// colour, at zooms below `LOD_CELL_PX` where arcs and outlines cannot be
+/// seen anyway.
+pub(crate) fn all(
+    ctx: &CanvasRenderingContext2d,
+    look: &Look,
+    view: &View,
+    stones: &[(Pt, Color)],
+) {
+    ctx.clear_rect(0.0, 0.0, view.size.0, view.size.1);
+    if view.cell_px >= LOD_CELL_PX {
+        for &(p, c) in stones {
+            stone(ctx, look, view.cell_px, view.screen(p), c);
+        }
+        return;
+    }
+    let size = view.cell_px * 0.8;
+    for c in [Color::Black, Color::White] {
+        ctx.begin_path();
+        for &(p, _) in stones.iter().filter(|&&(_, s)| s == c) {
+            let (sx, sy) = view.screen(p);
+            ctx.rect(sx - size / 2.0, sy - size / 2.0, size, size);
+        }
+        ctx.set_fill_style_str(look.stones[c as usize]);
+        ctx.fill();
+    }
+}
+
+/// Clear `p`'s square and draw the stone `stone_at` says is there, if any. A stone stays
+/// inside its cell, so no neighbour needs redrawing.
+pub(crate) fn cell(
+    ctx: &CanvasRenderingContext2d,
+    look: &Look,
+    view: &View,
+    p: Pt,
+    stone_at: Option<Color>,
+) {
+    let (sx, sy) = view.screen(p);
+    let px = view.cell_px;
+    ctx.clear_rect(sx - px / 2.0, sy - px / 2.0, px, px);
+    let Some(c) = stone_at else {
+        return;
+    };
+    if px < LOD_CELL_PX {
+        let size = px * 0.8;
+        ctx.set_fill_style_str(look.stones[c as usize]);
+        ctx.fill_rect(sx - size / 2.0, sy - size / 2.0, size, size);
+    } else {
+        stone(ctx, look, px, (sx, sy), c);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn views_put_the_camera_cell_in_the_middle() {
+        let view = View {
+            cam: (2.0, -1.0),
+            cell_px: 10.0,
+            size: (200.0, 100.0),
+        };
+        assert_eq!(view.screen(Pt::new(2, -1)), (100.0, 50.0));
+        assert_eq!(view.screen(Pt::new(3, 0)), (110.0, 60.0));
+        assert!(view.shows(Pt::new(-8, -1)) && !view.shows(Pt::new(-10, -1)));
+    }
+}
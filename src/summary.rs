@@ -0,0 +1,55 @@
+//! The card shown after a win: how long the game went on, how long each side
+//! took over its moves, and the threats each made and stopped.
+
+use crate::threats::{self, Threat};
+use crate::{Color, Game};
+
+pub(crate) struct Summary {
+    pub(crate) moves: usize,
+    pub(crate) duration_ms: f64,
+    /// Each side's average time per move, counted from the move before; `None`
+    /// when a side has no timed moves (or the game was loaded from a link).
+    pub(crate) average_ms: [Option<f64>; 2],
+    /// Open threes or stronger each side made, and moves on a cell where the
+    /// opponent would have made one.
+    pub(crate) threats_made: [u32; 2],
+    pub(crate) threats_blocked: [u32; 2],
+}
+
+impl Summary {
+    /// Sum up `game`, whose moves were played at `move_times` (ms, one per
+    /// move) over `duration_ms`.
+    pub(crate) fn new(game: &Game, move_times: &[f64], duration_ms: f64) -> Summary {
+        let mut g = Game::with_rules(game.rules);
+        let mut threats_made = [0; 2];
+        let mut threats_blocked = [0; 2];
+        let mut spent = [(0.0, 0); 2];
+        let timed = move_times.len() == game.moves.len();
+        for (i, &p) in game.moves.iter().enumerate() {
+            let mover = g.player;
+            if threats::classify(&g, p, mover) >= Threat::OpenThree {
+                threats_made[mover as usize] += 1;
+            }
+            if threats::classify(&g, p, mover.other()) >= Threat::OpenThree {
+                threats_blocked[mover as usize] += 1;
+            }
+            if timed && i > 0 {
+                let (total, n) = &mut spent[mover as usize];
+                *total += move_times[i] - move_times[i - 1];
+                *n += 1;
+            }
+            g.play(p);
+        }
+        let average = |c: Color| {
+            let (total, n) = spent[c as usize];
+            (n > 0).then(|| total / n as f64)
+        };
+        Summary {
+            moves: game.moves.len(),
+            duration_ms,
+            average_ms: [average(Color::Black), average(Color::White)],
+            threats_made,
+            threats_blocked,
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Colours, line widths and HUD text size for drawing, with a high-contrast
+//! variant for low-vision players and a wooden board.
+//!
+//! The usual theme is dark or light as the browser's `prefers-color-scheme`
+//! asks, following it when it changes, unless the player has picked one; that
+//! choice is kept in `localStorage` and can be set from JS with
+//! `setColorScheme("light" | "dark" | "auto")`.
+
+use std::cell::Cell;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{window, Storage};
+
+const SCHEME_KEY: &str = "gomoku.scheme";
+
+pub(crate) struct Theme {
+    pub(crate) background: &'static str,
+    pub(crate) grid: &'static str,
+    pub(crate) grid_width: f64,
+    /// The edge of a bounded board.
+    pub(crate) edge: &'static str,
+    /// Black's and White's stones.
+    pub(crate) stones: [&'static str; 2],
+    /// A ring around every stone, setting it off from the grid, if any.
+    pub(crate) stone_outline: Option<&'static str>,
+    /// Round stones: a highlight and rim shading, and a drop shadow.
+    pub(crate) shaded_stones: bool,
+    /// Wood grain drawn over the background, in this colour, if any.
+    pub(crate) board_grain: Option<&'static str>,
+    /// Background of the HUD's panels, status lines and overlays.
+    pub(crate) panel: &'static str,
+    /// Controller pills: the fill for a human and an AI side, and the border.
+    pub(crate) pill_human: &'static str,
+    pub(crate) pill_ai: &'static str,
+    pub(crate) pill_border: &'static str,
+    pub(crate) text: &'static str,
+    pub(crate) muted_text: &'static str,
+    /// Coordinate labels along the edges.
+    pub(crate) labels: &'static str,
+    /// HUD font sizes, and the pills and strips around them, are scaled by this.
+    text_scale: f64,
+}
+
+pub(crate) static STANDARD: Theme = Theme {
+    background: "#0b0d11",
+    grid: "#20242b",
+    grid_width: 1.0,
+    edge: "#4b5563",
+    stones: ["#e6edf3", "#38bdf8"],
+    stone_outline: None,
+    shaded_stones: true,
+    board_grain: None,
+    panel: "rgba(0,0,0,0.6)",
+    pill_human: "#1f2937",
+    pill_ai: "#111827",
+    pill_border: "#374151",
+    text: "#e5e7eb",
+    muted_text: "#cbd5e1",
+    labels: "#6b7280",
+    text_scale: 1.0,
+};
+
+/// `STANDARD`'s light counterpart, for browsers that prefer a light scheme.
+pub(crate) static LIGHT: Theme = Theme {
+    background: "#f5f5f4",
+    grid: "#d6d3d1",
+    grid_width: 1.0,
+    edge: "#78716c",
+    stones: ["#1f2937", "#0284c7"],
+    stone_outline: None,
+    shaded_stones: true,
+    board_grain: None,
+    panel: "rgba(255,255,255,0.85)",
+    pill_human: "#e7e5e4",
+    pill_ai: "#f5f5f4",
+    pill_border: "#a8a29e",
+    text: "#1c1917",
+    muted_text: "#44403c",
+    labels: "#78716c",
+    text_scale: 1.0,
+};
+
+/// Pure black and white with thick grid lines, yellow for White's stones, and
+/// larger HUD text on opaque panels.
+pub(crate) static HIGH_CONTRAST: Theme = Theme {
+    background: "#000000",
+    grid: "#a3a3a3",
+    grid_width: 2.0,
+    edge: "#ffffff",
+    stones: ["#ffffff", "#facc15"],
+    stone_outline: Some("#000000"),
+    shaded_stones: false,
+    board_grain: None,
+    panel: "#000000",
+    pill_human: "#000000",
+    pill_ai: "#000000",
+    pill_border: "#ffffff",
+    text: "#ffffff",
+    muted_text: "#ffffff",
+    labels: "#ffffff",
+    text_scale: 1.25,
+};
+
+/// A goban: black and white stones on grained wood, with dark lines.
+pub(crate) static WOOD: Theme = Theme {
+    background: "#d6a45c",
+    grid: "#6b4423",
+    grid_width: 1.0,
+    edge: "#4a2e14",
+    stones: ["#151515", "#f4f3ee"],
+    stone_outline: None,
+    shaded_stones: true,
+    board_grain: Some("rgba(120,72,30,0.22)"),
+    panel: "rgba(40,24,10,0.8)",
+    pill_human: "#3b2a1a",
+    pill_ai: "#2a1d12",
+    pill_border: "#7c5a3a",
+    text: "#fbf3e4",
+    muted_text: "#ecdcc0",
+    labels: "#4a2e14",
+    text_scale: 1.0,
+};
+
+impl Theme {
+    pub(crate) fn is_high_contrast(&self) -> bool {
+        std::ptr::eq(self, &HIGH_CONTRAST)
+    }
+
+    /// Whether this is one of the dark and light themes the colour scheme picks.
+    pub(crate) fn follows_scheme(&self) -> bool {
+        std::ptr::eq(self, &STANDARD) || std::ptr::eq(self, &LIGHT)
+    }
+
+    /// How much larger than usual HUD text is drawn, with `set_text_zoom`'s
+    /// zoom applied.
+    pub(crate) fn text_scale(&self) -> f64 {
+        self.text_scale * TEXT_ZOOM.with(Cell::get)
+    }
+
+    /// `px` (before scaling) in the HUD's typeface, e.g. `"bold 13px ui-sans-serif…"`.
+    pub(crate) fn font(&self, px: f64, bold: bool) -> String {
+        let weight = if bold { "bold " } else { "" };
+        let px = (px * self.text_scale()).round();
+        format!("{weight}{px}px ui-sans-serif, system-ui, -apple-system")
+    }
+}
+
+/// Whether the browser asks for more contrast (`prefers-contrast: more`).
+pub(crate) fn prefers_contrast() -> bool {
+    window()
+        .and_then(|w| w.match_media("(prefers-contrast: more)").ok().flatten())
+        .is_some_and(|m| m.matches())
+}
+
+/// Dark or light, or whichever the browser prefers.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Scheme {
+    Auto,
+    Light,
+    Dark,
+}
+
+thread_local! {
+    /// `None` until first read from storage.
+    static SCHEME: Cell<Option<Scheme>> = const { Cell::new(None) };
+    /// Set when the scheme, or the browser's preference, changes, until the
+    /// next frame takes it.
+    static SCHEME_CHANGED: Cell<bool> = const { Cell::new(false) };
+    /// Scales HUD text on top of every theme's own size (see `set_text_zoom`).
+    static TEXT_ZOOM: Cell<f64> = const { Cell::new(1.0) };
+}
+
+/// Draw HUD text in every theme `zoom` times its usual size.
+pub(crate) fn set_text_zoom(zoom: f64) {
+    TEXT_ZOOM.with(|z| z.set(zoom));
+}
+
+fn storage() -> Option<Storage> {
+    window()?.local_storage().ok().flatten()
+}
+
+/// The player's choice of scheme; `Auto` unless they have made one.
+pub(crate) fn scheme() -> Scheme {
+    SCHEME.with(|s| {
+        *s.get().get_or_insert_with(|| {
+            match storage().and_then(|s| s.get_item(SCHEME_KEY).ok().flatten()).as_deref() {
+                Some("light") => Scheme::Light,
+                Some("dark") => Scheme::Dark,
+                _ => Scheme::Auto,
+            }
+        })
+    })
+}
+
+/// Pick `scheme` and remember it.
+pub(crate) fn set_scheme(scheme: Scheme) {
+    SCHEME.with(|s| s.set(Some(scheme)));
+    SCHEME_CHANGED.with(|c| c.set(true));
+    if let Some(s) = storage() {
+        let _ = match scheme {
+            Scheme::Auto => s.remove_item(SCHEME_KEY),
+            Scheme::Light => s.set_item(SCHEME_KEY, "light"),
+            Scheme::Dark => s.set_item(SCHEME_KEY, "dark"),
+        };
+    }
+}
+
+/// Pick `"light"`, `"dark"` or `"auto"` (follow the browser), and remember it.
+#[wasm_bindgen(js_name = setColorScheme)]
+pub fn set_color_scheme(name: &str) -> Result<(), JsValue> {
+    set_scheme(match name {
+        "auto" => Scheme::Auto,
+        "light" => Scheme::Light,
+        "dark" => Scheme::Dark,
+        _ => return Err(format!("unknown colour scheme {name:?}").into()),
+    });
+    Ok(())
+}
+
+/// Whether the scheme or the browser's preference changed since the last call.
+pub(crate) fn take_scheme_changed() -> bool {
+    SCHEME_CHANGED.with(|c| c.replace(false))
+}
+
+/// Whether the browser asks for a light scheme (`prefers-color-scheme: light`).
+/// Browsers that cannot say get the dark one.
+fn prefers_light() -> bool {
+    window()
+        .and_then(|w| w.match_media("(prefers-color-scheme: light)").ok().flatten())
+        .is_some_and(|m| m.matches())
+}
+
+/// The dark or light theme, as the player's scheme (or the browser) asks.
+pub(crate) fn scheme_theme() -> &'static Theme {
+    let light = match scheme() {
+        Scheme::Auto => prefers_light(),
+        Scheme::Light => true,
+        Scheme::Dark => false,
+    };
+    if light {
+        &LIGHT
+    } else {
+        &STANDARD
+    }
+}
+
+/// Note changes to the browser's preferred scheme, for the next frame to pick up.
+pub(crate) fn watch_scheme() {
+    let Some(query) = window().and_then(|w| w.match_media("(prefers-color-scheme: light)").ok().flatten()) else {
+        return;
+    };
+    let closure = Closure::<dyn FnMut()>::new(|| SCHEME_CHANGED.with(|c| c.set(true)));
+    let _ = query.add_event_listener_with_callback("change", closure.as_ref().unchecked_ref());
+    closure.forget();
+}
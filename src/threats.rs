@@ -0,0 +1,168 @@
+//! Threat detection: what a stone on a point would make, for either side, and the
+//! candidate moves that matter because of it.
+
+use crate::patterns::{self, Shape};
+use crate::{Color, Game, Pt, Style};
+
+/// What a single stone would create, weakest first.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub(crate) enum Threat {
+    None,
+    /// One move from an open four.
+    OpenThree,
+    /// One move from a win, with one way to complete.
+    Four,
+    /// Two open threes at once.
+    DoubleThree,
+    /// A four and an open three: blocking the four leaves the three.
+    FourThree,
+    /// Two fours at once.
+    DoubleFour,
+    /// A four with both ends open.
+    OpenFour,
+    /// A completed line.
+    Five,
+}
+
+/// Quiet (non-threat) moves kept per node, best `score_point` first.
+const QUIET_WIDTH: usize = 6;
+/// Most candidates searched from any node.
+const MAX_WIDTH: usize = 14;
+
+/// Classify `who` playing at empty `p`, from the same line shapes `score_point`
+/// reads.
+pub(crate) fn classify(game: &Game, p: Pt, who: Color) -> Threat {
+    combine(patterns::shapes(game, p, who).map(|(mine, _)| mine))
+}
+
+/// The threat made by a stone with these shapes along its four lines.
+fn combine(shapes: [Shape; 4]) -> Threat {
+    let (mut fours, mut open_fours, mut threes) = (0, 0, 0);
+    for shape in shapes {
+        match shape {
+            Shape::Five => return Threat::Five,
+            Shape::OpenFour => open_fours += 1,
+            Shape::Four => fours += 1,
+            Shape::OpenThree => threes += 1,
+            _ => {}
+        }
+    }
+    match (open_fours, fours, threes) {
+        (o, _, _) if o > 0 => Threat::OpenFour,
+        (_, f, _) if f > 1 => Threat::DoubleFour,
+        (_, 1, t) if t > 0 => Threat::FourThree,
+        (_, 1, _) => Threat::Four,
+        (_, _, t) if t > 1 => Threat::DoubleThree,
+        (_, _, 1) => Threat::OpenThree,
+        _ => Threat::None,
+    }
+}
+
+/// A threat-bearing move: what it makes for the side to move and what it takes from the other.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Candidate {
+    pub(crate) p: Pt,
+    pub(crate) mine: Threat,
+    pub(crate) theirs: Threat,
+    pub(crate) score: i32,
+}
+
+/// Moves worth searching for the side to move, most forcing first.
+///
+/// Forced situations collapse the list: take a win, block the opponent's, play
+/// an unstoppable shape, or — facing a shape that would become unstoppable —
+/// answer it or counter with a four. Only the win is taken as forced when a
+/// turn is two stones. Otherwise every threat for either side is kept, plus a
+/// few of the best quiet points.
+pub(crate) fn candidates(game: &Game) -> Vec<Candidate> {
+    let me = game.player;
+    let mut all: Vec<Candidate> = game
+        .frontier
+        .iter()
+        .filter(|&&p| game.playable(p))
+        .map(|&p| {
+            let shapes = patterns::shapes(game, p, me);
+            Candidate {
+                p,
+                mine: combine(shapes.map(|(mine, _)| mine)),
+                theirs: combine(shapes.map(|(_, theirs)| theirs)),
+                score: game.score_shapes(p, me, &shapes, Style::Balanced),
+            }
+        })
+        .collect();
+    all.sort_by(|a, b| {
+        (b.mine.max(b.theirs), b.score)
+            .cmp(&(a.mine.max(a.theirs), a.score))
+            .then((a.p.y, a.p.x).cmp(&(b.p.y, b.p.x)))
+    });
+
+    // Pente captures can break lines, so the forced cases are only hints there.
+    if !game.rules.captures {
+        if let Some(c) = all.iter().find(|c| c.mine == Threat::Five) {
+            return vec![*c];
+        }
+    }
+    // The rest assume one stone a turn: with two, a four wins past a block.
+    if !game.rules.captures && game.rules.stones_per_turn == 1 {
+        let narrowed = |keep: &dyn Fn(&Candidate) -> bool| -> Vec<Candidate> {
+            all.iter().copied().filter(|c| keep(c)).collect()
+        };
+        let blocks = narrowed(&|c| c.theirs == Threat::Five);
+        if !blocks.is_empty() {
+            return blocks;
+        }
+        let wins = narrowed(&|c| c.mine >= Threat::FourThree);
+        if !wins.is_empty() {
+            return wins;
+        }
+        if all.iter().any(|c| c.theirs >= Threat::FourThree) {
+            let mut answers = narrowed(&|c| c.theirs >= Threat::Four || c.mine >= Threat::Four);
+            answers.truncate(MAX_WIDTH);
+            return answers;
+        }
+    }
+
+    let mut keep: Vec<Candidate> = Vec::with_capacity(MAX_WIDTH);
+    let mut quiet = 0;
+    for c in all {
+        if c.mine > Threat::None || c.theirs > Threat::None {
+            keep.push(c);
+        } else if quiet < QUIET_WIDTH {
+            quiet += 1;
+            keep.push(c);
+        }
+        if keep.len() == MAX_WIDTH {
+            break;
+        }
+    }
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Rules, Variant};
+
+    #[test]
+    fn connect6_keeps_the_two_stone_win_past_a_block() {
+        let rules = Variant::Connect6.apply(Rules::default());
+        let moves = [
+            (0, 0),
+            (0, 5),
+            (1, 5),
+            (1, 0),
+            (2, 0),
+            (2, 5),
+            (3, 5),
+            (3, 0),
+            (10, 10),
+            (4, 5),
+            (20, 20),
+        ];
+        let moves: Vec<Pt> = moves.iter().map(|&(x, y)| Pt::new(x, y)).collect();
+        let game = Game::from_moves(rules, &moves);
+        assert_eq!(game.moves.len(), moves.len());
+        let found: Vec<Pt> = candidates(&game).iter().map(|c| c.p).collect();
+        assert!(found.contains(&Pt::new(4, 0)) || found.contains(&Pt::new(-1, 0)));
+    }
+}
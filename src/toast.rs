@@ -0,0 +1,100 @@
+//! Short messages that appear above the minimap for a moment and fade away:
+//! "Move undone", "Opponent disconnected", "Saved". Anything can queue one
+//! with `App::toast`; a few are on screen at once, the rest wait their turn,
+//! and each one's time starts when it is shown.
+
+use std::collections::VecDeque;
+
+use web_sys::CanvasRenderingContext2d;
+
+use crate::theme::Theme;
+
+/// How long a toast stays, fade included.
+const TOAST_MS: f64 = 2600.0;
+/// How long a toast takes to fade out at the end, and in at the start.
+const TOAST_FADE_MS: f64 = 300.0;
+/// Toasts on screen at once; later ones wait.
+const MAX_SHOWN: usize = 3;
+/// Beyond this many waiting or shown, new toasts are dropped.
+const MAX_QUEUED: usize = 8;
+
+struct Toast {
+    text: String,
+    /// When it appeared; `None` while it waits its turn.
+    shown_ms: Option<f64>,
+}
+
+#[derive(Default)]
+pub(crate) struct Toasts {
+    queue: VecDeque<Toast>,
+}
+
+impl Toasts {
+    /// Queue `text`, unless the same message is already queued or shown.
+    pub(crate) fn push(&mut self, text: String) {
+        if self.queue.len() >= MAX_QUEUED || self.queue.iter().any(|t| t.text == text) {
+            return;
+        }
+        self.queue.push_back(Toast {
+            text,
+            shown_ms: None,
+        });
+    }
+
+    /// Drop toasts whose time is up and show waiting ones in their place.
+    /// Whether any are left, so frames must keep being drawn.
+    pub(crate) fn step(&mut self, now_ms: f64) -> bool {
+        self.queue
+            .retain(|t| t.shown_ms.is_none_or(|at| now_ms - at < TOAST_MS));
+        for t in self.queue.iter_mut().take(MAX_SHOWN) {
+            t.shown_ms.get_or_insert(now_ms);
+        }
+        !self.queue.is_empty()
+    }
+
+    /// Draw the shown toasts right-aligned at `right`, the newest lowest, its
+    /// bottom at `bottom`.
+    pub(crate) fn draw(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        theme: &Theme,
+        now_ms: f64,
+        right: f64,
+        bottom: f64,
+    ) {
+        ctx.set_font(&theme.font(13.0, false));
+        ctx.set_text_align("left");
+        ctx.set_text_baseline("middle");
+        let h = 30.0 * theme.text_scale();
+        let mut y = bottom - h;
+        let shown: Vec<(&Toast, f64)> = self
+            .queue
+            .iter()
+            .filter_map(|t| Some((t, t.shown_ms?)))
+            .collect();
+        for &(toast, at) in shown.iter().rev() {
+            let age = now_ms - at;
+            let alpha = (age / TOAST_FADE_MS)
+                .min((TOAST_MS - age) / TOAST_FADE_MS)
+                .clamp(0.0, 1.0);
+            let tw = ctx
+                .measure_text(&toast.text)
+                .ok()
+                .map(|m| m.width())
+                .unwrap_or(0.0);
+            let w = tw + 24.0;
+            ctx.save();
+            ctx.set_global_alpha(alpha);
+            ctx.set_fill_style_str(theme.panel);
+            ctx.fill_rect(right - w, y, w, h);
+            ctx.set_stroke_style_str(theme.pill_border);
+            ctx.set_line_width(1.0);
+            ctx.stroke_rect(right - w, y, w, h);
+            ctx.set_fill_style_str(theme.text);
+            let _ = ctx.fill_text(&toast.text, right - w + 12.0, y + h / 2.0);
+            ctx.restore();
+            y -= h + 6.0;
+        }
+        ctx.set_text_baseline("alphabetic");
+    }
+}
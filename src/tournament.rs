@@ -0,0 +1,101 @@
+//! Engine-vs-engine matches: two AI configurations play game after game,
+//! swapping colours each time, while the score is kept.
+
+use crate::i18n::{tf, Msg};
+use crate::{AiConfig, Color, Game};
+
+/// Plies after which an unfinished game on an unbounded board counts as drawn.
+pub(crate) const MAX_PLIES: usize = 200;
+/// Pause between a finished game and the next.
+pub(crate) const NEXT_GAME_MS: f64 = 2500.0;
+
+pub(crate) struct Tournament {
+    players: [AiConfig; 2],
+    wins: [u32; 2],
+    draws: u32,
+    /// Games started before the current one.
+    round: u32,
+    /// When the finished game gives way to the next; `None` while one is played.
+    next_game_at: Option<f64>,
+}
+
+impl Tournament {
+    pub(crate) fn new(players: [AiConfig; 2]) -> Self {
+        Tournament {
+            players,
+            wins: [0; 2],
+            draws: 0,
+            round: 0,
+            next_game_at: None,
+        }
+    }
+
+    fn games(&self) -> u32 {
+        self.wins[0] + self.wins[1] + self.draws
+    }
+
+    /// Which player has `side` in the current game; the first takes Black in even rounds.
+    fn player(&self, side: Color) -> usize {
+        (side as usize + self.round as usize) % 2
+    }
+
+    /// "A" or "B", for whoever has `side` in the current game.
+    pub(crate) fn name(&self, side: Color) -> &'static str {
+        ["A", "B"][self.player(side)]
+    }
+
+    /// The configuration playing `side` in the current game.
+    pub(crate) fn config(&self, side: Color) -> AiConfig {
+        self.players[self.player(side)]
+    }
+
+    /// Score `game` once it is over. Returns `true` the first time it is, setting
+    /// the time the next game starts.
+    pub(crate) fn record(&mut self, game: &Game, now_ms: f64) -> bool {
+        if self.next_game_at.is_some() {
+            return false;
+        }
+        let stuck = !game.frontier.iter().any(|&p| game.playable(p));
+        match game.winner {
+            Some(w) => self.wins[self.player(w)] += 1,
+            None if stuck || game.moves.len() >= MAX_PLIES => self.draws += 1,
+            None => return false,
+        }
+        self.next_game_at = Some(now_ms + NEXT_GAME_MS);
+        true
+    }
+
+    /// Whether the pause after a finished game is over; clears it if so.
+    pub(crate) fn next_game_due(&mut self, now_ms: f64) -> bool {
+        if self.next_game_at.is_some_and(|t| now_ms >= t) {
+            self.next_game_at = None;
+            self.round += 1;
+            return true;
+        }
+        false
+    }
+
+    /// Score table lines: a heading, then each player and the draws.
+    pub(crate) fn table(&self) -> Vec<String> {
+        let n = self.games();
+        let pct = |w: u32| match n {
+            0 => String::new(),
+            n => format!(
+                " ({:.0}%)",
+                (w as f64 + self.draws as f64 / 2.0) / n as f64 * 100.0
+            ),
+        };
+        let mut rows = vec![tf(Msg::TournamentHeader, &[&n])];
+        for (i, ai) in self.players.iter().enumerate() {
+            rows.push(format!(
+                "{} {}: {}{}",
+                ["A", "B"][i],
+                ai.label(),
+                self.wins[i],
+                pct(self.wins[i])
+            ));
+        }
+        rows.push(tf(Msg::Draws, &[&self.draws]));
+        rows
+    }
+}
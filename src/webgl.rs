@@ -0,0 +1,244 @@
+//! A WebGL stone layer for very large games, where drawing thousands of arcs
+//! on the 2D stone layer makes every pan and zoom slow. Each stone is one
+//! point sprite, shaded round in the fragment shader, so a redraw is a single
+//! draw call however many stones there are; the positions are uploaded once
+//! per new position and the camera only changes uniforms.
+//!
+//! The layer is made the first time a game grows past `WEBGL_STONES`, and is
+//! `None` where WebGL is unavailable, in which case the 2D layer carries on.
+//! The grid stays on its own 2D layer, since its cost follows the screen size
+//! rather than the stone count. White's marks and stone outlines are not drawn
+//! here: at the zooms such games are played they are too small to see.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    Document, Element, HtmlCanvasElement, WebGlBuffer, WebGlProgram, WebGlRenderingContext as Gl,
+    WebGlShader, WebGlUniformLocation,
+};
+
+use crate::{Color, Pt, PtMap};
+
+/// Above this many stones the WebGL layer takes over from the 2D one.
+pub(crate) const WEBGL_STONES: usize = 2000;
+
+const VERTEX_SHADER: &str = r#"
+attribute vec2 a_pos;
+attribute float a_white;
+uniform vec2 u_cam;
+uniform vec2 u_half;
+uniform float u_px;
+uniform float u_dpr;
+varying float v_white;
+void main() {
+    vec2 screen = (a_pos - u_cam) * u_px / u_half;
+    gl_Position = vec4(screen.x, -screen.y, 0.0, 1.0);
+    gl_PointSize = u_px * 0.8 * u_dpr;
+    v_white = a_white;
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+precision mediump float;
+uniform vec3 u_black;
+uniform vec3 u_white;
+varying float v_white;
+void main() {
+    float d = length(gl_PointCoord - vec2(0.5));
+    float edge = fwidth(d);
+    float alpha = 1.0 - smoothstep(0.5 - edge, 0.5, d);
+    if (alpha <= 0.0) discard;
+    vec3 color = mix(u_black, u_white, v_white);
+    gl_FragColor = vec4(color * alpha, alpha);
+}
+"#;
+
+pub(crate) struct GlStones {
+    pub(crate) canvas: HtmlCanvasElement,
+    gl: Gl,
+    program: WebGlProgram,
+    buffer: WebGlBuffer,
+    /// Stones in the buffer.
+    count: i32,
+    dpr: f64,
+    /// The position in the buffer (its key and move count); `None` while the
+    /// layer is hidden.
+    shown: Option<(u64, usize)>,
+}
+
+impl GlStones {
+    /// A WebGL canvas of the page's `layer` class just before `above`, or
+    /// `None` if the browser will not give one.
+    pub(crate) fn new(doc: &Document, above: &Element) -> Option<GlStones> {
+        let canvas = doc
+            .create_element("canvas")
+            .ok()?
+            .dyn_into::<HtmlCanvasElement>()
+            .ok()?;
+        canvas.set_class_name("layer");
+        let _ = canvas.set_attribute("aria-hidden", "true");
+        // Kept after compositing so the magnifier can copy from it.
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, &"preserveDrawingBuffer".into(), &true.into()).ok()?;
+        let gl = canvas
+            .get_context_with_context_options("webgl", &options)
+            .ok()??
+            .dyn_into::<Gl>()
+            .ok()?;
+        // `fwidth` for the stones' smooth edges.
+        gl.get_extension("OES_standard_derivatives").ok()??;
+        let program = link(&gl).ok()?;
+        let buffer = gl.create_buffer()?;
+        canvas.set_hidden(true);
+        above
+            .parent_node()?
+            .insert_before(&canvas, Some(above))
+            .ok()?;
+        Some(GlStones {
+            canvas,
+            gl,
+            program,
+            buffer,
+            count: 0,
+            dpr: 1.0,
+            shown: None,
+        })
+    }
+
+    pub(crate) fn resize(&mut self, w: f64, h: f64, dpr: f64) {
+        self.canvas.set_width((w * dpr) as u32);
+        self.canvas.set_height((h * dpr) as u32);
+        self.gl.viewport(
+            0,
+            0,
+            self.canvas.width() as i32,
+            self.canvas.height() as i32,
+        );
+        self.dpr = dpr;
+    }
+
+    /// Show the layer with the position `key`, whose stones are `cells`,
+    /// uploading them unless already there. `true` if it needs drawing.
+    pub(crate) fn show(&mut self, key: (u64, usize), cells: &PtMap<Color>) -> bool {
+        if self.shown == Some(key) {
+            return false;
+        }
+        if self.shown.is_none() {
+            self.canvas.set_hidden(false);
+        }
+        self.upload(cells);
+        self.shown = Some(key);
+        true
+    }
+
+    /// Hide the layer while the 2D one draws the stones; `true` if it was shown.
+    pub(crate) fn hide(&mut self) -> bool {
+        self.canvas.set_hidden(true);
+        self.shown.take().is_some()
+    }
+
+    /// Replace the stones in the buffer with `cells`.
+    fn upload(&mut self, cells: &PtMap<Color>) {
+        let data: Vec<f32> = cells
+            .iter()
+            .flat_map(|(p, &c): (&Pt, &Color)| {
+                [p.x as f32, p.y as f32, (c == Color::White) as u8 as f32]
+            })
+            .collect();
+        self.gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.buffer));
+        self.gl.buffer_data_with_array_buffer_view(
+            Gl::ARRAY_BUFFER,
+            &js_sys::Float32Array::from(&data[..]),
+            Gl::STATIC_DRAW,
+        );
+        self.count = cells.len() as i32;
+    }
+
+    /// Draw the uploaded stones centred on cell `(cam_x, cam_y)` at `px` CSS
+    /// px per cell, over a `w`×`h` view, in `stones` (`#rrggbb`) colours.
+    pub(crate) fn draw(
+        &self,
+        (cam_x, cam_y): (f64, f64),
+        px: f64,
+        (w, h): (f64, f64),
+        stones: [&str; 2],
+    ) {
+        let gl = &self.gl;
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(Gl::COLOR_BUFFER_BIT);
+        gl.use_program(Some(&self.program));
+        let uniform = |name: &str| -> Option<WebGlUniformLocation> {
+            gl.get_uniform_location(&self.program, name)
+        };
+        gl.uniform2f(uniform("u_cam").as_ref(), cam_x as f32, cam_y as f32);
+        gl.uniform2f(
+            uniform("u_half").as_ref(),
+            (w / 2.0) as f32,
+            (h / 2.0) as f32,
+        );
+        gl.uniform1f(uniform("u_px").as_ref(), px as f32);
+        gl.uniform1f(uniform("u_dpr").as_ref(), self.dpr as f32);
+        for (name, hex) in [("u_black", stones[0]), ("u_white", stones[1])] {
+            let [r, g, b] = rgb(hex);
+            gl.uniform3f(uniform(name).as_ref(), r, g, b);
+        }
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&self.buffer));
+        let stride = 3 * 4;
+        for (name, size, offset) in [("a_pos", 2, 0), ("a_white", 1, 2 * 4)] {
+            let at = gl.get_attrib_location(&self.program, name);
+            if at < 0 {
+                continue;
+            }
+            gl.vertex_attrib_pointer_with_i32(at as u32, size, Gl::FLOAT, false, stride, offset);
+            gl.enable_vertex_attrib_array(at as u32);
+        }
+        gl.enable(Gl::BLEND);
+        gl.blend_func(Gl::ONE, Gl::ONE_MINUS_SRC_ALPHA);
+        gl.draw_arrays(Gl::POINTS, 0, self.count);
+    }
+}
+
+fn link(gl: &Gl) -> Result<WebGlProgram, JsValue> {
+    let vertex = compile(gl, Gl::VERTEX_SHADER, VERTEX_SHADER)?;
+    let fragment = compile(
+        gl,
+        Gl::FRAGMENT_SHADER,
+        &format!("#extension GL_OES_standard_derivatives : enable\n{FRAGMENT_SHADER}"),
+    )?;
+    let program = gl.create_program().ok_or("no program")?;
+    gl.attach_shader(&program, &vertex);
+    gl.attach_shader(&program, &fragment);
+    gl.link_program(&program);
+    if !gl
+        .get_program_parameter(&program, Gl::LINK_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        return Err(gl.get_program_info_log(&program).unwrap_or_default().into());
+    }
+    Ok(program)
+}
+
+fn compile(gl: &Gl, kind: u32, source: &str) -> Result<WebGlShader, JsValue> {
+    let shader = gl.create_shader(kind).ok_or("no shader")?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+    if !gl
+        .get_shader_parameter(&shader, Gl::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        return Err(gl.get_shader_info_log(&shader).unwrap_or_default().into());
+    }
+    Ok(shader)
+}
+
+/// `#rrggbb` as 0-to-1 channels; anything else is taken as grey.
+fn rgb(hex: &str) -> [f32; 3] {
+    let channel = |i: usize| {
+        hex.get(1 + 2 * i..3 + 2 * i)
+            .and_then(|h| u8::from_str_radix(h, 16).ok())
+            .map_or(0.5, |v| v as f32 / 255.0)
+    };
+    [channel(0), channel(1), channel(2)]
+}
@@ -0,0 +1,150 @@
+//! The heuristic's pattern scores, tunable without recompiling.
+//!
+//! Scores are per `Shape`, so one set serves any win length; the names read as
+//! in five-in-a-row (one short of a win is a four).
+
+use crate::patterns::Shape;
+
+/// Everything `score_point` adds up, by pattern.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Weights {
+    // Extending one's own line through the point.
+    five: i32,
+    open_four: i32,
+    four: i32,
+    open_three: i32,
+    three: i32,
+    open_two: i32,
+    two: i32,
+    open_one: i32,
+    other: i32,
+    // Occupying a point the opponent's line runs through.
+    block_five: i32,
+    block_open_four: i32,
+    block_four: i32,
+    block_open_three: i32,
+    block_three: i32,
+    // Pente: per pair taken (or denied), plus a step per pair already captured.
+    capture: i32,
+    capture_step: i32,
+    deny_capture: i32,
+    deny_capture_step: i32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            five: 1_000_000,
+            open_four: 50_000,
+            four: 20_000,
+            open_three: 10_000,
+            three: 1_000,
+            open_two: 500,
+            two: 100,
+            open_one: 50,
+            other: 10,
+            block_five: 900_000,
+            block_open_four: 40_000,
+            block_four: 15_000,
+            block_open_three: 8_000,
+            block_three: 800,
+            capture: 4_000,
+            capture_step: 4_000,
+            deny_capture: 3_000,
+            deny_capture_step: 3_000,
+        }
+    }
+}
+
+impl Weights {
+    /// Making `shape` along one line.
+    pub(crate) fn attack(&self, shape: Shape) -> i32 {
+        match shape {
+            Shape::Five => self.five,
+            Shape::OpenFour => self.open_four,
+            Shape::Four => self.four,
+            Shape::OpenThree => self.open_three,
+            Shape::Three => self.three,
+            Shape::OpenTwo => self.open_two,
+            Shape::Two => self.two,
+            Shape::OpenOne => self.open_one,
+            Shape::None => self.other,
+        }
+    }
+
+    /// Taking the point where the opponent would make `shape`.
+    pub(crate) fn defence(&self, shape: Shape) -> i32 {
+        match shape {
+            Shape::Five => self.block_five,
+            Shape::OpenFour => self.block_open_four,
+            Shape::Four => self.block_four,
+            Shape::OpenThree => self.block_open_three,
+            Shape::Three => self.block_three,
+            _ => 0,
+        }
+    }
+
+    /// Capturing `pairs` pairs with `taken` already captured.
+    pub(crate) fn captures(&self, pairs: i32, taken: i32) -> i32 {
+        pairs * (self.capture + taken * self.capture_step)
+    }
+
+    /// Denying the opponent `pairs` pairs when they have captured `lost`.
+    pub(crate) fn denied_captures(&self, pairs: i32, lost: i32) -> i32 {
+        pairs * (self.deny_capture + lost * self.deny_capture_step)
+    }
+
+    /// Every weight under its JavaScript name, in declaration order.
+    fn slots(&mut self) -> [(&'static str, &mut i32); 18] {
+        [
+            ("five", &mut self.five),
+            ("openFour", &mut self.open_four),
+            ("four", &mut self.four),
+            ("openThree", &mut self.open_three),
+            ("three", &mut self.three),
+            ("openTwo", &mut self.open_two),
+            ("two", &mut self.two),
+            ("openOne", &mut self.open_one),
+            ("other", &mut self.other),
+            ("blockFive", &mut self.block_five),
+            ("blockOpenFour", &mut self.block_open_four),
+            ("blockFour", &mut self.block_four),
+            ("blockOpenThree", &mut self.block_open_three),
+            ("blockThree", &mut self.block_three),
+            ("capture", &mut self.capture),
+            ("captureStep", &mut self.capture_step),
+            ("denyCapture", &mut self.deny_capture),
+            ("denyCaptureStep", &mut self.deny_capture_step),
+        ]
+    }
+
+    /// `(name, value)` for every weight.
+    pub(crate) fn entries(&self) -> Vec<(&'static str, i32)> {
+        let mut copy = *self;
+        copy.slots().into_iter().map(|(n, v)| (n, *v)).collect()
+    }
+
+    /// Set the weight called `name`; `false` if there is none.
+    pub(crate) fn set(&mut self, name: &str, value: i32) -> bool {
+        match self.slots().into_iter().find(|(n, _)| *n == name) {
+            Some((_, slot)) => {
+                *slot = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// These weights with `"openFour:60000,four:25000"`-style overrides applied;
+    /// `None` if a name is unknown or a value is not an integer.
+    pub(crate) fn with_overrides(mut self, spec: &str) -> Option<Weights> {
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, value) = item.split_once(':')?;
+            let value = value.trim().parse().ok()?;
+            if !self.set(name.trim(), value) {
+                return None;
+            }
+        }
+        Some(self)
+    }
+}
@@ -0,0 +1,77 @@
+//! Web Workers running their own instance of this module, started from the
+//! page's compiled wasm: the search helpers (`smp`) and the offscreen stone
+//! layer (`layers`).
+//!
+//! A worker imports the bindings' JS module, whose URL is its own
+//! `import.meta.url`, from a small script kept in a Blob. Its first message
+//! carries the compiled module, the export to call once it is instantiated
+//! and that export's argument; every later message goes to a second export.
+//! `start` finds no window in a worker and leaves it at that.
+
+use std::cell::OnceCell;
+
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+use web_sys::{Blob, BlobPropertyBag, Url, Worker, WorkerOptions, WorkerType};
+
+#[wasm_bindgen]
+extern "C" {
+    /// The URL of the bindings' JS module.
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["import", "meta"], js_name = url)]
+    static GLUE_URL: String;
+}
+
+const WORKER_JS: &str = r#"import init, * as wasm from "GLUE";
+let ready, handler;
+self.onmessage = ({ data }) => {
+    if (!ready) {
+        const [module, attach, onMessage, arg] = data;
+        handler = onMessage;
+        ready = init({ module_or_path: module }).then(() => wasm[attach](arg));
+    } else {
+        ready.then(() => wasm[handler](data));
+    }
+};
+"#;
+
+thread_local! {
+    /// The worker script's Blob URL, kept for the page's lifetime since a
+    /// worker may still be fetching it after its constructor returns.
+    static SCRIPT_URL: OnceCell<Option<String>> = const { OnceCell::new() };
+}
+
+fn script_url() -> Option<String> {
+    SCRIPT_URL.with(|url| {
+        url.get_or_init(|| {
+            let script = WORKER_JS.replace("GLUE", &GLUE_URL.with(String::clone));
+            let props = BlobPropertyBag::new();
+            props.set_type("text/javascript");
+            let parts = Array::of1(&script.into());
+            let blob = Blob::new_with_str_sequence_and_options(&parts, &props).ok()?;
+            Url::create_object_url_with_blob(&blob).ok()
+        })
+        .clone()
+    })
+}
+
+/// A worker that calls the export `attach` with `arg` once started, then
+/// `on_message` with each message posted to it; `transfer` lists what `arg`
+/// hands over rather than copies.
+pub(crate) fn spawn(
+    attach: &str,
+    on_message: &str,
+    arg: &JsValue,
+    transfer: &Array,
+) -> Option<Worker> {
+    let opts = WorkerOptions::new();
+    opts.set_type(WorkerType::Module);
+    let worker = Worker::new_with_options(&script_url()?, &opts).ok()?;
+    let first = Array::of4(&wasm_bindgen::module(), &attach.into(), &on_message.into(), arg);
+    match worker.post_message_with_transfer(&first, transfer) {
+        Ok(()) => Some(worker),
+        Err(_) => {
+            worker.terminate();
+            None
+        }
+    }
+}